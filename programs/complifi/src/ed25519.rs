@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::get_instruction_relative;
+
+use crate::CompliFiError;
+
+pub const ED25519_PROGRAM_ID: Pubkey = solana_sdk_ids::ed25519_program::ID;
+
+// Ed25519 program instruction data: [num_signatures: u8, padding: u8] followed by one
+// 14-byte offsets entry per signature (seven little-endian u16 values)
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+
+// Instruction index meaning "data lives in the Ed25519 instruction itself"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Check that the instruction immediately before the current one is an Ed25519 program
+/// instruction verifying a single signature by `signer` over exactly `message`.
+pub fn verify_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let ix = get_instruction_relative(-1, instructions)
+        .map_err(|_| error!(CompliFiError::SignatureVerificationFailed))?;
+    require_keys_eq!(ix.program_id, ED25519_PROGRAM_ID, CompliFiError::SignatureVerificationFailed);
+    
+    let data = &ix.data;
+    require!(
+        data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        CompliFiError::SignatureVerificationFailed
+    );
+    
+    let offset = |field: usize| {
+        let at = OFFSETS_START + field * 2;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    let (signature_ix, pubkey_offset, pubkey_ix) = (offset(1), offset(2) as usize, offset(3));
+    let (message_offset, message_size, message_ix) = (offset(4) as usize, offset(5) as usize, offset(6));
+    
+    // Referencing data in other instructions would let the signed bytes differ from what we check
+    require!(
+        signature_ix == CURRENT_INSTRUCTION
+            && pubkey_ix == CURRENT_INSTRUCTION
+            && message_ix == CURRENT_INSTRUCTION,
+        CompliFiError::SignatureVerificationFailed
+    );
+    
+    let signed_pubkey = data.get(pubkey_offset..pubkey_offset + PUBKEY_LEN);
+    let signed_message = data.get(message_offset..message_offset + message_size);
+    require!(
+        signed_pubkey == Some(signer.as_ref()) && signed_message == Some(message),
+        CompliFiError::SignatureVerificationFailed
+    );
+    Ok(())
+}
@@ -22,4 +22,271 @@ pub enum CompliFiError {
     
     #[msg("Oracle data fetch failed")]
     OracleDataFetchFailed,
-}
\ No newline at end of file
+    
+    #[msg("Attestation has no expiry configured")]
+    AttestationHasNoExpiry,
+    
+    #[msg("Risk threshold override must not loosen the policy")]
+    OverrideLoosensPolicy,
+    
+    #[msg("A reporting period is already open")]
+    ReportPeriodActive,
+    
+    #[msg("Report account is not the active reporting period")]
+    ReportNotActive,
+    
+    #[msg("Attestation is not opted in to auto-renewal")]
+    AutoRenewDisabled,
+    
+    #[msg("Number of accounts does not match the number of entries")]
+    AccountCountMismatch,
+    
+    #[msg("Too few multisig members are compliant")]
+    MultisigNotCompliant,
+    
+    #[msg("Emergency verification requires a reason")]
+    EmergencyReasonRequired,
+    
+    #[msg("Time oracle account and timestamp must be provided together")]
+    InvalidOracleTimestamp,
+    
+    #[msg("Time oracle timestamp is outside the allowed tolerance")]
+    TimestampOutOfTolerance,
+    
+    #[msg("Signature verification failed")]
+    SignatureVerificationFailed,
+    
+    #[msg("Bridge message has already been consumed")]
+    BridgeMessageReplayed,
+    
+    #[msg("Attestation was not issued by the required screening vendor")]
+    VendorNotAccepted,
+    
+    #[msg("Revealed value does not match the stored commitment")]
+    CommitmentMismatch,
+    
+    #[msg("Payment mandate allowance is exhausted")]
+    MandateExhausted,
+    
+    #[msg("Payment mandate has expired")]
+    MandateExpired,
+    
+    #[msg("Not enough policy signers approved the change")]
+    InsufficientPolicySigners,
+    
+    #[msg("Attestation lacks a wallet ownership proof")]
+    OwnershipProofRequired,
+    
+    #[msg("Verification throughput cap reached for this slot")]
+    ThroughputExceeded,
+    
+    #[msg("No verifications to close for the current day")]
+    VerificationDayEmpty,
+    
+    #[msg("Attestation is not in the legacy layout")]
+    AttestationAlreadyMigrated,
+    
+    #[msg("Token account data is malformed or does not match the mint")]
+    InvalidTokenAccount,
+    
+    #[msg("The token's own transfer restrictions block this transfer")]
+    TokenTransferRestricted,
+    
+    #[msg("Compiled policy is stale; recompile it")]
+    CompiledPolicyStale,
+    
+    #[msg("Jurisdiction changed; the attestation must be re-verified")]
+    JurisdictionRecheckRequired,
+    
+    #[msg("Attestation history does not chain to the current record")]
+    AttestationChainBroken,
+    
+    #[msg("Transaction amount exceeds the allowed limit")]
+    AmountExceedsLimit,
+    
+    #[msg("Action is outside the policy's trading window")]
+    OutsideTradingWindow,
+    
+    #[msg("Policy logic token stream is malformed")]
+    InvalidPolicyLogic,
+    
+    #[msg("Policy logic is not satisfied")]
+    PolicyLogicNotSatisfied,
+    
+    #[msg("Identity verification is older than the policy allows")]
+    IdentityVerificationStale,
+    
+    #[msg("Sanctions screening is older than the policy allows")]
+    SanctionsScreeningStale,
+    
+    #[msg("Accreditation is missing or older than the policy allows")]
+    AccreditationStale,
+    
+    #[msg("The state's rollup must be passed to keep its counts current")]
+    RollupRequired,
+    
+    #[msg("Wallet is frozen")]
+    WalletFrozen,
+    
+    #[msg("Anonymous usage cap reached; full KYC is required")]
+    AnonymousCapExceeded,
+    
+    #[msg("Consortium-governed policies only change through a consortium vote")]
+    ConsortiumVoteRequired,
+    
+    #[msg("Signer is not a member of the consortium")]
+    NotConsortiumMember,
+    
+    #[msg("Proposal was already applied or the policy has changed since it was made")]
+    ProposalStale,
+    
+    #[msg("Member already voted on this proposal")]
+    AlreadyVoted,
+    
+    #[msg("No deferred verification is pending on this receipt")]
+    NoPendingVerification,
+    
+    #[msg("Wallet is under a regulatory hold")]
+    RegulatoryHold,
+    
+    #[msg("Fee recipient and system program are required to charge the verification fee")]
+    FeeAccountsRequired,
+    
+    #[msg("User compliance record is required for this call")]
+    UserRecordRequired,
+    
+    #[msg("Verification nonce must be greater than the last one used")]
+    StaleNonce,
+    
+    #[msg("Rollout baseline is required for users outside the rollout cohort")]
+    RolloutBaselineRequired,
+    
+    #[msg("KYC attestation has expired")]
+    AttestationExpired,
+    
+    #[msg("Risk score comes from an outdated risk model")]
+    RiskModelOutdated,
+    
+    #[msg("Attestation is still within its jurisdiction's retention period")]
+    RetentionNotElapsed,
+    
+    #[msg("Attestation and policy use different jurisdiction schemes")]
+    JurisdictionSchemeMismatch,
+    
+    #[msg("Attestation has fewer reviewed documents than the policy requires for its level")]
+    InsufficientDocumentsReviewed,
+    
+    #[msg("Verification is paused")]
+    ProgramPaused,
+    
+    #[msg("Policy is not the designated treasury policy")]
+    NotTreasuryPolicy,
+    
+    #[msg("Attester has already signed this attestation")]
+    AlreadyCosigned,
+    
+    #[msg("Attestation has no room for another co-signer")]
+    CosignerLimitReached,
+    
+    #[msg("Attestation signers do not carry enough trust weight")]
+    InsufficientAttesterTrust,
+    
+    #[msg("The policy's fee ledger must be passed to record the fee")]
+    FeeLedgerRequired,
+    
+    #[msg("The attestation does not cover this counterparty")]
+    CounterpartyNotAllowed,
+    
+    #[msg("The state keeps an attestation journal, which must be passed")]
+    JournalRequired,
+    
+    #[msg("Wallet is on the sanctions blocklist")]
+    WalletSanctioned,
+    
+    #[msg("Cumulative volume requires a higher KYC level; step up verification")]
+    StepUpRequired,
+    
+    #[msg("Attestation's KYC level is below the policy minimum")]
+    InsufficientKycLevel,
+    
+    #[msg("Signer is not an allowlisted attestor")]
+    UnauthorizedAttestor,
+    
+    #[msg("Signed attestation is not newer than the wallet's current attestation")]
+    SignedAttestationReplayed,
+    
+    #[msg("Policy already has the maximum number of action thresholds")]
+    ActionThresholdLimitReached,
+    
+    #[msg("Policy is not active at the current time")]
+    PolicyNotActive,
+    
+    #[msg("Counter overflowed")]
+    CounterOverflow,
+    
+    #[msg("Verification requested before the policy's reverification interval elapsed")]
+    VerificationTooSoon,
+    
+    #[msg("Batch is empty or larger than the program allows")]
+    BatchTooLarge,
+    
+    #[msg("Account is not the attestation PDA for its wallet")]
+    AttestationAddressMismatch,
+    
+    #[msg("Wallet already has an attestation")]
+    AttestationAlreadyExists,
+    
+    #[msg("Oracle risk data is older than the policy allows")]
+    StaleOracleData,
+    
+    #[msg("Violation category is not one of the VIOLATION_CATEGORY_* values")]
+    InvalidViolationCategory,
+    
+    #[msg("Action is not on the policy's action whitelist")]
+    ActionNotAllowed,
+    
+    #[msg("Policy already has the maximum number of allowed actions")]
+    AllowedActionLimitReached,
+    
+    #[msg("Attestor is not delegated this jurisdiction")]
+    JurisdictionNotDelegated,
+    
+    #[msg("Attestation is newer than the policy's minimum attestation age")]
+    AttestationTooNew,
+    
+    #[msg("Verification deadline has passed")]
+    DeadlineExceeded,
+    
+    #[msg("Certificate accounts are required to mint a compliance certificate")]
+    CertificateAccountsRequired,
+    
+    #[msg("String argument is longer than the program accepts")]
+    StringTooLong,
+    
+    #[msg("Attestation was issued by an attestor that is no longer allowlisted")]
+    UntrustedAttestor,
+    
+    #[msg("Wallet already has a pending KYC request")]
+    KycRequestPending,
+    
+    #[msg("Policy already has the maximum number of jurisdiction risk caps")]
+    JurisdictionRiskCapLimitReached,
+    
+    #[msg("Policy requires the attestation to be co-signed by a second attester")]
+    DualAttestationRequired,
+    
+    #[msg("Deferred risk checks cannot gate an instruction executed in the same call")]
+    DeferredVerificationUnsupported,
+    
+    #[msg("KYC is required but no jurisdiction is allowed")]
+    NoJurisdictionsAllowed,
+    
+    #[msg("The user's compliance receipt must be passed to record the pass")]
+    ComplianceReceiptRequired,
+    
+    #[msg("The state's issuance policy must be passed to issue an attestation")]
+    IssuancePolicyRequired,
+    
+    #[msg("KYC level is out of range")]
+    InvalidKycLevel,
+}
@@ -1,204 +1,7369 @@
+// Anchor handlers take their instruction arguments individually, and issuance needs many
+#![allow(clippy::too_many_arguments)]
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, set_return_data};
+use anchor_lang::system_program;
 mod state;
 mod error;
+mod ed25519;
+mod token2022;
+mod switchboard;
+mod logic;
+mod merkle;
 pub use state::*;
 pub use error::*;
+pub use logic::*;
+use ed25519::verify_ed25519_signature;
+use token2022::{
+    create_associated_token_account, initialize_non_transferable_mint, mint_to, read_mint_transfer_config,
+    read_token_account, token_account_owner, NON_TRANSFERABLE_MINT_LEN,
+};
+pub use token2022::{ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID};
+use switchboard::{aggregator_risk_score, read_aggregator_result};
+use merkle::{keccak256v, merkle_root_from_proof};
+pub use switchboard::SWITCHBOARD_PROGRAM_ID;
 
 declare_id!("8n1D2rYYeUnfrWN4qTDHAvvbEokbPop6cbeSNZ3brNeU");
 
 // Constants for integration
+#[allow(dead_code)]
 const SAS_PROGRAM_ID: &str = "SASFcCrMYnS1ZZz7B4XGpBKMJHrHkGGtT9oJRKrWYAh";
-const RANGE_ORACLE_PROGRAM_ID: &str = "RNG3P5GZ3WjKQjJ1f6yHSHJNLSBV4V5qrDdBvuKKTtd";
+pub const RANGE_ORACLE_PROGRAM_ID: Pubkey = pubkey!("RNG3P5GZ3WjKQjJ1f6yHSHJNLSBV4V5qrDdBvuKKTtd");
+
+// Risk oracle a policy reads single-oracle scores from
+pub const ORACLE_KIND_RANGE: u8 = 0;          // The wallet's Range Oracle entry
+pub const ORACLE_KIND_SWITCHBOARD: u8 = 1;    // The wallet's registered Switchboard aggregator
+
+// Seed for the per-wallet record of which Switchboard aggregator scores it
+pub const RISK_FEED_SEED: &[u8] = b"risk-feed";
+
+// Seeds for the soulbound compliance certificate mint, which is its own mint authority,
+// and for each holder's certificate metadata
+pub const CERTIFICATE_MINT_SEED: &[u8] = b"certificate-mint";
+pub const CERTIFICATE_SEED: &[u8] = b"certificate";
+
+// Upper bound on oracle accounts consulted for a quorum risk check
+pub const MAX_RISK_ORACLES: usize = 8;
+
+// Seed for per-user cached risk score PDAs
+pub const RISK_CACHE_SEED: &[u8] = b"risk-cache";
+
+// Upper bound on users refreshed in one refresh_risk_batch call
+pub const MAX_RISK_REFRESH_BATCH: usize = 8;
+
+// Upper bound on attestations created in one create_kyc_attestations_batch call
+pub const MAX_ATTESTATION_BATCH: usize = 8;
+
+// Upper bound on users checked in one verify_compliance_batch call
+pub const MAX_VERIFICATION_BATCH: usize = 8;
+
+// Seed for cached verification outcomes, one per (user, policy, action, amount bucket)
+pub const VERIFICATION_RECEIPT_SEED: &[u8] = b"verification-receipt";
+
+// Seed for the per-user receipt of the latest pass, which other programs read instead of a CPI
+pub const COMPLIANCE_RECEIPT_SEED: &[u8] = b"receipt";
+
+// Highest amount bucket: a u64 amount has at most 20 decimal digits
+pub const MAX_AMOUNT_BUCKET: u8 = 20;
+
+// Decisions a policy with a latency budget returns from verify_compliance and
+// finalize_verification, as the first byte of return data
+pub const VERIFICATION_DECISION_PASS: u8 = 0;
+pub const VERIFICATION_DECISION_FAIL: u8 = 1;
+pub const VERIFICATION_DECISION_PENDING: u8 = 2; // Cheap checks passed; the oracle check is deferred
+
+// Seed for per-nullifier anonymous usage PDAs
+pub const NULLIFIER_SEED: &[u8] = b"nullifier";
+
+// Seed for a state's dashboard rollup PDA
+pub const ROLLUP_SEED: &[u8] = b"rollup";
+
+// Seed for a state's attestation journal PDA
+pub const ATTESTATION_JOURNAL_SEED: &[u8] = b"attestation-journal";
+
+// Seed for a policy's verification fee ledger PDA
+pub const FEE_LEDGER_SEED: &[u8] = b"fee-ledger";
+
+// KYC levels an attestation can stand at, as counted by the rollup
+pub const KYC_LEVEL_NONE: u8 = 0;             // Unverified or revoked
+pub const KYC_LEVEL_VERIFIED: u8 = 1;         // Verified on the attester's word
+pub const KYC_LEVEL_OWNERSHIP_PROVEN: u8 = 2; // Verified, and the wallet proved control of its key
+pub const KYC_LEVEL_COUNT: usize = 3;
+
+// KYC levels a policy can require a reviewed-document minimum for, leaving room for a
+// level above the current ones
+pub const DOCUMENT_REQUIREMENT_LEVELS: usize = 4;
+
+// Decimals of lamports, the default denomination
+pub const NATIVE_DECIMALS: u8 = 9;
+
+// Largest decimals where 10^decimals still fits in a u64
+pub const MAX_DECIMALS: u8 = 19;
+
+// Seed for per-jurisdiction metadata PDAs
+pub const JURISDICTION_SEED: &[u8] = b"jurisdiction";
+
+// Longest jurisdiction name stored on its metadata
+pub const MAX_JURISDICTION_NAME_LEN: usize = 32;
+
+// Seed for per-jurisdiction transaction amount cap PDAs
+pub const JURISDICTION_LIMIT_SEED: &[u8] = b"jurisdiction-limit";
+
+// Jurisdiction risk tiers run from 0 (lowest) to RISK_TIER_COUNT - 1 (highest)
+pub const RISK_TIER_COUNT: usize = 4;
+
+// Seed for screening vendor registry PDAs
+pub const VENDOR_SEED: &[u8] = b"vendor";
+
+// Vendor id recorded when no registered vendor issued the attestation
+pub const UNATTRIBUTED_VENDOR_ID: u8 = 0;
+
+// Bytes in a policy's allowed-jurisdiction bitmap, one bit for each of the 256 u8 codes
+pub const JURISDICTION_BITMAP_LEN: usize = 32;
+
+// Jurisdiction coding scheme of new attestations and policies until one is set
+pub const DEFAULT_JURISDICTION_SCHEME: u8 = 0;
+
+// Longest vendor name stored in the registry
+pub const MAX_VENDOR_NAME_LEN: usize = 32;
+
+// Prefix of the challenge a wallet signs to prove ownership at attestation time
+pub const OWNERSHIP_CHALLENGE_PREFIX: &[u8] = b"complifi-ownership:";
+
+// Upper bound on governance signers of a single policy
+pub const MAX_POLICY_SIGNERS: usize = 5;
+
+// Upper bound on members of an attester consortium
+pub const MAX_CONSORTIUM_MEMBERS: usize = 8;
+
+// Counterparties a single attestation can be scoped to
+pub const MAX_ALLOWED_COUNTERPARTIES: usize = 4;
+
+// Upper bound on attesters co-signing a single attestation besides its issuer
+pub const MAX_ATTESTATION_COSIGNERS: usize = 4;
+
+// Seed for an attester's trust profile
+pub const ATTESTER_SEED: &[u8] = b"attester";
+
+// Seed for an attestor allowlist entry, which lets its key issue attestations
+pub const ATTESTOR_SEED: &[u8] = b"attestor";
+
+// Seeds for attester consortiums and their policy change proposals
+pub const CONSORTIUM_SEED: &[u8] = b"consortium";
+pub const CONSORTIUM_PROPOSAL_SEED: &[u8] = b"consortium-proposal";
+
+// Seed for named policies, derived per authority: [POLICY_SEED, authority, name]
+pub const POLICY_SEED: &[u8] = b"policy";
+
+// Longest policy name; it is used whole as a PDA seed
+pub const MAX_POLICY_NAME_LEN: usize = 32;
+
+// Seed for compiled (flattened) policy snapshots
+pub const COMPILED_POLICY_SEED: &[u8] = b"compiled-policy";
+
+// Seed for the policy snapshot users outside a staged rollout's cohort are held to
+pub const ROLLOUT_BASELINE_SEED: &[u8] = b"rollout-baseline";
+
+// Rollout share at which every user is on the current policy
+pub const FULL_ROLLOUT_BPS: u16 = 10_000;
+
+// Seed for committed daily verification roots
+pub const DAILY_ROOT_SEED: &[u8] = b"daily-root";
+
+// Length of a verification day for rolling root accumulation
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+// Underwriting signal: each of its four components scores 0..=UNDERWRITING_COMPONENT_MAX.
+// Attestation age earns full marks at UNDERWRITING_MATURITY_DAYS, and each recorded
+// violation costs UNDERWRITING_VIOLATION_PENALTY.
+pub const UNDERWRITING_COMPONENT_MAX: u16 = 250;
+pub const UNDERWRITING_MATURITY_DAYS: i64 = 365;
+pub const UNDERWRITING_VIOLATION_PENALTY: u16 = 50;
+
+// Seed for recurring-payment mandate PDAs
+pub const MANDATE_SEED: &[u8] = b"mandate";
+
+// Seed of the PDA that endorses verification result digests
+pub const RESULT_SIGNER_SEED: &[u8] = b"result-signer";
+
+// Longest action and violation reason strings the program accepts
+pub const MAX_ACTION_LEN: usize = 64;
+pub const MAX_REASON_LEN: usize = 256;
+
+// Longest action string a user record keeps from its latest verification
+pub const MAX_RECORDED_ACTION_LEN: usize = 32;
+
+// Longest violation reason a user's violation record keeps
+pub const MAX_VIOLATION_REASON_LEN: usize = 64;
+
+// Categories record_violation files violations under, for aggregating them by type
+pub const VIOLATION_CATEGORY_RISK: u8 = 0;
+pub const VIOLATION_CATEGORY_JURISDICTION: u8 = 1;
+pub const VIOLATION_CATEGORY_SANCTION: u8 = 2;
+pub const VIOLATION_CATEGORY_KYC: u8 = 3;
+pub const VIOLATION_CATEGORY_COUNT: u8 = 4;
+
+// Longest reason a sanctions blocklist entry may carry
+pub const MAX_SANCTION_REASON_LEN: usize = 64;
+
+// KYC fields an attestation can commit to for later disclosure
+pub const MAX_DISCLOSURE_FIELDS: usize = 4;
+
+// Upper bound on members checked in a single multisig verification
+pub const MAX_MULTISIG_MEMBERS: usize = 10;
 
 // Seeds for PDAs
+pub const COMPLIANCE_STATE_SEED: &[u8] = b"compliance-state";
 pub const KYC_ATTESTATION_SEED: &[u8] = b"kyc-attestation";
+pub const KYC_REQUEST_SEED: &[u8] = b"kyc-request";
+pub const USER_RECORD_SEED: &[u8] = b"user-record";
+pub const USER_VIOLATIONS_SEED: &[u8] = b"user-violations";
+pub const SANCTION_SEED: &[u8] = b"sanction";
+pub const REPORT_SEED: &[u8] = b"report";
+pub const BRIDGE_MESSAGE_SEED: &[u8] = b"bridge-msg";
+pub const DELEGATION_SEED: &[u8] = b"delegation";
+pub const ENTITY_HIERARCHY_SEED: &[u8] = b"entity-hierarchy";
+
+// Source chain id reserved for attestations issued on this chain
+pub const LOCAL_CHAIN_ID: u16 = 0;
+
+// Highest score on the risk scale
+pub const MAX_RISK_SCORE: u8 = 10;
+
+// Actions a policy can give their own risk threshold
+pub const MAX_ACTION_THRESHOLDS: usize = 8;
+
+// Actions a policy can whitelist
+pub const MAX_ALLOWED_ACTIONS: usize = 8;
+
+// Jurisdictions a policy can give a tighter risk cap
+pub const MAX_JURISDICTION_RISK_CAPS: usize = 8;
+
+// Default allowed drift in seconds between a time oracle timestamp and the cluster clock
+pub const DEFAULT_TIME_ORACLE_TOLERANCE: u64 = 30;
+
+// Default lifetime percentages at which expiry reminders are emitted
+pub const DEFAULT_EXPIRY_REMINDER_THRESHOLDS: [u8; 4] = [50, 80, 95, 0];
+
+// Default attestation validity per jurisdiction risk tier; 0 leaves the attestation open-ended
+pub const DEFAULT_TIER_VALIDITY: [i64; RISK_TIER_COUNT] = [
+    0,
+    365 * SECONDS_PER_DAY,
+    180 * SECONDS_PER_DAY,
+    90 * SECONDS_PER_DAY,
+];
+
+// Longest default validity a risk tier may carry: ten years
+pub const MAX_TIER_VALIDITY: i64 = 3650 * SECONDS_PER_DAY;
 
 #[program]
 pub mod complifi {
     use super::*;
 
-    /// Initialize the compliance verification program
+    /// Initialize the compliance verification program. The state is a PDA of a fixed seed,
+    /// so it can only be created once.
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         let state = &mut ctx.accounts.state;
         state.authority = ctx.accounts.authority.key();
         state.verification_count = 0;
         state.violation_count = 0;
+        state.expiry_reminder_thresholds = DEFAULT_EXPIRY_REMINDER_THRESHOLDS;
+        state.risk_monitor = Pubkey::default(); // No monitor until one is registered
+        state.active_report = Pubkey::default();
+        state.renewal_authority = Pubkey::default(); // Only attesters renew until one is registered
+        state.emergency_count = 0;
+        state.time_oracle = Pubkey::default();
+        state.time_oracle_tolerance = DEFAULT_TIME_ORACLE_TOLERANCE;
+        state.bridge_relayer = Pubkey::default(); // Bridging disabled until a relayer is registered
+        state.auditor = Pubkey::default(); // No field reveals until an auditor is registered
+        state.max_verifications_per_slot = 0; // Unthrottled until a cap is set
+        state.verifications_this_slot = 0;
+        state.throughput_slot = 0;
+        state.daily_verification_root = [0; 32];
+        state.verification_day = 0;
+        state.day_verification_count = 0;
+        state.marginal_pass_count = 0;
+        state.policy_count = 0;
+        state.paused = false;
+        state.rollup_enabled = false; // No rollup until init_rollup
+        state.pending_authority = Pubkey::default();
+        state.treasury_policy = Pubkey::default(); // Treasury operations rejected until one is designated
+        state.journal_enabled = false; // No journal until init_attestation_journal
+        state.cpi_events = false; // Events go to program logs only
+        state.event_seq = 0;
+        state.sanction_merkle_root = [0; 32]; // No off-chain sanctions list
+        state.issuance_policy = Pubkey::default(); // Issuance minimums only apply from a passed policy
+        Ok(())
+    }
+
+    /// Admin function to create the state's dashboard rollup. From then on every instruction
+    /// that changes a rolled-up count must pass it. Counts start from zero, so create the
+    /// rollup before issuing attestations for them to be exact.
+    pub fn init_rollup(ctx: Context<InitRollup>) -> Result<()> {
+        let rollup = &mut ctx.accounts.rollup;
+        rollup.state = ctx.accounts.state.key();
+        rollup.verified_users = 0;
+        rollup.frozen_users = 0;
+        rollup.violations = 0;
+        rollup.level_counts = [0; KYC_LEVEL_COUNT];
+        rollup.created_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.state.rollup_enabled = true;
+        
+        msg!("Rollup created for state: {}", rollup.state);
+        Ok(())
+    }
+
+    /// Return the state's dashboard rollup
+    pub fn get_rollup(ctx: Context<GetRollup>) -> Result<GlobalRollup> {
+        Ok((*ctx.accounts.rollup).clone())
+    }
+
+    /// Admin function to start the state's attestation journal. From then on every attestation
+    /// create, reissue, revoke and close must pass it, and is appended to its hash chain, so
+    /// the whole issuance history can be replayed from the emitted entries and checked
+    /// against the head.
+    pub fn init_attestation_journal(ctx: Context<InitAttestationJournal>) -> Result<()> {
+        let journal = &mut ctx.accounts.journal;
+        journal.state = ctx.accounts.state.key();
+        journal.head = [0; 32];
+        journal.sequence = 0;
+        journal.created_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.state.journal_enabled = true;
+        
+        msg!("Attestation journal created for state: {}", journal.state);
         Ok(())
     }
 
-    /// Initialize a new compliance policy
-    pub fn initialize_policy(ctx: Context<InitializePolicy>) -> Result<()> {
+    /// Return the head hash and sequence number of the state's attestation journal
+    pub fn get_journal_head(ctx: Context<GetJournalHead>) -> Result<JournalHead> {
+        let journal = &ctx.accounts.journal;
+        Ok(JournalHead {
+            head: journal.head,
+            sequence: journal.sequence,
+        })
+    }
+
+    /// Initialize a new compliance policy at the PDA of `policy_name` under the authority, so one
+    /// authority can keep several (e.g. "retail" and "institutional").
+    /// `policy_signers` and `threshold` establish multisig governance of the policy from creation;
+    /// pass an empty list and 0 to govern it by the authority alone. `allowed_jurisdictions` and
+    /// `require_kyc` bootstrap a working policy in the same transaction; omitted, the policy
+    /// requires KYC and allows no jurisdiction until `set_policy` says otherwise.
+    pub fn initialize_policy(
+        ctx: Context<InitializePolicy>,
+        policy_name: String,
+        policy_signers: Vec<Pubkey>,
+        threshold: u8,
+        allowed_jurisdictions: Option<[u8; JURISDICTION_BITMAP_LEN]>,
+        require_kyc: Option<bool>,
+    ) -> Result<()> {
+        require!(
+            !policy_name.is_empty() && policy_name.len() <= MAX_POLICY_NAME_LEN,
+            CompliFiError::InvalidPolicyParameters
+        );
+        if !policy_signers.is_empty() || threshold > 0 {
+            require!(
+                threshold >= 1 && threshold as usize <= policy_signers.len(),
+                CompliFiError::InvalidPolicyParameters
+            );
+            require!(
+                policy_signers.len() <= MAX_POLICY_SIGNERS,
+                CompliFiError::InvalidPolicyParameters
+            );
+        }
+        
         let policy = &mut ctx.accounts.policy;
+        policy.policy_signers = [Pubkey::default(); MAX_POLICY_SIGNERS];
+        for (i, signer) in policy_signers.iter().enumerate() {
+            require!(
+                !policy_signers[..i].contains(signer),
+                CompliFiError::InvalidPolicyParameters
+            );
+            policy.policy_signers[i] = *signer;
+        }
+        policy.policy_signer_count = policy_signers.len() as u8;
+        policy.policy_threshold = threshold;
         policy.authority = ctx.accounts.authority.key();
         policy.max_risk_score = 3; // Default: Medium risk tolerance
-        policy.require_kyc = true; // Default: Require KYC
-        policy.allowed_jurisdictions = [0; 10]; // Default: No jurisdictions allowed
+        policy.action_thresholds = [ActionThreshold::default(); MAX_ACTION_THRESHOLDS]; // Default: Every action uses max_risk_score
+        policy.action_threshold_count = 0;
+        policy.allowed_actions = [[0; 8]; MAX_ALLOWED_ACTIONS]; // Default: Every action is permitted
+        policy.allowed_action_count = 0;
+        policy.require_kyc = require_kyc.unwrap_or(true); // Default: Require KYC
+        policy.min_kyc_level = 0; // Default: Any verified tier
+        policy.effective_from = 0; // Default: Active from creation, with no expiry
+        policy.effective_until = 0;
+        policy.min_verify_interval = 0; // Default: No reverification cooldown
+        policy.max_oracle_staleness = 0; // Default: Oracle entries of any age are accepted
+        policy.min_attestation_age = 0; // Default: Attestations count from the moment they are issued
+        policy.allowed_jurisdictions = allowed_jurisdictions.unwrap_or([0; JURISDICTION_BITMAP_LEN]); // Default: No jurisdictions allowed
+        policy.min_oracle_quorum = 0; // Default: Single oracle lookup
+        policy.oracle_kind = ORACLE_KIND_RANGE; // Default: Range Oracle
+        policy.require_trusted_attestor = false; // Default: Attestations outlive their issuer's allowlisting
+        policy.behavioral_risk_mode = BEHAVIORAL_RISK_MAX; // Default: Higher of oracle and behavioral risk
+        policy.behavioral_weight_bps = 0;
+        policy.multisig_min_compliant = 0; // Default: Every multisig member must be compliant
+        policy.denomination_mint = Pubkey::default(); // Default: Native SOL
+        policy.decimals = NATIVE_DECIMALS;
+        policy.required_vendor = UNATTRIBUTED_VENDOR_ID; // Default: Any screening vendor
+        policy.sign_results = false; // Default: No result digest
+        policy.require_ownership_proof = false; // Default: Attester's word is enough
+        policy.require_dual_attestation = false; // Default: One attester suffices
+        policy.risk_scale_max = MAX_RISK_SCORE; // Default: Thresholds on the oracle's 0-10 scale
+        policy.early_renewal_fee = 0; // Default: Free renewals
+        policy.late_renewal_fee = 0;
+        policy.fee_recipient = ctx.accounts.authority.key();
+        policy.risk_conflict_resolution = RISK_CONFLICT_ORACLE_WINS; // Default: Oracle score decides
+        policy.risk_tolerance = 0; // Default: Hard cutoff at max_risk_score
+        policy.warn_risk_score = 0; // Default: No warning band
+        policy.jurisdiction_risk_caps = [JurisdictionRiskCap::default(); MAX_JURISDICTION_RISK_CAPS]; // Default: No jurisdiction is capped
+        policy.jurisdiction_risk_cap_count = 0;
+        policy.expiry_grace_period = 0; // Default: Attestations fail from the moment they expire
+        policy.verification_count = 0;
+        policy.violation_count = 0;
+        policy.tier_validity = DEFAULT_TIER_VALIDITY;
+        policy.require_recheck_after_jurisdiction_change = false;
+        policy.max_amount = 0; // Default: No transaction amount cap
+        policy.trading_window_start = 0; // Default: Open all day
+        policy.trading_window_end = SECONDS_PER_DAY as u32;
+        policy.logic = [0; MAX_POLICY_LOGIC_LEN];
+        policy.logic_len = 0; // Default: No custom logic
+        policy.identity_max_age = 0; // Default: Attestation facts never go stale on their own
+        policy.sanctions_max_age = 0;
+        policy.accreditation_max_age = 0;
+        policy.receipt_ttl = 0; // Default: No verification caching
+        policy.cache_ttl = 0; // Default: Every verification reads the oracle
+        policy.compliance_receipt_ttl = 0; // Default: No compliance receipt required
+        policy.defer_risk_check = false; // Default: Oracle check runs inline
+        policy.verification_fee = 0; // Default: Free verification
+        policy.risk_fee_multiplier_bps = 0;
+        policy.anonymous_lifetime_cap = 0; // Default: No anonymous usage
+        policy.consortium = Pubkey::default(); // Default: Governed by its authority
+        policy.rollout_bps = FULL_ROLLOUT_BPS; // Default: Every user on the current policy
+        policy.min_risk_model_version = 0; // Default: Any risk model version
+        policy.jurisdiction_scheme = DEFAULT_JURISDICTION_SCHEME;
+        policy.min_documents_for_level = [0; DOCUMENT_REQUIREMENT_LEVELS]; // Default: No document minimum
+        policy.emit_json_log = false; // Default: Outcomes are only emitted as events
+        policy.min_trust_weight = 0; // Default: Any single attester is trusted
+        policy.fee_ledger_enabled = false; // No fee ledger until init_fee_ledger
+        policy.freeze_threshold = 0; // Default: Risk never freezes a wallet by itself
+        policy.unfreeze_threshold = 0;
+        policy.step_up_threshold = 0; // Default: Partial KYC covers any volume
+        policy.step_up_level = KYC_LEVEL_NONE;
+        policy.jurisdiction_recheck_days = 0;
+        policy.revision = 0;
+        policy.name = policy_name;
+        
+        // Registering the policy under a state counts it towards the state's active policies
+        if let Some(state) = &mut ctx.accounts.state {
+            state.policy_count = state.policy_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        
+        msg!("Compliance policy '{}' initialized with default settings", ctx.accounts.policy.name);
+        Ok(())
+    }
+
+    /// Decommission a policy, returning its rent to the authority. A policy that needs
+    /// co-signers for changes needs them to be closed too.
+    pub fn close_policy(ctx: Context<ClosePolicy>) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        if let Some(state) = &mut ctx.accounts.state {
+            // Policies created before the count was kept were never counted
+            state.policy_count = state.policy_count.saturating_sub(1);
+        }
         
-        msg!("Compliance policy initialized with default settings");
+        emit!(PolicyClosedEvent {
+            policy: ctx.accounts.policy.key(),
+            authority: ctx.accounts.authority.key(),
+            name: ctx.accounts.policy.name.clone(),
+        });
+        
+        msg!("Compliance policy '{}' closed", ctx.accounts.policy.name);
         Ok(())
     }
     
+    /// Ask to be KYC-verified (signed by the wallet itself). Attesters pick pending requests up
+    /// off-chain, and the create_kyc_attestation that answers one marks it fulfilled. A
+    /// fulfilled request can be renewed; a pending one cannot.
+    pub fn request_kyc(ctx: Context<RequestKyc>) -> Result<()> {
+        let request = &mut ctx.accounts.kyc_request;
+        require!(
+            request.requested_at == 0 || request.status == KYC_REQUEST_FULFILLED,
+            CompliFiError::KycRequestPending
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        request.user = ctx.accounts.user.key();
+        request.requested_at = now;
+        request.status = KYC_REQUEST_PENDING;
+        request.fulfilled_by = Pubkey::default();
+        request.fulfilled_at = 0;
+        
+        emit!(KycRequestedEvent {
+            user: request.user,
+            requested_at: now,
+        });
+        
+        msg!("KYC requested by wallet: {}", request.user);
+        Ok(())
+    }
+
     /// Create or update a KYC attestation for a wallet
     pub fn create_kyc_attestation(
         ctx: Context<CreateKycAttestation>,
         wallet: Pubkey,
         is_verified: bool,
         jurisdiction: u8,
+        expires_at: i64,
+        oracle_timestamp: Option<i64>,
+        revocation_authority: Option<Pubkey>,
+        documents_reviewed: u8,
+        level: u8,
     ) -> Result<()> {
-        let attestation = &mut ctx.accounts.attestation;
         let clock = Clock::get()?;
         
+        // A trusted time oracle co-signs the issuing timestamp; it must stay close to the cluster clock
+        let issued_at = match (&ctx.accounts.time_oracle, oracle_timestamp) {
+            (Some(_), Some(timestamp)) => {
+                let drift = timestamp.abs_diff(clock.unix_timestamp);
+                require!(
+                    drift <= ctx.accounts.state.time_oracle_tolerance,
+                    CompliFiError::TimestampOutOfTolerance
+                );
+                timestamp
+            }
+            (None, None) => clock.unix_timestamp,
+            _ => return err!(CompliFiError::InvalidOracleTimestamp),
+        };
+        
+        require_jurisdiction_delegated(&ctx.accounts.attestor_entry, jurisdiction)?;
+        require!((level as usize) < DOCUMENT_REQUIREMENT_LEVELS, CompliFiError::InvalidKycLevel);
+        
+        // Optional proof that the wallet controls its key: it signed the ownership challenge
+        // in an Ed25519 instruction immediately before this one
+        let ownership_proven = match &ctx.accounts.instructions {
+            Some(instructions) => {
+                let challenge = ownership_challenge(&wallet, &ctx.accounts.authority.key());
+                verify_ed25519_signature(instructions, &wallet, &challenge)?;
+                true
+            }
+            None => false,
+        };
+        
+        // Without an explicit expiry, the jurisdiction's risk tier picks the policy's default validity
+        let expires_at = match (expires_at, &ctx.accounts.policy, &ctx.accounts.jurisdiction_meta) {
+            (0, Some(policy), Some(meta)) => match policy.tier_validity[meta.risk_tier as usize] {
+                0 => 0,
+                validity => issued_at.checked_add(validity).ok_or(CompliFiError::CounterOverflow)?,
+            },
+            _ => expires_at,
+        };
+        
+        let attestation = &mut ctx.accounts.attestation;
+        
+        // Moving an existing attestation to a new jurisdiction starts the recheck clock;
+        // re-attesting in the same jurisdiction is the full re-verification that clears it
+        let existing = attestation.wallet != Pubkey::default();
+        // Only the attester that issued an attestation may re-issue or downgrade it
+        require!(
+            !existing || attestation.authority == ctx.accounts.authority.key(),
+            CompliFiError::Unauthorized
+        );
+        let moved = existing && attestation.jurisdiction != jurisdiction;
+        let previous_level = existing.then(|| kyc_level(attestation));
+        attestation.jurisdiction_changed_at = if moved { issued_at } else { 0 };
+        
+        // Re-attestation links the new record to the one it replaces
+        attestation.prev_attestation_hash = if existing { attestation_hash(attestation)? } else { [0; 32] };
+        
         attestation.wallet = wallet;
-        attestation.is_verified = is_verified;
+        attestation.set_verified(is_verified);
         attestation.authority = ctx.accounts.authority.key();
-        attestation.timestamp = clock.unix_timestamp;
+        attestation.timestamp = issued_at;
         attestation.jurisdiction = jurisdiction;
+        attestation.expires_at = expires_at;
+        attestation.reminders_fired = 0; // A fresh attestation starts a new reminder cycle
+        attestation.validity_period = if expires_at > 0 { expires_at - issued_at } else { 0 };
+        attestation.renew_count = 0;
+        attestation.source_chain = LOCAL_CHAIN_ID;
+        attestation.bump = ctx.bumps.attestation;
+        attestation.screening_vendor_id = match &ctx.accounts.vendor {
+            Some(vendor) => vendor.id,
+            None => UNATTRIBUTED_VENDOR_ID,
+        };
+        attestation.jurisdiction_scheme = DEFAULT_JURISDICTION_SCHEME; // Re-coded jurisdictions need the scheme set again
+        attestation.field_commitments = [[0; 32]; MAX_DISCLOSURE_FIELDS]; // New KYC data needs new commitments
+        attestation.set_ownership_proven(ownership_proven);
+        attestation.revocation_authority = revocation_authority.unwrap_or(attestation.authority);
+        attestation.imported_from = Pubkey::default();
+        // Issuing KYC verifies identity and screens sanctions; accreditation is refreshed separately
+        attestation.identity_verified_at = issued_at;
+        attestation.sanctions_checked_at = issued_at;
+        attestation.documents_reviewed = documents_reviewed;
+        attestation.cosigners = [Pubkey::default(); MAX_ATTESTATION_COSIGNERS]; // Co-signers endorsed the old record
+        attestation.cosigner_count = 0;
+        attestation.allowed_counterparties = [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES];
+        attestation.level = level;
         
-        emit!(KycAttestationEvent {
+        // The policy may require more reviewed documents the higher the attestation's level
+        require_documents_reviewed(&ctx.accounts.state, &ctx.accounts.policy, attestation.level, documents_reviewed)?;
+        let level = kyc_level(attestation);
+        if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+            rollup.record_level_change(previous_level, Some(level))?;
+        }
+        let operation = if existing { JOURNAL_OP_UPDATE } else { JOURNAL_OP_CREATE };
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, operation, attestation)?;
+        
+        // The attestation answers the wallet's pending self-service request, if it made one
+        if let Some(request) = &mut ctx.accounts.kyc_request {
+            if request.status == KYC_REQUEST_PENDING {
+                request.status = KYC_REQUEST_FULFILLED;
+                request.fulfilled_by = ctx.accounts.authority.key();
+                request.fulfilled_at = clock.unix_timestamp;
+                emit!(KycRequestFulfilledEvent {
+                    user: wallet,
+                    attester: request.fulfilled_by,
+                });
+            }
+        }
+        
+        let event = KycAttestationEvent {
             wallet,
             is_verified,
             jurisdiction,
-        });
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        };
+        if ctx.accounts.state.cpi_events {
+            emit_cpi!(event);
+        }
+        emit!(event);
         
         msg!("KYC attestation created for wallet: {}", wallet);
         Ok(())
     }
 
-    /// Verify compliance for a user action
-    pub fn verify_compliance(
-        ctx: Context<VerifyCompliance>,
-        user: Pubkey,
-        action: String,
+    /// Create attestations for up to MAX_ATTESTATION_BATCH new wallets in one call (allowlisted
+    /// attestors only). Remaining accounts are the uninitialized attestation PDAs, one per entry
+    /// and in order; each is created with the defaults an expiry-less create_kyc_attestation
+    /// would give it. Wallets that already have an attestation go through create_kyc_attestation.
+    pub fn create_kyc_attestations_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateKycAttestationsBatch<'info>>,
+        entries: Vec<KycEntry>,
     ) -> Result<()> {
-        let policy = &ctx.accounts.policy;
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_ATTESTATION_BATCH,
+            CompliFiError::BatchTooLarge
+        );
+        require!(
+            ctx.remaining_accounts.len() == entries.len(),
+            CompliFiError::AccountCountMismatch
+        );
         
-        // 1. Check KYC attestation using our PDA-based registry
-        if policy.require_kyc {
-            // Find the KYC attestation PDA for this user
-            let (_attestation_pda, _) = Pubkey::find_program_address(
-                &[KYC_ATTESTATION_SEED, user.as_ref()],
-                &id()
-            );
-            
-            // Check if the attestation exists and is verified
-            let attestation_account: &KycAttestation = &ctx.accounts.attestation;
+        let now = Clock::get()?.unix_timestamp;
+        let authority = ctx.accounts.authority.key();
+        let space = 8 + KycAttestation::LEN;
+        let lamports = Rent::get()?.minimum_balance(space);
+        for (entry, account) in entries.iter().zip(ctx.remaining_accounts.iter()) {
+            require_jurisdiction_delegated(&ctx.accounts.attestor_entry, entry.jurisdiction)?;
+            require_documents_reviewed(&ctx.accounts.state, &ctx.accounts.policy, 0, 0)?;
+            let (address, bump) = kyc_attestation_pda(&authority, &entry.wallet);
+            require_keys_eq!(*account.key, address, CompliFiError::AttestationAddressMismatch);
+            require!(account.data_is_empty(), CompliFiError::AttestationAlreadyExists);
             
-            // Verify the attestation is for the correct user
-            require!(attestation_account.wallet == user, CompliFiError::KycNotVerified);
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: account.clone(),
+                    },
+                    &[&[KYC_ATTESTATION_SEED, authority.as_ref(), entry.wallet.as_ref(), &[bump]]],
+                ),
+                lamports,
+                space as u64,
+                &crate::ID,
+            )?;
             
-            // Verify the attestation is valid
-            require!(attestation_account.is_verified, CompliFiError::KycNotVerified);
+            let mut attestation = KycAttestation {
+                wallet: entry.wallet,
+                flags: 0,
+                authority,
+                timestamp: now,
+                jurisdiction: entry.jurisdiction,
+                expires_at: 0,
+                reminders_fired: 0,
+                renew_count: 0,
+                validity_period: 0,
+                source_chain: LOCAL_CHAIN_ID,
+                screening_vendor_id: UNATTRIBUTED_VENDOR_ID,
+                field_commitments: [[0; 32]; MAX_DISCLOSURE_FIELDS],
+                revocation_authority: authority,
+                jurisdiction_changed_at: 0,
+                prev_attestation_hash: [0; 32],
+                imported_from: Pubkey::default(),
+                identity_verified_at: now,
+                sanctions_checked_at: now,
+                accreditation_verified_at: 0,
+                jurisdiction_scheme: DEFAULT_JURISDICTION_SCHEME,
+                documents_reviewed: 0,
+                cosigners: [Pubkey::default(); MAX_ATTESTATION_COSIGNERS],
+                cosigner_count: 0,
+                allowed_counterparties: [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES],
+                level: 0,
+                bump,
+            };
+            attestation.set_verified(entry.is_verified);
+            attestation.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
             
-            // Check jurisdiction is allowed
-            let jurisdiction_idx = (attestation_account.jurisdiction / 8) as usize;
-            let jurisdiction_bit = 1 << (attestation_account.jurisdiction % 8);
-            
-            if jurisdiction_idx < policy.allowed_jurisdictions.len() {
-                require!(
-                    (policy.allowed_jurisdictions[jurisdiction_idx] & jurisdiction_bit) != 0,
-                    CompliFiError::RestrictedJurisdiction
-                );
-            } else {
-                return err!(CompliFiError::RestrictedJurisdiction);
+            if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+                rollup.record_level_change(None, Some(kyc_level(&attestation)))?;
             }
+            journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, JOURNAL_OP_CREATE, &attestation)?;
+            
+            emit!(KycAttestationEvent {
+                wallet: entry.wallet,
+                is_verified: entry.is_verified,
+                jurisdiction: entry.jurisdiction,
+                seq: next_event_seq(&mut ctx.accounts.state)?,
+            });
+        }
+        
+        msg!("Created {} KYC attestations", entries.len());
+        Ok(())
+    }
+
+    /// Revoke a wallet's attestation; signed by its authority or its designated revocation authority
+    pub fn revoke_kyc_attestation(ctx: Context<RevokeKycAttestation>, wallet: Pubkey) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+        let previous_level = kyc_level(attestation);
+        attestation.set_verified(false);
+        attestation.timestamp = Clock::get()?.unix_timestamp;
+        
+        if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+            rollup.record_level_change(Some(previous_level), Some(kyc_level(attestation)))?;
+        }
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, JOURNAL_OP_REVOKE, attestation)?;
+        
+        emit!(KycAttestationEvent {
+            wallet,
+            is_verified: false,
+            jurisdiction: attestation.jurisdiction,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        });
+        
+        msg!("KYC attestation revoked for wallet {} by {}", wallet, ctx.accounts.authority.key());
+        Ok(())
+    }
+
+    /// Emergency override: revoke any wallet's attestation as the state authority, whichever
+    /// attester issued it
+    pub fn force_revoke_attestation(ctx: Context<ForceRevokeAttestation>, wallet: Pubkey) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+        let previous_level = kyc_level(attestation);
+        attestation.set_verified(false);
+        attestation.timestamp = Clock::get()?.unix_timestamp;
+        
+        if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+            rollup.record_level_change(Some(previous_level), Some(kyc_level(attestation)))?;
         }
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, JOURNAL_OP_REVOKE, attestation)?;
+        
+        emit!(KycAttestationEvent {
+            wallet,
+            is_verified: false,
+            jurisdiction: attestation.jurisdiction,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        });
+        emit!(AttestationForceRevokedEvent {
+            wallet,
+            revoked_by: ctx.accounts.authority.key(),
+            attester: attestation.authority,
+        });
+        
+        msg!("KYC attestation of wallet {} force-revoked by the state authority", wallet);
+        Ok(())
+    }
+
+    /// Close an attestation that is no longer needed, returning its rent to the attester that
+    /// issued it (attester only)
+    pub fn close_kyc_attestation(ctx: Context<CloseKycAttestation>, wallet: Pubkey) -> Result<()> {
+        record_attestation_removed(&ctx.accounts.state, &mut ctx.accounts.rollup, &ctx.accounts.attestation)?;
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, JOURNAL_OP_CLOSE, &ctx.accounts.attestation)?;
+        
+        emit!(KycAttestationClosedEvent {
+            wallet,
+            authority: ctx.accounts.authority.key(),
+        });
         
-        // 2. Get wallet risk score from Range Security Oracle
-        let risk_score = get_wallet_risk_score(&ctx, &user)?;
+        msg!("KYC attestation closed for wallet {}", wallet);
+        Ok(())
+    }
+
+    /// Close an attestation once it is older than its jurisdiction's retention period
+    /// (permissionless crank). The rent goes back to the attester that paid for it.
+    pub fn purge_attestation(ctx: Context<PurgeAttestation>, wallet: Pubkey) -> Result<()> {
+        let attestation = &ctx.accounts.attestation;
+        let retention = ctx.accounts.jurisdiction_meta.retention_seconds;
+        let now = Clock::get()?.unix_timestamp;
         require!(
-            risk_score <= policy.max_risk_score, 
-            CompliFiError::RiskScoreTooHigh
+            retention > 0 && now - attestation.timestamp > retention,
+            CompliFiError::RetentionNotElapsed
         );
         
-        // 3. Increment verification count
-        let state = &mut ctx.accounts.state;
-        state.verification_count = state.verification_count.checked_add(1).unwrap();
+        record_attestation_removed(&ctx.accounts.state, &mut ctx.accounts.rollup, attestation)?;
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, JOURNAL_OP_CLOSE, attestation)?;
         
-        // 4. Emit verification event
-        emit!(VerificationEvent {
-            user,
-            action,
-            verified: true,
-            risk_score,
+        emit!(DataPurgedEvent {
+            wallet,
+            jurisdiction: attestation.jurisdiction,
+            attested_at: attestation.timestamp,
+            purged_at: now,
         });
         
-        msg!("Compliance verification passed for user: {}", user);
+        msg!("Attestation for wallet {} purged after {}s retention", wallet, retention);
         Ok(())
     }
 
-    /// Admin function to set compliance policy
-    pub fn set_policy(
-        ctx: Context<SetPolicy>,
-        max_risk_score: u8,
-        require_kyc: bool,
-        allowed_jurisdictions: [u8; 10],
-    ) -> Result<()> {
-        let policy = &mut ctx.accounts.policy;
+    /// Admin function to freeze a wallet, blocking every verification until it is unfrozen
+    pub fn freeze_wallet(ctx: Context<FreezeWallet>, wallet: Pubkey) -> Result<()> {
+        set_wallet_frozen(ctx, wallet, true)
+    }
+
+    /// Admin function to lift a wallet's freeze
+    pub fn unfreeze_wallet(ctx: Context<FreezeWallet>, wallet: Pubkey) -> Result<()> {
+        set_wallet_frozen(ctx, wallet, false)
+    }
+
+    /// Admin function to put a wallet on the sanctions blocklist. A listed wallet fails every
+    /// verification that passes its sanction entry, whatever its KYC, jurisdiction or risk.
+    pub fn add_sanction(ctx: Context<AddSanction>, wallet: Pubkey, reason: String) -> Result<()> {
+        require!(reason.len() <= MAX_SANCTION_REASON_LEN, CompliFiError::InvalidPolicyParameters);
         
-        // Validate policy parameters
-        require!(max_risk_score <= 10, CompliFiError::InvalidPolicyParameters);
+        let sanction = &mut ctx.accounts.sanction;
+        sanction.wallet = wallet;
+        sanction.reason = reason;
+        sanction.added_by = ctx.accounts.authority.key();
+        sanction.added_at = Clock::get()?.unix_timestamp;
         
-        policy.max_risk_score = max_risk_score;
-        policy.require_kyc = require_kyc;
-        policy.allowed_jurisdictions = allowed_jurisdictions;
+        emit!(SanctionAddedEvent {
+            wallet,
+            reason: sanction.reason.clone(),
+            authority: sanction.added_by,
+        });
         
-        msg!("Policy updated: max_risk_score={}, require_kyc={}", 
-            max_risk_score, require_kyc);
+        msg!("Wallet {} sanctioned: {}", wallet, sanction.reason);
+        Ok(())
+    }
+
+    /// Admin function to take a wallet off the sanctions blocklist, closing its entry
+    pub fn remove_sanction(ctx: Context<RemoveSanction>, wallet: Pubkey) -> Result<()> {
+        emit!(SanctionRemovedEvent {
+            wallet,
+            authority: ctx.accounts.authority.key(),
+        });
         
+        msg!("Sanction removed for wallet {}", wallet);
         Ok(())
     }
 
-    /// Record a compliance violation
-    pub fn record_violation(
-        ctx: Context<RecordViolation>,
-        user: Pubkey,
-        reason: String,
+    /// Admin function to commit the root of an off-chain sanctions list, a sorted-pair keccak
+    /// merkle tree whose leaves are keccak256(wallet). The zero root withdraws the list.
+    pub fn set_sanction_root(ctx: Context<UpdateState>, root: [u8; 32]) -> Result<()> {
+        ctx.accounts.state.sanction_merkle_root = root;
+        
+        msg!("Sanction merkle root updated");
+        Ok(())
+    }
+
+    /// Check `wallet` against the committed sanctions list. Fails if `proof` proves the
+    /// wallet's leaf is in the tree; any other proof passes, as does every wallet while no
+    /// root is committed.
+    pub fn check_sanction_proof(ctx: Context<CheckSanctionProof>, wallet: Pubkey, proof: Vec<[u8; 32]>) -> Result<()> {
+        let root = ctx.accounts.state.sanction_merkle_root;
+        if root != [0; 32] {
+            let leaf = keccak256v(&[wallet.as_ref()]);
+            require!(merkle_root_from_proof(leaf, &proof) != root, CompliFiError::WalletSanctioned);
+        }
+        
+        msg!("Wallet {} is not proven to be on the sanctions list", wallet);
+        Ok(())
+    }
+
+    /// Freeze or unfreeze a wallet from its current risk score, with hysteresis (permissionless
+    /// crank). A score above the policy's freeze threshold freezes the wallet; only a score
+    /// below the lower unfreeze threshold lifts that freeze again. Freezes placed by an
+    /// operator are left alone.
+    pub fn refresh_risk_freeze(ctx: Context<RefreshRiskFreeze>, wallet: Pubkey) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require!(policy.freeze_threshold > 0, CompliFiError::InvalidPolicyParameters);
+        
+        let (risk_score, _) = assess_risk(
+            policy,
+            ctx.remaining_accounts,
+            single_risk_oracle(
+                policy,
+                &ctx.accounts.range_oracle,
+                &ctx.accounts.risk_feed,
+                &ctx.accounts.switchboard_aggregator,
+                &wallet,
+            )?,
+            &wallet,
+            Some(&ctx.accounts.attestation),
+            &ctx.accounts.user_record,
+        )?;
+        
+        let attestation = &ctx.accounts.attestation;
+        let frozen = if !attestation.frozen() {
+            risk_score > policy.freeze_threshold
+        } else if attestation.risk_frozen() {
+            risk_score >= policy.unfreeze_threshold
+        } else {
+            true
+        };
+        
+        if frozen != attestation.frozen() {
+            let accounts = &mut *ctx.accounts;
+            apply_wallet_frozen(&accounts.state, &mut accounts.rollup, &mut accounts.attestation, frozen)?;
+            accounts.attestation.set_risk_frozen(frozen);
+            emit!(RiskFreezeChangedEvent {
+                wallet,
+                frozen,
+                risk_score,
+            });
+        }
+        
+        msg!("Wallet {} risk {} frozen: {}", wallet, risk_score, frozen);
+        Ok(())
+    }
+
+    /// Admin function to place a regulatory hold on a wallet pending investigation. Unlike a
+    /// freeze it lapses by itself at `expires_at`. Placing a hold again replaces its expiry.
+    pub fn place_hold(ctx: Context<SetRegulatoryHold>, user: Pubkey, expires_at: i64) -> Result<()> {
+        require!(expires_at > Clock::get()?.unix_timestamp, CompliFiError::InvalidPolicyParameters);
+        
+        let record = &mut ctx.accounts.user_record;
+        record.user = user;
+        record.regulatory_hold = true;
+        record.hold_expires_at = expires_at;
+        // Flagging the attestation makes verify_compliance insist on the record
+        ctx.accounts.attestation.set_held(true);
+        
+        msg!("Regulatory hold placed on {} until {}", user, expires_at);
+        Ok(())
+    }
+
+    /// Admin function to lift a wallet's regulatory hold before it expires
+    pub fn lift_hold(ctx: Context<SetRegulatoryHold>, user: Pubkey) -> Result<()> {
+        let record = &mut ctx.accounts.user_record;
+        record.user = user;
+        record.regulatory_hold = false;
+        record.hold_expires_at = 0;
+        ctx.accounts.attestation.set_held(false);
+        
+        msg!("Regulatory hold lifted from {}", user);
+        Ok(())
+    }
+
+    /// Check a wallet's attestation history: `prior_records` are the records it replaced,
+    /// oldest first, each as it stood when it was replaced. The oldest must be a first
+    /// issuance, and every record must carry the hash of the one before it.
+    pub fn verify_attestation_chain(
+        ctx: Context<VerifyAttestationChain>,
+        wallet: Pubkey,
+        prior_records: Vec<KycAttestation>,
     ) -> Result<()> {
-        let state = &mut ctx.accounts.state;
-        state.violation_count = state.violation_count.checked_add(1).unwrap();
+        let mut expected_prev = [0; 32];
+        for record in &prior_records {
+            require_keys_eq!(record.wallet, wallet, CompliFiError::AttestationChainBroken);
+            require!(record.prev_attestation_hash == expected_prev, CompliFiError::AttestationChainBroken);
+            expected_prev = attestation_hash(record)?;
+        }
+        require!(
+            ctx.accounts.attestation.prev_attestation_hash == expected_prev,
+            CompliFiError::AttestationChainBroken
+        );
         
-        emit!(ViolationEvent {
-            user,
-            reason,
-        });
+        msg!("Attestation chain verified for wallet {}: {} prior records", wallet, prior_records.len());
+        Ok(())
+    }
+
+    /// Repack an attestation stored in the legacy unpacked-boolean layout (permissionless)
+    pub fn migrate_attestation(
+        ctx: Context<MigrateAttestation>,
+        wallet: Pubkey,
+        authority: Pubkey,
+    ) -> Result<()> {
+        let info = ctx.accounts.attestation.to_account_info();
+        let migrated: KycAttestation = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() == 8 + LegacyKycAttestation::LEN
+                    && &data[..8] == KycAttestation::DISCRIMINATOR,
+                CompliFiError::AttestationAlreadyMigrated
+            );
+            LegacyKycAttestation::deserialize(&mut &data[8..])?.into()
+        };
+        let migrated = KycAttestation { bump: ctx.bumps.attestation, ..migrated };
         
-        msg!("Compliance violation recorded for user: {}", user);
+        info.resize(8 + KycAttestation::LEN)?;
+        migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Attestation by {} for wallet {} migrated to packed flags", authority, wallet);
         Ok(())
     }
-}
+
+    /// Admin function to grow the state account to the current ComplianceState::LEN after a
+    /// program upgrade added fields. The new fields read as zero; the authority pays the rent.
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        migrate_account::<ComplianceState>(
+            &accounts.state,
+            &accounts.authority,
+            &accounts.system_program,
+            ComplianceState::LEN,
+            0,
+        )?;
+        
+        msg!("State migrated to {} bytes", 8 + ComplianceState::LEN);
+        Ok(())
+    }
+
+    /// Grow a policy account to the current CompliancePolicy::LEN (policy authority only)
+    pub fn migrate_policy(ctx: Context<MigratePolicy>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        migrate_account::<CompliancePolicy>(
+            &accounts.policy,
+            &accounts.authority,
+            &accounts.system_program,
+            CompliancePolicy::LEN,
+            0,
+        )?;
+        
+        msg!("Policy {} migrated to {} bytes", ctx.accounts.policy.key(), 8 + CompliancePolicy::LEN);
+        Ok(())
+    }
+
+    /// Grow an attestation to the current KycAttestation::LEN (issuing attester only).
+    /// Attestations still in the legacy unpacked layout go through migrate_attestation.
+    pub fn migrate_attestation_size(ctx: Context<MigrateAttestationSize>, wallet: Pubkey) -> Result<()> {
+        // The issuing authority follows the wallet and the packed flags
+        let accounts = &ctx.accounts;
+        migrate_account::<KycAttestation>(
+            &accounts.attestation,
+            &accounts.authority,
+            &accounts.system_program,
+            KycAttestation::LEN,
+            32 + 2,
+        )?;
+        
+        // An attestation grown past the bump field reads it as zero until it is set here
+        let info = accounts.attestation.to_account_info();
+        let mut attestation = KycAttestation::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        attestation.bump = ctx.bumps.attestation;
+        attestation.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Attestation for wallet {} migrated to {} bytes", wallet, 8 + KycAttestation::LEN);
+        Ok(())
+    }
+
+    /// Admin function to register a sanctions-screening vendor under a numeric id
+    pub fn register_vendor(
+        ctx: Context<RegisterVendor>,
+        id: u8,
+        name: String,
+        key: Pubkey,
+    ) -> Result<()> {
+        require!(id != UNATTRIBUTED_VENDOR_ID, CompliFiError::InvalidPolicyParameters);
+        require!(name.len() <= MAX_VENDOR_NAME_LEN, CompliFiError::InvalidPolicyParameters);
+        
+        let vendor = &mut ctx.accounts.vendor;
+        vendor.id = id;
+        vendor.name = name;
+        vendor.key = key;
+        
+        msg!("Screening vendor {} registered: {}", id, vendor.name);
+        Ok(())
+    }
+
+    /// Admin function to recreate an attestation from a prior CompliFi deployment, keeping its
+    /// original issuing timestamp and recording the program it came from
+    pub fn import_attestation(
+        ctx: Context<ImportAttestation>,
+        wallet: Pubkey,
+        is_verified: bool,
+        jurisdiction: u8,
+        original_timestamp: i64,
+        source_program: Pubkey,
+    ) -> Result<()> {
+        require!(
+            source_program != Pubkey::default() && source_program != crate::ID,
+            CompliFiError::InvalidPolicyParameters
+        );
+        require!(
+            original_timestamp <= Clock::get()?.unix_timestamp,
+            CompliFiError::InvalidOracleTimestamp
+        );
+        
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.wallet = wallet;
+        attestation.set_verified(is_verified);
+        attestation.authority = ctx.accounts.authority.key();
+        attestation.timestamp = original_timestamp;
+        attestation.jurisdiction = jurisdiction;
+        attestation.expires_at = 0;
+        attestation.reminders_fired = 0;
+        attestation.validity_period = 0;
+        attestation.renew_count = 0;
+        attestation.source_chain = LOCAL_CHAIN_ID;
+        attestation.bump = ctx.bumps.attestation;
+        attestation.screening_vendor_id = UNATTRIBUTED_VENDOR_ID;
+        attestation.jurisdiction_scheme = DEFAULT_JURISDICTION_SCHEME;
+        attestation.documents_reviewed = 0;
+        attestation.cosigners = [Pubkey::default(); MAX_ATTESTATION_COSIGNERS];
+        attestation.cosigner_count = 0;
+        attestation.allowed_counterparties = [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES];
+        attestation.level = 0; // The source deployment recorded no tier
+        attestation.field_commitments = [[0; 32]; MAX_DISCLOSURE_FIELDS];
+        attestation.set_ownership_proven(false);
+        attestation.revocation_authority = attestation.authority;
+        attestation.jurisdiction_changed_at = 0;
+        attestation.prev_attestation_hash = [0; 32];
+        attestation.imported_from = source_program;
+        attestation.identity_verified_at = original_timestamp;
+        attestation.sanctions_checked_at = original_timestamp;
+        attestation.accreditation_verified_at = 0;
+        
+        if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+            rollup.record_level_change(None, Some(kyc_level(attestation)))?;
+        }
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, JOURNAL_OP_CREATE, attestation)?;
+        
+        emit!(KycAttestationEvent {
+            wallet,
+            is_verified,
+            jurisdiction,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        });
+        
+        msg!("KYC attestation for wallet {} imported from {}", wallet, source_program);
+        Ok(())
+    }
+
+    /// Create an attestation from a relayer-signed message proving KYC on another chain.
+    /// The transaction must include an Ed25519 program instruction, immediately before this one,
+    /// verifying the relayer's signature over the borsh-serialized `message`.
+    pub fn create_bridged_attestation(
+        ctx: Context<CreateBridgedAttestation>,
+        message: BridgedKycMessage,
+    ) -> Result<()> {
+        require!(message.source_chain != LOCAL_CHAIN_ID, CompliFiError::InvalidPolicyParameters);
+        
+        let relayer = ctx.accounts.state.bridge_relayer;
+        verify_ed25519_signature(
+            &ctx.accounts.instructions,
+            &relayer,
+            &message.try_to_vec()?,
+        )?;
+        
+        // Each (source_chain, sequence) message may only be consumed once
+        let receipt = &mut ctx.accounts.message_receipt;
+        require!(!receipt.consumed, CompliFiError::BridgeMessageReplayed);
+        receipt.consumed = true;
+        receipt.source_chain = message.source_chain;
+        receipt.sequence = message.sequence;
+        receipt.wallet = message.wallet;
+        
+        let issued_at = Clock::get()?.unix_timestamp;
+        let attestation = &mut ctx.accounts.attestation;
+        let existing = attestation.wallet != Pubkey::default();
+        let previous_level = existing.then(|| kyc_level(attestation));
+        attestation.prev_attestation_hash = if existing { attestation_hash(attestation)? } else { [0; 32] };
+        attestation.wallet = message.wallet;
+        attestation.set_verified(message.is_verified);
+        attestation.authority = relayer;
+        attestation.timestamp = issued_at;
+        attestation.jurisdiction = message.jurisdiction;
+        attestation.expires_at = message.expires_at;
+        attestation.reminders_fired = 0;
+        attestation.validity_period = if message.expires_at > 0 { message.expires_at - issued_at } else { 0 };
+        attestation.renew_count = 0;
+        attestation.source_chain = message.source_chain;
+        attestation.bump = ctx.bumps.attestation;
+        attestation.screening_vendor_id = UNATTRIBUTED_VENDOR_ID;
+        attestation.jurisdiction_scheme = DEFAULT_JURISDICTION_SCHEME;
+        attestation.documents_reviewed = 0;
+        attestation.cosigners = [Pubkey::default(); MAX_ATTESTATION_COSIGNERS];
+        attestation.cosigner_count = 0;
+        attestation.allowed_counterparties = [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES];
+        attestation.level = 0; // Bridged messages carry no tier
+        attestation.field_commitments = [[0; 32]; MAX_DISCLOSURE_FIELDS];
+        attestation.set_ownership_proven(false);
+        attestation.revocation_authority = relayer;
+        attestation.jurisdiction_changed_at = 0;
+        attestation.imported_from = Pubkey::default();
+        attestation.identity_verified_at = issued_at;
+        attestation.sanctions_checked_at = issued_at;
+        attestation.accreditation_verified_at = 0;
+        
+        if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+            rollup.record_level_change(previous_level, Some(kyc_level(attestation)))?;
+        }
+        let operation = if existing { JOURNAL_OP_UPDATE } else { JOURNAL_OP_CREATE };
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, operation, attestation)?;
+        
+        emit!(KycAttestationEvent {
+            wallet: message.wallet,
+            is_verified: message.is_verified,
+            jurisdiction: message.jurisdiction,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        });
+        
+        msg!(
+            "Bridged KYC attestation created for wallet {} from chain {}",
+            message.wallet,
+            message.source_chain
+        );
+        Ok(())
+    }
+
+    /// Create or refresh an attestation from a KYC result an allowlisted attestor signed
+    /// off-chain, so providers need not send a transaction per user. The transaction must
+    /// include an Ed25519 program instruction, immediately before this one, verifying the
+    /// attestor's signature over the borsh-serialized `message`. Anyone may submit it and
+    /// pays for the attestation.
+    pub fn verify_signed_attestation(
+        ctx: Context<VerifySignedAttestation>,
+        provider: Pubkey,
+        message: SignedKycMessage,
+    ) -> Result<()> {
+        verify_ed25519_signature(&ctx.accounts.instructions, &provider, &message.try_to_vec()?)?;
+        
+        // Signed messages carry no sequence, so each must expire and may only move the
+        // attestation forward: a replayed message cannot undo a revocation or a later issue
+        let issued_at = Clock::get()?.unix_timestamp;
+        require!(message.expires_at > issued_at, CompliFiError::AttestationExpired);
+        require_jurisdiction_delegated(&ctx.accounts.attestor_entry, message.jurisdiction)?;
+        let attestation = &mut ctx.accounts.attestation;
+        let existing = attestation.wallet != Pubkey::default();
+        require!(
+            !existing || (attestation.authority == provider && message.expires_at > attestation.expires_at),
+            CompliFiError::SignedAttestationReplayed
+        );
+        
+        let previous_level = existing.then(|| kyc_level(attestation));
+        attestation.prev_attestation_hash = if existing { attestation_hash(attestation)? } else { [0; 32] };
+        attestation.jurisdiction_changed_at = if existing && attestation.jurisdiction != message.jurisdiction {
+            issued_at
+        } else {
+            0
+        };
+        attestation.wallet = message.wallet;
+        attestation.set_verified(true);
+        attestation.authority = provider;
+        attestation.timestamp = issued_at;
+        attestation.jurisdiction = message.jurisdiction;
+        attestation.expires_at = message.expires_at;
+        attestation.reminders_fired = 0;
+        attestation.validity_period = message.expires_at - issued_at;
+        attestation.renew_count = 0;
+        attestation.source_chain = LOCAL_CHAIN_ID;
+        attestation.bump = ctx.bumps.attestation;
+        attestation.screening_vendor_id = UNATTRIBUTED_VENDOR_ID;
+        attestation.jurisdiction_scheme = DEFAULT_JURISDICTION_SCHEME;
+        attestation.documents_reviewed = 0;
+        attestation.cosigners = [Pubkey::default(); MAX_ATTESTATION_COSIGNERS];
+        attestation.cosigner_count = 0;
+        attestation.allowed_counterparties = [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES];
+        attestation.level = 0; // Signed messages carry no tier
+        attestation.field_commitments = [[0; 32]; MAX_DISCLOSURE_FIELDS];
+        attestation.set_ownership_proven(false);
+        attestation.revocation_authority = provider;
+        attestation.imported_from = Pubkey::default();
+        attestation.identity_verified_at = issued_at;
+        attestation.sanctions_checked_at = issued_at;
+        attestation.accreditation_verified_at = 0;
+        
+        if let Some(rollup) = active_rollup(&ctx.accounts.state, &mut ctx.accounts.rollup)? {
+            rollup.record_level_change(previous_level, Some(kyc_level(attestation)))?;
+        }
+        let operation = if existing { JOURNAL_OP_UPDATE } else { JOURNAL_OP_CREATE };
+        journal_attestation(&ctx.accounts.state, &mut ctx.accounts.journal, operation, attestation)?;
+        
+        emit!(KycAttestationEvent {
+            wallet: message.wallet,
+            is_verified: true,
+            jurisdiction: message.jurisdiction,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        });
+        
+        msg!("Signed KYC attestation from {} accepted for wallet {}", provider, message.wallet);
+        Ok(())
+    }
+
+    /// Admin function to register the relayer trusted to sign cross-chain KYC messages
+    pub fn set_bridge_relayer(ctx: Context<UpdateState>, relayer: Pubkey) -> Result<()> {
+        ctx.accounts.state.bridge_relayer = relayer;
+        
+        msg!("Bridge relayer set to: {}", relayer);
+        Ok(())
+    }
+
+    /// Admin function to commit the rolling root of the current verification day to a
+    /// DailyRoot PDA and start a new day
+    pub fn close_verification_day(ctx: Context<CloseVerificationDay>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.day_verification_count > 0, CompliFiError::VerificationDayEmpty);
+        
+        let daily_root = &mut ctx.accounts.daily_root;
+        daily_root.state = state.key();
+        daily_root.day = state.verification_day;
+        daily_root.root = state.daily_verification_root;
+        daily_root.verification_count = state.day_verification_count;
+        daily_root.closed_at = Clock::get()?.unix_timestamp;
+        
+        state.daily_verification_root = [0; 32];
+        state.day_verification_count = 0;
+        
+        msg!("Verification day {} closed with {} verifications", daily_root.day, daily_root.verification_count);
+        Ok(())
+    }
+
+    /// Admin function to cap verifications across all users per slot (0 = unlimited)
+    pub fn set_max_verifications_per_slot(
+        ctx: Context<UpdateState>,
+        max_verifications_per_slot: u32,
+    ) -> Result<()> {
+        ctx.accounts.state.max_verifications_per_slot = max_verifications_per_slot;
+        
+        msg!("Max verifications per slot set to: {}", max_verifications_per_slot);
+        Ok(())
+    }
+
+    /// Admin function to cap transaction amounts for users from a jurisdiction (0 = no cap)
+    pub fn set_jurisdiction_limit(
+        ctx: Context<SetJurisdictionLimit>,
+        jurisdiction: u8,
+        max_amount: u64,
+    ) -> Result<()> {
+        let limit = &mut ctx.accounts.jurisdiction_limit;
+        limit.jurisdiction = jurisdiction;
+        limit.max_amount = max_amount;
+        
+        msg!("Jurisdiction {} amount cap set to {}", jurisdiction, max_amount);
+        Ok(())
+    }
+
+    /// Admin function to record a jurisdiction's risk tier
+    pub fn set_jurisdiction_meta(
+        ctx: Context<SetJurisdictionMeta>,
+        jurisdiction: u8,
+        risk_tier: u8,
+    ) -> Result<()> {
+        require!((risk_tier as usize) < RISK_TIER_COUNT, CompliFiError::InvalidPolicyParameters);
+        
+        let meta = &mut ctx.accounts.jurisdiction_meta;
+        meta.jurisdiction = jurisdiction;
+        meta.risk_tier = risk_tier;
+        
+        msg!("Jurisdiction {} set to risk tier {}", jurisdiction, risk_tier);
+        Ok(())
+    }
+
+    /// Admin function to set how long attestations in a jurisdiction are retained before
+    /// `purge_attestation` may close them (0 = kept indefinitely)
+    pub fn set_jurisdiction_retention(
+        ctx: Context<SetJurisdictionMeta>,
+        jurisdiction: u8,
+        retention_seconds: i64,
+    ) -> Result<()> {
+        require!(retention_seconds >= 0, CompliFiError::InvalidPolicyParameters);
+        
+        let meta = &mut ctx.accounts.jurisdiction_meta;
+        meta.jurisdiction = jurisdiction;
+        meta.retention_seconds = retention_seconds;
+        
+        msg!("Jurisdiction {} retention set to {}s", jurisdiction, retention_seconds);
+        Ok(())
+    }
+
+    /// Admin function to describe a jurisdiction code with its name and ISO 3166-1 alpha-2
+    /// code. Purely informational: verification only ever compares codes.
+    pub fn set_jurisdiction_info(
+        ctx: Context<SetJurisdictionMeta>,
+        jurisdiction: u8,
+        name: String,
+        iso_code: [u8; 2],
+    ) -> Result<()> {
+        require!(name.len() <= MAX_JURISDICTION_NAME_LEN, CompliFiError::InvalidPolicyParameters);
+        require!(iso_code.iter().all(u8::is_ascii_uppercase), CompliFiError::InvalidPolicyParameters);
+        
+        let meta = &mut ctx.accounts.jurisdiction_meta;
+        meta.jurisdiction = jurisdiction;
+        meta.name = name;
+        meta.iso_code = iso_code;
+        
+        msg!("Jurisdiction {} described as {}", jurisdiction, meta.name);
+        Ok(())
+    }
+
+    /// Admin function to register the auditor that may receive revealed KYC fields
+    pub fn set_auditor(ctx: Context<UpdateState>, auditor: Pubkey) -> Result<()> {
+        ctx.accounts.state.auditor = auditor;
+        
+        msg!("Auditor set to: {}", auditor);
+        Ok(())
+    }
+
+    /// Admin function to propose a new state authority. Nothing changes until the proposed
+    /// key signs `accept_authority`; proposing the default key cancels a pending transfer.
+    pub fn transfer_authority(ctx: Context<UpdateState>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.state.pending_authority = new_authority;
+        
+        msg!("Authority transfer proposed to: {}", new_authority);
+        Ok(())
+    }
+
+    /// Complete a proposed authority transfer (proposed authority only)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let old = state.authority;
+        state.authority = state.pending_authority;
+        state.pending_authority = Pubkey::default();
+        
+        emit!(AuthorityTransferredEvent {
+            old,
+            new: state.authority,
+        });
+        
+        msg!("Authority transferred from {} to {}", old, state.authority);
+        Ok(())
+    }
+
+    /// Attester commits to the KYC data behind an attestation without storing the plaintext.
+    /// Each commitment is sha256(wallet || field_id || value || blinding).
+    pub fn commit_attestation_fields(
+        ctx: Context<CommitAttestationFields>,
+        wallet: Pubkey,
+        field_commitments: [[u8; 32]; MAX_DISCLOSURE_FIELDS],
+    ) -> Result<()> {
+        ctx.accounts.attestation.field_commitments = field_commitments;
+        
+        msg!("KYC field commitments stored for wallet: {}", wallet);
+        Ok(())
+    }
+
+    /// Record that the attestation authority re-ran some of the wallet's checks now.
+    /// `checks` is a bitmask of ATTESTATION_CHECK_* bits.
+    pub fn refresh_attestation_checks(
+        ctx: Context<CommitAttestationFields>,
+        wallet: Pubkey,
+        checks: u8,
+    ) -> Result<()> {
+        let all = ATTESTATION_CHECK_IDENTITY | ATTESTATION_CHECK_SANCTIONS | ATTESTATION_CHECK_ACCREDITATION;
+        require!(checks != 0 && checks & !all == 0, CompliFiError::InvalidPolicyParameters);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let attestation = &mut ctx.accounts.attestation;
+        if checks & ATTESTATION_CHECK_IDENTITY != 0 {
+            attestation.identity_verified_at = now;
+        }
+        if checks & ATTESTATION_CHECK_SANCTIONS != 0 {
+            attestation.sanctions_checked_at = now;
+        }
+        if checks & ATTESTATION_CHECK_ACCREDITATION != 0 {
+            attestation.accreditation_verified_at = now;
+        }
+        
+        msg!("Attestation checks {:#05b} refreshed for wallet: {}", checks, wallet);
+        Ok(())
+    }
+
+    /// Record which jurisdiction coding scheme the attestation's jurisdiction is in (attestation
+    /// authority only). Policies only accept attestations coded in their own scheme.
+    pub fn set_attestation_jurisdiction_scheme(
+        ctx: Context<CommitAttestationFields>,
+        wallet: Pubkey,
+        scheme: u8,
+    ) -> Result<()> {
+        ctx.accounts.attestation.jurisdiction_scheme = scheme;
+        
+        msg!("Jurisdiction scheme of wallet {} set to {}", wallet, scheme);
+        Ok(())
+    }
+
+    /// Admin function to allow a key to issue attestations through create_kyc_attestation
+    pub fn add_attestor(ctx: Context<AddAttestor>, attestor: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.attestor_entry;
+        entry.attestor = attestor;
+        entry.added_by = ctx.accounts.authority.key();
+        entry.added_at = Clock::get()?.unix_timestamp;
+        entry.allowed_jurisdictions = [0; JURISDICTION_BITMAP_LEN]; // Default: Any jurisdiction
+        
+        emit!(AttestorAddedEvent {
+            attestor,
+            authority: entry.added_by,
+        });
+        
+        msg!("Attestor {} allowlisted", attestor);
+        Ok(())
+    }
+
+    /// Admin function to stop a key issuing attestations, closing its allowlist entry.
+    /// Attestations it already issued stay as they are.
+    pub fn remove_attestor(ctx: Context<RemoveAttestor>, attestor: Pubkey) -> Result<()> {
+        emit!(AttestorRemovedEvent {
+            attestor,
+            authority: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Attestor {} removed from the allowlist", attestor);
+        Ok(())
+    }
+
+    /// Admin function to scope an attestor to the jurisdictions set in `allowed_jurisdictions`.
+    /// An empty bitmap lifts the scope, letting it attest any jurisdiction.
+    pub fn set_attestor_jurisdictions(
+        ctx: Context<SetAttestorJurisdictions>,
+        attestor: Pubkey,
+        allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN],
+    ) -> Result<()> {
+        ctx.accounts.attestor_entry.allowed_jurisdictions = allowed_jurisdictions;
+        
+        msg!("Jurisdictions attestor {} may attest updated", attestor);
+        Ok(())
+    }
+
+    /// Admin function to set how much an attester's signature counts toward a policy's
+    /// minimum trust weight, registering the attester on first use
+    pub fn set_attester_trust_weight(
+        ctx: Context<SetAttesterTrustWeight>,
+        attester: Pubkey,
+        trust_weight: u8,
+    ) -> Result<()> {
+        let profile = &mut ctx.accounts.attester_profile;
+        profile.attester = attester;
+        profile.trust_weight = trust_weight;
+        
+        emit!(AttesterTrustUpdatedEvent {
+            attester,
+            trust_weight,
+        });
+        
+        msg!("Attester {} trust weight set to {}", attester, trust_weight);
+        Ok(())
+    }
+
+    /// Co-sign an existing attestation as a registered attester, adding its trust weight to
+    /// the attestation's. Re-issuing the attestation clears its co-signers.
+    pub fn cosign_attestation(ctx: Context<CosignAttestation>, wallet: Pubkey) -> Result<()> {
+        let cosigner = ctx.accounts.cosigner.key();
+        let attestation = &mut ctx.accounts.attestation;
+        let count = attestation.cosigner_count as usize;
+        require!(
+            cosigner != attestation.authority && !attestation.cosigners[..count].contains(&cosigner),
+            CompliFiError::AlreadyCosigned
+        );
+        require!(count < MAX_ATTESTATION_COSIGNERS, CompliFiError::CosignerLimitReached);
+        
+        attestation.cosigners[count] = cosigner;
+        attestation.cosigner_count += 1;
+        
+        emit!(AttestationCosignedEvent {
+            wallet,
+            cosigner,
+            cosigner_count: attestation.cosigner_count,
+        });
+        
+        msg!("Attestation for wallet {} co-signed by {}", wallet, cosigner);
+        Ok(())
+    }
+
+    /// Scope the attestation to dealings with the given counterparties (attestation authority
+    /// only). An empty list lifts the scope.
+    pub fn set_allowed_counterparties(
+        ctx: Context<CommitAttestationFields>,
+        wallet: Pubkey,
+        counterparties: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            counterparties.len() <= MAX_ALLOWED_COUNTERPARTIES,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        let allowed = &mut ctx.accounts.attestation.allowed_counterparties;
+        *allowed = [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES];
+        allowed[..counterparties.len()].copy_from_slice(&counterparties);
+        
+        msg!("Attestation for wallet {} scoped to {} counterparties", wallet, counterparties.len());
+        Ok(())
+    }
+
+    /// Reveal a single committed KYC field to the auditor; only the audit event is recorded
+    pub fn reveal_attestation_field(
+        ctx: Context<RevealAttestationField>,
+        wallet: Pubkey,
+        field_id: u8,
+        value: Vec<u8>,
+        blinding: [u8; 32],
+    ) -> Result<()> {
+        let commitments = &ctx.accounts.attestation.field_commitments;
+        require!(
+            (field_id as usize) < MAX_DISCLOSURE_FIELDS,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        let commitment = commitments[field_id as usize];
+        require!(
+            commitment != [0; 32] && field_commitment(&wallet, field_id, &value, &blinding) == commitment,
+            CompliFiError::CommitmentMismatch
+        );
+        
+        emit!(FieldRevealedEvent {
+            wallet,
+            field_id,
+            commitment,
+            auditor: ctx.accounts.auditor.key(),
+            revealed_at: Clock::get()?.unix_timestamp,
+        });
+        
+        msg!("KYC field {} revealed for wallet: {}", field_id, wallet);
+        Ok(())
+    }
+
+    /// Emit a reminder for each lifetime threshold an attestation has crossed since the last
+    /// crank (permissionless crank)
+    pub fn emit_expiry_reminders(ctx: Context<EmitExpiryReminders>, wallet: Pubkey) -> Result<()> {
+        let thresholds = ctx.accounts.state.expiry_reminder_thresholds;
+        let attestation = &mut ctx.accounts.attestation;
+        let clock = Clock::get()?;
+        
+        // After a renewal the thresholds measure the renewed period, not the whole history
+        let lifetime_start = attestation.lifetime_start();
+        require!(
+            attestation.expires_at > lifetime_start,
+            CompliFiError::AttestationHasNoExpiry
+        );
+        
+        let pct_elapsed = lifetime_pct_elapsed(
+            lifetime_start,
+            attestation.expires_at,
+            clock.unix_timestamp,
+        );
+        
+        // A crank that comes late fires every threshold it jumped past, lowest first, so no
+        // reminder is lost to a gap between cranks
+        let mut fired = 0;
+        for (i, threshold) in thresholds.iter().enumerate() {
+            let bit = 1u8 << i;
+            if *threshold != 0 && pct_elapsed >= *threshold && attestation.reminders_fired & bit == 0 {
+                attestation.reminders_fired |= bit;
+                fired += 1;
+                emit!(KycExpiryReminderEvent {
+                    wallet,
+                    threshold: *threshold,
+                    pct_elapsed,
+                });
+                msg!("Expiry reminder for wallet {}: {}% threshold reached", wallet, threshold);
+            }
+        }
+        
+        if fired == 0 {
+            msg!("No expiry reminder due for wallet: {}", wallet);
+        }
+        Ok(())
+    }
+
+    /// Admin function to configure expiry reminder thresholds
+    pub fn set_expiry_reminder_thresholds(
+        ctx: Context<UpdateState>,
+        thresholds: [u8; 4],
+    ) -> Result<()> {
+        // Thresholds must be strictly ascending percentages, with unused (0) slots at the end
+        let mut previous = 0u8;
+        let mut seen_unused = false;
+        for threshold in thresholds {
+            if threshold == 0 {
+                seen_unused = true;
+                continue;
+            }
+            require!(
+                !seen_unused && threshold > previous && threshold <= 100,
+                CompliFiError::InvalidPolicyParameters
+            );
+            previous = threshold;
+        }
+        
+        ctx.accounts.state.expiry_reminder_thresholds = thresholds;
+        
+        msg!("Expiry reminder thresholds updated: {:?}", thresholds);
+        Ok(())
+    }
+
+    /// Opt in or out of automatic attestation renewal (signed by the attested wallet)
+    pub fn set_auto_renew(ctx: Context<SetAutoRenew>, auto_renew: bool) -> Result<()> {
+        ctx.accounts.attestation.set_auto_renew(auto_renew);
+        
+        msg!("Auto-renew for wallet {} set to {}", ctx.accounts.wallet.key(), auto_renew);
+        Ok(())
+    }
+
+    /// Extend an opted-in attestation by its original validity period (keeper instruction)
+    pub fn renew_attestation(ctx: Context<RenewAttestation>, wallet: Pubkey) -> Result<()> {
+        let attestation = &ctx.accounts.attestation;
+        require!(attestation.auto_renew(), CompliFiError::AutoRenewDisabled);
+        require!(attestation.validity_period > 0, CompliFiError::AttestationHasNoExpiry);
+        
+        // Renewing before expiry is charged the lower early fee
+        let policy = &ctx.accounts.policy;
+        let fee = if Clock::get()?.unix_timestamp < attestation.expires_at {
+            policy.early_renewal_fee
+        } else {
+            policy.late_renewal_fee
+        };
+        if fee > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.renewer.to_account_info(),
+                        to: ctx.accounts.fee_recipient.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+        
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.expires_at = attestation
+            .expires_at
+            .checked_add(attestation.validity_period)
+            .ok_or(CompliFiError::CounterOverflow)?;
+        attestation.renew_count = attestation.renew_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        attestation.reminders_fired = 0; // The extended lifetime starts a new reminder cycle
+        
+        emit!(AttestationRenewedEvent {
+            wallet,
+            expires_at: attestation.expires_at,
+            renew_count: attestation.renew_count,
+            fee,
+        });
+        
+        msg!("Attestation for wallet {} renewed until {}", wallet, attestation.expires_at);
+        Ok(())
+    }
+
+    /// Admin function to register the trusted time oracle and its allowed clock drift
+    pub fn set_time_oracle(
+        ctx: Context<UpdateState>,
+        time_oracle: Pubkey,
+        tolerance_secs: u64,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.time_oracle = time_oracle;
+        state.time_oracle_tolerance = tolerance_secs;
+        
+        msg!("Time oracle set to {} with tolerance {}s", time_oracle, tolerance_secs);
+        Ok(())
+    }
+
+    /// Admin function to register an authority allowed to renew any opted-in attestation
+    pub fn set_renewal_authority(ctx: Context<UpdateState>, renewal_authority: Pubkey) -> Result<()> {
+        ctx.accounts.state.renewal_authority = renewal_authority;
+        
+        msg!("Renewal authority set to: {}", renewal_authority);
+        Ok(())
+    }
+
+    /// Verify compliance for a user action. A pass ends its return data with a
+    /// `VerificationResult` for CPI callers.
+    ///
+    /// With `simulate` set, every check runs, the oracle read included, but a pass stops
+    /// there: nothing is counted, cached, charged or minted, the nonce is not consumed, and
+    /// the pass is reported as a `SimulatedVerificationEvent`, with the `VerificationResult`
+    /// alone as return data.
+    ///
+    /// Within the policy's `cache_ttl`, the risk score the user record cached from the last
+    /// oracle read is reused and no oracle account is needed; `force_refresh` reads the
+    /// oracle regardless, bypassing verification receipts too.
+    ///
+    /// A pass is written to the user's `ComplianceReceipt` when it is passed, as it must be
+    /// under a policy with a `compliance_receipt_ttl`.
+    pub fn verify_compliance(
+        ctx: Context<VerifyCompliance>,
+        user: Pubkey,
+        action: String,
+        override_max_risk: Option<u8>,
+        amount: Option<u64>,
+        nonce: Option<u64>,
+        counterparty: Pubkey,
+        deadline: Option<i64>,
+        mint_certificate: bool,
+        simulate: bool,
+        force_refresh: bool,
+    ) -> Result<()> {
+        // Nothing is verified while the circuit breaker is tripped
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        require!(action.len() <= MAX_ACTION_LEN, CompliFiError::StringTooLong);
+        
+        // A caller-set deadline keeps a transaction that lands late from gating anything
+        if let Some(deadline) = deadline {
+            require!(Clock::get()?.unix_timestamp <= deadline, CompliFiError::DeadlineExceeded);
+        }
+        
+        // A frozen, sanctioned or held wallet is blocked ahead of every policy check, whatever
+        // the policy says
+        let attestation = ctx.accounts.attestation.as_deref();
+        check_wallet_not_blocked(
+            attestation,
+            &ctx.accounts.sanction,
+            &ctx.accounts.user_record,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        // A delegate is evaluated against the principal it acts for, and a sub-account
+        // against its parent entity's KYB. A sub-account's oracle risk stays its own.
+        require!(
+            ctx.accounts.delegation.is_none() || ctx.accounts.entity.is_none(),
+            CompliFiError::Unauthorized
+        );
+        let principal = match (&ctx.accounts.delegation, &ctx.accounts.entity) {
+            (Some(delegation), _) => {
+                require_keys_eq!(delegation.delegate, user, CompliFiError::Unauthorized);
+                delegation.principal
+            }
+            (None, Some(entity)) => entity.parent,
+            (None, None) => user,
+        };
+        let risk_subject = if ctx.accounts.entity.is_some() { user } else { principal };
+        
+        // During a staged rollout, users outside the cohort stay on the baseline snapshot.
+        // Otherwise a compiled policy is read in place of the live one, as long as it is up to date.
+        let rollout_bps = ctx.accounts.policy.rollout_bps;
+        let policy: &CompliancePolicy = if !in_rollout_cohort(&principal, rollout_bps) {
+            let baseline = ctx.accounts.rollout_baseline.as_ref().ok_or(CompliFiError::RolloutBaselineRequired)?;
+            &baseline.effective
+        } else {
+            match &ctx.accounts.compiled_policy {
+                Some(compiled) => {
+                    require!(
+                        compiled.source_revision == ctx.accounts.policy.revision,
+                        CompliFiError::CompiledPolicyStale
+                    );
+                    &compiled.effective
+                }
+                None => &ctx.accounts.policy,
+            }
+        };
+        
+        // Attester profiles are this program's accounts; the rest of remaining_accounts are oracles
+        let (attesters, oracles): (Vec<AccountInfo>, Vec<AccountInfo>) = ctx
+            .remaining_accounts
+            .iter()
+            .cloned()
+            .partition(|account| account.owner == &crate::ID);
+        
+        // Every check that only reads accounts runs ahead of the first write, in the same set
+        // `guard` applies
+        let now = Clock::get()?.unix_timestamp;
+        check_verification_requirements(
+            policy,
+            &action,
+            attestation,
+            &principal,
+            &attesters,
+            &ctx.accounts.attestor_entry,
+            &counterparty,
+            amount,
+            ctx.accounts.jurisdiction_limit.as_ref().map_or(0, |limit| limit.max_amount),
+            ctx.accounts.user_record.as_deref(),
+            now,
+        )?;
+        
+        // A per-call override may only tighten the stored threshold for the action, never loosen it
+        let action_max_risk = policy.action_max_risk_score(&action);
+        if let Some(max_risk) = override_max_risk {
+            require!(max_risk <= action_max_risk, CompliFiError::OverrideLoosensPolicy);
+        }
+        let overridden = override_max_risk.is_some_and(|max_risk| max_risk < action_max_risk);
+        // A jurisdiction with a tighter cap holds its users to it, whichever threshold applies
+        let threshold = override_max_risk.unwrap_or(action_max_risk);
+        let jurisdiction = attestation.map(|attestation| attestation.jurisdiction);
+        let max_risk_score = jurisdiction.map_or(threshold, |jurisdiction| {
+            policy.jurisdiction_max_risk_score(jurisdiction, threshold)
+        });
+        let jurisdiction_capped = max_risk_score < threshold;
+        
+        // A nonce binds the call to one ordered request, so a replayed request is rejected
+        if let Some(nonce) = nonce {
+            let record = ctx.accounts.user_record.as_mut().ok_or(CompliFiError::UserRecordRequired)?;
+            require!(nonce > record.nonce, CompliFiError::StaleNonce);
+            if !simulate {
+                record.nonce = nonce;
+            }
+        }
+        
+        // A fresh receipt for this user, policy, action and amount bucket stands in for the oracle,
+        // and failing that the score the user record cached under this policy. A sub-account's
+        // record is its parent's, so it holds none of the sub-account's own scores.
+        let receipt_risk = ctx.accounts.receipt.as_ref()
+            .filter(|receipt| {
+                !force_refresh && receipt.valid_until > now && receipt.policy_revision == ctx.accounts.policy.revision
+            })
+            .map(|receipt| (receipt.risk_score, receipt.risk_model_version));
+        let record_risk = ctx.accounts.user_record.as_ref()
+            .filter(|_| !force_refresh && policy.cache_ttl > 0 && ctx.accounts.entity.is_none())
+            .and_then(|record| {
+                record.cached_risk(&ctx.accounts.policy.key(), ctx.accounts.policy.revision, policy.cache_ttl, now)
+            });
+        let cached_risk = receipt_risk.or(record_risk);
+        
+        // Under a latency budget a cache miss defers the oracle check to finalize_verification,
+        // tracked on the receipt. Delegated and simulated calls always run it inline, as do
+        // calls under a jurisdiction cap, which finalize_verification has no attestation to read.
+        if cached_risk.is_none()
+            && policy.defer_risk_check
+            && ctx.accounts.delegation.is_none()
+            && !simulate
+            && !jurisdiction_capped
+        {
+            if let Some(receipt) = &mut ctx.accounts.receipt {
+                receipt.policy_revision = ctx.accounts.policy.revision;
+                receipt.pending_since = now;
+                receipt.pending_amount = amount.unwrap_or(0);
+                set_return_data(&[VERIFICATION_DECISION_PENDING]);
+                msg!("Compliance verification pending oracle check for user: {}", user);
+                return Ok(());
+            }
+        }
+        
+        // The oracle read is the expensive step, so every rejection that can be settled locally
+        // lands before it: the per-slot throughput cap, and the accounts a pass will need
+        let clock = Clock::get()?;
+        let slot = clock.slot;
+        {
+            let state = &mut ctx.accounts.state;
+            if state.throughput_slot != slot {
+                state.throughput_slot = slot;
+                state.verifications_this_slot = 0;
+            }
+            if state.max_verifications_per_slot > 0 {
+                require!(
+                    state.verifications_this_slot < state.max_verifications_per_slot,
+                    CompliFiError::ThroughputExceeded
+                );
+            }
+        }
+        require!(
+            policy.verification_fee == 0
+                || (ctx.accounts.fee_recipient.is_some() && ctx.accounts.system_program.is_some()),
+            CompliFiError::FeeAccountsRequired
+        );
+        require!(
+            policy.compliance_receipt_ttl == 0 || ctx.accounts.compliance_receipt.is_some(),
+            CompliFiError::ComplianceReceiptRequired
+        );
+        require!(
+            !mint_certificate
+                || (ctx.accounts.certificate.is_some()
+                    && ctx.accounts.certificate_mint.is_some()
+                    && ctx.accounts.certificate_token_account.is_some()
+                    && ctx.accounts.token_program.is_some()
+                    && ctx.accounts.associated_token_program.is_some()
+                    && ctx.accounts.system_program.is_some()),
+            CompliFiError::CertificateAccountsRequired
+        );
+        
+        // 2. Get wallet risk score from Range Security Oracle (or a quorum of oracles)
+        let (risk_score, risk_model_version) = match (receipt_risk, record_risk) {
+            (Some(cached), _) => {
+                msg!("Verification receipt hit for amount bucket {}", amount_bucket(amount));
+                cached
+            }
+            (None, Some(cached)) => {
+                msg!("Cached risk score reused for user: {}", user);
+                cached
+            }
+            (None, None) => assess_risk(
+                policy,
+                &oracles,
+                single_risk_oracle(
+                    policy,
+                    &ctx.accounts.range_oracle,
+                    &ctx.accounts.risk_feed,
+                    &ctx.accounts.switchboard_aggregator,
+                    &risk_subject,
+                )?,
+                &risk_subject,
+                attestation,
+                &ctx.accounts.user_record,
+            )?,
+        };
+        
+        // Scores within the tolerance band above the threshold still pass, but are flagged.
+        // A per-call override asks for a strict check, so it gets no band.
+        let risk_tolerance = if overridden { 0 } else { policy.risk_tolerance };
+        let marginal = risk_score > max_risk_score
+            && risk_score <= max_risk_score.saturating_add(risk_tolerance);
+        
+        // When the oracle and the attester's verdict disagree, the policy decides who wins.
+        // Without an attestation there is no attester verdict, and the oracle's stands.
+        let oracle_passes = risk_score <= max_risk_score || marginal;
+        let attester_passes = attestation.map_or(oracle_passes, |attestation| attestation.is_verified());
+        if oracle_passes != attester_passes {
+            let attester_wins = attester_wins_conflict(policy, attester_passes);
+            let passes = if attester_wins { attester_passes } else { oracle_passes };
+            emit!(RiskConflictResolvedEvent {
+                user,
+                risk_score,
+                attester_verified: attester_passes,
+                winner: if attester_wins { RISK_SOURCE_ATTESTER } else { RISK_SOURCE_ORACLE },
+                passed: passes,
+            });
+            if !passes {
+                return if oracle_passes {
+                    err!(CompliFiError::KycNotVerified)
+                } else {
+                    err!(CompliFiError::RiskScoreTooHigh)
+                };
+            }
+        } else {
+            require!(
+                oracle_passes, 
+                CompliFiError::RiskScoreTooHigh
+            );
+        }
+        
+        // A simulated call has passed every check; it leaves no trace beyond its own event
+        if simulate {
+            let amount = amount.unwrap_or(0);
+            set_return_data(
+                &VerificationResult {
+                    verified: true,
+                    risk_score,
+                    jurisdiction: jurisdiction.unwrap_or_default(),
+                }
+                .try_to_vec()?,
+            );
+            emit!(SimulatedVerificationEvent {
+                user,
+                principal,
+                action,
+                risk_score,
+                overridden,
+                amount,
+                fee: risk_weighted_fee(policy.verification_fee, risk_score, policy.risk_fee_multiplier_bps),
+                risk_model_version,
+                policy_hash: policy_hash(policy)?,
+            });
+            msg!("Simulated compliance verification passed for user: {}", user);
+            return Ok(());
+        }
+        
+        // 3. Increment verification count; the throughput cap was checked ahead of the oracle
+        let state = &mut ctx.accounts.state;
+        state.verifications_this_slot = state.verifications_this_slot.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        state.verification_count = state.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        if marginal {
+            state.marginal_pass_count = state.marginal_pass_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+            emit!(MarginalRiskEvent {
+                user,
+                risk_score,
+                max_risk_score,
+            });
+        }
+        
+        // Passing scores in the warning band go through, but are flagged for review
+        if policy.warn_risk_score > 0 && (policy.warn_risk_score..=max_risk_score).contains(&risk_score) {
+            emit!(RiskWarningEvent {
+                user,
+                risk_score,
+            });
+        }
+        
+        if let Some(report) = active_report(state, &mut ctx.accounts.report)? {
+            report.verification_count = report.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+            if let Some(jurisdiction) = jurisdiction {
+                let count = &mut report.jurisdiction_counts[jurisdiction as usize];
+                *count = count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+            }
+        }
+        
+        if let Some(record) = &mut ctx.accounts.user_record {
+            record.record_verification(principal, &action, risk_score, amount.unwrap_or(0), clock.unix_timestamp);
+            // Only a fresh oracle read is cached, so reusing a score never extends its life
+            if cached_risk.is_none() && ctx.accounts.entity.is_none() {
+                record.cache_risk(
+                    ctx.accounts.policy.key(),
+                    ctx.accounts.policy.revision,
+                    risk_score,
+                    risk_model_version,
+                    clock.unix_timestamp,
+                );
+            }
+        }
+        
+        // Other programs gate on this account alone, so it names the policy the user passed
+        if let Some(receipt) = &mut ctx.accounts.compliance_receipt {
+            receipt.user = user;
+            receipt.policy = ctx.accounts.policy.key();
+            receipt.verified_at = clock.unix_timestamp;
+            receipt.risk_score = risk_score;
+            receipt.valid_until = clock.unix_timestamp.saturating_add(policy.compliance_receipt_ttl);
+        }
+        
+        // 4. Fold the result digest into the day's rolling root, and publish it for
+        // off-chain verifiers when result signing is enabled
+        let digest = verification_digest(&user, &action, true, slot, policy)?;
+        if state.day_verification_count == 0 {
+            state.verification_day = clock.unix_timestamp / SECONDS_PER_DAY;
+        }
+        state.daily_verification_root =
+            solana_sha256_hasher::hashv(&[&state.daily_verification_root, &digest]).to_bytes();
+        state.day_verification_count = state.day_verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        // A deferring policy leads with the decision byte, ahead of any digest; the
+        // VerificationResult always comes last
+        let mut return_data = Vec::new();
+        if policy.defer_risk_check {
+            return_data.push(VERIFICATION_DECISION_PASS);
+        }
+        if policy.sign_results {
+            return_data.extend_from_slice(&digest);
+        }
+        VerificationResult {
+            verified: true,
+            risk_score,
+            jurisdiction: jurisdiction.unwrap_or_default(),
+        }
+        .serialize(&mut return_data)?;
+        set_return_data(&return_data);
+        
+        // Cache the outcome for later calls in the same amount bucket
+        if let Some(receipt) = &mut ctx.accounts.receipt {
+            if cached_risk.is_none() {
+                receipt.pending_since = 0; // A full check settles any deferred one
+                if policy.receipt_ttl > 0 {
+                    receipt.policy_revision = ctx.accounts.policy.revision;
+                    receipt.risk_score = risk_score;
+                    receipt.risk_model_version = risk_model_version;
+                    receipt.verified_at = now;
+                    receipt.valid_until = now.checked_add(policy.receipt_ttl).ok_or(CompliFiError::CounterOverflow)?;
+                }
+            }
+        }
+        
+        // 5. Charge the verification fee, scaled up by the user's risk score
+        let fee = risk_weighted_fee(policy.verification_fee, risk_score, policy.risk_fee_multiplier_bps);
+        if fee > 0 {
+            let (Some(fee_recipient), Some(system_program)) =
+                (&ctx.accounts.fee_recipient, &ctx.accounts.system_program)
+            else {
+                return err!(CompliFiError::FeeAccountsRequired);
+            };
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: fee_recipient.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+            
+            // Once the policy keeps a ledger, every fee it collects must be recorded in it
+            if ctx.accounts.policy.fee_ledger_enabled {
+                let ledger = ctx.accounts.fee_ledger.as_mut().ok_or(CompliFiError::FeeLedgerRequired)?;
+                ledger.record(ctx.accounts.authority.key(), fee, now);
+            }
+        }
+        
+        // 6. Issue the compliance certificate on request. The token is minted once; later
+        // passes only refresh the metadata it is read alongside.
+        if mint_certificate {
+            let (
+                Some(certificate),
+                Some(mint),
+                Some(token_account),
+                Some(token_program),
+                Some(associated_token_program),
+                Some(system_program),
+            ) = (
+                &mut ctx.accounts.certificate,
+                &ctx.accounts.certificate_mint,
+                &ctx.accounts.certificate_token_account,
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+                &ctx.accounts.system_program,
+            ) else {
+                return err!(CompliFiError::CertificateAccountsRequired);
+            };
+            
+            // CreateIdempotent also rejects a token account that is not the user's ATA
+            let token_program = token_program.to_account_info();
+            let mint = mint.to_account_info();
+            create_associated_token_account(
+                &associated_token_program.to_account_info(),
+                &ctx.accounts.authority.to_account_info(),
+                token_account,
+                &ctx.accounts.user.to_account_info(),
+                &mint,
+                &system_program.to_account_info(),
+                &token_program,
+            )?;
+            if read_token_account(token_account)?.amount == 0 {
+                let bump = ctx.bumps.certificate_mint.ok_or(CompliFiError::CertificateAccountsRequired)?;
+                mint_to(&token_program, &mint, token_account, &mint, 1, &[&[CERTIFICATE_MINT_SEED, &[bump]]])?;
+                certificate.minted_at = now;
+                emit!(CertificateMintedEvent {
+                    user: ctx.accounts.user.key(),
+                    token_account: token_account.key(),
+                    risk_score,
+                    verified_at: now,
+                });
+            }
+            certificate.user = ctx.accounts.user.key();
+            certificate.mint = mint.key();
+            certificate.token_account = token_account.key();
+            certificate.verified_at = now;
+            certificate.risk_score = risk_score;
+        }
+        
+        // 7. Emit verification event
+        let amount = amount.unwrap_or(0);
+        if policy.emit_json_log {
+            log_verification_json(&user, &action, true, risk_score);
+        }
+        let event = VerificationEvent {
+            user,
+            principal,
+            action,
+            verified: true,
+            risk_score,
+            overridden,
+            amount,
+            scaled_amount: scale_amount(amount, policy.decimals),
+            fee,
+            risk_model_version,
+            policy_hash: policy_hash(policy)?,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+            integrator: ctx.accounts.integrator.as_ref().map_or(Pubkey::default(), |integrator| integrator.key()),
+        };
+        if ctx.accounts.state.cpi_events {
+            emit_cpi!(event);
+        }
+        emit!(event);
+        
+        // Counted on the policy account itself, whichever effective configuration applied
+        let policy = &mut ctx.accounts.policy;
+        policy.verification_count = policy.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        msg!("Compliance verification passed for user: {}", user);
+        Ok(())
+    }
+
+    /// Verify `user` as verify_compliance does, then invoke `target_program` with
+    /// `target_ix_data` in the same instruction, so the gated call cannot be split from its
+    /// check and front-run. Every remaining account is handed to the target instruction with
+    /// its signer and writable flags; policies that read oracles or attester profiles from
+    /// remaining accounts therefore fail closed here. Nothing is invoked unless verification
+    /// passes.
+    pub fn verify_and_execute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyAndExecute<'info>>,
+        user: Pubkey,
+        action: String,
+        override_max_risk: Option<u8>,
+        amount: Option<u64>,
+        target_ix_data: Vec<u8>,
+    ) -> Result<()> {
+        // A deferred oracle check would let the target run before the risk is known
+        let verify = &ctx.accounts.verify;
+        require!(
+            !(verify.policy.defer_risk_check && verify.receipt.is_some()),
+            CompliFiError::DeferredVerificationUnsupported
+        );
+        
+        verify_compliance(
+            Context::new(ctx.program_id, &mut ctx.accounts.verify, &[], ctx.bumps.verify),
+            user,
+            action,
+            override_max_risk,
+            amount,
+            None,
+            Pubkey::default(),
+            None,
+            false,
+            false,
+            false,
+        )?;
+        
+        let target_program = &ctx.accounts.target_program;
+        let target_ix = Instruction {
+            program_id: target_program.key(),
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|account| {
+                    if account.is_writable {
+                        AccountMeta::new(*account.key, account.is_signer)
+                    } else {
+                        AccountMeta::new_readonly(*account.key, account.is_signer)
+                    }
+                })
+                .collect(),
+            data: target_ix_data,
+        };
+        let mut target_accounts = ctx.remaining_accounts.to_vec();
+        target_accounts.push(target_program.to_account_info());
+        invoke(&target_ix, &target_accounts)?;
+        
+        msg!("Gated instruction for user {} executed by program {}", user, target_program.key());
+        Ok(())
+    }
+
+    /// Complete a verification verify_compliance deferred under a latency budget by running
+    /// its oracle check (permissionless). Returns the decision byte: on a pass the receipt
+    /// caches the outcome, on a fail the pending verification is dropped.
+    pub fn finalize_verification(
+        ctx: Context<FinalizeVerification>,
+        user: Pubkey,
+        action: String,
+        amount_bucket: u8,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        
+        let policy = &ctx.accounts.policy;
+        require!(policy_active(policy, Clock::get()?.unix_timestamp), CompliFiError::PolicyNotActive);
+        let receipt = &mut ctx.accounts.receipt;
+        require!(
+            receipt.pending_since > 0 && receipt.policy_revision == policy.revision,
+            CompliFiError::NoPendingVerification
+        );
+        
+        let (risk_score, risk_model_version) = assess_risk(
+            policy,
+            ctx.remaining_accounts,
+            single_risk_oracle(
+                policy,
+                &ctx.accounts.range_oracle,
+                &ctx.accounts.risk_feed,
+                &ctx.accounts.switchboard_aggregator,
+                &user,
+            )?,
+            &user,
+            None,
+            &ctx.accounts.user_record,
+        )?;
+        let passed = risk_score <= policy.action_max_risk_score(&action).saturating_add(policy.risk_tolerance);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let amount = receipt.pending_amount;
+        receipt.pending_since = 0;
+        receipt.pending_amount = 0;
+        if passed {
+            receipt.risk_score = risk_score;
+            receipt.risk_model_version = risk_model_version;
+            receipt.verified_at = now;
+            receipt.valid_until = now.checked_add(policy.receipt_ttl).ok_or(CompliFiError::CounterOverflow)?;
+            let state = &mut ctx.accounts.state;
+            state.verification_count = state.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        set_return_data(&[if passed { VERIFICATION_DECISION_PASS } else { VERIFICATION_DECISION_FAIL }]);
+        
+        if policy.emit_json_log {
+            log_verification_json(&user, &action, passed, risk_score);
+        }
+        emit!(VerificationEvent {
+            user,
+            principal: user,
+            action,
+            verified: passed,
+            risk_score,
+            overridden: false,
+            amount,
+            scaled_amount: scale_amount(amount, policy.decimals),
+            fee: 0, // Finalizing is permissionless, so nobody is charged
+            risk_model_version,
+            policy_hash: policy_hash(policy)?,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+            integrator: Pubkey::default(), // The deferred call's integrator is not kept on the receipt
+        });
+        
+        if passed {
+            let policy = &mut ctx.accounts.policy;
+            policy.verification_count = policy.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        
+        msg!("Deferred verification finalized for user {} (bucket {}): {}", user, amount_bucket, passed);
+        Ok(())
+    }
+
+    /// Dry-run the checks verify_compliance applies, without mutating state, and return the
+    /// reason codes (CompliFiError variant index) that failed. Stops at the first failure
+    /// unless `collect_all_failures` is set.
+    pub fn simulate_compliance(
+        ctx: Context<SimulateCompliance>,
+        user: Pubkey,
+        collect_all_failures: bool,
+    ) -> Result<Vec<u8>> {
+        let policy = &ctx.accounts.policy;
+        let principal = match &ctx.accounts.delegation {
+            Some(delegation) => {
+                require_keys_eq!(delegation.delegate, user, CompliFiError::Unauthorized);
+                delegation.principal
+            }
+            None => user,
+        };
+        
+        let range_oracle = single_risk_oracle(
+            policy,
+            &ctx.accounts.range_oracle,
+            &ctx.accounts.risk_feed,
+            &ctx.accounts.switchboard_aggregator,
+            &principal,
+        )?;
+        let failures = compliance_failures(
+            policy,
+            policy.max_risk_score,
+            &ctx.accounts.attestation,
+            &principal,
+            ctx.remaining_accounts,
+            range_oracle,
+            &ctx.accounts.user_record,
+            collect_all_failures,
+            Clock::get()?.unix_timestamp,
+        );
+        Ok(failures.into_iter().map(|failure| failure as u8).collect())
+    }
+
+    /// Check whether `user` would pass compliance for `action` without counting a verification
+    /// or emitting events. The outcome is written as a `ComplianceCheckResult` in return data,
+    /// for a caller that CPIs in to read with `get_return_data`.
+    pub fn check_compliance(ctx: Context<CheckCompliance>, user: Pubkey, action: String) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        let range_oracle = single_risk_oracle(
+            policy,
+            &ctx.accounts.range_oracle,
+            &ctx.accounts.risk_feed,
+            &ctx.accounts.switchboard_aggregator,
+            &user,
+        )?;
+        let failures = compliance_failures(
+            policy,
+            policy.action_max_risk_score(&action),
+            &ctx.accounts.attestation,
+            &user,
+            ctx.remaining_accounts,
+            range_oracle,
+            &ctx.accounts.user_record,
+            false,
+            Clock::get()?.unix_timestamp,
+        );
+        let risk_score = assess_risk_score(
+            policy,
+            ctx.remaining_accounts,
+            range_oracle,
+            &user,
+            Some(&ctx.accounts.attestation),
+            &ctx.accounts.user_record,
+        )
+        .unwrap_or(u8::MAX);
+        
+        let result = ComplianceCheckResult {
+            passed: failures.is_empty(),
+            risk_score,
+            reason_code: failures.first().map_or(0, |failure| *failure as u8),
+        };
+        set_return_data(&result.try_to_vec()?);
+        
+        msg!("Compliance check for {} on action {}: passed={}", user, action, result.passed);
+        Ok(())
+    }
+
+    /// Non-erroring counterpart of verify_compliance: runs the same KYC and risk checks and,
+    /// instead of aborting on failure, records the denial reason on the user's record so a
+    /// front-end can show it. Reason codes are those of `simulate_compliance`.
+    pub fn record_compliance_check(
+        ctx: Context<RecordComplianceCheck>,
+        user: Pubkey,
+        action: String,
+    ) -> Result<()> {
+        let user_record = Some(ctx.accounts.user_record.clone());
+        let range_oracle = single_risk_oracle(
+            &ctx.accounts.policy,
+            &ctx.accounts.range_oracle,
+            &ctx.accounts.risk_feed,
+            &ctx.accounts.switchboard_aggregator,
+            &user,
+        )?;
+        let failures = compliance_failures(
+            &ctx.accounts.policy,
+            ctx.accounts.policy.action_max_risk_score(&action),
+            &ctx.accounts.attestation,
+            &user,
+            ctx.remaining_accounts,
+            range_oracle,
+            &user_record,
+            false,
+            Clock::get()?.unix_timestamp,
+        );
+        
+        let record = &mut ctx.accounts.user_record;
+        record.user = user;
+        match failures.first() {
+            Some(failure) => {
+                record.last_denial_code = *failure as u8;
+                record.last_denial_ts = Clock::get()?.unix_timestamp;
+                msg!("Compliance check denied {} on action {}: {}", user, action, failure);
+            }
+            None => msg!("Compliance check passed for {} on action: {}", user, action),
+        }
+        Ok(())
+    }
+
+    /// Read a user's behavioral risk and latest denial in a single return-data blob
+    pub fn get_user_status(ctx: Context<GetUserStatus>, user: Pubkey) -> Result<UserStatus> {
+        let record = &ctx.accounts.user_record;
+        Ok(UserStatus {
+            user,
+            behavioral_risk: record.behavioral_risk,
+            last_denial_code: record.last_denial_code,
+            last_denial_ts: record.last_denial_ts,
+        })
+    }
+
+    /// Create the receipt verify_compliance caches `user`'s outcome for `action` under `policy`
+    /// in, for amounts in `amount_bucket` (see `amount_bucket`). Permissionless.
+    pub fn init_verification_receipt(
+        ctx: Context<InitVerificationReceipt>,
+        user: Pubkey,
+        action: String,
+        amount_bucket: u8,
+    ) -> Result<()> {
+        require!(amount_bucket <= MAX_AMOUNT_BUCKET, CompliFiError::InvalidPolicyParameters);
+        
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.user = user;
+        receipt.policy = ctx.accounts.policy.key();
+        receipt.action_hash = action_hash(&action);
+        receipt.amount_bucket = amount_bucket;
+        receipt.policy_revision = 0;
+        receipt.risk_score = 0;
+        receipt.verified_at = 0; // Never verified
+        receipt.valid_until = 0;
+        receipt.pending_since = 0;
+        receipt.pending_amount = 0;
+        receipt.risk_model_version = 0;
+        
+        msg!("Verification receipt created for user {} action {} bucket {}", user, action, amount_bucket);
+        Ok(())
+    }
+
+    /// Create the cache `refresh_risk_batch` keeps `user`'s risk score in (permissionless)
+    pub fn init_risk_cache(ctx: Context<InitRiskCache>, user: Pubkey) -> Result<()> {
+        let cache = &mut ctx.accounts.risk_cache;
+        cache.user = user;
+        cache.risk_score = 0;
+        cache.oracle = Pubkey::default();
+        cache.oracle_updated_at = 0;
+        cache.model_version = 0;
+        cache.refreshed_at = 0; // Never refreshed
+        
+        msg!("Risk cache created for user: {}", user);
+        Ok(())
+    }
+
+    /// Keeper crank refreshing the cached risk scores of up to MAX_RISK_REFRESH_BATCH users.
+    /// Remaining accounts are one (oracle, risk cache) pair per user, in order. A user whose
+    /// oracle lookup or cache update fails is skipped rather than aborting the batch.
+    pub fn refresh_risk_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefreshRiskBatch<'info>>,
+        users: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            !users.is_empty() && users.len() <= MAX_RISK_REFRESH_BATCH,
+            CompliFiError::InvalidPolicyParameters
+        );
+        require!(
+            ctx.remaining_accounts.len() == users.len() * 2,
+            CompliFiError::AccountCountMismatch
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let mut refreshed = 0;
+        for (user, accounts) in users.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let (oracle, cache) = (&accounts[0], &accounts[1]);
+            match refresh_risk_cache(oracle, cache, user, now) {
+                Ok(risk_score) => {
+                    refreshed += 1;
+                    emit!(RiskRefreshedEvent {
+                        user: *user,
+                        risk_score,
+                        oracle: *oracle.key,
+                        refreshed_at: now,
+                    });
+                }
+                Err(_) => msg!("Risk refresh skipped for user: {}", user),
+            }
+        }
+        
+        msg!("Refreshed {} of {} risk caches", refreshed, users.len());
+        Ok(())
+    }
+
+    /// Normalized creditworthiness signal for lending protocols, built from the user's KYC
+    /// level, attestation age, violation history and risk score. Returned as return data so a
+    /// lender can read it after a CPI.
+    pub fn get_underwriting_signal(ctx: Context<GetUnderwritingSignal>, user: Pubkey) -> Result<UnderwritingSignal> {
+        let attestation = &ctx.accounts.attestation;
+        let record = &ctx.accounts.user_record;
+        let now = Clock::get()?.unix_timestamp;
+        
+        let kyc_level = kyc_level(attestation);
+        let attestation_age = (now - attestation.identity_verified_at).max(0);
+        let violation_count = record.as_ref().map_or(0, |r| r.violation_count);
+        // No policy picks the oracle kind here, so a registered Switchboard feed wins over a Range entry
+        let aggregator = registered_aggregator(
+            ctx.accounts.risk_feed.as_deref().map(|feed| &**feed),
+            ctx.accounts.switchboard_aggregator.as_ref().map(AsRef::as_ref),
+            &user,
+        )?;
+        let oracle_score = match (aggregator, &ctx.accounts.range_oracle) {
+            (Some(aggregator), _) => get_wallet_risk_score(aggregator, &user, ORACLE_KIND_SWITCHBOARD, 0)?.0,
+            (None, Some(oracle)) => get_wallet_risk_score(oracle, &user, ORACLE_KIND_RANGE, 0)?.0,
+            (None, None) => {
+                return Err(oracle_failure(&user, ORACLE_FAILURE_MISSING, CompliFiError::OracleDataFetchFailed))
+            }
+        };
+        let risk_score = oracle_score.max(record.as_ref().map_or(0, |r| r.behavioral_risk));
+        
+        let kyc_component =
+            UNDERWRITING_COMPONENT_MAX * kyc_level as u16 / (KYC_LEVEL_COUNT as u16 - 1);
+        let maturity = UNDERWRITING_MATURITY_DAYS * SECONDS_PER_DAY;
+        let age_component =
+            (UNDERWRITING_COMPONENT_MAX as i64 * attestation_age.min(maturity) / maturity) as u16;
+        let violation_component = UNDERWRITING_COMPONENT_MAX
+            .saturating_sub(UNDERWRITING_VIOLATION_PENALTY.saturating_mul(violation_count.min(u16::MAX as u32) as u16));
+        let risk_component = UNDERWRITING_COMPONENT_MAX
+            * MAX_RISK_SCORE.saturating_sub(risk_score) as u16
+            / MAX_RISK_SCORE as u16;
+        
+        Ok(UnderwritingSignal {
+            user,
+            score: kyc_component + age_component + violation_component + risk_component,
+            kyc_component,
+            age_component,
+            violation_component,
+            risk_component,
+            kyc_level,
+            attestation_age,
+            violation_count,
+            risk_score,
+        })
+    }
+
+    /// Read the program's headline counters in a single return-data blob
+    pub fn get_metrics(ctx: Context<GetMetrics>) -> Result<ComplianceMetrics> {
+        let state = &ctx.accounts.state;
+        let slot = Clock::get()?.slot;
+        
+        Ok(ComplianceMetrics {
+            verification_count: state.verification_count,
+            violation_count: state.violation_count,
+            emergency_count: state.emergency_count,
+            // The stored count is stale once the slot it refers to has passed
+            verifications_this_slot: if state.throughput_slot == slot {
+                state.verifications_this_slot
+            } else {
+                0
+            },
+            active_policy_count: state.policy_count,
+            paused: state.paused,
+        })
+    }
+
+    /// Minimal compliance gate for other programs: returns Ok only when `user` would pass
+    /// verify_compliance for `action` under the stored policy, and errors otherwise. It runs the
+    /// same wallet blocks, policy gates and pre-write checks, then the same risk decision; with
+    /// no counterparty or amount, attestations scoped to counterparties do not pass it and amount
+    /// caps do not apply. Writes nothing, so it is safe to make the first CPI of a caller's
+    /// instruction. A caller forwards, all read-only:
+    ///   state          - the ComplianceState the policy belongs to
+    ///   policy         - the CompliancePolicy to enforce
+    ///   attestation    - the KycAttestation `attestation_authority` issued the user,
+    ///                    [KYC_ATTESTATION_SEED, attestation_authority, user]
+    ///   user_record    - the user's UserComplianceRecord PDA, [USER_RECORD_SEED, user], or None
+    ///   sanction       - the user's sanction entry address, [SANCTION_SEED, user], listed or not
+    ///   attestor_entry - the issuer's allowlist entry, [ATTESTOR_SEED, issuer], or None
+    ///   range_oracle   - the user's Range Oracle risk entry, under a Range policy
+    ///   risk_feed      - the user's RiskFeed PDA, [RISK_FEED_SEED, user], under a Switchboard policy
+    ///   switchboard_aggregator - the aggregator registered in risk_feed, under a Switchboard policy
+    ///   remaining      - attester profiles and, under a quorum policy, the risk oracle accounts,
+    ///                    as for verify_compliance
+    pub fn guard(
+        ctx: Context<Guard>,
+        user: Pubkey,
+        attestation_authority: Pubkey,
+        action: String,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        require!(action.len() <= MAX_ACTION_LEN, CompliFiError::StringTooLong);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let policy = &ctx.accounts.policy;
+        let attestation = &ctx.accounts.attestation;
+        check_wallet_not_blocked(Some(attestation), &ctx.accounts.sanction, &ctx.accounts.user_record, now)?;
+        
+        let (attesters, oracles): (Vec<AccountInfo>, Vec<AccountInfo>) = ctx
+            .remaining_accounts
+            .iter()
+            .cloned()
+            .partition(|account| account.owner == &crate::ID);
+        check_verification_requirements(
+            policy,
+            &action,
+            Some(attestation),
+            &user,
+            &attesters,
+            &ctx.accounts.attestor_entry,
+            &Pubkey::default(),
+            None,
+            0,
+            ctx.accounts.user_record.as_deref(),
+            now,
+        )?;
+        
+        let max_risk_score =
+            policy.jurisdiction_max_risk_score(attestation.jurisdiction, policy.action_max_risk_score(&action));
+        let risk_score = assess_risk_score(
+            policy,
+            &oracles,
+            single_risk_oracle(
+                policy,
+                &ctx.accounts.range_oracle,
+                &ctx.accounts.risk_feed,
+                &ctx.accounts.switchboard_aggregator,
+                &user,
+            )?,
+            &user,
+            Some(attestation),
+            &ctx.accounts.user_record,
+        )?;
+        let oracle_passes = risk_score <= max_risk_score.saturating_add(policy.risk_tolerance);
+        let attester_passes = attestation.is_verified();
+        if oracle_passes != attester_passes && attester_wins_conflict(policy, attester_passes) {
+            require!(attester_passes, CompliFiError::KycNotVerified);
+        } else {
+            require!(oracle_passes, CompliFiError::RiskScoreTooHigh);
+        }
+        
+        msg!(
+            "Compliance guard passed for {} on action: {} (attested by {})",
+            user,
+            action,
+            attestation_authority
+        );
+        Ok(())
+    }
+
+    /// Verify an anonymous action of `amount` by the holder of `nullifier` (a privacy-preserving
+    /// per-device or per-identity tag). It passes without KYC while the nullifier's lifetime
+    /// volume stays within the policy's `anonymous_lifetime_cap`; past the cap the signer must
+    /// pass the policy's KYC checks, passing its attestation.
+    pub fn verify_anonymous(
+        ctx: Context<VerifyAnonymous>,
+        nullifier: [u8; 32],
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        
+        let policy = &ctx.accounts.policy;
+        require!(policy_active(policy, Clock::get()?.unix_timestamp), CompliFiError::PolicyNotActive);
+        let usage = &mut ctx.accounts.usage;
+        let total = usage.cumulative_volume.checked_add(amount).ok_or(CompliFiError::AnonymousCapExceeded)?;
+        
+        if total > policy.anonymous_lifetime_cap {
+            let user = ctx.accounts.user.key();
+            let attestation = ctx.accounts.attestation.as_ref().ok_or(CompliFiError::AnonymousCapExceeded)?;
+            require!(!attestation.frozen(), CompliFiError::WalletFrozen);
+            check_kyc_attestation(policy, attestation, &user)?;
+            msg!("Anonymous cap exceeded; verified {} by KYC", user);
+        }
+        
+        let now = Clock::get()?.unix_timestamp;
+        if usage.first_used_at == 0 {
+            usage.nullifier = nullifier;
+            usage.first_used_at = now;
+        }
+        usage.cumulative_volume = total;
+        usage.last_used_at = now;
+        
+        msg!("Anonymous usage recorded: {} of cap {}", total, policy.anonymous_lifetime_cap);
+        Ok(())
+    }
+
+    /// Check a Token-2022 transfer against both the mint's own transfer restrictions and
+    /// CompliFi's KYC/risk checks on the source and destination owners
+    pub fn verify_token2022_transfer(
+        ctx: Context<VerifyToken2022Transfer>,
+        mint: Pubkey,
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        let now = Clock::get()?.unix_timestamp;
+        check_wallet_not_blocked(Some(&ctx.accounts.source_attestation), &ctx.accounts.source_sanction, &None, now)?;
+        check_wallet_not_blocked(
+            Some(&ctx.accounts.destination_attestation),
+            &ctx.accounts.destination_sanction,
+            &None,
+            now,
+        )?;
+        
+        // 1. Mint layer: non-transferable or unauditable confidential mints, frozen accounts
+        let config = read_mint_transfer_config(&ctx.accounts.token_mint)?;
+        require!(!config.non_transferable, CompliFiError::TokenTransferRestricted);
+        require!(
+            !config.confidential || config.confidential_auditable,
+            CompliFiError::TokenTransferRestricted
+        );
+        
+        let source_account = read_token_account(&ctx.accounts.source_token)?;
+        let destination_account = read_token_account(&ctx.accounts.destination_token)?;
+        for account in [&source_account, &destination_account] {
+            require_keys_eq!(account.mint, mint, CompliFiError::InvalidTokenAccount);
+            require!(!account.frozen, CompliFiError::TokenTransferRestricted);
+        }
+        
+        // 2. CompliFi layer: both owners must pass KYC and risk checks
+        let policy = &ctx.accounts.policy;
+        require!(policy_active(policy, now), CompliFiError::PolicyNotActive);
+        let parties = [
+            (
+                source_account.owner,
+                &ctx.accounts.source_attestation,
+                single_risk_oracle(
+                    policy,
+                    &ctx.accounts.source_range_oracle,
+                    &ctx.accounts.source_risk_feed,
+                    &ctx.accounts.source_switchboard_aggregator,
+                    &source_account.owner,
+                )?,
+            ),
+            (
+                destination_account.owner,
+                &ctx.accounts.destination_attestation,
+                single_risk_oracle(
+                    policy,
+                    &ctx.accounts.destination_range_oracle,
+                    &ctx.accounts.destination_risk_feed,
+                    &ctx.accounts.destination_switchboard_aggregator,
+                    &destination_account.owner,
+                )?,
+            ),
+        ];
+        for (owner, attestation, range_oracle) in parties {
+            if policy.require_kyc {
+                check_kyc_attestation(policy, attestation, &owner)?;
+            }
+            let risk_score =
+                assess_risk_score(policy, ctx.remaining_accounts, range_oracle, &owner, Some(attestation), &None)?;
+            require!(risk_score <= policy.max_risk_score, CompliFiError::RiskScoreTooHigh);
+        }
+        
+        let state = &mut ctx.accounts.state;
+        state.verification_count = state.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        emit!(Token2022TransferVerifiedEvent {
+            mint,
+            source,
+            destination,
+            amount,
+            transfer_hook_program: config.transfer_hook_program.unwrap_or_default(),
+        });
+        
+        msg!("Token-2022 transfer of {} verified for mint: {}", amount, mint);
+        Ok(())
+    }
+
+    /// Verify a derivative position: `user` must pass the underlying policy, then the
+    /// derivative policy's overlay on top of it
+    pub fn verify_derivative(
+        ctx: Context<VerifyDerivative>,
+        user: Pubkey,
+        underlying_policy: Pubkey,
+        derivative_policy: Pubkey,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        check_wallet_not_blocked(
+            Some(&ctx.accounts.attestation),
+            &ctx.accounts.sanction,
+            &ctx.accounts.user_record,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        let risk_score = assess_risk_score(
+            &ctx.accounts.underlying,
+            ctx.remaining_accounts,
+            single_risk_oracle(
+                &ctx.accounts.underlying,
+                &ctx.accounts.range_oracle,
+                &ctx.accounts.risk_feed,
+                &ctx.accounts.switchboard_aggregator,
+                &user,
+            )?,
+            &user,
+            Some(&ctx.accounts.attestation),
+            &ctx.accounts.user_record,
+        )?;
+        
+        // The underlying requirement is checked first so failures there surface immediately.
+        // The score is on the underlying policy's scale and moves onto the derivative's.
+        let now = Clock::get()?.unix_timestamp;
+        for policy in [&ctx.accounts.underlying, &ctx.accounts.derivative] {
+            require!(policy_active(policy, now), CompliFiError::PolicyNotActive);
+            if policy.require_kyc {
+                check_kyc_attestation(policy, &ctx.accounts.attestation, &user)?;
+            }
+            let risk_score = rescale_risk_score(risk_score, ctx.accounts.underlying.risk_scale(), policy.risk_scale());
+            require!(risk_score <= policy.max_risk_score, CompliFiError::RiskScoreTooHigh);
+        }
+        
+        let state = &mut ctx.accounts.state;
+        state.verification_count = state.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        emit!(DerivativeVerificationEvent {
+            user,
+            underlying_policy,
+            derivative_policy,
+            risk_score,
+        });
+        
+        msg!("Derivative compliance verification passed for user: {}", user);
+        Ok(())
+    }
+
+    /// Verify `user` once and issue a standing mandate for recurring actions up to `cap`
+    /// within the next `period_secs`
+    pub fn create_mandate(
+        ctx: Context<CreateMandate>,
+        user: Pubkey,
+        cap: u64,
+        period_secs: i64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        let now = Clock::get()?.unix_timestamp;
+        check_wallet_not_blocked(Some(&ctx.accounts.attestation), &ctx.accounts.sanction, &ctx.accounts.user_record, now)?;
+        require!(cap > 0 && period_secs > 0, CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &ctx.accounts.policy;
+        require!(policy_active(policy, now), CompliFiError::PolicyNotActive);
+        if policy.require_kyc {
+            check_kyc_attestation(policy, &ctx.accounts.attestation, &user)?;
+        }
+        let risk_score = assess_risk_score(
+            policy,
+            ctx.remaining_accounts,
+            single_risk_oracle(
+                policy,
+                &ctx.accounts.range_oracle,
+                &ctx.accounts.risk_feed,
+                &ctx.accounts.switchboard_aggregator,
+                &user,
+            )?,
+            &user,
+            Some(&ctx.accounts.attestation),
+            &ctx.accounts.user_record,
+        )?;
+        require!(risk_score <= policy.max_risk_score, CompliFiError::RiskScoreTooHigh);
+        
+        let mandate = &mut ctx.accounts.mandate;
+        mandate.user = user;
+        mandate.policy = policy.key();
+        mandate.authority = ctx.accounts.authority.key();
+        mandate.cap = cap;
+        mandate.remaining = cap;
+        mandate.created_at = now;
+        mandate.expires_at = now.checked_add(period_secs).ok_or(CompliFiError::CounterOverflow)?;
+        
+        msg!("Payment mandate for user {} created: cap={}, expires_at={}", user, cap, mandate.expires_at);
+        Ok(())
+    }
+
+    /// Spend from a payment mandate instead of running a full verification
+    pub fn consume_mandate(ctx: Context<ConsumeMandate>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        // A wallet frozen, sanctioned or held after the mandate was issued loses its standing allowance
+        let now = Clock::get()?.unix_timestamp;
+        check_wallet_not_blocked(Some(&ctx.accounts.attestation), &ctx.accounts.sanction, &None, now)?;
+        
+        let mandate = &mut ctx.accounts.mandate;
+        require!(now < mandate.expires_at, CompliFiError::MandateExpired);
+        mandate.remaining = mandate
+            .remaining
+            .checked_sub(amount)
+            .ok_or(CompliFiError::MandateExhausted)?;
+        
+        emit!(MandateConsumedEvent {
+            user: mandate.user,
+            amount,
+            remaining: mandate.remaining,
+        });
+        
+        msg!("Payment mandate for user {} consumed {}, {} remaining", mandate.user, amount, mandate.remaining);
+        Ok(())
+    }
+
+    /// Authorize `delegate` to act on the signing principal's compliance status
+    pub fn authorize_delegate(ctx: Context<AuthorizeDelegate>, delegate: Pubkey) -> Result<()> {
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.principal = ctx.accounts.principal.key();
+        delegation.delegate = delegate;
+        delegation.created_at = Clock::get()?.unix_timestamp;
+        
+        emit!(DelegationEvent {
+            principal: delegation.principal,
+            delegate,
+            authorized: true,
+        });
+        
+        msg!("Delegate {} authorized for principal {}", delegate, delegation.principal);
+        Ok(())
+    }
+
+    /// Revoke a delegate and reclaim the delegation record's rent
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>, delegate: Pubkey) -> Result<()> {
+        let principal = ctx.accounts.principal.key();
+        
+        emit!(DelegationEvent {
+            principal,
+            delegate,
+            authorized: false,
+        });
+        
+        msg!("Delegate {} revoked for principal {}", delegate, principal);
+        Ok(())
+    }
+
+    /// Link a sub-account wallet to a parent entity so it verifies under the parent's KYB
+    /// attestation and jurisdiction (attester only)
+    pub fn link_subaccount(ctx: Context<LinkSubaccount>, subaccount: Pubkey) -> Result<()> {
+        let parent = ctx.accounts.parent_attestation.wallet;
+        require_keys_neq!(parent, subaccount, CompliFiError::InvalidPolicyParameters);
+        
+        let entity = &mut ctx.accounts.entity;
+        entity.parent = parent;
+        entity.subaccount = subaccount;
+        entity.linked_at = Clock::get()?.unix_timestamp;
+        
+        emit!(EntityLinkEvent {
+            parent,
+            subaccount,
+            linked: true,
+        });
+        
+        msg!("Sub-account {} linked to parent entity {}", subaccount, parent);
+        Ok(())
+    }
+
+    /// Unlink a sub-account from its parent entity and reclaim the link's rent (attester only)
+    pub fn unlink_subaccount(ctx: Context<UnlinkSubaccount>, subaccount: Pubkey) -> Result<()> {
+        let parent = ctx.accounts.entity.parent;
+        
+        emit!(EntityLinkEvent {
+            parent,
+            subaccount,
+            linked: false,
+        });
+        
+        msg!("Sub-account {} unlinked from parent entity {}", subaccount, parent);
+        Ok(())
+    }
+
+    /// Verify a multisig wallet by requiring its members to be compliant.
+    /// `remaining_accounts` holds one equal-sized group per member, in `members` order: the
+    /// member's attestation account, then its risk oracle accounts as assess_member_risk reads them.
+    pub fn verify_compliance_multisig<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyComplianceMultisig<'info>>,
+        multisig: Pubkey,
+        members: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        
+        let policy = &ctx.accounts.policy;
+        require!(policy_active(policy, Clock::get()?.unix_timestamp), CompliFiError::PolicyNotActive);
+        require!(
+            !members.is_empty() && members.len() <= MAX_MULTISIG_MEMBERS,
+            CompliFiError::InvalidPolicyParameters
+        );
+        let group_len = ctx.remaining_accounts.len() / members.len();
+        require!(
+            group_len > 0 && ctx.remaining_accounts.len().is_multiple_of(members.len()),
+            CompliFiError::AccountCountMismatch
+        );
+        
+        let failed_members: Vec<Pubkey> = members
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(group_len))
+            .filter(|(member, accounts)| check_multisig_member(policy, &accounts[0], &accounts[1..], member).is_err())
+            .map(|(member, _)| *member)
+            .collect();
+        
+        // 0 requires every member; otherwise at least that many must pass
+        let required = match policy.multisig_min_compliant {
+            0 => members.len(),
+            min => (min as usize).min(members.len()),
+        };
+        let compliant = members.len() - failed_members.len();
+        let verified = compliant >= required;
+        
+        emit!(MultisigVerificationEvent {
+            multisig,
+            verified,
+            compliant_members: compliant as u8,
+            required_members: required as u8,
+            failed_members: failed_members.clone(),
+        });
+        
+        if !verified {
+            msg!("Multisig {} failed compliance; non-compliant members: {:?}", multisig, failed_members);
+            return err!(CompliFiError::MultisigNotCompliant);
+        }
+        
+        let state = &mut ctx.accounts.state;
+        state.verification_count = state.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        if let Some(report) = active_report(state, &mut ctx.accounts.report)? {
+            report.verification_count = report.verification_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        
+        msg!("Compliance verification passed for multisig: {}", multisig);
+        Ok(())
+    }
+
+    /// Verify up to MAX_VERIFICATION_BATCH users for one action atomically, e.g. every
+    /// counterparty of a settled batch of trades. remaining_accounts holds one equal-sized
+    /// group per user, in order: the user's attestation PDA, then its risk oracle accounts as
+    /// assess_member_risk reads them. Any user failing its checks fails the whole batch.
+    pub fn verify_compliance_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyComplianceBatch<'info>>,
+        users: Vec<Pubkey>,
+        action: String,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        require!(action.len() <= MAX_ACTION_LEN, CompliFiError::StringTooLong);
+        require!(
+            !users.is_empty() && users.len() <= MAX_VERIFICATION_BATCH,
+            CompliFiError::BatchTooLarge
+        );
+        let group_len = ctx.remaining_accounts.len() / users.len();
+        require!(
+            group_len > 0 && ctx.remaining_accounts.len().is_multiple_of(users.len()),
+            CompliFiError::AccountCountMismatch
+        );
+        
+        let policy = &ctx.accounts.policy;
+        let now = Clock::get()?.unix_timestamp;
+        require!(policy_active(policy, now), CompliFiError::PolicyNotActive);
+        require!(within_trading_window(policy, now), CompliFiError::OutsideTradingWindow);
+        require!(policy.is_action_allowed(&action), CompliFiError::ActionNotAllowed);
+        let max_risk_score = policy.action_max_risk_score(&action);
+        
+        let mut passed = Vec::with_capacity(users.len());
+        for (user, accounts) in users.iter().zip(ctx.remaining_accounts.chunks(group_len)) {
+            // Each member may be attested by any authority, so its namespace is read back
+            let attestation = Account::<KycAttestation>::try_from(&accounts[0])?;
+            let (address, _) = kyc_attestation_pda(&attestation.authority, user);
+            require_keys_eq!(*accounts[0].key, address, CompliFiError::AttestationAddressMismatch);
+            let (risk_score, risk_model_version) =
+                check_batch_member(policy, &attestation, &accounts[1..], user, max_risk_score, now)?;
+            passed.push((*user, attestation.jurisdiction, risk_score, risk_model_version));
+        }
+        
+        // Count the batch within the global per-slot throughput cap
+        let batch_size = users.len() as u64;
+        let slot = Clock::get()?.slot;
+        let state = &mut ctx.accounts.state;
+        if state.throughput_slot != slot {
+            state.throughput_slot = slot;
+            state.verifications_this_slot = 0;
+        }
+        let this_slot = state.verifications_this_slot.checked_add(users.len() as u32).ok_or(CompliFiError::CounterOverflow)?;
+        require!(
+            state.max_verifications_per_slot == 0 || this_slot <= state.max_verifications_per_slot,
+            CompliFiError::ThroughputExceeded
+        );
+        state.verifications_this_slot = this_slot;
+        state.verification_count = state.verification_count.checked_add(batch_size).ok_or(CompliFiError::CounterOverflow)?;
+        
+        if let Some(report) = active_report(state, &mut ctx.accounts.report)? {
+            report.verification_count = report.verification_count.checked_add(batch_size).ok_or(CompliFiError::CounterOverflow)?;
+            for (_, jurisdiction, _, _) in &passed {
+                let count = &mut report.jurisdiction_counts[*jurisdiction as usize];
+                *count = count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+            }
+        }
+        
+        let snapshot = policy_hash(policy)?;
+        for (user, _, risk_score, risk_model_version) in passed {
+            emit!(VerificationEvent {
+                user,
+                principal: user,
+                action: action.clone(),
+                verified: true,
+                risk_score,
+                overridden: false,
+                amount: 0,
+                scaled_amount: 0,
+                fee: 0,
+                risk_model_version,
+                policy_hash: snapshot,
+                seq: next_event_seq(&mut ctx.accounts.state)?,
+                integrator: Pubkey::default(),
+            });
+        }
+        
+        msg!("Compliance verification passed for a batch of {} users", batch_size);
+        Ok(())
+    }
+
+    /// Break-glass override that records a forced verification pass (authority only)
+    pub fn emergency_verify(
+        ctx: Context<EmergencyVerify>,
+        user: Pubkey,
+        action: String,
+        reason: String,
+    ) -> Result<()> {
+        require!(!reason.trim().is_empty(), CompliFiError::EmergencyReasonRequired);
+        
+        // Tracked separately so forced passes never blend into normal verification stats
+        let state = &mut ctx.accounts.state;
+        state.emergency_count = state.emergency_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        emit!(EmergencyVerificationEvent {
+            user,
+            action,
+            reason,
+            authority: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Emergency verification forced for user: {}", user);
+        Ok(())
+    }
+
+    /// Admin function to designate the stricter internal policy treasury operations are held to
+    pub fn set_treasury_policy(ctx: Context<UpdateState>, policy: Pubkey) -> Result<()> {
+        ctx.accounts.state.treasury_policy = policy;
+        
+        msg!("Treasury policy set to: {}", policy);
+        Ok(())
+    }
+
+    /// Admin function to designate the policy whose reviewed-document minimums every
+    /// attestation issuance must meet
+    pub fn set_issuance_policy(ctx: Context<UpdateState>, policy: Pubkey) -> Result<()> {
+        ctx.accounts.state.issuance_policy = policy;
+        
+        msg!("Issuance policy set to: {}", policy);
+        Ok(())
+    }
+
+    /// Check a movement of the protocol's own funds against the treasury policy. The signing
+    /// operator must pass its KYC and risk checks, and `amount` must fit its amount cap.
+    pub fn verify_treasury_op(ctx: Context<VerifyTreasuryOp>, operation: String, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, CompliFiError::ProgramPaused);
+        
+        let policy = &ctx.accounts.policy;
+        require!(policy_active(policy, Clock::get()?.unix_timestamp), CompliFiError::PolicyNotActive);
+        let attestation = &ctx.accounts.attestation;
+        let operator = ctx.accounts.operator.key();
+        require!(!attestation.frozen(), CompliFiError::WalletFrozen);
+        require!(
+            !regulatory_hold_active(attestation, &ctx.accounts.user_record, Clock::get()?.unix_timestamp),
+            CompliFiError::RegulatoryHold
+        );
+        
+        // Operators are always KYC-checked, whether or not the policy requires it of users
+        check_kyc_attestation(policy, attestation, &operator)?;
+        let risk_score = assess_risk_score(
+            policy,
+            ctx.remaining_accounts,
+            single_risk_oracle(
+                policy,
+                &ctx.accounts.range_oracle,
+                &ctx.accounts.risk_feed,
+                &ctx.accounts.switchboard_aggregator,
+                &operator,
+            )?,
+            &operator,
+            Some(attestation),
+            &ctx.accounts.user_record,
+        )?;
+        require!(risk_score <= policy.max_risk_score, CompliFiError::RiskScoreTooHigh);
+        require!(policy.max_amount == 0 || amount <= policy.max_amount, CompliFiError::AmountExceedsLimit);
+        
+        emit!(TreasuryOperationEvent {
+            operator,
+            operation: operation.clone(),
+            amount,
+            risk_score,
+        });
+        
+        msg!("Treasury operation {} of {} approved for operator {}", operation, amount, operator);
+        Ok(())
+    }
+
+    /// Admin function to set the fees charged for renewing before and after expiry
+    pub fn set_renewal_fees(
+        ctx: Context<SetPolicy>,
+        early_renewal_fee: u64,
+        late_renewal_fee: u64,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.early_renewal_fee = early_renewal_fee;
+        policy.late_renewal_fee = late_renewal_fee;
+        policy.fee_recipient = fee_recipient;
+        
+        msg!("Renewal fees updated: early={}, late={}", early_renewal_fee, late_renewal_fee);
+        Ok(())
+    }
+
+    /// Admin function to create the policy's verification fee ledger. From then on every
+    /// fee-paying verification against the policy must pass it.
+    pub fn init_fee_ledger(ctx: Context<InitFeeLedger>) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let ledger = &mut ctx.accounts.fee_ledger;
+        ledger.policy = ctx.accounts.policy.key();
+        ledger.fees_collected = 0;
+        ledger.fee_payer_count = 0;
+        ledger.last_payer = Pubkey::default();
+        ledger.last_fee = 0;
+        ledger.last_paid_at = 0;
+        ledger.created_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.policy.fee_ledger_enabled = true;
+        
+        msg!("Fee ledger created for policy: {}", ledger.policy);
+        Ok(())
+    }
+
+    /// Return the policy's fee ledger, for treasury reconciliation
+    pub fn get_fee_ledger(ctx: Context<GetFeeLedger>) -> Result<FeeLedger> {
+        Ok((*ctx.accounts.fee_ledger).clone())
+    }
+
+    /// Admin function to set the fee charged per verification. The base fee grows by
+    /// `risk_fee_multiplier_bps` of itself for each point of the user's risk score.
+    pub fn set_verification_fee(
+        ctx: Context<SetPolicy>,
+        verification_fee: u64,
+        risk_fee_multiplier_bps: u16,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.verification_fee = verification_fee;
+        policy.risk_fee_multiplier_bps = risk_fee_multiplier_bps;
+        
+        msg!("Verification fee updated: base={}, multiplier={} bps", verification_fee, risk_fee_multiplier_bps);
+        Ok(())
+    }
+
+    /// Admin function to reject risk scores produced by oracle models older than `min_version`
+    /// (0 = any version)
+    pub fn set_min_risk_model_version(ctx: Context<SetPolicy>, min_version: u32) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.min_risk_model_version = min_version;
+        
+        msg!("Minimum risk model version set to {}", min_version);
+        Ok(())
+    }
+
+    /// Admin function to set the jurisdiction coding scheme the policy's allowed-jurisdiction
+    /// bitmap is built for
+    pub fn set_jurisdiction_scheme(ctx: Context<SetPolicy>, scheme: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.jurisdiction_scheme = scheme;
+        
+        msg!("Jurisdiction scheme set to {}", scheme);
+        Ok(())
+    }
+
+    /// Admin function to require attestations to carry a wallet ownership proof
+    pub fn set_require_ownership_proof(ctx: Context<SetPolicy>, required: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.require_ownership_proof = required;
+        
+        msg!("Ownership proof required: {}", required);
+        Ok(())
+    }
+
+    /// Admin function to put verifications under dual control: an attestation only counts once
+    /// a registered attester other than its issuer has co-signed it
+    pub fn set_require_dual_attestation(ctx: Context<SetPolicy>, required: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.require_dual_attestation = required;
+        
+        msg!("Dual attestation required: {}", required);
+        Ok(())
+    }
+
+    /// Admin function to require the issuer of a verified attestation to still be allowlisted,
+    /// so removing an attestor retires the attestations it issued
+    pub fn set_require_trusted_attestor(ctx: Context<SetPolicy>, required: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.require_trusted_attestor = required;
+        
+        msg!("Trusted attestor required: {}", required);
+        Ok(())
+    }
+
+    /// Admin function to toggle returning a signed-result digest from verify_compliance
+    pub fn set_result_signing(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.sign_results = enabled;
+        
+        msg!("Verification result signing: {}", enabled);
+        Ok(())
+    }
+
+    /// Admin function to require attestations from a specific screening vendor (0 = any)
+    pub fn set_required_vendor(ctx: Context<SetPolicy>, required_vendor: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.required_vendor = required_vendor;
+        
+        msg!("Required screening vendor updated: {}", required_vendor);
+        Ok(())
+    }
+
+    /// Admin function to set the token amounts are denominated in
+    pub fn set_denomination(
+        ctx: Context<SetPolicy>,
+        denomination_mint: Pubkey,
+        decimals: u8,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(decimals <= MAX_DECIMALS, CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.denomination_mint = denomination_mint;
+        policy.decimals = decimals;
+        
+        msg!("Denomination updated: mint={}, decimals={}", denomination_mint, decimals);
+        Ok(())
+    }
+
+    /// Admin function to set how many multisig members must be compliant (0 = all)
+    pub fn set_multisig_threshold(ctx: Context<SetPolicy>, min_compliant: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            min_compliant as usize <= MAX_MULTISIG_MEMBERS,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        ctx.accounts.policy.multisig_min_compliant = min_compliant;
+        
+        msg!("Multisig threshold updated: min_compliant={}", min_compliant);
+        Ok(())
+    }
+
+    /// Snapshot the effective policy into its CompiledPolicy PDA for verify_compliance to read
+    /// (permissionless; the result is fully determined by the policy)
+    pub fn compile_policy(ctx: Context<CompilePolicy>) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        let compiled = &mut ctx.accounts.compiled_policy;
+        compiled.policy = policy.key();
+        compiled.source_revision = policy.revision;
+        compiled.compiled_at = Clock::get()?.unix_timestamp;
+        compiled.effective = (**policy).clone();
+        
+        msg!("Policy {} compiled at revision {}", compiled.policy, compiled.source_revision);
+        Ok(())
+    }
+
+    /// Admin function to start a staged rollout: the policy as it stands is snapshotted as the
+    /// baseline, and only the `rollout_bps` share of users sees changes made after this
+    pub fn begin_policy_rollout(ctx: Context<BeginPolicyRollout>, rollout_bps: u16) -> Result<()> {
+        require!(rollout_bps <= FULL_ROLLOUT_BPS, CompliFiError::InvalidPolicyParameters);
+        let snapshot = (**ctx.accounts.policy).clone();
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let baseline = &mut ctx.accounts.rollout_baseline;
+        baseline.policy = ctx.accounts.policy.key();
+        baseline.source_revision = snapshot.revision;
+        baseline.compiled_at = Clock::get()?.unix_timestamp;
+        baseline.effective = snapshot;
+        ctx.accounts.policy.rollout_bps = rollout_bps;
+        
+        msg!("Policy rollout started at {} bps from revision {}", rollout_bps, baseline.source_revision);
+        Ok(())
+    }
+
+    /// Admin function to widen or narrow a staged rollout's cohort
+    pub fn set_rollout_bps(ctx: Context<SetPolicy>, rollout_bps: u16) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(rollout_bps <= FULL_ROLLOUT_BPS, CompliFiError::InvalidPolicyParameters);
+        ctx.accounts.policy.rollout_bps = rollout_bps;
+        
+        msg!("Policy rollout set to {} bps", rollout_bps);
+        Ok(())
+    }
+
+    /// Create a consortium of attesters that jointly govern the policies assigned to it.
+    /// `threshold` of the `members` must vote for a change before it applies.
+    pub fn create_consortium(
+        ctx: Context<CreateConsortium>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_CONSORTIUM_MEMBERS,
+            CompliFiError::InvalidPolicyParameters
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= members.len(),
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        let consortium = &mut ctx.accounts.consortium;
+        consortium.founder = ctx.accounts.founder.key();
+        consortium.members = [Pubkey::default(); MAX_CONSORTIUM_MEMBERS];
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), CompliFiError::InvalidPolicyParameters);
+            consortium.members[i] = *member;
+        }
+        consortium.member_count = members.len() as u8;
+        consortium.threshold = threshold;
+        
+        msg!("Consortium created with {} members, threshold {}", members.len(), threshold);
+        Ok(())
+    }
+
+    /// Admin function to hand a policy's governance to a consortium. From then on it only
+    /// changes through `propose_policy_change` and `consortium_vote`.
+    pub fn assign_policy_consortium(ctx: Context<AssignPolicyConsortium>) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.consortium = ctx.accounts.consortium.key();
+        
+        msg!("Policy governance assigned to consortium: {}", ctx.accounts.consortium.key());
+        Ok(())
+    }
+
+    /// Propose new core settings (as in `set_policy`) for a consortium-governed policy. The
+    /// proposal is tied to the policy's current revision; the proposer's vote is counted.
+    pub fn propose_policy_change(
+        ctx: Context<ProposePolicyChange>,
+        max_risk_score: u8,
+        require_kyc: bool,
+        allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN],
+    ) -> Result<()> {
+        require!(max_risk_score <= ctx.accounts.policy.risk_scale(), CompliFiError::InvalidPolicyParameters);
+        let member_index = consortium_member_index(&ctx.accounts.consortium, ctx.accounts.proposer.key)?;
+        
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.consortium = ctx.accounts.consortium.key();
+        proposal.policy = ctx.accounts.policy.key();
+        proposal.revision = ctx.accounts.policy.revision;
+        proposal.max_risk_score = max_risk_score;
+        proposal.require_kyc = require_kyc;
+        proposal.allowed_jurisdictions = allowed_jurisdictions;
+        proposal.votes = 0;
+        proposal.vote_count = 0;
+        proposal.executed = false;
+        
+        record_consortium_vote(proposal, &ctx.accounts.consortium, &mut ctx.accounts.policy, member_index)
+    }
+
+    /// Vote for a pending consortium proposal. The vote that reaches the consortium's
+    /// threshold applies the change to the policy.
+    pub fn consortium_vote(ctx: Context<ConsortiumVote>) -> Result<()> {
+        let member_index = consortium_member_index(&ctx.accounts.consortium, ctx.accounts.voter.key)?;
+        record_consortium_vote(
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.consortium,
+            &mut ctx.accounts.policy,
+            member_index,
+        )
+    }
+
+    /// Admin function to set compliance policy. Requiring KYC while allowing no jurisdiction
+    /// blocks everyone, so it is rejected unless `allow_empty_jurisdictions` says it is meant.
+    pub fn set_policy(
+        ctx: Context<SetPolicy>,
+        max_risk_score: u8,
+        require_kyc: bool,
+        allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN],
+        min_kyc_level: u8,
+        effective_from: i64,
+        effective_until: i64,
+        min_verify_interval: i64,
+        max_oracle_staleness: i64,
+        min_attestation_age: i64,
+        max_amount: u64,
+        allow_empty_jurisdictions: bool,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        
+        // Validate policy parameters
+        require!(max_risk_score <= policy.risk_scale(), CompliFiError::InvalidPolicyParameters);
+        require!(
+            !require_kyc || allow_empty_jurisdictions || allowed_jurisdictions.iter().any(|byte| *byte != 0),
+            CompliFiError::NoJurisdictionsAllowed
+        );
+        require!(
+            effective_from == 0 || effective_until == 0 || effective_from < effective_until,
+            CompliFiError::InvalidPolicyParameters
+        );
+        require!(
+            min_verify_interval >= 0 && max_oracle_staleness >= 0 && min_attestation_age >= 0,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        policy.max_risk_score = max_risk_score;
+        policy.require_kyc = require_kyc;
+        policy.allowed_jurisdictions = allowed_jurisdictions;
+        policy.min_kyc_level = min_kyc_level;
+        policy.effective_from = effective_from;
+        policy.effective_until = effective_until;
+        policy.min_verify_interval = min_verify_interval;
+        policy.max_oracle_staleness = max_oracle_staleness;
+        policy.min_attestation_age = min_attestation_age;
+        policy.max_amount = max_amount;
+        
+        emit!(PolicyUpdatedEvent {
+            policy: policy.key(),
+            authority: ctx.accounts.authority.key(),
+            revision: policy.revision,
+            max_risk_score,
+            require_kyc,
+            allowed_jurisdictions,
+            min_kyc_level,
+            effective_from,
+            effective_until,
+            min_verify_interval,
+            max_oracle_staleness,
+            min_attestation_age,
+            max_amount,
+            policy_hash: policy_hash(policy)?,
+        });
+        
+        msg!("Policy updated: max_risk_score={}, require_kyc={}, min_kyc_level={}", 
+            max_risk_score, require_kyc, min_kyc_level);
+        
+        Ok(())
+    }
+
+    /// Admin function to give `action` its own risk threshold in place of `max_risk_score`,
+    /// replacing any it already has. Actions are matched by the first 8 bytes of their
+    /// sha256 hash.
+    pub fn set_action_threshold(ctx: Context<SetPolicy>, action: String, max_risk_score: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(max_risk_score <= ctx.accounts.policy.risk_scale(), CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &mut ctx.accounts.policy;
+        let action_hash = short_action_hash(&action);
+        let count = policy.action_threshold_count as usize;
+        let index = match policy.action_thresholds[..count].iter().position(|t| t.action_hash == action_hash) {
+            Some(index) => index,
+            None => {
+                require!(count < MAX_ACTION_THRESHOLDS, CompliFiError::ActionThresholdLimitReached);
+                policy.action_threshold_count += 1;
+                count
+            }
+        };
+        policy.action_thresholds[index] = ActionThreshold { action_hash, max_risk_score };
+        
+        msg!("Risk threshold for action {} set to {}", action, max_risk_score);
+        Ok(())
+    }
+
+    /// Admin function to return `action` to the policy-wide `max_risk_score`
+    pub fn remove_action_threshold(ctx: Context<SetPolicy>, action: String) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        let action_hash = short_action_hash(&action);
+        let count = policy.action_threshold_count as usize;
+        let index = policy.action_thresholds[..count]
+            .iter()
+            .position(|t| t.action_hash == action_hash)
+            .ok_or(CompliFiError::InvalidPolicyParameters)?;
+        // Keep the used slots contiguous by moving the last one into the gap
+        policy.action_thresholds[index] = policy.action_thresholds[count - 1];
+        policy.action_thresholds[count - 1] = ActionThreshold::default();
+        policy.action_threshold_count -= 1;
+        
+        msg!("Risk threshold for action {} removed", action);
+        Ok(())
+    }
+
+    /// Admin function to cap the risk score of `jurisdiction`'s users, replacing any cap it
+    /// already has. The cap only ever tightens: verify_compliance applies the stricter of it
+    /// and the action's threshold.
+    pub fn set_jurisdiction_risk_cap(ctx: Context<SetPolicy>, jurisdiction: u8, max_risk_score: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(max_risk_score <= ctx.accounts.policy.risk_scale(), CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &mut ctx.accounts.policy;
+        let count = policy.jurisdiction_risk_cap_count as usize;
+        let index = match policy.jurisdiction_risk_caps[..count].iter().position(|cap| cap.jurisdiction == jurisdiction) {
+            Some(index) => index,
+            None => {
+                require!(count < MAX_JURISDICTION_RISK_CAPS, CompliFiError::JurisdictionRiskCapLimitReached);
+                policy.jurisdiction_risk_cap_count += 1;
+                count
+            }
+        };
+        policy.jurisdiction_risk_caps[index] = JurisdictionRiskCap { jurisdiction, max_risk_score };
+        
+        msg!("Risk cap for jurisdiction {} set to {}", jurisdiction, max_risk_score);
+        Ok(())
+    }
+
+    /// Admin function to lift `jurisdiction`'s risk cap
+    pub fn remove_jurisdiction_risk_cap(ctx: Context<SetPolicy>, jurisdiction: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        let count = policy.jurisdiction_risk_cap_count as usize;
+        let index = policy.jurisdiction_risk_caps[..count]
+            .iter()
+            .position(|cap| cap.jurisdiction == jurisdiction)
+            .ok_or(CompliFiError::InvalidPolicyParameters)?;
+        // Keep the used slots contiguous by moving the last one into the gap
+        policy.jurisdiction_risk_caps[index] = policy.jurisdiction_risk_caps[count - 1];
+        policy.jurisdiction_risk_caps[count - 1] = JurisdictionRiskCap::default();
+        policy.jurisdiction_risk_cap_count -= 1;
+        
+        msg!("Risk cap for jurisdiction {} removed", jurisdiction);
+        Ok(())
+    }
+
+    /// Admin function to add `action` to the policy's action whitelist. While the whitelist
+    /// is non-empty, verify_compliance rejects every action not on it.
+    pub fn allow_action(ctx: Context<SetPolicy>, action: String) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        let action_hash = short_action_hash(&action);
+        let count = policy.allowed_action_count as usize;
+        if !policy.allowed_actions[..count].contains(&action_hash) {
+            require!(count < MAX_ALLOWED_ACTIONS, CompliFiError::AllowedActionLimitReached);
+            policy.allowed_actions[count] = action_hash;
+            policy.allowed_action_count += 1;
+        }
+        
+        msg!("Action {} allowed", action);
+        Ok(())
+    }
+
+    /// Admin function to take `action` off the whitelist. Removing the last entry lifts the
+    /// whitelist, permitting every action again.
+    pub fn disallow_action(ctx: Context<SetPolicy>, action: String) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        let action_hash = short_action_hash(&action);
+        let count = policy.allowed_action_count as usize;
+        let index = policy.allowed_actions[..count]
+            .iter()
+            .position(|hash| *hash == action_hash)
+            .ok_or(CompliFiError::InvalidPolicyParameters)?;
+        // Keep the used slots contiguous by moving the last one into the gap
+        policy.allowed_actions[index] = policy.allowed_actions[count - 1];
+        policy.allowed_actions[count - 1] = [0; 8];
+        policy.allowed_action_count -= 1;
+        
+        msg!("Action {} disallowed", action);
+        Ok(())
+    }
+
+    /// Admin function to allow or disallow a single jurisdiction, leaving the rest of the
+    /// bitmap as it is
+    pub fn set_jurisdiction_allowed(ctx: Context<SetPolicy>, jurisdiction: u8, allowed: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        let (index, bit) = jurisdiction_bit(jurisdiction);
+        if allowed {
+            policy.allowed_jurisdictions[index] |= bit;
+        } else {
+            policy.allowed_jurisdictions[index] &= !bit;
+        }
+        
+        emit!(PolicyUpdatedEvent {
+            policy: policy.key(),
+            authority: ctx.accounts.authority.key(),
+            revision: policy.revision,
+            max_risk_score: policy.max_risk_score,
+            require_kyc: policy.require_kyc,
+            allowed_jurisdictions: policy.allowed_jurisdictions,
+            min_kyc_level: policy.min_kyc_level,
+            effective_from: policy.effective_from,
+            effective_until: policy.effective_until,
+            min_verify_interval: policy.min_verify_interval,
+            max_oracle_staleness: policy.max_oracle_staleness,
+            min_attestation_age: policy.min_attestation_age,
+            max_amount: policy.max_amount,
+            policy_hash: policy_hash(policy)?,
+        });
+        
+        msg!("Jurisdiction {} {}", jurisdiction, if allowed { "allowed" } else { "disallowed" });
+        Ok(())
+    }
+
+    /// Admin function to require a quorum of risk oracles to agree
+    pub fn set_oracle_quorum(ctx: Context<SetPolicy>, min_oracle_quorum: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            min_oracle_quorum as usize <= MAX_RISK_ORACLES,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        ctx.accounts.policy.min_oracle_quorum = min_oracle_quorum;
+        
+        msg!("Oracle quorum updated: min_oracle_quorum={}", min_oracle_quorum);
+        Ok(())
+    }
+
+    /// Admin function to choose the risk oracle single-oracle scores are read from. Quorum
+    /// checks always read Range Oracle entries.
+    pub fn set_oracle_kind(ctx: Context<SetPolicy>, oracle_kind: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            oracle_kind == ORACLE_KIND_RANGE || oracle_kind == ORACLE_KIND_SWITCHBOARD,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        ctx.accounts.policy.oracle_kind = oracle_kind;
+        
+        msg!("Oracle kind updated: oracle_kind={}", oracle_kind);
+        Ok(())
+    }
+
+    /// Admin function to register the Switchboard aggregator that scores a wallet. A Switchboard
+    /// feed does not name the wallet it scores, so verify_compliance only accepts the registered one.
+    pub fn set_risk_feed(ctx: Context<SetRiskFeed>, wallet: Pubkey, aggregator: Pubkey) -> Result<()> {
+        let feed = &mut ctx.accounts.risk_feed;
+        feed.wallet = wallet;
+        feed.aggregator = aggregator;
+        feed.set_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Risk feed of wallet {} set to aggregator {}", wallet, aggregator);
+        Ok(())
+    }
+
+    /// Admin function to configure how behavioral risk combines with the oracle score
+    pub fn set_behavioral_risk_mode(
+        ctx: Context<SetPolicy>,
+        mode: u8,
+        weight_bps: u16,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            mode == BEHAVIORAL_RISK_MAX || mode == BEHAVIORAL_RISK_WEIGHTED,
+            CompliFiError::InvalidPolicyParameters
+        );
+        require!(weight_bps <= 10_000, CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.behavioral_risk_mode = mode;
+        policy.behavioral_weight_bps = weight_bps;
+        
+        msg!("Behavioral risk mode updated: mode={}, weight_bps={}", mode, weight_bps);
+        Ok(())
+    }
+
+    /// Admin function to set the default attestation validity for each jurisdiction risk tier
+    pub fn set_tier_validity(
+        ctx: Context<SetPolicy>,
+        tier_validity: [i64; RISK_TIER_COUNT],
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            tier_validity.iter().all(|v| (0..=MAX_TIER_VALIDITY).contains(v)),
+            CompliFiError::InvalidPolicyParameters
+        );
+        ctx.accounts.policy.tier_validity = tier_validity;
+        
+        msg!("Tier validity updated: {:?}", tier_validity);
+        Ok(())
+    }
+
+    /// Admin function to set how many supporting documents an attestation issued against the
+    /// policy must have had reviewed at each KYC level (0 = no minimum)
+    pub fn set_min_documents_for_level(
+        ctx: Context<SetPolicy>,
+        min_documents: [u8; DOCUMENT_REQUIREMENT_LEVELS],
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.min_documents_for_level = min_documents;
+        
+        msg!("Minimum documents per level set to {:?}", min_documents);
+        Ok(())
+    }
+
+    /// Admin function to require re-attestation within `recheck_days` of a jurisdiction change
+    pub fn set_jurisdiction_recheck(
+        ctx: Context<SetPolicy>,
+        require_recheck: bool,
+        recheck_days: u16,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.require_recheck_after_jurisdiction_change = require_recheck;
+        policy.jurisdiction_recheck_days = recheck_days;
+        
+        msg!("Jurisdiction recheck updated: required={}, days={}", require_recheck, recheck_days);
+        Ok(())
+    }
+
+    /// Admin function to set how old each attestation fact may be, in seconds (0 = no limit).
+    /// A nonzero accreditation age also requires the wallet to have been accredited.
+    pub fn set_check_max_ages(
+        ctx: Context<SetPolicy>,
+        identity_max_age: i64,
+        sanctions_max_age: i64,
+        accreditation_max_age: i64,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            identity_max_age >= 0 && sanctions_max_age >= 0 && accreditation_max_age >= 0,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.identity_max_age = identity_max_age;
+        policy.sanctions_max_age = sanctions_max_age;
+        policy.accreditation_max_age = accreditation_max_age;
+        
+        msg!(
+            "Check max ages updated: identity={}s, sanctions={}s, accreditation={}s",
+            identity_max_age,
+            sanctions_max_age,
+            accreditation_max_age
+        );
+        Ok(())
+    }
+
+    /// Admin function to set how long a passing verification is cached per amount bucket,
+    /// in seconds (0 = no caching)
+    pub fn set_receipt_ttl(ctx: Context<SetPolicy>, receipt_ttl: i64) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(receipt_ttl >= 0, CompliFiError::InvalidPolicyParameters);
+        ctx.accounts.policy.receipt_ttl = receipt_ttl;
+        
+        msg!("Verification receipt TTL set to {}s", receipt_ttl);
+        Ok(())
+    }
+
+    /// Admin function to set how long a user's risk score is reused from their user record
+    /// after an oracle read, in seconds (0 = no caching)
+    pub fn set_cache_ttl(ctx: Context<SetPolicy>, cache_ttl: i64) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(cache_ttl >= 0, CompliFiError::InvalidPolicyParameters);
+        ctx.accounts.policy.cache_ttl = cache_ttl;
+        
+        msg!("Risk score cache TTL set to {}s", cache_ttl);
+        Ok(())
+    }
+
+    /// Admin function to set how long a pass keeps a user's compliance receipt fresh, in
+    /// seconds. Any value above 0 makes the receipt a required account of verify_compliance.
+    pub fn set_compliance_receipt_ttl(ctx: Context<SetPolicy>, compliance_receipt_ttl: i64) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(compliance_receipt_ttl >= 0, CompliFiError::InvalidPolicyParameters);
+        ctx.accounts.policy.compliance_receipt_ttl = compliance_receipt_ttl;
+        
+        msg!("Compliance receipt TTL set to {}s", compliance_receipt_ttl);
+        Ok(())
+    }
+
+    /// Admin function to set the lifetime volume a nullifier may use without KYC (0 = none)
+    pub fn set_anonymous_lifetime_cap(ctx: Context<SetPolicy>, cap: u64) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.anonymous_lifetime_cap = cap;
+        
+        msg!("Anonymous lifetime cap set to {}", cap);
+        Ok(())
+    }
+
+    /// Admin function to let verify_compliance defer its oracle check to finalize_verification,
+    /// returning a Pending decision, when it is passed a receipt without a fresh outcome
+    pub fn set_defer_risk_check(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.defer_risk_check = enabled;
+        
+        msg!("Deferred risk check {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    /// Admin function to require the attestation's issuer and co-signers to carry at least
+    /// `min_trust_weight` between them (0 = off). Their `Attester` profiles are passed to
+    /// `verify_compliance` in `remaining_accounts`, alongside any quorum oracles.
+    pub fn set_min_trust_weight(ctx: Context<SetPolicy>, min_trust_weight: u16) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.min_trust_weight = min_trust_weight;
+        
+        msg!("Minimum attester trust weight set to {}", min_trust_weight);
+        Ok(())
+    }
+
+    /// Admin function to also log each verification outcome as a single-line JSON object, for
+    /// consumers that scrape program logs instead of decoding events
+    pub fn set_emit_json_log(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.emit_json_log = enabled;
+        
+        msg!("JSON outcome log {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    /// Admin function to require KYC level `step_up_level` once a user's cumulative verified
+    /// volume would pass `step_up_threshold` (0 = off). Below the threshold any verified
+    /// attestation will do, letting users start with partial KYC.
+    pub fn set_step_up(ctx: Context<SetPolicy>, step_up_threshold: u64, step_up_level: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!((step_up_level as usize) < KYC_LEVEL_COUNT, CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.step_up_threshold = step_up_threshold;
+        policy.step_up_level = step_up_level;
+        
+        msg!("Step-up to KYC level {} required past volume {}", step_up_level, step_up_threshold);
+        Ok(())
+    }
+
+    /// Admin function to set the risk scores refresh_risk_freeze freezes above and unfreezes
+    /// below. Keeping `unfreeze_threshold` under `freeze_threshold` stops a score hovering at
+    /// the boundary from flapping the freeze. A `freeze_threshold` of 0 turns it off.
+    pub fn set_freeze_thresholds(
+        ctx: Context<SetPolicy>,
+        freeze_threshold: u8,
+        unfreeze_threshold: u8,
+    ) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            freeze_threshold <= ctx.accounts.policy.risk_scale() && unfreeze_threshold <= freeze_threshold,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.freeze_threshold = freeze_threshold;
+        policy.unfreeze_threshold = unfreeze_threshold;
+        
+        msg!("Freeze thresholds set: freeze above {}, unfreeze below {}", freeze_threshold, unfreeze_threshold);
+        Ok(())
+    }
+
+    /// Admin function to restrict verifications to a daily UTC window, in seconds of day.
+    /// A start after the end wraps past midnight; 0 to 86400 leaves the window open.
+    pub fn set_trading_window(ctx: Context<SetPolicy>, start: u32, end: u32) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let day = SECONDS_PER_DAY as u32;
+        require!(start <= day && end <= day, CompliFiError::InvalidPolicyParameters);
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.trading_window_start = start;
+        policy.trading_window_end = end;
+        
+        msg!("Trading window updated: {}..{}", start, end);
+        Ok(())
+    }
+
+    /// Admin function to set the policy's custom boolean logic, a postfix token stream
+    /// (see logic.rs). An empty stream removes it.
+    pub fn set_policy_logic(ctx: Context<SetPolicy>, tokens: Vec<u8>) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        if !tokens.is_empty() {
+            validate_policy_logic(&tokens)?;
+        }
+        
+        let policy = &mut ctx.accounts.policy;
+        policy.logic = [0; MAX_POLICY_LOGIC_LEN];
+        policy.logic[..tokens.len()].copy_from_slice(&tokens);
+        policy.logic_len = tokens.len() as u8;
+        
+        msg!("Policy logic updated: {:?}", tokens);
+        Ok(())
+    }
+
+    /// Admin function to let scores up to `risk_tolerance` above the threshold pass as marginal
+    pub fn set_risk_tolerance(ctx: Context<SetPolicy>, risk_tolerance: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        ctx.accounts.policy.risk_tolerance = risk_tolerance;
+        
+        msg!("Risk tolerance updated: {}", risk_tolerance);
+        Ok(())
+    }
+
+    /// Admin function to flag passing scores from `warn_risk_score` up to the threshold for
+    /// manual review. 0 turns the warning band off.
+    pub fn set_warn_risk_score(ctx: Context<SetPolicy>, warn_risk_score: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            warn_risk_score <= ctx.accounts.policy.max_risk_score,
+            CompliFiError::InvalidPolicyParameters
+        );
+        
+        ctx.accounts.policy.warn_risk_score = warn_risk_score;
+        
+        msg!("Warning risk score updated: {}", warn_risk_score);
+        Ok(())
+    }
+
+    /// Admin function to set the top of the risk scale the policy's thresholds are expressed
+    /// on. Oracle scores (0-10) are normalized onto 0..=risk_scale_max before any comparison,
+    /// so every threshold already set must fit the new scale.
+    pub fn set_risk_scale(ctx: Context<SetPolicy>, risk_scale_max: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        let policy = &mut ctx.accounts.policy;
+        let mut thresholds = [policy.max_risk_score, policy.warn_risk_score, policy.freeze_threshold]
+            .into_iter()
+            .chain(policy.action_thresholds[..policy.action_threshold_count as usize].iter().map(|t| t.max_risk_score))
+            .chain(policy.jurisdiction_risk_caps[..policy.jurisdiction_risk_cap_count as usize].iter().map(|c| c.max_risk_score));
+        require!(
+            risk_scale_max > 0 && thresholds.all(|threshold| threshold <= risk_scale_max),
+            CompliFiError::InvalidPolicyParameters
+        );
+        policy.risk_scale_max = risk_scale_max;
+        
+        msg!("Risk scale set to 0-{}", risk_scale_max);
+        Ok(())
+    }
+
+    /// Admin function to keep passing attestations for `grace_period` seconds past their
+    /// expiry, flagging each such pass with a GracePeriodWarningEvent. 0 ends the grace.
+    pub fn set_expiry_grace_period(ctx: Context<SetPolicy>, grace_period: i64) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(grace_period >= 0, CompliFiError::InvalidPolicyParameters);
+        ctx.accounts.policy.expiry_grace_period = grace_period;
+        
+        msg!("Expiry grace period updated: {}s", grace_period);
+        Ok(())
+    }
+
+    /// Admin function to choose who wins when the oracle and attester disagree
+    pub fn set_risk_conflict_resolution(ctx: Context<SetPolicy>, resolution: u8) -> Result<()> {
+        authorize_policy_change(&mut ctx.accounts.policy, ctx.accounts.authority.key, ctx.remaining_accounts)?;
+        
+        require!(
+            resolution <= RISK_CONFLICT_REQUIRE_BOTH,
+            CompliFiError::InvalidPolicyParameters
+        );
+        ctx.accounts.policy.risk_conflict_resolution = resolution;
+        
+        msg!("Risk conflict resolution updated: {}", resolution);
+        Ok(())
+    }
+
+    /// Admin function to pause or resume every verification instruction. Emergency
+    /// verification stays available while paused.
+    pub fn set_paused(ctx: Context<UpdateState>, paused: bool) -> Result<()> {
+        ctx.accounts.state.paused = paused;
+        
+        emit!(PauseStateChangedEvent {
+            paused,
+            authority: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Verification {}", if paused { "paused" } else { "resumed" });
+        Ok(())
+    }
+
+    /// Admin function to also emit VerificationEvent and KycAttestationEvent through a self-CPI
+    /// (`emit_cpi!`), which indexers can read from the transaction's inner instructions even
+    /// when logs are truncated. The log events are emitted either way.
+    pub fn set_cpi_events(ctx: Context<UpdateState>, enabled: bool) -> Result<()> {
+        ctx.accounts.state.cpi_events = enabled;
+        
+        msg!("CPI events {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    /// Admin function to zero the state's verification and violation counters at the start of
+    /// a new reporting period, leaving every other setting as it is
+    pub fn reset_stats(ctx: Context<UpdateState>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.verification_count = 0;
+        state.violation_count = 0;
+        
+        emit!(StatsResetEvent {
+            reset_by: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        msg!("Verification and violation counters reset");
+        Ok(())
+    }
+
+    /// Admin function to create the soulbound Token-2022 mint compliance certificates are
+    /// issued from. The mint is a PDA that signs as its own mint authority.
+    pub fn init_certificate_mint(ctx: Context<InitCertificateMint>) -> Result<()> {
+        let mint = ctx.accounts.certificate_mint.to_account_info();
+        let space = NON_TRANSFERABLE_MINT_LEN;
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: mint.clone(),
+                },
+                &[&[CERTIFICATE_MINT_SEED, &[ctx.bumps.certificate_mint]]],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &TOKEN_2022_PROGRAM_ID,
+        )?;
+        initialize_non_transferable_mint(&ctx.accounts.token_program.to_account_info(), &mint, mint.key, 0)?;
+        
+        msg!("Certificate mint created: {}", mint.key);
+        Ok(())
+    }
+
+    /// Admin function to register the off-chain behavioral risk monitor
+    pub fn set_risk_monitor(ctx: Context<UpdateState>, monitor: Pubkey) -> Result<()> {
+        ctx.accounts.state.risk_monitor = monitor;
+        
+        msg!("Risk monitor set to: {}", monitor);
+        Ok(())
+    }
+
+    /// Report a user's on-platform behavioral risk (registered monitor only)
+    pub fn update_behavioral_risk(
+        ctx: Context<UpdateBehavioralRisk>,
+        user: Pubkey,
+        score: u8,
+    ) -> Result<()> {
+        require!(score <= MAX_RISK_SCORE, CompliFiError::InvalidPolicyParameters);
+        
+        let record = &mut ctx.accounts.user_record;
+        record.user = user;
+        record.behavioral_risk = score;
+        record.behavioral_updated_at = Clock::get()?.unix_timestamp;
+        // Flagging the attestation makes risk assessment insist on the record
+        ctx.accounts.attestation.set_behavioral_risk_reported(score > 0);
+        
+        emit!(BehavioralRiskUpdatedEvent {
+            user,
+            behavioral_risk: score,
+        });
+        
+        msg!("Behavioral risk for user {} set to {}", user, score);
+        Ok(())
+    }
+
+    /// Record a compliance violation
+    pub fn record_violation(
+        ctx: Context<RecordViolation>,
+        user: Pubkey,
+        reason: String,
+        category: u8,
+    ) -> Result<()> {
+        require!(category < VIOLATION_CATEGORY_COUNT, CompliFiError::InvalidViolationCategory);
+        require!(reason.len() <= MAX_REASON_LEN, CompliFiError::StringTooLong);
+        
+        let state = &mut ctx.accounts.state;
+        state.violation_count = state.violation_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        
+        if let Some(report) = active_report(state, &mut ctx.accounts.report)? {
+            report.violation_count = report.violation_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        if let Some(rollup) = active_rollup(state, &mut ctx.accounts.rollup)? {
+            rollup.violations = rollup.violations.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        if let Some(record) = &mut ctx.accounts.user_record {
+            record.violation_count = record.violation_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        if let Some(policy) = &mut ctx.accounts.policy {
+            policy.violation_count = policy.violation_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        }
+        ctx.accounts.user_violations.record(user, &reason, category, Clock::get()?.unix_timestamp);
+        
+        emit!(ViolationEvent {
+            user,
+            reason,
+            category,
+            seq: next_event_seq(&mut ctx.accounts.state)?,
+        });
+        
+        msg!("Compliance violation recorded for user: {}", user);
+        Ok(())
+    }
+
+    /// Open a reporting period that accumulates verification and violation activity
+    pub fn begin_report(ctx: Context<BeginReport>, period_id: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require_keys_eq!(state.active_report, Pubkey::default(), CompliFiError::ReportPeriodActive);
+        
+        let report = &mut ctx.accounts.report;
+        report.state = state.key();
+        report.period_id = period_id;
+        report.started_at = Clock::get()?.unix_timestamp;
+        report.finalized_at = 0;
+        
+        state.active_report = report.key();
+        
+        msg!("Reporting period {} opened", period_id);
+        Ok(())
+    }
+
+    /// Close the active reporting period and seal its contents with a hash
+    pub fn finalize_report(ctx: Context<FinalizeReport>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let report = &mut ctx.accounts.report;
+        require_keys_eq!(state.active_report, report.key(), CompliFiError::ReportNotActive);
+        
+        report.finalized_at = Clock::get()?.unix_timestamp;
+        report.report_hash = compute_report_hash(report);
+        state.active_report = Pubkey::default();
+        
+        emit!(ReportFinalizedEvent {
+            period_id: report.period_id,
+            verification_count: report.verification_count,
+            violation_count: report.violation_count,
+            report_hash: report.report_hash,
+        });
+        
+        msg!("Reporting period {} finalized", report.period_id);
+        Ok(())
+    }
+}
+
+// Percentage (0-100) of an attestation's lifetime that has elapsed at `now`
+fn lifetime_pct_elapsed(issued_at: i64, expires_at: i64, now: i64) -> u8 {
+    let lifetime = (expires_at - issued_at) as i128;
+    let elapsed = (now - issued_at).max(0) as i128;
+    (elapsed * 100 / lifetime).min(100) as u8
+}
+
+// The wallet whose compliance is evaluated: the principal when `user` is a delegate
+fn compliance_subject(user: &AccountInfo, delegation: &Option<Account<DelegationRecord>>) -> Pubkey {
+    delegation.as_ref().map_or(user.key(), |d| d.principal)
+}
+
+// Whether `user` falls in a staged rollout's cohort. The bucket is the first 8 bytes of
+// sha256(user) mod 10000, so a user's assignment never changes and a wider rollout only adds users.
+fn in_rollout_cohort(user: &Pubkey, rollout_bps: u16) -> bool {
+    if rollout_bps >= FULL_ROLLOUT_BPS {
+        return true;
+    }
+    let hash = solana_sha256_hasher::hash(user.as_ref()).to_bytes();
+    let bucket = u64::from_le_bytes(hash[..8].try_into().unwrap()) % FULL_ROLLOUT_BPS as u64;
+    bucket < rollout_bps as u64
+}
+
+// Wallet whose attestation verify_compliance reads: a delegate's principal, a sub-account's
+// parent entity, or the user itself
+fn verification_subject(
+    user: &AccountInfo,
+    delegation: &Option<Account<DelegationRecord>>,
+    entity: &Option<Account<EntityHierarchy>>,
+) -> Pubkey {
+    match entity {
+        Some(entity) if delegation.is_none() => entity.parent,
+        _ => compliance_subject(user, delegation),
+    }
+}
+
+// Every check `subject` fails, in the order verify_compliance runs them. Unless
+// `collect_all` is set, only the first failure is returned.
+fn compliance_failures(
+    policy: &CompliancePolicy,
+    max_risk_score: u8,
+    attestation: &KycAttestation,
+    subject: &Pubkey,
+    oracles: &[AccountInfo],
+    range_oracle: Option<&AccountInfo>,
+    user_record: &Option<Account<UserComplianceRecord>>,
+    collect_all: bool,
+    now: i64,
+) -> Vec<CompliFiError> {
+    let max_risk_score = policy.jurisdiction_max_risk_score(attestation.jurisdiction, max_risk_score);
+    let mut failures = Vec::new();
+    if !policy_active(policy, now) {
+        failures.push(CompliFiError::PolicyNotActive);
+    }
+    if attestation.frozen() {
+        failures.push(CompliFiError::WalletFrozen);
+    }
+    if policy.require_kyc {
+        failures.extend(kyc_failures(policy, attestation, subject, now));
+    }
+    if collect_all || failures.is_empty() {
+        match assess_risk_score(policy, oracles, range_oracle, subject, Some(attestation), user_record) {
+            Ok(risk_score) if risk_score > max_risk_score => {
+                failures.push(CompliFiError::RiskScoreTooHigh)
+            }
+            Ok(_) => {}
+            Err(_) => failures.push(CompliFiError::OracleDataFetchFailed),
+        }
+    }
+    if !collect_all {
+        failures.truncate(1);
+    }
+    failures
+}
+
+// Whether `now` falls in the policy's effective window; a bound of 0 leaves that side open
+fn policy_active(policy: &CompliancePolicy, now: i64) -> bool {
+    (policy.effective_from == 0 || now >= policy.effective_from)
+        && (policy.effective_until == 0 || now < policy.effective_until)
+}
+
+// Whether `now` falls in the policy's daily trading window, which may wrap past midnight
+fn within_trading_window(policy: &CompliancePolicy, now: i64) -> bool {
+    let (start, end) = (policy.trading_window_start, policy.trading_window_end);
+    if start == 0 && end == SECONDS_PER_DAY as u32 {
+        return true;
+    }
+    let time_of_day = now.rem_euclid(SECONDS_PER_DAY) as u32;
+    if start <= end {
+        time_of_day >= start && time_of_day < end
+    } else {
+        time_of_day >= start || time_of_day < end
+    }
+}
+
+// The checks a verification of `action` runs before it reads an oracle or writes anything: the
+// policy's gates, the attestation's standing and the user's limits. `jurisdiction_cap` is the
+// user's jurisdiction amount limit, 0 for none.
+fn check_verification_requirements(
+    policy: &CompliancePolicy,
+    action: &str,
+    attestation: Option<&KycAttestation>,
+    principal: &Pubkey,
+    attesters: &[AccountInfo],
+    attestor_entry: &Option<UncheckedAccount>,
+    counterparty: &Pubkey,
+    amount: Option<u64>,
+    jurisdiction_cap: u64,
+    user_record: Option<&UserComplianceRecord>,
+    now: i64,
+) -> Result<()> {
+    require!(policy_active(policy, now), CompliFiError::PolicyNotActive);
+    require!(within_trading_window(policy, now), CompliFiError::OutsideTradingWindow);
+    require!(policy.is_action_allowed(action), CompliFiError::ActionNotAllowed);
+    
+    // 1. Check KYC attestation using our PDA-based registry. A user with no attestation
+    // at all only gets past a policy that does not require KYC.
+    if policy.require_kyc {
+        let attestation = attestation.ok_or(CompliFiError::KycNotVerified)?;
+        check_kyc_attestation(policy, attestation, principal)?;
+    }
+    
+    // The attestation's signers must be trusted enough between them
+    if policy.min_trust_weight > 0 {
+        let attestation = attestation.ok_or(CompliFiError::InsufficientAttesterTrust)?;
+        require!(
+            attestation_trust_weight(attestation, attesters)? >= policy.min_trust_weight,
+            CompliFiError::InsufficientAttesterTrust
+        );
+    }
+    
+    // The attestation's issuer must still hold its allowlist entry, which removal closes
+    if policy.require_trusted_attestor {
+        require!(
+            attestor_entry.as_ref().is_some_and(|entry| !entry.data_is_empty()),
+            CompliFiError::UntrustedAttestor
+        );
+    }
+    
+    // The remaining checks are on facts the attestation records; without one they have
+    // nothing to read
+    if let Some(attestation) = attestation {
+        // An attestation scoped to particular counterparties only covers dealings with them
+        require!(
+            attestation.counterparty_allowed(counterparty),
+            CompliFiError::CounterpartyNotAllowed
+        );
+        
+        // A user who moved jurisdiction must be re-attested within the recheck window
+        let changed_at = attestation.jurisdiction_changed_at;
+        if policy.require_recheck_after_jurisdiction_change && changed_at > 0 {
+            let window = policy.jurisdiction_recheck_days as i64 * SECONDS_PER_DAY;
+            require!(now - changed_at <= window, CompliFiError::JurisdictionRecheckRequired);
+        }
+        
+        // Each attestation fact must be fresh enough for the policy, independently of the others
+        if let Some(failure) = stale_attestation_check(policy, attestation, now) {
+            return Err(failure.into());
+        }
+        
+        // A freshly issued attestation only counts once it has aged past the policy minimum,
+        // so a wallet cannot be attested and used in the same breath
+        if policy.min_attestation_age > 0 {
+            require!(
+                now - attestation.timestamp >= policy.min_attestation_age,
+                CompliFiError::AttestationTooNew
+            );
+        }
+    }
+    
+    // Custom boolean logic over the attestation, when the policy defines one
+    if policy.logic_len > 0 {
+        let attestation = attestation.ok_or(CompliFiError::PolicyLogicNotSatisfied)?;
+        let mut checks = 0;
+        let passed = evaluate_policy_logic(&policy.logic[..policy.logic_len as usize], |check, operand| {
+            checks += 1;
+            policy_logic_check(policy, attestation, principal, check, operand)
+        })?;
+        msg!("Policy logic evaluated {} checks", checks);
+        require!(passed, CompliFiError::PolicyLogicNotSatisfied);
+    }
+    
+    // The amount must fit the policy-wide cap and the user's jurisdiction cap, if any
+    if let Some(amount) = amount {
+        let cap = [policy.max_amount, jurisdiction_cap]
+            .into_iter()
+            .filter(|cap| *cap > 0)
+            .min();
+        if let Some(cap) = cap {
+            require!(amount <= cap, CompliFiError::AmountExceedsLimit);
+        }
+    }
+    
+    // Partial KYC covers the user until their verified volume, this amount included, passes
+    // the step-up threshold; from then on the attestation must stand at the step-up level.
+    // The volume lives on the user record, so it must be passed.
+    if policy.step_up_threshold > 0 {
+        let record = user_record.ok_or(CompliFiError::UserRecordRequired)?;
+        let volume = record.verified_volume.saturating_add(amount.unwrap_or(0));
+        require!(
+            volume <= policy.step_up_threshold
+                || attestation.is_some_and(|attestation| kyc_level(attestation) >= policy.step_up_level),
+            CompliFiError::StepUpRequired
+        );
+    }
+    
+    // Under a reverification cooldown the user must wait out the interval since their last
+    // pass, which the user record keeps, so it must be passed
+    if policy.min_verify_interval > 0 {
+        let record = user_record.ok_or(CompliFiError::UserRecordRequired)?;
+        require!(
+            record.last_verified_at == 0
+                || now.saturating_sub(record.last_verified_at) >= policy.min_verify_interval,
+            CompliFiError::VerificationTooSoon
+        );
+    }
+    Ok(())
+}
+
+// The first attestation fact older than the policy's max age for it, if any
+fn stale_attestation_check(
+    policy: &CompliancePolicy,
+    attestation: &KycAttestation,
+    now: i64,
+) -> Option<CompliFiError> {
+    [
+        (attestation.identity_verified_at, policy.identity_max_age, CompliFiError::IdentityVerificationStale),
+        (attestation.sanctions_checked_at, policy.sanctions_max_age, CompliFiError::SanctionsScreeningStale),
+        (attestation.accreditation_verified_at, policy.accreditation_max_age, CompliFiError::AccreditationStale),
+    ]
+    .into_iter()
+    .find(|(checked_at, max_age, _)| *max_age > 0 && now - checked_at > *max_age)
+    .map(|(_, _, failure)| failure)
+}
+
+// Whether the attester's verdict beats the oracle's when they disagree.
+// Under require-both the failing source always wins.
+fn attester_wins_conflict(policy: &CompliancePolicy, attester_passes: bool) -> bool {
+    match policy.risk_conflict_resolution {
+        RISK_CONFLICT_ATTESTER_WINS => true,
+        RISK_CONFLICT_REQUIRE_BOTH => !attester_passes,
+        _ => false,
+    }
+}
+
+// Check that an attestation marks `user` as verified in a jurisdiction the policy allows
+fn check_kyc_attestation(
+    policy: &CompliancePolicy,
+    attestation: &KycAttestation,
+    user: &Pubkey,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    if let Some(failure) = kyc_failures(policy, attestation, user, now).first() {
+        // Anchor errors carry no payload, so the offending code goes out in the logs
+        if matches!(failure, CompliFiError::RestrictedJurisdiction) {
+            log_jurisdiction_rejection(user, attestation.jurisdiction);
+        }
+        return Err((*failure).into());
+    }
+    
+    // An attestation that passed although expired is inside the policy's grace period
+    if attestation.expires_at > 0 && now >= attestation.expires_at {
+        emit!(GracePeriodWarningEvent {
+            user: *user,
+            expired_at: attestation.expires_at,
+            grace_ends_at: attestation.expires_at.saturating_add(policy.expiry_grace_period),
+        });
+    }
+    Ok(())
+}
+
+// Every KYC requirement the attestation fails, in the order they are checked
+fn kyc_failures(
+    policy: &CompliancePolicy,
+    attestation: &KycAttestation,
+    user: &Pubkey,
+    now: i64,
+) -> Vec<CompliFiError> {
+    let mut failures = Vec::new();
+    
+    // Verify the attestation is for the correct user and is valid
+    if attestation.wallet != *user || !attestation.is_verified() {
+        failures.push(CompliFiError::KycNotVerified);
+    }
+    
+    // An attestation past its expiry and the policy's grace period no longer vouches for the
+    // wallet (0 = never expires)
+    if attestation.expires_at > 0 && now >= attestation.expires_at.saturating_add(policy.expiry_grace_period) {
+        failures.push(CompliFiError::AttestationExpired);
+    }
+    
+    // The wallet itself must have proven control of its key
+    if policy.require_ownership_proof && !attestation.ownership_proven() {
+        failures.push(CompliFiError::OwnershipProofRequired);
+    }
+    
+    // Under dual control a second attester must have vouched for the wallet too;
+    // cosign_attestation only accepts registered attesters other than the issuer
+    if policy.require_dual_attestation && attestation.cosigner_count == 0 {
+        failures.push(CompliFiError::DualAttestationRequired);
+    }
+    
+    // The attester must have verified the wallet to at least the policy's tier
+    if attestation.level < policy.min_kyc_level {
+        failures.push(CompliFiError::InsufficientKycLevel);
+    }
+    
+    // Only the required screening vendor may clear the wallet
+    if policy.required_vendor != UNATTRIBUTED_VENDOR_ID
+        && attestation.screening_vendor_id != policy.required_vendor
+    {
+        failures.push(CompliFiError::VendorNotAccepted);
+    }
+    
+    // A jurisdiction code only means something in the scheme the policy's bitmap uses
+    if attestation.jurisdiction_scheme != policy.jurisdiction_scheme {
+        failures.push(CompliFiError::JurisdictionSchemeMismatch);
+    }
+    
+    // Check jurisdiction is allowed
+    if !jurisdiction_allowed(policy, attestation.jurisdiction) {
+        failures.push(CompliFiError::RestrictedJurisdiction);
+    }
+    failures
+}
+
+// Log a verification outcome as one line of JSON, e.g.
+// {"user":"..","action":"swap","result":true,"risk":2}
+fn log_verification_json(user: &Pubkey, action: &str, result: bool, risk_score: u8) {
+    msg!(
+        "{{\"user\":\"{}\",\"action\":\"{}\",\"result\":{},\"risk\":{}}}",
+        user,
+        json_escape(action),
+        result,
+        risk_score
+    );
+}
+
+// Log a jurisdiction rejection as one line of JSON, e.g.
+// {"error":"RestrictedJurisdiction","user":"..","jurisdiction":42}
+fn log_jurisdiction_rejection(user: &Pubkey, jurisdiction: u8) {
+    msg!(
+        "{{\"error\":\"RestrictedJurisdiction\",\"user\":\"{}\",\"jurisdiction\":{}}}",
+        user,
+        jurisdiction
+    );
+}
+
+// Escape a string for a JSON string literal
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Summed trust weight of the attestation's issuer and co-signers, read from their `Attester`
+// profiles (accounts owned by this program). Each signer counts once; signers without a
+// profile carry no weight.
+fn attestation_trust_weight(attestation: &KycAttestation, attesters: &[AccountInfo]) -> Result<u16> {
+    let cosigners = &attestation.cosigners[..attestation.cosigner_count as usize];
+    let mut counted: Vec<Pubkey> = Vec::with_capacity(cosigners.len() + 1);
+    let mut total: u16 = 0;
+    for info in attesters {
+        let profile = Attester::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let signed = profile.attester == attestation.authority || cosigners.contains(&profile.attester);
+        if signed && !counted.contains(&profile.attester) {
+            counted.push(profile.attester);
+            total = total.saturating_add(profile.trust_weight as u16);
+        }
+    }
+    Ok(total)
+}
+
+// Whether the policy's jurisdiction bitmap allows `jurisdiction`
+fn jurisdiction_allowed(policy: &CompliancePolicy, jurisdiction: u8) -> bool {
+    let (jurisdiction_idx, jurisdiction_bit) = jurisdiction_bit(jurisdiction);
+    
+    jurisdiction_idx < policy.allowed_jurisdictions.len()
+        && (policy.allowed_jurisdictions[jurisdiction_idx] & jurisdiction_bit) != 0
+}
+
+// Fails unless the attestor behind `attestor_entry` is delegated `jurisdiction`; an entry
+// with no jurisdictions set may attest any of them
+fn require_jurisdiction_delegated(attestor_entry: &AccountInfo, jurisdiction: u8) -> Result<()> {
+    let entry = Attestor::try_deserialize(&mut &attestor_entry.try_borrow_data()?[..])?;
+    let (index, bit) = jurisdiction_bit(jurisdiction);
+    require!(
+        entry.allowed_jurisdictions.iter().all(|byte| *byte == 0) || entry.allowed_jurisdictions[index] & bit != 0,
+        CompliFiError::JurisdictionNotDelegated
+    );
+    Ok(())
+}
+
+// Byte index and bit mask of `jurisdiction` in an allowed-jurisdiction bitmap
+fn jurisdiction_bit(jurisdiction: u8) -> (usize, u8) {
+    ((jurisdiction / 8) as usize, 1 << (jurisdiction % 8))
+}
+
+// Outcome of a single policy logic check against the subject's attestation
+fn policy_logic_check(
+    policy: &CompliancePolicy,
+    attestation: &KycAttestation,
+    subject: &Pubkey,
+    check: u8,
+    operand: u8,
+) -> bool {
+    match check {
+        LOGIC_KYC_VERIFIED => attestation.wallet == *subject && attestation.is_verified(),
+        LOGIC_JURISDICTION_ALLOWED => jurisdiction_allowed(policy, attestation.jurisdiction),
+        LOGIC_OWNERSHIP_PROVEN => attestation.ownership_proven(),
+        LOGIC_JURISDICTION_IS => attestation.jurisdiction == operand,
+        LOGIC_VENDOR_IS => attestation.screening_vendor_id == operand,
+        _ => false,
+    }
+}
+
+// Full KYC and risk check for a multisig member whose attestation and oracle accounts are
+// passed as raw accounts
+fn check_multisig_member<'info>(
+    policy: &CompliancePolicy,
+    attestation: &'info AccountInfo<'info>,
+    oracles: &'info [AccountInfo<'info>],
+    member: &Pubkey,
+) -> Result<()> {
+    let attestation = match policy.require_kyc {
+        true => {
+            let attestation = Account::<KycAttestation>::try_from(attestation)?;
+            check_kyc_attestation(policy, &attestation, member)?;
+            Some(attestation)
+        }
+        false => None,
+    };
+    
+    let (risk_score, _) = assess_member_risk(policy, oracles, member, attestation.as_deref())?;
+    require!(risk_score <= policy.max_risk_score, CompliFiError::RiskScoreTooHigh);
+    Ok(())
+}
+
+// The per-user checks of verify_compliance_batch, returning the user's risk score and the
+// model behind it. Checks that need per-user accounts the batch does not carry fail closed.
+fn check_batch_member<'info>(
+    policy: &CompliancePolicy,
+    attestation: &KycAttestation,
+    oracles: &'info [AccountInfo<'info>],
+    user: &Pubkey,
+    max_risk_score: u8,
+    now: i64,
+) -> Result<(u8, u32)> {
+    let max_risk_score = policy.jurisdiction_max_risk_score(attestation.jurisdiction, max_risk_score);
+    require!(!attestation.frozen(), CompliFiError::WalletFrozen);
+    require!(!regulatory_hold_active(attestation, &None, now), CompliFiError::RegulatoryHold);
+    require!(!policy.require_trusted_attestor, CompliFiError::UntrustedAttestor);
+    require!(policy.min_trust_weight == 0, CompliFiError::InsufficientAttesterTrust);
+    require!(
+        policy.step_up_threshold == 0 && policy.min_verify_interval == 0,
+        CompliFiError::UserRecordRequired
+    );
+    
+    if policy.require_kyc {
+        check_kyc_attestation(policy, attestation, user)?;
+    }
+    require!(attestation.counterparty_allowed(&Pubkey::default()), CompliFiError::CounterpartyNotAllowed);
+    if let Some(failure) = stale_attestation_check(policy, attestation, now) {
+        return Err(failure.into());
+    }
+    if policy.min_attestation_age > 0 {
+        require!(
+            now - attestation.timestamp >= policy.min_attestation_age,
+            CompliFiError::AttestationTooNew
+        );
+    }
+    if policy.logic_len > 0 {
+        let passed = evaluate_policy_logic(&policy.logic[..policy.logic_len as usize], |check, operand| {
+            policy_logic_check(policy, attestation, user, check, operand)
+        })?;
+        require!(passed, CompliFiError::PolicyLogicNotSatisfied);
+    }
+    
+    let (risk_score, risk_model_version) = assess_member_risk(policy, oracles, user, Some(attestation))?;
+    require!(risk_score <= max_risk_score, CompliFiError::RiskScoreTooHigh);
+    Ok((risk_score, risk_model_version))
+}
+
+// Number the next verification, attestation or violation event, giving indexers a total
+// order across instructions and within a slot
+fn next_event_seq(state: &mut ComplianceState) -> Result<u64> {
+    state.event_seq = state.event_seq.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+    Ok(state.event_seq)
+}
+
+// The report that must receive activity while a reporting period is open
+fn active_report<'a, 'info>(
+    state: &ComplianceState,
+    report: &'a mut Option<Box<Account<'info, ComplianceReport>>>,
+) -> Result<Option<&'a mut Box<Account<'info, ComplianceReport>>>> {
+    if state.active_report == Pubkey::default() {
+        return Ok(None);
+    }
+    
+    // Activity during an open period cannot skip the report
+    let report = report.as_mut().ok_or(CompliFiError::ReportNotActive)?;
+    require_keys_eq!(report.key(), state.active_report, CompliFiError::ReportNotActive);
+    Ok(Some(report))
+}
+
+// The state's rollup, which must be passed once the state has one
+fn active_rollup<'a, 'info>(
+    state: &ComplianceState,
+    rollup: &'a mut Option<Account<'info, GlobalRollup>>,
+) -> Result<Option<&'a mut Account<'info, GlobalRollup>>> {
+    if !state.rollup_enabled {
+        return Ok(None);
+    }
+    
+    // Skipping the rollup would leave its counts out of date
+    let rollup = rollup.as_mut().ok_or(CompliFiError::RollupRequired)?;
+    Ok(Some(rollup))
+}
+
+// Append an attestation lifecycle operation to the state's journal, which must be passed
+// once the state has one
+fn journal_attestation(
+    state: &ComplianceState,
+    journal: &mut Option<Account<AttestationJournal>>,
+    operation: u8,
+    attestation: &KycAttestation,
+) -> Result<()> {
+    if !state.journal_enabled {
+        return Ok(());
+    }
+    
+    // Skipping the journal would leave a gap in the issuance history
+    let journal = journal.as_mut().ok_or(CompliFiError::JournalRequired)?;
+    let record_hash = attestation_hash(attestation)?;
+    let timestamp = Clock::get()?.unix_timestamp;
+    let sequence = journal.append(operation, &attestation.wallet, &record_hash, timestamp)?;
+    
+    emit!(AttestationJournaledEvent {
+        sequence,
+        operation,
+        wallet: attestation.wallet,
+        record_hash,
+        timestamp,
+        head: journal.head,
+    });
+    Ok(())
+}
+
+// Whether a regulatory hold still blocks the wallet at `now`. A held attestation whose
+// record is missing counts as held, so the hold cannot be skipped by omitting the record.
+fn regulatory_hold_active(
+    attestation: &KycAttestation,
+    user_record: &Option<Account<UserComplianceRecord>>,
+    now: i64,
+) -> bool {
+    attestation.held()
+        && user_record
+            .as_ref()
+            .is_none_or(|record| record.regulatory_hold && record.hold_expires_at > now)
+}
+
+// Block a frozen, sanctioned or held wallet. `sanction` is the wallet's sanction entry address,
+// which blocks it whenever an entry exists there.
+fn check_wallet_not_blocked(
+    attestation: Option<&KycAttestation>,
+    sanction: &UncheckedAccount,
+    user_record: &Option<Account<UserComplianceRecord>>,
+    now: i64,
+) -> Result<()> {
+    require!(!attestation.is_some_and(KycAttestation::frozen), CompliFiError::WalletFrozen);
+    require!(sanction.data_is_empty(), CompliFiError::WalletSanctioned);
+    require!(
+        !attestation.is_some_and(|attestation| regulatory_hold_active(attestation, user_record, now)),
+        CompliFiError::RegulatoryHold
+    );
+    Ok(())
+}
+
+// Take an attestation that is being closed out of the rollup's counts
+fn record_attestation_removed(
+    state: &ComplianceState,
+    rollup: &mut Option<Account<GlobalRollup>>,
+    attestation: &KycAttestation,
+) -> Result<()> {
+    if let Some(rollup) = active_rollup(state, rollup)? {
+        rollup.record_level_change(Some(kyc_level(attestation)), None)?;
+        if attestation.frozen() {
+            rollup.frozen_users = rollup.frozen_users.saturating_sub(1);
+        }
+    }
+    Ok(())
+}
+
+// KYC level an attestation stands at
+// Reviewed-document minimum for an attestation issued at `level`. Once the state designates an
+// issuance policy, that policy must be passed, so leaving the policy out skips no minimum.
+fn require_documents_reviewed(
+    state: &ComplianceState,
+    policy: &Option<Account<CompliancePolicy>>,
+    level: u8,
+    documents_reviewed: u8,
+) -> Result<()> {
+    let policy = match policy {
+        Some(policy) => policy,
+        None if state.issuance_policy == Pubkey::default() => return Ok(()),
+        None => return err!(CompliFiError::IssuancePolicyRequired),
+    };
+    require!(
+        state.issuance_policy == Pubkey::default() || policy.key() == state.issuance_policy,
+        CompliFiError::IssuancePolicyRequired
+    );
+    require!(
+        documents_reviewed >= policy.min_documents_for_level[level as usize],
+        CompliFiError::InsufficientDocumentsReviewed
+    );
+    Ok(())
+}
+
+fn kyc_level(attestation: &KycAttestation) -> u8 {
+    match (attestation.is_verified(), attestation.ownership_proven()) {
+        (false, _) => KYC_LEVEL_NONE,
+        (true, false) => KYC_LEVEL_VERIFIED,
+        (true, true) => KYC_LEVEL_OWNERSHIP_PROVEN,
+    }
+}
+
+// Shared body of freeze_wallet and unfreeze_wallet; repeating the current state is a no-op.
+// The operator's decision replaces any freeze refresh_risk_freeze placed.
+fn set_wallet_frozen(ctx: Context<FreezeWallet>, wallet: Pubkey, frozen: bool) -> Result<()> {
+    let accounts = ctx.accounts;
+    apply_wallet_frozen(&accounts.state, &mut accounts.rollup, &mut accounts.attestation, frozen)?;
+    accounts.attestation.set_risk_frozen(false);
+    
+    msg!("Wallet {} frozen: {}", wallet, frozen);
+    Ok(())
+}
+
+// Shared body of the migrate_* instructions: grow a `T` account allocated under an older,
+// shorter layout to 8 + `len` bytes, zero-filling the new fields and topping up its rent
+// from `authority`. The account's stored authority key, `authority_offset` bytes into its
+// fields, must be the signer's. Accounts already at full size are left as they are.
+fn migrate_account<'info, T: AccountDeserialize + Discriminator>(
+    info: &AccountInfo<'info>,
+    authority: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    len: usize,
+    authority_offset: usize,
+) -> Result<()> {
+    let old_len = {
+        let data = info.try_borrow_data()?;
+        require!(data.starts_with(T::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+        let at = 8 + authority_offset;
+        let stored = data.get(at..at + 32).ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        require!(stored == authority.key.as_ref(), CompliFiError::Unauthorized);
+        data.len()
+    };
+    
+    let new_len = 8 + len;
+    if old_len < new_len {
+        let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: authority.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        info.resize(new_len)?;
+        info.try_borrow_mut_data()?[old_len..].fill(0);
+    }
+    
+    // The grown account must read back in the current layout
+    T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    Ok(())
+}
+
+// Set the attestation's frozen flag, keeping the rollup's frozen count in step
+fn apply_wallet_frozen(
+    state: &ComplianceState,
+    rollup: &mut Option<Account<GlobalRollup>>,
+    attestation: &mut KycAttestation,
+    frozen: bool,
+) -> Result<()> {
+    if attestation.frozen() != frozen {
+        attestation.set_frozen(frozen);
+        if let Some(rollup) = active_rollup(state, rollup)? {
+            rollup.frozen_users = if frozen {
+                rollup.frozen_users.checked_add(1).ok_or(CompliFiError::CounterOverflow)?
+            } else {
+                rollup.frozen_users.saturating_sub(1)
+            };
+        }
+    }
+    Ok(())
+}
+
+// SHA-256 over period_id, started_at, finalized_at, verification_count, violation_count
+// (all little-endian u64/i64) followed by the 256 jurisdiction counts (little-endian u32)
+fn compute_report_hash(report: &ComplianceReport) -> [u8; 32] {
+    let jurisdiction_counts: Vec<u8> = report
+        .jurisdiction_counts
+        .iter()
+        .flat_map(|count| count.to_le_bytes())
+        .collect();
+    
+    solana_sha256_hasher::hashv(&[
+        &report.period_id.to_le_bytes(),
+        &report.started_at.to_le_bytes(),
+        &report.finalized_at.to_le_bytes(),
+        &report.verification_count.to_le_bytes(),
+        &report.violation_count.to_le_bytes(),
+        &jurisdiction_counts,
+    ])
+    .to_bytes()
+}
+
+// Link hash of an attestation record: sha256 of its borsh serialization (account data after
+// the 8-byte discriminator)
+fn attestation_hash(attestation: &KycAttestation) -> Result<[u8; 32]> {
+    Ok(solana_sha256_hasher::hash(&attestation.try_to_vec()?).to_bytes())
+}
+
+// Digest of a verification result, endorsed by the program's RESULT_SIGNER_SEED PDA and
+// returned as return data. Off-chain verifiers replay it as sha256 over:
+//   user (32) || action length (u32 LE) || action (UTF-8) || result (u8, 1 = passed)
+//   || slot (u64 LE) || policy_hash (32)
+// where policy_hash is the policy's snapshot hash.
+fn verification_digest(
+    user: &Pubkey,
+    action: &str,
+    verified: bool,
+    slot: u64,
+    policy: &CompliancePolicy,
+) -> Result<[u8; 32]> {
+    let policy_hash = policy_hash(policy)?;
+    Ok(solana_sha256_hasher::hashv(&[
+        user.as_ref(),
+        &(action.len() as u32).to_le_bytes(),
+        action.as_bytes(),
+        &[verified as u8],
+        &slot.to_le_bytes(),
+        policy_hash.as_ref(),
+    ])
+    .to_bytes())
+}
+
+// Snapshot hash of a policy's configuration: sha256(borsh-serialized CompliancePolicy, i.e.
+// account data after the 8-byte discriminator)
+fn policy_hash(policy: &CompliancePolicy) -> Result<[u8; 32]> {
+    // The activity counters move with every verification; the snapshot covers configuration only
+    let mut config = policy.clone();
+    config.verification_count = 0;
+    config.violation_count = 0;
+    Ok(solana_sha256_hasher::hash(&config.try_to_vec()?).to_bytes())
+}
+
+// Message a wallet signs to prove it controls its key: prefix || wallet || attester
+fn ownership_challenge(wallet: &Pubkey, attester: &Pubkey) -> Vec<u8> {
+    [OWNERSHIP_CHALLENGE_PREFIX, wallet.as_ref(), attester.as_ref()].concat()
+}
+
+// Commitment to one KYC field: sha256(wallet || field_id || value || blinding)
+fn field_commitment(wallet: &Pubkey, field_id: u8, value: &[u8], blinding: &[u8; 32]) -> [u8; 32] {
+    solana_sha256_hasher::hashv(&[wallet.as_ref(), &[field_id], value, blinding]).to_bytes()
+}
+
+/// Address and bump of the KycAttestation `authority` issued for `wallet`,
+/// [KYC_ATTESTATION_SEED, authority, wallet]
+pub fn kyc_attestation_pda(authority: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[KYC_ATTESTATION_SEED, authority.as_ref(), wallet.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Order-of-magnitude bucket of a raw base-unit amount, used to key verification receipts.
+/// Bucket 0 is a call without an amount, bucket 1 holds 0-9 and bucket n >= 2 holds
+/// [10^(n-1), 10^n): 1_000 and 1_100 share bucket 4, while 10_000 falls in bucket 5.
+/// The largest u64 amounts land in MAX_AMOUNT_BUCKET.
+pub fn amount_bucket(amount: Option<u64>) -> u8 {
+    match amount {
+        None => 0,
+        Some(amount) => (amount.checked_ilog10().unwrap_or(0) + 1) as u8,
+    }
+}
+
+// Receipt seed component identifying an action
+fn action_hash(action: &str) -> [u8; 32] {
+    solana_sha256_hasher::hash(action.as_bytes()).to_bytes()
+}
+
+// Convert a raw base-unit amount into whole units of the policy's denomination
+fn scale_amount(raw: u64, decimals: u8) -> u64 {
+    raw / 10u64.pow(decimals as u32)
+}
+
+// Approve a change to the policy and bump its revision, which marks any compiled copy stale.
+// Policies with governance signers need `policy_threshold` of them to approve a change;
+// the authority counts if it is a listed signer, the others sign as remaining accounts.
+fn authorize_policy_change(
+    policy: &mut CompliancePolicy,
+    authority: &Pubkey,
+    cosigners: &[AccountInfo],
+) -> Result<()> {
+    require!(policy.consortium == Pubkey::default(), CompliFiError::ConsortiumVoteRequired);
+    policy.revision = policy.revision.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+    if policy.policy_threshold == 0 {
+        return Ok(());
+    }
+    
+    let signers = &policy.policy_signers[..policy.policy_signer_count as usize];
+    let mut approvals: Vec<Pubkey> = Vec::with_capacity(signers.len());
+    let candidates = std::iter::once(authority)
+        .chain(cosigners.iter().filter(|a| a.is_signer).map(|a| a.key));
+    for key in candidates {
+        if signers.contains(key) && !approvals.contains(key) {
+            approvals.push(*key);
+        }
+    }
+    
+    require!(
+        approvals.len() >= policy.policy_threshold as usize,
+        CompliFiError::InsufficientPolicySigners
+    );
+    Ok(())
+}
+
+// Position of `member` in the consortium's member list
+fn consortium_member_index(consortium: &Consortium, member: &Pubkey) -> Result<usize> {
+    consortium.members[..consortium.member_count as usize]
+        .iter()
+        .position(|m| m == member)
+        .ok_or(error!(CompliFiError::NotConsortiumMember))
+}
+
+// Count a member's vote for a proposal, applying it to the policy once the threshold is met.
+// A proposal made against an older policy revision can no longer pass.
+fn record_consortium_vote(
+    proposal: &mut ConsortiumProposal,
+    consortium: &Consortium,
+    policy: &mut CompliancePolicy,
+    member_index: usize,
+) -> Result<()> {
+    require!(
+        !proposal.executed && proposal.revision == policy.revision,
+        CompliFiError::ProposalStale
+    );
+    let bit = 1 << member_index;
+    require!(proposal.votes & bit == 0, CompliFiError::AlreadyVoted);
+    proposal.votes |= bit;
+    proposal.vote_count = proposal.vote_count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+    
+    if proposal.vote_count >= consortium.threshold {
+        policy.max_risk_score = proposal.max_risk_score;
+        policy.require_kyc = proposal.require_kyc;
+        policy.allowed_jurisdictions = proposal.allowed_jurisdictions;
+        policy.revision = policy.revision.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        proposal.executed = true;
+        msg!("Consortium proposal passed with {} votes", proposal.vote_count);
+    } else {
+        msg!("Consortium vote recorded: {} of {}", proposal.vote_count, consortium.threshold);
+    }
+    Ok(())
+}
+
+// Oracle (or quorum) risk for `subject`, combined with its behavioral risk. Outside quorum
+// mode the score comes from `range_oracle` when the caller passes it.
+fn assess_risk_score(
+    policy: &CompliancePolicy,
+    oracles: &[AccountInfo],
+    range_oracle: Option<&AccountInfo>,
+    subject: &Pubkey,
+    attestation: Option<&KycAttestation>,
+    user_record: &Option<Account<UserComplianceRecord>>,
+) -> Result<u8> {
+    Ok(assess_risk(policy, oracles, range_oracle, subject, attestation, user_record)?.0)
+}
+
+// assess_risk_score along with the version of the risk model behind the oracle score.
+// Scores from models older than the policy's minimum are rejected. Once the monitor has
+// reported behavioral risk the attestation is flagged, and the user record must be passed.
+fn assess_risk(
+    policy: &CompliancePolicy,
+    oracles: &[AccountInfo],
+    range_oracle: Option<&AccountInfo>,
+    subject: &Pubkey,
+    attestation: Option<&KycAttestation>,
+    user_record: &Option<Account<UserComplianceRecord>>,
+) -> Result<(u8, u32)> {
+    let min_version = policy.min_risk_model_version;
+    let (risk_score, model_version) = if policy.min_oracle_quorum > 0 {
+        get_quorum_risk_score(oracles, subject, policy.min_oracle_quorum, min_version, policy.max_oracle_staleness)?
+    } else if let Some(oracle) = range_oracle {
+        get_wallet_risk_score(oracle, subject, policy.oracle_kind, policy.max_oracle_staleness)?
+    } else {
+        return Err(oracle_failure(subject, ORACLE_FAILURE_MISSING, CompliFiError::OracleDataFetchFailed));
+    };
+    require!(model_version >= min_version, CompliFiError::RiskModelOutdated);
+    
+    let behavioral_risk = match user_record {
+        Some(record) => record.behavioral_risk,
+        None if attestation.is_some_and(KycAttestation::behavioral_risk_reported) => {
+            return err!(CompliFiError::UserRecordRequired)
+        }
+        None => 0,
+    };
+    let risk_score = combine_risk_scores(policy, risk_score, behavioral_risk);
+    Ok((rescale_risk_score(risk_score, MAX_RISK_SCORE, policy.risk_scale()), model_version))
+}
+
+// Re-express a score on the 0..=from_max scale on 0..=to_max, rounding up so the mapping
+// never understates risk. Scores past the top of the old scale land on the top of the new one.
+fn rescale_risk_score(risk_score: u8, from_max: u8, to_max: u8) -> u8 {
+    (risk_score as u16 * to_max as u16).div_ceil(from_max as u16).min(to_max as u16) as u8
+}
+
+// Verification fee for a risk score: base_fee * (1 + risk_score * multiplier_bps / 10000),
+// saturating rather than failing on absurd configurations
+fn risk_weighted_fee(base_fee: u64, risk_score: u8, multiplier_bps: u16) -> u64 {
+    let surcharge = base_fee as u128 * risk_score as u128 * multiplier_bps as u128 / 10_000;
+    u64::try_from(base_fee as u128 + surcharge).unwrap_or(u64::MAX)
+}
+
+// Combine the oracle score with on-platform behavioral risk according to the policy
+fn combine_risk_scores(policy: &CompliancePolicy, oracle_score: u8, behavioral_risk: u8) -> u8 {
+    match policy.behavioral_risk_mode {
+        BEHAVIORAL_RISK_WEIGHTED => {
+            let weight = policy.behavioral_weight_bps as u32;
+            let weighted = oracle_score as u32 * (10_000 - weight) + behavioral_risk as u32 * weight;
+            // Round up so blending never understates risk
+            weighted.div_ceil(10_000) as u8
+        }
+        _ => oracle_score.max(behavioral_risk),
+    }
+}
 
 // Helper function to verify attestation with SAS
-fn verify_attestation(ctx: &Context<VerifyCompliance>, user: &Pubkey) -> Result<bool> {
+#[allow(dead_code)]
+fn verify_attestation(_ctx: &Context<VerifyCompliance>, user: &Pubkey) -> Result<bool> {
     // In a real implementation, we would call the SAS program here
     // For hackathon purposes, we'll simulate this check
     
-    msg!("Verifying KYC attestation for user: {}", user);
+    msg!("Verifying KYC attestation for user: {}", user);
+    
+    // Simulated attestation check - in production this would call the SAS program
+    // and verify the attestation cryptographically
+    Ok(true)
+}
+
+// Wallet risk score and the model version behind it, from the user's entry in a Range
+// Oracle account or from a Switchboard aggregator, as `oracle_kind` says. Scores last
+// updated more than `max_staleness` seconds ago are rejected (0 = any age).
+fn get_wallet_risk_score(oracle: &AccountInfo, user: &Pubkey, oracle_kind: u8, max_staleness: i64) -> Result<(u8, u32)> {
+    msg!("Fetching risk score for user: {}", user);
+    let (risk_score, model_version, last_updated) = match oracle_kind {
+        ORACLE_KIND_SWITCHBOARD => {
+            let result = read_aggregator_result(oracle)
+                .and_then(|result| Ok((aggregator_risk_score(&result)?, result.updated_at)));
+            match result {
+                // Switchboard feeds carry no risk model version
+                Ok((risk_score, updated_at)) => (risk_score, 0, updated_at),
+                Err(error) => {
+                    let reason_code = if *oracle.owner == SWITCHBOARD_PROGRAM_ID {
+                        ORACLE_FAILURE_MALFORMED
+                    } else {
+                        ORACLE_FAILURE_WRONG_OWNER
+                    };
+                    return Err(oracle_failure(user, reason_code, error));
+                }
+            }
+        }
+        _ => {
+            let entry = read_range_entry(oracle, user)
+                .map_err(|reason_code| oracle_failure(user, reason_code, CompliFiError::OracleDataFetchFailed))?;
+            (entry.risk_score, entry.model_version, entry.last_updated)
+        }
+    };
+    if oracle_entry_stale(last_updated, max_staleness, Clock::get()?.unix_timestamp) {
+        return Err(oracle_failure(user, ORACLE_FAILURE_STALE, CompliFiError::StaleOracleData));
+    }
+    Ok((risk_score, model_version))
+}
+
+// Report why an oracle lookup for `user` failed, then hand back the error it fails with.
+// The event lands in the failed transaction's logs for monitoring to pick up.
+fn oracle_failure(user: &Pubkey, reason_code: u8, error: impl Into<Error>) -> Error {
+    emit!(OracleFailureEvent {
+        user: *user,
+        reason_code,
+    });
+    error.into()
+}
+
+// Whether a score last updated at `last_updated` is older than `max_staleness` seconds allow
+// (0 = no limit)
+fn oracle_entry_stale(last_updated: i64, max_staleness: i64, now: i64) -> bool {
+    max_staleness > 0 && now.saturating_sub(last_updated) > max_staleness
+}
+
+// Oracle account a single-oracle check reads for `subject`: the Range entry, or for a
+// Switchboard policy the aggregator registered for the subject. With no oracle account
+// passed there is nothing to score the subject by, and assess_risk fails.
+fn single_risk_oracle<'a, 'info>(
+    policy: &CompliancePolicy,
+    range_oracle: &'a Option<UncheckedAccount<'info>>,
+    risk_feed: &Option<Box<Account<'info, RiskFeed>>>,
+    switchboard_aggregator: &'a Option<UncheckedAccount<'info>>,
+    subject: &Pubkey,
+) -> Result<Option<&'a AccountInfo<'info>>> {
+    if policy.oracle_kind != ORACLE_KIND_SWITCHBOARD {
+        return Ok(range_oracle.as_ref().map(AsRef::as_ref));
+    }
+    registered_aggregator(
+        risk_feed.as_deref().map(|feed| &**feed),
+        switchboard_aggregator.as_ref().map(AsRef::as_ref),
+        subject,
+    )
+}
+
+// The aggregator passed for `subject`, once checked against the subject's risk feed
+fn registered_aggregator<'a, 'info>(
+    risk_feed: Option<&RiskFeed>,
+    aggregator: Option<&'a AccountInfo<'info>>,
+    subject: &Pubkey,
+) -> Result<Option<&'a AccountInfo<'info>>> {
+    let Some(aggregator) = aggregator else {
+        return Ok(None);
+    };
+    let Some(feed) = risk_feed else {
+        return Err(oracle_failure(subject, ORACLE_FAILURE_MISSING, CompliFiError::OracleDataFetchFailed));
+    };
+    if feed.wallet != *subject || feed.aggregator != aggregator.key() {
+        return Err(oracle_failure(subject, ORACLE_FAILURE_WRONG_SUBJECT, CompliFiError::OracleDataFetchFailed));
+    }
+    Ok(Some(aggregator))
+}
+
+// assess_risk for a member of a batch, whose oracle accounts follow its attestation in
+// remaining_accounts: the quorum's oracles, the member's Range entry, or under a Switchboard
+// policy its RiskFeed PDA and the aggregator registered there
+fn assess_member_risk<'info>(
+    policy: &CompliancePolicy,
+    oracles: &'info [AccountInfo<'info>],
+    member: &Pubkey,
+    attestation: Option<&KycAttestation>,
+) -> Result<(u8, u32)> {
+    if policy.min_oracle_quorum > 0 {
+        return assess_risk(policy, oracles, None, member, attestation, &None);
+    }
+    let oracle = match oracles {
+        [] => None,
+        [entry] if policy.oracle_kind != ORACLE_KIND_SWITCHBOARD => Some(entry),
+        [feed, aggregator] if policy.oracle_kind == ORACLE_KIND_SWITCHBOARD => {
+            let feed = Account::<RiskFeed>::try_from(feed)
+                .map_err(|_| oracle_failure(member, ORACLE_FAILURE_MISSING, CompliFiError::OracleDataFetchFailed))?;
+            registered_aggregator(Some(&feed), Some(aggregator), member)?
+        }
+        _ => return err!(CompliFiError::AccountCountMismatch),
+    };
+    assess_risk(policy, &[], oracle, member, attestation, &None)
+}
+
+// Read a wallet's risk entry from an account owned by the Range Oracle program
+fn read_oracle_entry(oracle: &AccountInfo, user: &Pubkey) -> Result<RangeRiskEntry> {
+    read_range_entry(oracle, user).map_err(|_| error!(CompliFiError::OracleDataFetchFailed))
+}
+
+// read_oracle_entry, failing with the ORACLE_FAILURE_* reason the entry could not be read
+fn read_range_entry(oracle: &AccountInfo, user: &Pubkey) -> core::result::Result<RangeRiskEntry, u8> {
+    if *oracle.owner != RANGE_ORACLE_PROGRAM_ID {
+        return Err(ORACLE_FAILURE_WRONG_OWNER);
+    }
+    
+    let data = oracle.try_borrow_data().map_err(|_| ORACLE_FAILURE_MALFORMED)?;
+    let entry = data
+        .get(8..)
+        .and_then(|mut body| RangeRiskEntry::deserialize(&mut body).ok())
+        .ok_or(ORACLE_FAILURE_MALFORMED)?;
+    
+    if entry.wallet != *user {
+        return Err(ORACLE_FAILURE_WRONG_SUBJECT);
+    }
+    Ok(entry)
+}
+
+// Copy `user`'s oracle entry into its risk cache, returning the refreshed score
+fn refresh_risk_cache<'info>(
+    oracle: &AccountInfo<'info>,
+    cache_info: &'info AccountInfo<'info>,
+    user: &Pubkey,
+    now: i64,
+) -> Result<u8> {
+    let entry = read_oracle_entry(oracle, user)?;
+    require!(cache_info.is_writable, CompliFiError::Unauthorized);
+    
+    let mut cache = Account::<RiskCache>::try_from(cache_info)?;
+    require_keys_eq!(cache.user, *user, CompliFiError::Unauthorized);
+    cache.risk_score = entry.risk_score;
+    cache.oracle = *oracle.key;
+    cache.oracle_updated_at = entry.last_updated;
+    cache.model_version = entry.model_version;
+    cache.refreshed_at = now;
+    cache.exit(&crate::ID)?;
+    Ok(entry.risk_score)
+}
+
+// Risk score agreed on by at least `quorum` of the oracle accounts passed in `oracles`, with
+// the model version of the oracle that set it. Unreadable accounts and those reporting a model
+// older than `min_model_version` or an entry older than `max_staleness` count as unresponsive;
+// duplicates are only counted once.
+fn get_quorum_risk_score(
+    oracles: &[AccountInfo],
+    user: &Pubkey,
+    quorum: u8,
+    min_model_version: u32,
+    max_staleness: i64,
+) -> Result<(u8, u32)> {
+    require!(oracles.len() <= MAX_RISK_ORACLES, CompliFiError::OracleDataFetchFailed);
+    
+    let now = Clock::get()?.unix_timestamp;
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(oracles.len());
+    let mut scores: Vec<(u8, u32)> = Vec::with_capacity(oracles.len());
+    for oracle in oracles {
+        if seen.contains(oracle.key) {
+            continue;
+        }
+        seen.push(*oracle.key);
+        
+        match read_oracle_entry(oracle, user) {
+            // Staleness comes first: a current model does not make an old score current
+            Ok(entry) if oracle_entry_stale(entry.last_updated, max_staleness, now) => {
+                msg!("Oracle {} reported stale data for user: {}", oracle.key, user)
+            }
+            Ok(entry) if entry.model_version >= min_model_version => {
+                scores.push((entry.risk_score, entry.model_version))
+            }
+            Ok(_) => msg!("Oracle {} reported an outdated risk model for user: {}", oracle.key, user),
+            Err(_) => msg!("Oracle {} did not respond for user: {}", oracle.key, user),
+        }
+    }
+    
+    if scores.len() < quorum as usize {
+        return Err(oracle_failure(user, ORACLE_FAILURE_NO_QUORUM, CompliFiError::OracleDataFetchFailed));
+    }
+    
+    // The quorum-th lowest score is the strictest value that `quorum` oracles agree is not exceeded
+    scores.sort_unstable();
+    Ok(scores[quorum as usize - 1])
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ComplianceState::LEN,
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(policy_name: String)]
+pub struct InitializePolicy<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CompliancePolicy::LEN,
+        seeds = [POLICY_SEED, authority.key().as_ref(), policy_name.as_bytes()],
+        bump
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Option<Account<'info, ComplianceState>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePolicy<'info> {
+    #[account(
+        mut,
+        close = authority,
+        constraint = policy.authority == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Option<Account<'info, ComplianceState>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(user_key: Pubkey, action: String, override_max_risk: Option<u8>, amount: Option<u64>)]
+pub struct VerifyCompliance<'info> {
+    #[account(mut, seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Only the public key is used to look up attestations/risk off-chain; no data is read or written.
+    pub user: UncheckedAccount<'info>,
+    
+    /// CHECK: Only the public key is used, to pick which authority's attestation of the subject is read
+    pub attestation_authority: UncheckedAccount<'info>,
+    
+    /// Front-end or protocol the verification is attributed to in its VerificationEvent. It
+    /// signs, directly or as a calling program's PDA, so usage cannot be pinned on another.
+    pub integrator: Option<Signer<'info>>,
+    
+    /// Present when `user` acts as a delegate; compliance is then evaluated against the principal
+    #[account(
+        seeds = [DELEGATION_SEED, delegation.principal.as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Option<Account<'info, DelegationRecord>>,
+    
+    /// Present when `user` is a sub-account verifying under its parent entity's KYB
+    #[account(
+        seeds = [ENTITY_HIERARCHY_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub entity: Option<Account<'info, EntityHierarchy>>,
+    
+    #[account(
+        seeds = [
+            KYC_ATTESTATION_SEED,
+            attestation_authority.key().as_ref(),
+            verification_subject(&user, &delegation, &entity).as_ref(),
+        ],
+        bump = attestation.bump,
+    )]
+    /// Absent for a user who was never attested, which only a policy without require_kyc accepts
+    pub attestation: Option<Account<'info, KycAttestation>>,
+    
+    /// CHECK: Allowlist entry of the attestation's issuer, required under a policy that demands
+    /// a trusted attestor; only this program's accounts are accepted
+    #[account(
+        seeds = [ATTESTOR_SEED, attestation.as_ref().map_or(Pubkey::default(), |attestation| attestation.authority).as_ref()],
+        bump,
+        constraint = attestor_entry.owner == &crate::ID @ CompliFiError::UntrustedAttestor
+    )]
+    pub attestor_entry: Option<UncheckedAccount<'info>>,
+    
+    /// Created on first use when passed; each pass is recorded in it
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserComplianceRecord::LEN,
+        seeds = [USER_RECORD_SEED, verification_subject(&user, &delegation, &entity).as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    #[account(mut)]
+    pub report: Option<Box<Account<'info, ComplianceReport>>>,
+    
+    #[account(
+        seeds = [COMPILED_POLICY_SEED, policy.key().as_ref()],
+        bump,
+    )]
+    pub compiled_policy: Option<Box<Account<'info, CompiledPolicy>>>,
+    
+    /// Required for users outside the cohort while a staged rollout is in progress
+    #[account(
+        seeds = [ROLLOUT_BASELINE_SEED, policy.key().as_ref()],
+        bump,
+    )]
+    pub rollout_baseline: Option<Box<Account<'info, CompiledPolicy>>>,
+    
+    #[account(
+        seeds = [JURISDICTION_LIMIT_SEED, &[attestation.as_ref().map_or(0, |attestation| attestation.jurisdiction)]],
+        bump,
+    )]
+    pub jurisdiction_limit: Option<Account<'info, JurisdictionLimit>>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The subject's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            VERIFICATION_RECEIPT_SEED,
+            user.key().as_ref(),
+            policy.key().as_ref(),
+            &action_hash(&action),
+            &[amount_bucket(amount)],
+        ],
+        bump,
+    )]
+    pub receipt: Option<Account<'info, VerificationReceipt>>,
+    
+    /// Created on first use when passed; each pass overwrites it
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ComplianceReceipt::LEN,
+        seeds = [COMPLIANCE_RECEIPT_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub compliance_receipt: Option<Box<Account<'info, ComplianceReceipt>>>,
+    
+    /// CHECK: Only receives the verification fee; address is pinned by the policy
+    #[account(mut, address = policy.fee_recipient @ CompliFiError::Unauthorized)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Required for fee-paying verifications once the policy keeps a fee ledger
+    #[account(mut, seeds = [FEE_LEDGER_SEED, policy.key().as_ref()], bump)]
+    pub fee_ledger: Option<Account<'info, FeeLedger>>,
+    
+    /// CHECK: The user's sanction entry address; the user is blocked if an entry exists there.
+    /// Integrators pass it on every call, as the address is valid whether or not it is listed.
+    #[account(seeds = [SANCTION_SEED, user.key().as_ref()], bump)]
+    pub sanction: UncheckedAccount<'info>,
+    
+    /// Certificate metadata, created on the user's first certificate; needed with `mint_certificate`
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ComplianceCertificate::LEN,
+        seeds = [CERTIFICATE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub certificate: Option<Box<Account<'info, ComplianceCertificate>>>,
+    
+    /// CHECK: The certificate mint PDA, created by init_certificate_mint
+    #[account(mut, seeds = [CERTIFICATE_MINT_SEED], bump)]
+    pub certificate_mint: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The user's associated token account for the certificate mint, created on first use;
+    /// the associated token program rejects any other address
+    #[account(mut)]
+    pub certificate_token_account: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Address pinned to Token-2022
+    #[account(address = TOKEN_2022_PROGRAM_ID)]
+    pub token_program: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Address pinned to the associated token program
+    #[account(address = ASSOCIATED_TOKEN_PROGRAM_ID)]
+    pub associated_token_program: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user_key: Pubkey, action: String, override_max_risk: Option<u8>, amount: Option<u64>)]
+pub struct VerifyAndExecute<'info> {
+    pub verify: VerifyCompliance<'info>,
+    
+    /// CHECK: Program the gated instruction is sent to; it only ever runs after verification
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey, action: String, amount_bucket: u8)]
+pub struct FinalizeVerification<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            VERIFICATION_RECEIPT_SEED,
+            user.as_ref(),
+            policy.key().as_ref(),
+            &action_hash(&action),
+            &[amount_bucket],
+        ],
+        bump,
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+}
+
+#[derive(Accounts)]
+pub struct CompilePolicy<'info> {
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CompiledPolicy::LEN,
+        seeds = [COMPILED_POLICY_SEED, policy.key().as_ref()],
+        bump
+    )]
+    pub compiled_policy: Box<Account<'info, CompiledPolicy>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BeginPolicyRollout<'info> {
+    #[account(
+        mut,
+        constraint = policy.authority == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub policy: Box<Account<'info, CompliancePolicy>>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CompiledPolicy::LEN,
+        seeds = [ROLLOUT_BASELINE_SEED, policy.key().as_ref()],
+        bump
+    )]
+    pub rollout_baseline: Box<Account<'info, CompiledPolicy>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateCompliance<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    /// CHECK: Only the public key is used to derive the attestation and record accounts
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [DELEGATION_SEED, delegation.principal.as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Option<Account<'info, DelegationRecord>>,
+    
+    #[account(
+        seeds = [
+            KYC_ATTESTATION_SEED,
+            attestation.authority.as_ref(),
+            compliance_subject(&user, &delegation).as_ref(),
+        ],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, compliance_subject(&user, &delegation).as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: Deserialized as the subject's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The subject's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct CheckCompliance<'info> {
+    #[account(seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey, action: String, amount_bucket: u8)]
+pub struct InitVerificationReceipt<'info> {
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [
+            VERIFICATION_RECEIPT_SEED,
+            user.as_ref(),
+            policy.key().as_ref(),
+            &action_hash(&action),
+            &[amount_bucket],
+        ],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct InitRiskCache<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RiskCache::LEN,
+        seeds = [RISK_CACHE_SEED, user.as_ref()],
+        bump
+    )]
+    pub risk_cache: Account<'info, RiskCache>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshRiskBatch<'info> {
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetMetrics<'info> {
+    pub state: Account<'info, ComplianceState>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct GetUnderwritingSignal<'info> {
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey, attestation_authority: Pubkey)]
+pub struct Guard<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation_authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: The user's sanction entry address; the user is blocked if an entry exists there
+    #[account(seeds = [SANCTION_SEED, user.as_ref()], bump)]
+    pub sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: Allowlist entry of the attestation's issuer, required under a policy that demands
+    /// a trusted attestor; only this program's accounts are accepted
+    #[account(
+        seeds = [ATTESTOR_SEED, attestation.authority.as_ref()],
+        bump,
+        constraint = attestor_entry.owner == &crate::ID @ CompliFiError::UntrustedAttestor
+    )]
+    pub attestor_entry: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct VerifyAnonymous<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + NullifierUsage::LEN,
+        seeds = [NULLIFIER_SEED, nullifier.as_ref()],
+        bump
+    )]
+    pub usage: Account<'info, NullifierUsage>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// Required once the nullifier's volume passes the anonymous cap
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub attestation: Option<Account<'info, KycAttestation>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct RecordComplianceCheck<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserComplianceRecord::LEN,
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserComplianceRecord>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct GetUserStatus<'info> {
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Account<'info, UserComplianceRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey, source: Pubkey, destination: Pubkey)]
+pub struct VerifyToken2022Transfer<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Token-2022 mint, parsed by hand for its transfer extensions
+    #[account(address = mint, owner = TOKEN_2022_PROGRAM_ID)]
+    pub token_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: Token-2022 token account, parsed by hand for its mint, owner and state
+    #[account(address = source, owner = TOKEN_2022_PROGRAM_ID)]
+    pub source_token: UncheckedAccount<'info>,
+    
+    /// CHECK: Token-2022 token account, parsed by hand for its mint, owner and state
+    #[account(address = destination, owner = TOKEN_2022_PROGRAM_ID)]
+    pub destination_token: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [
+            KYC_ATTESTATION_SEED,
+            source_attestation.authority.as_ref(),
+            token_account_owner(&source_token).as_ref(),
+        ],
+        bump,
+    )]
+    pub source_attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [
+            KYC_ATTESTATION_SEED,
+            destination_attestation.authority.as_ref(),
+            token_account_owner(&destination_token).as_ref(),
+        ],
+        bump,
+    )]
+    pub destination_attestation: Account<'info, KycAttestation>,
+    
+    /// CHECK: The source owner's sanction entry address; blocked if an entry exists there
+    #[account(seeds = [SANCTION_SEED, token_account_owner(&source_token).as_ref()], bump)]
+    pub source_sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: The destination owner's sanction entry address; blocked if an entry exists there
+    #[account(seeds = [SANCTION_SEED, token_account_owner(&destination_token).as_ref()], bump)]
+    pub destination_sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: Deserialized as the source owner's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = source_range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub source_range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The source owner's registered Switchboard feed; required with `source_switchboard_aggregator`
+    pub source_risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `source_risk_feed`
+    #[account(constraint = source_switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub source_switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Deserialized as the destination owner's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = destination_range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub destination_range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The destination owner's registered Switchboard feed; required with `destination_switchboard_aggregator`
+    pub destination_risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `destination_risk_feed`
+    #[account(constraint = destination_switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub destination_switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey, underlying_policy: Pubkey, derivative_policy: Pubkey)]
+pub struct VerifyDerivative<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        address = underlying_policy,
+        constraint = underlying.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub underlying: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        address = derivative_policy,
+        constraint = derivative.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub derivative: Account<'info, CompliancePolicy>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: The user's sanction entry address; the user is blocked if an entry exists there
+    #[account(seeds = [SANCTION_SEED, user.as_ref()], bump)]
+    pub sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct CreateMandate<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: The user's sanction entry address; the user is blocked if an entry exists there
+    #[account(seeds = [SANCTION_SEED, user.as_ref()], bump)]
+    pub sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The user's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PaymentMandate::LEN,
+        seeds = [MANDATE_SEED, policy.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub mandate: Account<'info, PaymentMandate>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeMandate<'info> {
+    #[account(seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        seeds = [MANDATE_SEED, mandate.policy.as_ref(), mandate.user.as_ref()],
+        bump,
+        constraint = mandate.authority == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub mandate: Account<'info, PaymentMandate>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), mandate.user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    /// CHECK: The user's sanction entry address; the user is blocked if an entry exists there
+    #[account(seeds = [SANCTION_SEED, mandate.user.as_ref()], bump)]
+    pub sanction: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct AuthorizeDelegate<'info> {
+    #[account(
+        init,
+        payer = principal,
+        space = 8 + DelegationRecord::LEN,
+        seeds = [DELEGATION_SEED, principal.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, DelegationRecord>,
+    
+    #[account(mut)]
+    pub principal: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subaccount: Pubkey)]
+pub struct LinkSubaccount<'info> {
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EntityHierarchy::LEN,
+        seeds = [ENTITY_HIERARCHY_SEED, subaccount.as_ref()],
+        bump
+    )]
+    pub entity: Account<'info, EntityHierarchy>,
+    
+    #[account(
+        seeds = [
+            KYC_ATTESTATION_SEED,
+            parent_attestation.authority.as_ref(),
+            parent_attestation.wallet.as_ref(),
+        ],
+        bump,
+    )]
+    pub parent_attestation: Account<'info, KycAttestation>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subaccount: Pubkey)]
+pub struct UnlinkSubaccount<'info> {
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [ENTITY_HIERARCHY_SEED, subaccount.as_ref()],
+        bump
+    )]
+    pub entity: Account<'info, EntityHierarchy>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct RevokeDelegate<'info> {
+    #[account(
+        mut,
+        close = principal,
+        seeds = [DELEGATION_SEED, principal.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, DelegationRecord>,
+    
+    #[account(mut)]
+    pub principal: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey, is_verified: bool, jurisdiction: u8)]
+pub struct CreateKycAttestation<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + KycAttestation::LEN,
+        seeds = [KYC_ATTESTATION_SEED, authority.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    /// The attestor. A provider operating through a program signs as its PDA, via
+    /// invoke_signed in a CPI; the PDA pays the rent, so it must be a funded system account.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: The signer's allowlist entry; only keys added by add_attestor have one
+    #[account(
+        seeds = [ATTESTOR_SEED, authority.key().as_ref()],
+        bump,
+        constraint = attestor_entry.owner == &crate::ID && !attestor_entry.data_is_empty()
+            @ CompliFiError::UnauthorizedAttestor
+    )]
+    pub attestor_entry: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    /// CHECK: This is the wallet we're creating an attestation for
+    pub wallet: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = time_oracle.key() == state.time_oracle @ CompliFiError::Unauthorized
+    )]
+    pub time_oracle: Option<Signer<'info>>,
+    
+    #[account(
+        seeds = [VENDOR_SEED, &[vendor.id]],
+        bump,
+        constraint = vendor.key == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub vendor: Option<Account<'info, ScreeningVendor>>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar; present when proving wallet ownership
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    
+    // Policy and jurisdiction metadata together supply a default expiry from the risk tier;
+    // the policy also sets the reviewed-document minimum per level, and must be the state's
+    // issuance policy once one is designated
+    #[account(constraint = policy.authority == state.authority @ CompliFiError::Unauthorized)]
+    pub policy: Option<Account<'info, CompliancePolicy>>,
+    
+    #[account(seeds = [JURISDICTION_SEED, &[jurisdiction]], bump)]
+    pub jurisdiction_meta: Option<Account<'info, JurisdictionMeta>>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+    
+    /// The wallet's self-service request, marked fulfilled when passed
+    #[account(mut, seeds = [KYC_REQUEST_SEED, wallet.key().as_ref()], bump)]
+    pub kyc_request: Option<Account<'info, KycRequest>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestKyc<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + KycRequest::LEN,
+        seeds = [KYC_REQUEST_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub kyc_request: Account<'info, KycRequest>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateKycAttestationsBatch<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: The signer's allowlist entry; only keys added by add_attestor have one
+    #[account(
+        seeds = [ATTESTOR_SEED, authority.key().as_ref()],
+        bump,
+        constraint = attestor_entry.owner == &crate::ID && !attestor_entry.data_is_empty()
+            @ CompliFiError::UnauthorizedAttestor
+    )]
+    pub attestor_entry: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+    
+    // The state's issuance policy, when one is designated
+    #[account(constraint = policy.authority == state.authority @ CompliFiError::Unauthorized)]
+    pub policy: Option<Account<'info, CompliancePolicy>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RevokeKycAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+        constraint = authority.key() == attestation.authority
+            || authority.key() == attestation.revocation_authority @ CompliFiError::Unauthorized
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct ForceRevokeAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct CloseKycAttestation<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+        constraint = authority.key() == attestation.authority @ CompliFiError::Unauthorized
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct PurgeAttestation<'info> {
+    #[account(
+        mut,
+        close = attester,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [JURISDICTION_SEED, &[attestation.jurisdiction]],
+        bump,
+    )]
+    pub jurisdiction_meta: Account<'info, JurisdictionMeta>,
+    
+    /// CHECK: Only receives the attestation's rent; address is pinned by the attestation
+    #[account(mut, address = attestation.authority @ CompliFiError::Unauthorized)]
+    pub attester: UncheckedAccount<'info>,
+    
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct FreezeWallet<'info> {
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddSanction<'info> {
+    #[account(
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SanctionEntry::LEN,
+        seeds = [SANCTION_SEED, wallet.as_ref()],
+        bump
+    )]
+    pub sanction: Account<'info, SanctionEntry>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RemoveSanction<'info> {
+    #[account(
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [SANCTION_SEED, wallet.as_ref()],
+        bump
+    )]
+    pub sanction: Account<'info, SanctionEntry>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CheckSanctionProof<'info> {
+    #[account(seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RefreshRiskFreeze<'info> {
+    #[account(seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, wallet.as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: Deserialized as the user's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The wallet's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct SetRegulatoryHold<'info> {
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserComplianceRecord::LEN,
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserComplianceRecord>,
+    
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitRollup<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalRollup::LEN,
+        seeds = [ROLLUP_SEED, state.key().as_ref()],
+        bump
+    )]
+    pub rollup: Account<'info, GlobalRollup>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitAttestationJournal<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AttestationJournal::LEN,
+        seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()],
+        bump
+    )]
+    pub journal: Account<'info, AttestationJournal>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetJournalHead<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Account<'info, AttestationJournal>,
+}
+
+#[derive(Accounts)]
+pub struct InitFeeLedger<'info> {
+    #[account(
+        mut,
+        constraint = policy.authority == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FeeLedger::LEN,
+        seeds = [FEE_LEDGER_SEED, policy.key().as_ref()],
+        bump
+    )]
+    pub fee_ledger: Account<'info, FeeLedger>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetFeeLedger<'info> {
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(seeds = [FEE_LEDGER_SEED, policy.key().as_ref()], bump)]
+    pub fee_ledger: Account<'info, FeeLedger>,
+}
+
+#[derive(Accounts)]
+pub struct GetRollup<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Account<'info, GlobalRollup>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct VerifyAttestationChain<'info> {
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey, authority: Pubkey)]
+pub struct MigrateAttestation<'info> {
+    /// CHECK: Still in the legacy layout, so it is decoded by hand after the owner and seeds checks
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [KYC_ATTESTATION_SEED, authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// CHECK: Possibly still at an older size, so decoded by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+    )]
+    pub state: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePolicy<'info> {
+    /// CHECK: Possibly still at an older size, so decoded by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+    )]
+    pub policy: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct MigrateAttestationSize<'info> {
+    /// CHECK: Possibly still at an older size, so decoded by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [KYC_ATTESTATION_SEED, authority.key().as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(jurisdiction: u8)]
+pub struct SetJurisdictionLimit<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + JurisdictionLimit::LEN,
+        seeds = [JURISDICTION_LIMIT_SEED, &[jurisdiction]],
+        bump
+    )]
+    pub jurisdiction_limit: Account<'info, JurisdictionLimit>,
+    
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitCertificateMint<'info> {
+    /// CHECK: Created here as the certificate mint, owned by Token-2022
+    #[account(mut, seeds = [CERTIFICATE_MINT_SEED], bump)]
+    pub certificate_mint: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Address pinned to Token-2022
+    #[account(address = TOKEN_2022_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct SetRiskFeed<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RiskFeed::LEN,
+        seeds = [RISK_FEED_SEED, wallet.as_ref()],
+        bump
+    )]
+    pub risk_feed: Account<'info, RiskFeed>,
+    
+    #[account(
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(jurisdiction: u8)]
+pub struct SetJurisdictionMeta<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + JurisdictionMeta::LEN,
+        seeds = [JURISDICTION_SEED, &[jurisdiction]],
+        bump
+    )]
+    pub jurisdiction_meta: Account<'info, JurisdictionMeta>,
+    
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u8)]
+pub struct RegisterVendor<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ScreeningVendor::LEN,
+        seeds = [VENDOR_SEED, &[id]],
+        bump
+    )]
+    pub vendor: Account<'info, ScreeningVendor>,
+    
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct ImportAttestation<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + KycAttestation::LEN,
+        seeds = [KYC_ATTESTATION_SEED, authority.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(mut, constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(provider: Pubkey, message: SignedKycMessage)]
+pub struct VerifySignedAttestation<'info> {
+    #[account(mut, seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + KycAttestation::LEN,
+        seeds = [KYC_ATTESTATION_SEED, provider.as_ref(), message.wallet.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    /// CHECK: The provider's allowlist entry; only keys added by add_attestor have one
+    #[account(
+        seeds = [ATTESTOR_SEED, provider.as_ref()],
+        bump,
+        constraint = attestor_entry.owner == &crate::ID && !attestor_entry.data_is_empty()
+            @ CompliFiError::UnauthorizedAttestor
+    )]
+    pub attestor_entry: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar, read for Ed25519 introspection
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(message: BridgedKycMessage)]
+pub struct CreateBridgedAttestation<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + KycAttestation::LEN,
+        seeds = [KYC_ATTESTATION_SEED, state.bridge_relayer.as_ref(), message.wallet.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + BridgeMessageReceipt::LEN,
+        seeds = [
+            BRIDGE_MESSAGE_SEED,
+            &message.source_chain.to_le_bytes(),
+            &message.sequence.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub message_receipt: Account<'info, BridgeMessageReceipt>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar, read for Ed25519 introspection
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    #[account(mut, seeds = [ATTESTATION_JOURNAL_SEED, state.key().as_ref()], bump)]
+    pub journal: Option<Account<'info, AttestationJournal>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyComplianceMultisig<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub report: Option<Box<Account<'info, ComplianceReport>>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyComplianceBatch<'info> {
+    #[account(mut, seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub report: Option<Box<Account<'info, ComplianceReport>>>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyVerify<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyTreasuryOp<'info> {
+    #[account(seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(constraint = policy.key() == state.treasury_policy @ CompliFiError::NotTreasuryPolicy)]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    pub operator: Signer<'info>,
+    
+    #[account(
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), operator.key().as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        seeds = [USER_RECORD_SEED, operator.key().as_ref()],
+        bump,
+    )]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: Deserialized as the operator's Range Oracle risk entry; only the oracle program's accounts are accepted
+    #[account(constraint = range_oracle.owner == &RANGE_ORACLE_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// The operator's registered Switchboard feed; required with `switchboard_aggregator`
+    pub risk_feed: Option<Box<Account<'info, RiskFeed>>>,
+    
+    /// CHECK: Read as a Switchboard aggregator under a Switchboard policy; only the Switchboard
+    /// program's accounts are accepted, and only the one registered in `risk_feed`
+    #[account(constraint = switchboard_aggregator.owner == &SWITCHBOARD_PROGRAM_ID @ CompliFiError::OracleDataFetchFailed)]
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct EmitExpiryReminders<'info> {
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoRenew<'info> {
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.key().as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
     
-    // Simulated attestation check - in production this would call the SAS program
-    // and verify the attestation cryptographically
-    Ok(true)
+    pub wallet: Signer<'info>,
 }
 
-// Helper function to get wallet risk score from Range Oracle
-fn get_wallet_risk_score(ctx: &Context<VerifyCompliance>, user: &Pubkey) -> Result<u8> {
-    // In a real implementation, we would call the Range Oracle program here
-    // For hackathon purposes, we'll simulate this check
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RenewAttestation<'info> {
+    pub state: Account<'info, ComplianceState>,
     
-    msg!("Fetching risk score for user: {}", user);
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        mut,
+        constraint = renewer.key() == attestation.authority
+            || renewer.key() == state.renewal_authority @ CompliFiError::Unauthorized
+    )]
+    pub renewer: Signer<'info>,
+    
+    /// CHECK: Only receives the renewal fee; address is pinned by the policy
+    #[account(mut, address = policy.fee_recipient @ CompliFiError::Unauthorized)]
+    pub fee_recipient: UncheckedAccount<'info>,
     
-    // Simulated risk score - in production this would call the Range Oracle
-    // and fetch the actual risk score for the wallet
-    Ok(2) // Low-medium risk
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+#[instruction(wallet: Pubkey)]
+pub struct CommitAttestationFields<'info> {
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+        constraint = attestation.authority == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(attestor: Pubkey)]
+pub struct AddAttestor<'info> {
+    #[account(
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
     #[account(
         init,
         payer = authority,
-        space = 8 + ComplianceState::LEN
+        space = 8 + Attestor::LEN,
+        seeds = [ATTESTOR_SEED, attestor.as_ref()],
+        bump
     )]
-    pub state: Account<'info, ComplianceState>,
+    pub attestor_entry: Account<'info, Attestor>,
     
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -207,13 +7372,57 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-pub struct InitializePolicy<'info> {
+#[instruction(attestor: Pubkey)]
+pub struct RemoveAttestor<'info> {
     #[account(
-        init,
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [ATTESTOR_SEED, attestor.as_ref()],
+        bump
+    )]
+    pub attestor_entry: Account<'info, Attestor>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(attestor: Pubkey)]
+pub struct SetAttestorJurisdictions<'info> {
+    #[account(
+        seeds = [COMPLIANCE_STATE_SEED],
+        bump,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut, seeds = [ATTESTOR_SEED, attestor.as_ref()], bump)]
+    pub attestor_entry: Account<'info, Attestor>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(attester: Pubkey)]
+pub struct SetAttesterTrustWeight<'info> {
+    #[account(
+        init_if_needed,
         payer = authority,
-        space = 8 + CompliancePolicy::LEN
+        space = 8 + Attester::LEN,
+        seeds = [ATTESTER_SEED, attester.as_ref()],
+        bump
     )]
-    pub policy: Account<'info, CompliancePolicy>,
+    pub attester_profile: Account<'info, Attester>,
+    
+    #[account(constraint = authority.key() == state.authority @ CompliFiError::Unauthorized)]
+    pub state: Account<'info, ComplianceState>,
     
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -222,50 +7431,171 @@ pub struct InitializePolicy<'info> {
 }
 
 #[derive(Accounts)]
-pub struct VerifyCompliance<'info> {
-    #[account(mut)]
+#[instruction(wallet: Pubkey)]
+pub struct CosignAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, KycAttestation>,
+    
+    /// Only registered attesters may co-sign
+    #[account(
+        seeds = [ATTESTER_SEED, cosigner.key().as_ref()],
+        bump,
+    )]
+    pub attester_profile: Account<'info, Attester>,
+    
+    pub cosigner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RevealAttestationField<'info> {
     pub state: Account<'info, ComplianceState>,
     
     #[account(
-        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), wallet.as_ref()],
+        bump,
     )]
-    pub policy: Account<'info, CompliancePolicy>,
+    pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(constraint = auditor.key() == state.auditor @ CompliFiError::Unauthorized)]
+    pub auditor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateState<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
     
     pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = state.pending_authority != Pubkey::default()
+            && new_authority.key() == state.pending_authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
     
-    /// CHECK: Only the public key is used to look up attestations/risk off-chain; no data is read or written.
-    pub user: UncheckedAccount<'info>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct UpdateBehavioralRisk<'info> {
+    #[account(
+        constraint = monitor.key() == state.risk_monitor @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init_if_needed,
+        payer = monitor,
+        space = 8 + UserComplianceRecord::LEN,
+        seeds = [USER_RECORD_SEED, user.as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserComplianceRecord>,
     
     #[account(
-        seeds = [KYC_ATTESTATION_SEED, user.key().as_ref()],
+    
+        mut,
+    
+        seeds = [KYC_ATTESTATION_SEED, attestation.authority.as_ref(), user.as_ref()],
+    
         bump,
+    
     )]
     pub attestation: Account<'info, KycAttestation>,
+    
+    #[account(mut)]
+    pub monitor: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateKycAttestation<'info> {
+pub struct CreateConsortium<'info> {
     #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + KycAttestation::LEN,
-        seeds = [KYC_ATTESTATION_SEED, wallet.key().as_ref()],
+        init,
+        payer = founder,
+        space = 8 + Consortium::LEN,
+        seeds = [CONSORTIUM_SEED, founder.key().as_ref()],
         bump
     )]
-    pub attestation: Account<'info, KycAttestation>,
+    pub consortium: Account<'info, Consortium>,
     
     #[account(mut)]
+    pub founder: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AssignPolicyConsortium<'info> {
+    #[account(
+        mut,
+        constraint = policy.authority == authority.key() @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
     pub authority: Signer<'info>,
     
-    #[account(mut)]
-    pub state: Account<'info, ComplianceState>,
+    pub consortium: Account<'info, Consortium>,
+}
+
+#[derive(Accounts)]
+pub struct ProposePolicyChange<'info> {
+    pub consortium: Account<'info, Consortium>,
     
-    /// CHECK: This is the wallet we're creating an attestation for
-    pub wallet: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = policy.consortium == consortium.key() @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + ConsortiumProposal::LEN,
+        seeds = [CONSORTIUM_PROPOSAL_SEED, policy.key().as_ref(), &policy.revision.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, ConsortiumProposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ConsortiumVote<'info> {
+    pub consortium: Account<'info, Consortium>,
+    
+    #[account(
+        mut,
+        constraint = policy.consortium == consortium.key() @ CompliFiError::Unauthorized
+    )]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(
+        mut,
+        constraint = proposal.policy == policy.key() @ CompliFiError::Unauthorized
+    )]
+    pub proposal: Account<'info, ConsortiumProposal>,
+    
+    pub voter: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct SetPolicy<'info> {
     #[account(
@@ -278,13 +7608,141 @@ pub struct SetPolicy<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(user: Pubkey)]
 pub struct RecordViolation<'info> {
+    #[account(mut, seeds = [COMPLIANCE_STATE_SEED], bump)]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub report: Option<Box<Account<'info, ComplianceReport>>>,
+    
+    #[account(mut, seeds = [ROLLUP_SEED, state.key().as_ref()], bump)]
+    pub rollup: Option<Account<'info, GlobalRollup>>,
+    
+    /// Counts the violation against the user's history when present
+    #[account(mut, seeds = [USER_RECORD_SEED, user.as_ref()], bump)]
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// Counts the violation against the policy it was found under when present
+    #[account(
+        mut,
+        constraint = policy.authority == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub policy: Option<Box<Account<'info, CompliancePolicy>>>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserViolationRecord::LEN,
+        seeds = [USER_VIOLATIONS_SEED, user.as_ref()],
+        bump
+    )]
+    pub user_violations: Account<'info, UserViolationRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(period_id: u64)]
+pub struct BeginReport<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ComplianceReport::LEN,
+        seeds = [REPORT_SEED, state.key().as_ref(), &period_id.to_le_bytes()],
+        bump
+    )]
+    pub report: Box<Account<'info, ComplianceReport>>,
+    
     #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVerificationDay<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
+    )]
     pub state: Account<'info, ComplianceState>,
     
     #[account(
+        init,
+        payer = authority,
+        space = 8 + DailyRoot::LEN,
+        seeds = [DAILY_ROOT_SEED, state.key().as_ref(), &state.verification_day.to_le_bytes()],
+        bump
+    )]
+    pub daily_root: Account<'info, DailyRoot>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeReport<'info> {
+    #[account(
+        mut,
         constraint = authority.key() == state.authority @ CompliFiError::Unauthorized
     )]
+    pub state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub report: Box<Account<'info, ComplianceReport>>,
+    
     pub authority: Signer<'info>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attestation_pda_matches_account_seeds() {
+        let authority = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let (address, bump) = kyc_attestation_pda(&authority, &wallet);
+        // The seeds every attestation account constraint derives from, and clients hardcode
+        let seeds: &[&[u8]] = &[b"kyc-attestation", authority.as_ref(), wallet.as_ref(), &[bump]];
+        assert_eq!(Pubkey::create_program_address(seeds, &crate::ID).unwrap(), address);
+        assert_eq!(KYC_ATTESTATION_SEED, b"kyc-attestation");
+        assert_ne!(kyc_attestation_pda(&authority, &Pubkey::new_unique()).0, address);
+    }
+
+    #[test]
+    fn attestation_pdas_are_namespaced_by_authority() {
+        // Two authorities attesting the same wallet each get their own account
+        let wallet = Pubkey::new_unique();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_ne!(kyc_attestation_pda(&first, &wallet).0, kyc_attestation_pda(&second, &wallet).0);
+        assert_eq!(kyc_attestation_pda(&first, &wallet), kyc_attestation_pda(&first, &wallet));
+    }
+
+    #[test]
+    fn rescales_risk_scores_rounding_up() {
+        // The oracle's own scale is left alone
+        assert_eq!(rescale_risk_score(2, MAX_RISK_SCORE, MAX_RISK_SCORE), 2);
+        assert_eq!(rescale_risk_score(2, MAX_RISK_SCORE, 100), 20);
+        // 2 of 10 is 1.4 of 7, which rounds up rather than down
+        assert_eq!(rescale_risk_score(2, MAX_RISK_SCORE, 7), 2);
+        assert_eq!(rescale_risk_score(0, MAX_RISK_SCORE, 5), 0);
+        // Scores off the top of the old scale saturate at the top of the new one
+        assert_eq!(rescale_risk_score(u8::MAX, MAX_RISK_SCORE, 100), 100);
+    }
+}
@@ -0,0 +1,234 @@
+use anchor_lang::prelude::*;
+
+use crate::CompliFiError;
+
+// A policy's boolean logic is a postfix (RPN) token stream of the byte constants below.
+// Check tokens push a result; JURISDICTION_IS and VENDOR_IS take the following byte as
+// their operand. AND and OR combine the top two results, NOT negates the top one, and a
+// valid stream leaves exactly one result.
+pub const MAX_POLICY_LOGIC_LEN: usize = 32;
+
+// Checks
+pub const LOGIC_KYC_VERIFIED: u8 = 0x01;          // Attestation is verified for the wallet
+pub const LOGIC_JURISDICTION_ALLOWED: u8 = 0x02;  // Jurisdiction is in the policy's allowed set
+pub const LOGIC_OWNERSHIP_PROVEN: u8 = 0x03;      // Wallet proved control of its key at issuance
+pub const LOGIC_JURISDICTION_IS: u8 = 0x04;       // Jurisdiction equals the operand
+pub const LOGIC_VENDOR_IS: u8 = 0x05;             // Screening vendor id equals the operand
+
+// Operators
+pub const LOGIC_AND: u8 = 0x80;
+pub const LOGIC_OR: u8 = 0x81;
+pub const LOGIC_NOT: u8 = 0x82;
+
+// One parsed token: the check or operator, its operand, and the node index where the
+// subexpression it closes begins
+#[derive(Clone, Copy)]
+struct Node {
+    token: u8,
+    operand: u8,
+    start: usize,
+}
+
+/// Validate a token stream, rejecting unknown tokens, missing operands, operator underflow
+/// and streams that do not reduce to a single result
+pub fn validate_policy_logic(tokens: &[u8]) -> Result<()> {
+    parse(tokens).map(|_| ())
+}
+
+/// Evaluate a validated token stream, calling `check(token, operand)` for each check it
+/// needs. AND and OR short-circuit, so checks on the skipped side are never called.
+pub fn evaluate_policy_logic(tokens: &[u8], mut check: impl FnMut(u8, u8) -> bool) -> Result<bool> {
+    let nodes = parse(tokens)?;
+    Ok(evaluate(&nodes, nodes.len() - 1, &mut check))
+}
+
+fn parse(tokens: &[u8]) -> Result<Vec<Node>> {
+    require!(
+        !tokens.is_empty() && tokens.len() <= MAX_POLICY_LOGIC_LEN,
+        CompliFiError::InvalidPolicyLogic
+    );
+    
+    let mut nodes: Vec<Node> = Vec::with_capacity(tokens.len());
+    // Start node of each result on the evaluation stack
+    let mut stack: Vec<usize> = Vec::with_capacity(tokens.len());
+    let mut at = 0;
+    while at < tokens.len() {
+        let token = tokens[at];
+        let index = nodes.len();
+        let (operand, start) = match token {
+            LOGIC_KYC_VERIFIED | LOGIC_JURISDICTION_ALLOWED | LOGIC_OWNERSHIP_PROVEN => (0, index),
+            LOGIC_JURISDICTION_IS | LOGIC_VENDOR_IS => {
+                at += 1;
+                let operand = *tokens.get(at).ok_or(CompliFiError::InvalidPolicyLogic)?;
+                (operand, index)
+            }
+            LOGIC_NOT => (0, stack.pop().ok_or(CompliFiError::InvalidPolicyLogic)?),
+            LOGIC_AND | LOGIC_OR => {
+                stack.pop().ok_or(CompliFiError::InvalidPolicyLogic)?;
+                (0, stack.pop().ok_or(CompliFiError::InvalidPolicyLogic)?)
+            }
+            _ => return err!(CompliFiError::InvalidPolicyLogic),
+        };
+        stack.push(start);
+        nodes.push(Node { token, operand, start });
+        at += 1;
+    }
+    
+    require!(stack.len() == 1, CompliFiError::InvalidPolicyLogic);
+    Ok(nodes)
+}
+
+// Evaluate the subexpression closed by node `at`
+fn evaluate(nodes: &[Node], at: usize, check: &mut impl FnMut(u8, u8) -> bool) -> bool {
+    let node = nodes[at];
+    match node.token {
+        LOGIC_NOT => !evaluate(nodes, at - 1, check),
+        LOGIC_AND | LOGIC_OR => {
+            // The right operand closes just before the operator; the left one before that
+            let right = at - 1;
+            let left = nodes[right].start - 1;
+            let left_value = evaluate(nodes, left, check);
+            if (node.token == LOGIC_AND) != left_value {
+                left_value
+            } else {
+                evaluate(nodes, right, check)
+            }
+        }
+        token => check(token, node.operand),
+    }
+}
+
+/// Builds a policy logic token stream in postfix order, validating it on `build`
+#[derive(Default)]
+pub struct PolicyLogicBuilder {
+    tokens: Vec<u8>,
+}
+
+impl PolicyLogicBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn kyc_verified(self) -> Self {
+        self.push(&[LOGIC_KYC_VERIFIED])
+    }
+    
+    pub fn jurisdiction_allowed(self) -> Self {
+        self.push(&[LOGIC_JURISDICTION_ALLOWED])
+    }
+    
+    pub fn ownership_proven(self) -> Self {
+        self.push(&[LOGIC_OWNERSHIP_PROVEN])
+    }
+    
+    pub fn jurisdiction_is(self, jurisdiction: u8) -> Self {
+        self.push(&[LOGIC_JURISDICTION_IS, jurisdiction])
+    }
+    
+    pub fn vendor_is(self, vendor_id: u8) -> Self {
+        self.push(&[LOGIC_VENDOR_IS, vendor_id])
+    }
+    
+    pub fn and(self) -> Self {
+        self.push(&[LOGIC_AND])
+    }
+    
+    pub fn or(self) -> Self {
+        self.push(&[LOGIC_OR])
+    }
+    
+    pub fn negate(self) -> Self {
+        self.push(&[LOGIC_NOT])
+    }
+    
+    pub fn build(self) -> Result<Vec<u8>> {
+        validate_policy_logic(&self.tokens)?;
+        Ok(self.tokens)
+    }
+    
+    fn push(mut self, tokens: &[u8]) -> Self {
+        self.tokens.extend_from_slice(tokens);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EU: u8 = 3;
+
+    // KYC AND (jurisdiction EU OR ownership proven)
+    fn kyc_and_eu_or_owner() -> Vec<u8> {
+        PolicyLogicBuilder::new()
+            .kyc_verified()
+            .jurisdiction_is(EU)
+            .ownership_proven()
+            .or()
+            .and()
+            .build()
+            .unwrap()
+    }
+
+    fn facts(kyc: bool, jurisdiction: u8, owner: bool) -> impl FnMut(u8, u8) -> bool {
+        move |token, operand| match token {
+            LOGIC_KYC_VERIFIED => kyc,
+            LOGIC_JURISDICTION_IS => jurisdiction == operand,
+            LOGIC_OWNERSHIP_PROVEN => owner,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn evaluates_nested_expression() {
+        let logic = kyc_and_eu_or_owner();
+        assert!(evaluate_policy_logic(&logic, facts(true, EU, false)).unwrap());
+        assert!(evaluate_policy_logic(&logic, facts(true, 1, true)).unwrap());
+        assert!(!evaluate_policy_logic(&logic, facts(true, 1, false)).unwrap());
+        assert!(!evaluate_policy_logic(&logic, facts(false, EU, true)).unwrap());
+    }
+
+    #[test]
+    fn not_negates_a_subexpression() {
+        // NOT (jurisdiction EU AND KYC)
+        let logic = PolicyLogicBuilder::new().jurisdiction_is(EU).kyc_verified().and().negate().build().unwrap();
+        assert!(!evaluate_policy_logic(&logic, facts(true, EU, false)).unwrap());
+        assert!(evaluate_policy_logic(&logic, facts(true, 1, false)).unwrap());
+    }
+
+    #[test]
+    fn short_circuits_skipped_operands() {
+        let logic = kyc_and_eu_or_owner();
+        let mut checked = Vec::new();
+        let passed = evaluate_policy_logic(&logic, |token, _| {
+            checked.push(token);
+            false
+        })
+        .unwrap();
+        assert!(!passed);
+        assert_eq!(checked, vec![LOGIC_KYC_VERIFIED]);
+
+        let mut checked = Vec::new();
+        evaluate_policy_logic(&logic, |token, operand| {
+            checked.push(token);
+            token == LOGIC_KYC_VERIFIED || operand == EU
+        })
+        .unwrap();
+        assert_eq!(checked, vec![LOGIC_KYC_VERIFIED, LOGIC_JURISDICTION_IS]);
+    }
+
+    #[test]
+    fn rejects_malformed_streams() {
+        for tokens in [
+            &[][..],
+            &[LOGIC_AND],
+            &[LOGIC_KYC_VERIFIED, LOGIC_OR],
+            &[LOGIC_KYC_VERIFIED, LOGIC_OWNERSHIP_PROVEN],
+            &[LOGIC_JURISDICTION_IS],
+            &[0x7f],
+            &[LOGIC_KYC_VERIFIED; MAX_POLICY_LOGIC_LEN + 1],
+        ] {
+            assert!(validate_policy_logic(tokens).is_err());
+        }
+    }
+}
@@ -0,0 +1,55 @@
+/// Keccak-256 of the concatenation of `vals`; the `sol_keccak256` syscall on-chain
+pub fn keccak256v(vals: &[&[u8]]) -> [u8; 32] {
+    solana_keccak_hasher::hashv(vals).to_bytes()
+}
+
+/// Root reached by folding `proof` into `leaf`, hashing each pair in sorted order so a
+/// proof carries no left/right flags
+pub fn merkle_root_from_proof(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak256v(&[&node, sibling])
+        } else {
+            keccak256v(&[sibling, &node])
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hash: [u8; 32]) -> String {
+        hash.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        merkle_root_from_proof(a, &[b])
+    }
+
+    #[test]
+    fn matches_known_keccak_vectors() {
+        assert_eq!(hex(keccak256v(&[])), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(hex(keccak256v(&[b"abc"])), "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45");
+        // Split input hashes the same as the concatenation, across a block boundary too
+        let long = [7u8; 300];
+        assert_eq!(keccak256v(&[&long[..100], &long[100..]]), keccak256v(&[&long]));
+    }
+
+    #[test]
+    fn verifies_sorted_pair_proofs() {
+        let leaves: Vec<[u8; 32]> = (1..=4u8).map(|i| keccak256v(&[&[i; 32]])).collect();
+        let left = pair(leaves[0], leaves[1]);
+        let right = pair(leaves[2], leaves[3]);
+        let root = pair(left, right);
+        // Pair order does not matter
+        assert_eq!(pair(right, left), root);
+        
+        assert_eq!(merkle_root_from_proof(leaves[0], &[leaves[1], right]), root);
+        assert_eq!(merkle_root_from_proof(leaves[3], &[leaves[2], left]), root);
+        // A leaf outside the tree, or a member with the wrong siblings, misses the root
+        let outsider = keccak256v(&[&[5u8; 32]]);
+        assert_ne!(merkle_root_from_proof(outsider, &[leaves[1], right]), root);
+        assert_ne!(merkle_root_from_proof(leaves[0], &[leaves[2], right]), root);
+    }
+}
@@ -1,59 +1,1590 @@
-use anchor_lang::prelude::*;
-
-#[account]
-pub struct ComplianceState {
-    pub authority: Pubkey,
-    pub verification_count: u64,
-    pub violation_count: u64,
-}
-
-impl ComplianceState {
-    pub const LEN: usize = 32 + 8 + 8;
-}
-
-#[account]
-pub struct CompliancePolicy {
-    pub authority: Pubkey,
-    pub max_risk_score: u8,
-    pub require_kyc: bool,
-    pub allowed_jurisdictions: [u8; 10], // Bitmap of allowed jurisdictions
-}
-
-impl CompliancePolicy {
-    pub const LEN: usize = 32 + 1 + 1 + 10;
-}
-
-#[account]
-pub struct KycAttestation {
-    pub wallet: Pubkey,      // The wallet this attestation is for
-    pub is_verified: bool,   // Whether the wallet has passed KYC
-    pub authority: Pubkey,   // Authority that created this attestation
-    pub timestamp: i64,      // When the attestation was created/updated
-    pub jurisdiction: u8,    // Jurisdiction code
-}
-
-impl KycAttestation {
-    pub const LEN: usize = 32 + 1 + 32 + 8 + 1;
-}
-
-#[event]
-pub struct VerificationEvent {
-    pub user: Pubkey,
-    pub action: String,
-    pub verified: bool,
-    pub risk_score: u8,
-}
-
-#[event]
-pub struct ViolationEvent {
-    pub user: Pubkey,
-    pub reason: String,
-}
-
-#[event]
-pub struct KycAttestationEvent {
-    pub wallet: Pubkey,
-    pub is_verified: bool,
-    pub jurisdiction: u8,
-}
-
+use anchor_lang::prelude::*;
+
+use crate::{
+    CompliFiError, DEFAULT_JURISDICTION_SCHEME, DOCUMENT_REQUIREMENT_LEVELS, JURISDICTION_BITMAP_LEN, KYC_LEVEL_COUNT,
+    KYC_LEVEL_NONE, MAX_ACTION_THRESHOLDS, MAX_ALLOWED_ACTIONS, MAX_ALLOWED_COUNTERPARTIES,
+    MAX_ATTESTATION_COSIGNERS, MAX_CONSORTIUM_MEMBERS, MAX_DISCLOSURE_FIELDS, MAX_JURISDICTION_NAME_LEN,
+    MAX_JURISDICTION_RISK_CAPS, MAX_POLICY_LOGIC_LEN, MAX_POLICY_NAME_LEN, MAX_POLICY_SIGNERS, MAX_RECORDED_ACTION_LEN,
+    MAX_RISK_SCORE, MAX_SANCTION_REASON_LEN, MAX_VIOLATION_REASON_LEN, RISK_TIER_COUNT,
+};
+
+#[account]
+pub struct ComplianceState {
+    pub authority: Pubkey,
+    pub verification_count: u64,
+    pub violation_count: u64,
+    pub expiry_reminder_thresholds: [u8; 4], // Ascending lifetime percentages, 0 = unused slot
+    pub risk_monitor: Pubkey,                // Off-chain monitor allowed to report behavioral risk
+    pub active_report: Pubkey,               // Report accumulating the current period (default = none)
+    pub renewal_authority: Pubkey,           // Keeper allowed to renew opted-in attestations
+    pub emergency_count: u64,                // Forced passes via emergency_verify
+    pub time_oracle: Pubkey,                 // Trusted signer of attestation issuing timestamps
+    pub time_oracle_tolerance: u64,          // Allowed drift in seconds from the cluster clock
+    pub bridge_relayer: Pubkey,              // Signer of cross-chain KYC messages
+    pub auditor: Pubkey,                     // Receives revealed KYC fields
+    pub max_verifications_per_slot: u32,     // Global throughput cap (0 = unlimited)
+    pub verifications_this_slot: u32,        // Verifications counted in throughput_slot
+    pub throughput_slot: u64,                // Slot verifications_this_slot refers to
+    pub daily_verification_root: [u8; 32],   // Rolling hash of the open day's verification digests
+    pub verification_day: i64,               // Day index (unix days) the open root started in
+    pub day_verification_count: u32,         // Verifications folded into the open root
+    pub marginal_pass_count: u64,            // Verifications that passed within the risk tolerance band
+    pub policy_count: u32,                   // Policies registered under this state
+    pub paused: bool,                        // Global circuit breaker
+    pub rollup_enabled: bool,                // Whether the state keeps a GlobalRollup
+    pub pending_authority: Pubkey,           // Proposed authority awaiting acceptance (default = none)
+    pub treasury_policy: Pubkey,             // Policy treasury operations are checked against (default = none)
+    pub journal_enabled: bool,               // Whether the state keeps an AttestationJournal
+    pub cpi_events: bool,                    // Also emit verification and attestation events by self-CPI
+    pub event_seq: u64,                      // Last sequence number given to a verification, attestation or violation event
+    pub sanction_merkle_root: [u8; 32],      // Root of the off-chain sanctions list tree; zero when none is committed
+    pub issuance_policy: Pubkey,             // Policy whose document minimums bind every issuance (default = none)
+}
+
+impl ComplianceState {
+    pub const LEN: usize = 32 + 8 + 8 + 4 + 32 + 32 + 32 + 8 + 32 + 8 + 32 + 32 + 4 + 4 + 8 + 32 + 8 + 4 + 8 + 4 + 1 + 1 + 32 + 32 + 1 + 1 + 8 + 32 + 32;
+}
+
+#[account]
+pub struct CompliancePolicy {
+    pub authority: Pubkey,
+    pub max_risk_score: u8,
+    pub require_kyc: bool,
+    pub allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN], // Bitmap of allowed jurisdictions
+    pub min_oracle_quorum: u8,           // Oracles that must agree on the risk score (0 = single oracle)
+    pub behavioral_risk_mode: u8,        // How behavioral risk combines with the oracle score
+    pub behavioral_weight_bps: u16,      // Weight of behavioral risk in weighted mode
+    pub multisig_min_compliant: u8,      // Multisig members that must pass (0 = all)
+    pub denomination_mint: Pubkey,       // Token amounts are denominated in (default = SOL)
+    pub decimals: u8,                    // Decimals of the denomination
+    pub required_vendor: u8,             // Screening vendor attestations must come from (0 = any)
+    pub sign_results: bool,              // Return a result digest from verify_compliance
+    pub policy_signers: [Pubkey; MAX_POLICY_SIGNERS], // Governance signers (first policy_signer_count used)
+    pub policy_signer_count: u8,         // Number of governance signers
+    pub policy_threshold: u8,            // Signers required to change the policy (0 = authority alone)
+    pub require_ownership_proof: bool,   // Reject attestations without a wallet ownership proof
+    pub early_renewal_fee: u64,          // Lamports charged to renew before expiry
+    pub late_renewal_fee: u64,           // Lamports charged to renew at or after expiry
+    pub fee_recipient: Pubkey,           // Receives renewal and verification fees
+    pub risk_conflict_resolution: u8,    // RISK_CONFLICT_* mode when oracle and attester disagree
+    pub revision: u64,                   // Bumped on every change; stales compiled copies
+    pub risk_tolerance: u8,              // Band above max_risk_score that passes as marginal
+    pub tier_validity: [i64; RISK_TIER_COUNT], // Default attestation validity per jurisdiction risk tier
+    pub require_recheck_after_jurisdiction_change: bool, // Moved users must be re-attested in time
+    pub jurisdiction_recheck_days: u16,  // Days a moved user has to be re-attested
+    pub max_amount: u64,                 // Per-transaction amount cap (0 = unlimited)
+    pub trading_window_start: u32,       // Daily window start, seconds of day UTC
+    pub trading_window_end: u32,         // Daily window end; before the start wraps past midnight
+    pub logic: [u8; MAX_POLICY_LOGIC_LEN], // Custom postfix boolean logic, see logic.rs
+    pub logic_len: u8,                   // Tokens used in `logic` (0 = none)
+    pub identity_max_age: i64,           // Max seconds since identity verification (0 = no limit)
+    pub sanctions_max_age: i64,          // Max seconds since sanctions screening (0 = no limit)
+    pub accreditation_max_age: i64,      // Max seconds since accreditation check (0 = not required)
+    pub receipt_ttl: i64,                // Seconds a passing verification is cached (0 = no caching)
+    pub anonymous_lifetime_cap: u64,     // Volume a nullifier may use without KYC (0 = none)
+    pub consortium: Pubkey,              // Consortium governing the policy (default = its authority)
+    pub defer_risk_check: bool,          // Let verify_compliance defer the oracle check on a cache miss
+    pub verification_fee: u64,           // Base lamports charged per verification (0 = free)
+    pub risk_fee_multiplier_bps: u16,    // Fee surcharge per risk point, in bps of the base fee
+    pub rollout_bps: u16,                // Share of users on the current policy; the rest use the rollout baseline
+    pub min_risk_model_version: u32,     // Oldest oracle risk model whose scores are accepted (0 = any)
+    pub jurisdiction_scheme: u8,         // Coding scheme allowed_jurisdictions is built for
+    pub min_documents_for_level: [u8; DOCUMENT_REQUIREMENT_LEVELS], // Reviewed documents required at issuance, per KYC level
+    pub emit_json_log: bool,             // Also log verification outcomes as JSON lines
+    pub min_trust_weight: u16,           // Summed trust weight the attestation's signers must reach (0 = off)
+    pub fee_ledger_enabled: bool,        // Verification fees are recorded in the policy's FeeLedger
+    pub freeze_threshold: u8,            // refresh_risk_freeze freezes above this risk score (0 = off)
+    pub unfreeze_threshold: u8,          // ...and only lifts its own freeze below this one
+    pub step_up_threshold: u64,          // Cumulative verified volume past which step_up_level is required (0 = off)
+    pub step_up_level: u8,               // KYC level required past step_up_threshold
+    pub min_kyc_level: u8,               // Lowest attestation tier accepted (0 = any)
+    pub action_thresholds: [ActionThreshold; MAX_ACTION_THRESHOLDS], // Per-action risk thresholds (first action_threshold_count used)
+    pub action_threshold_count: u8,
+    pub effective_from: i64,             // Policy rejects verifications before this time (0 = unbounded)
+    pub effective_until: i64,            // ...and from this time on (0 = unbounded)
+    pub min_verify_interval: i64,        // Seconds a user must wait between passed verifications (0 = off)
+    pub max_oracle_staleness: i64,       // Oldest oracle entry accepted, in seconds since its update (0 = any age)
+    pub name: String,                    // Name the policy PDA is derived from under its authority
+    pub allowed_actions: [[u8; 8]; MAX_ALLOWED_ACTIONS], // Whitelisted action hashes (first allowed_action_count used)
+    pub allowed_action_count: u8,        // 0 = every action permitted
+    pub min_attestation_age: i64,        // Seconds an attestation must have existed before it counts (0 = off)
+    pub oracle_kind: u8,                 // Risk oracle single-oracle scores come from (ORACLE_KIND_*)
+    pub require_trusted_attestor: bool,  // Reject attestations whose issuer is no longer allowlisted
+    pub warn_risk_score: u8,             // Lowest passing score flagged for review (0 = off)
+    pub jurisdiction_risk_caps: [JurisdictionRiskCap; MAX_JURISDICTION_RISK_CAPS], // Tighter per-jurisdiction risk limits (first jurisdiction_risk_cap_count used)
+    pub jurisdiction_risk_cap_count: u8,
+    pub expiry_grace_period: i64,        // Seconds an expired attestation still passes, with a warning (0 = none)
+    pub verification_count: u64,         // Verifications passed under this policy
+    pub violation_count: u64,            // Violations recorded against this policy
+    pub require_dual_attestation: bool,  // Attestations must also be co-signed by a second attester
+    pub risk_scale_max: u8,              // Top of the scale oracle scores are normalized onto (0 = the oracle's own)
+    pub cache_ttl: i64,                  // Seconds a user record's cached risk score is reused (0 = no caching)
+    pub compliance_receipt_ttl: i64,     // Seconds a pass keeps the user's compliance receipt fresh (0 = not required)
+}
+
+impl CompliancePolicy {
+    pub const LEN: usize = 32 + 1 + 1 + JURISDICTION_BITMAP_LEN + 1 + 1 + 2 + 1 + 32 + 1 + 1 + 1 + 32 * MAX_POLICY_SIGNERS + 1 + 1 + 1 + 8 + 8 + 32 + 1 + 8 + 1 + 8 * RISK_TIER_COUNT + 1 + 2 + 8 + 4 + 4 + MAX_POLICY_LOGIC_LEN + 1 + 8 + 8 + 8 + 8 + 8 + 32 + 1 + 8 + 2 + 2 + 4 + 1 + DOCUMENT_REQUIREMENT_LEVELS + 1 + 2 + 1 + 1 + 1 + 8 + 1 + 1 + ActionThreshold::LEN * MAX_ACTION_THRESHOLDS + 1 + 8 + 8 + 8 + 8 + (4 + MAX_POLICY_NAME_LEN) + 8 * MAX_ALLOWED_ACTIONS + 1 + 8 + 1 + 1 + 1 + JurisdictionRiskCap::LEN * MAX_JURISDICTION_RISK_CAPS + 1 + 8 + 8 + 8 + 1 + 1 + 8 + 8;
+    
+    /// Risk threshold for `action`: its own threshold if it has one, otherwise max_risk_score
+    pub fn action_max_risk_score(&self, action: &str) -> u8 {
+        let action_hash = short_action_hash(action);
+        self.action_thresholds[..self.action_threshold_count as usize]
+            .iter()
+            .find(|threshold| threshold.action_hash == action_hash)
+            .map_or(self.max_risk_score, |threshold| threshold.max_risk_score)
+    }
+    
+    /// Top of the risk scale the policy's thresholds are expressed on. Policies migrated from
+    /// before the scale existed read 0 and keep the oracle's 0-10 scale.
+    pub fn risk_scale(&self) -> u8 {
+        if self.risk_scale_max == 0 {
+            MAX_RISK_SCORE
+        } else {
+            self.risk_scale_max
+        }
+    }
+    
+    /// The stricter of `max_risk_score` and the risk cap of `jurisdiction`, if it has one
+    pub fn jurisdiction_max_risk_score(&self, jurisdiction: u8, max_risk_score: u8) -> u8 {
+        self.jurisdiction_risk_caps[..self.jurisdiction_risk_cap_count as usize]
+            .iter()
+            .find(|cap| cap.jurisdiction == jurisdiction)
+            .map_or(max_risk_score, |cap| cap.max_risk_score.min(max_risk_score))
+    }
+    
+    /// Whether `action` may be verified: always while the whitelist is empty, otherwise only
+    /// if it is listed
+    pub fn is_action_allowed(&self, action: &str) -> bool {
+        self.allowed_action_count == 0
+            || self.allowed_actions[..self.allowed_action_count as usize].contains(&short_action_hash(action))
+    }
+}
+
+/// Risk threshold a policy applies to one action
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ActionThreshold {
+    pub action_hash: [u8; 8], // First 8 bytes of sha256(action)
+    pub max_risk_score: u8,
+}
+
+impl ActionThreshold {
+    pub const LEN: usize = 8 + 1;
+}
+
+/// Risk limit a policy holds the users of one jurisdiction to, over any looser threshold
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct JurisdictionRiskCap {
+    pub jurisdiction: u8, // Jurisdiction code, as stored on attestations
+    pub max_risk_score: u8,
+}
+
+impl JurisdictionRiskCap {
+    pub const LEN: usize = 1 + 1;
+}
+
+/// Key an action is matched by in a policy's action thresholds: the first 8 bytes of
+/// sha256(action)
+pub fn short_action_hash(action: &str) -> [u8; 8] {
+    let hash = solana_sha256_hasher::hash(action.as_bytes()).to_bytes();
+    hash[..8].try_into().unwrap()
+}
+
+#[account]
+pub struct CompiledPolicy {
+    pub policy: Pubkey,               // Policy this snapshot was compiled from
+    pub source_revision: u64,         // Policy revision at compile time
+    pub compiled_at: i64,             // When the snapshot was taken
+    pub effective: CompliancePolicy,  // Flattened effective policy
+}
+
+impl CompiledPolicy {
+    pub const LEN: usize = 32 + 8 + 8 + CompliancePolicy::LEN;
+}
+
+#[account]
+pub struct ComplianceReport {
+    pub state: Pubkey,                    // Compliance state this report belongs to
+    pub period_id: u64,                   // Reporting period identifier
+    pub started_at: i64,                  // When the period was opened
+    pub finalized_at: i64,                // When the period was closed (0 = still open)
+    pub verification_count: u64,          // Verifications passed during the period
+    pub violation_count: u64,             // Violations recorded during the period
+    pub jurisdiction_counts: [u32; 256],  // Verifications per attested jurisdiction code
+    pub report_hash: [u8; 32],            // SHA-256 over the finalized contents
+}
+
+impl ComplianceReport {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 4 * 256 + 32;
+}
+
+// Behavioral risk combination modes
+pub const BEHAVIORAL_RISK_MAX: u8 = 0;      // Effective score is the higher of the two
+pub const BEHAVIORAL_RISK_WEIGHTED: u8 = 1; // Effective score is a weighted average
+
+// Resolutions when the oracle score and the attester's verdict disagree
+pub const RISK_CONFLICT_ORACLE_WINS: u8 = 0;   // The oracle score decides
+pub const RISK_CONFLICT_ATTESTER_WINS: u8 = 1; // The attester's verdict decides
+pub const RISK_CONFLICT_REQUIRE_BOTH: u8 = 2;  // Both must pass
+
+// Source whose verdict decided a risk conflict
+pub const RISK_SOURCE_ORACLE: u8 = 0;
+pub const RISK_SOURCE_ATTESTER: u8 = 1;
+
+// Why an oracle lookup failed, as reported by OracleFailureEvent
+pub const ORACLE_FAILURE_MISSING: u8 = 0;       // An account the lookup needs was not passed
+pub const ORACLE_FAILURE_WRONG_OWNER: u8 = 1;   // The account is not the oracle program's
+pub const ORACLE_FAILURE_MALFORMED: u8 = 2;     // Its data could not be read as a risk score
+pub const ORACLE_FAILURE_WRONG_SUBJECT: u8 = 3; // It scores, or is registered for, another wallet
+pub const ORACLE_FAILURE_STALE: u8 = 4;         // Its score is older than the policy allows
+pub const ORACLE_FAILURE_NO_QUORUM: u8 = 5;     // Too few oracles answered for the quorum
+
+#[account]
+pub struct UserComplianceRecord {
+    pub user: Pubkey,                // The wallet this record tracks
+    pub behavioral_risk: u8,         // On-platform behavioral risk reported by the monitor
+    pub behavioral_updated_at: i64,  // When behavioral risk was last reported
+    pub last_denial_code: u8,        // Reason code of the latest recorded denial
+    pub last_denial_ts: i64,         // When the latest denial was recorded (0 = never denied)
+    pub regulatory_hold: bool,       // Regulator hold pending investigation
+    pub hold_expires_at: i64,        // When the hold lapses by itself
+    pub nonce: u64,                  // Highest verification nonce used so far
+    pub violation_count: u32,        // Violations recorded against the user
+    pub last_verified_at: i64,       // When the user last passed verify_compliance (0 = never)
+    pub verification_count: u64,     // Passes recorded for the user
+    pub last_action: String,         // Action of the latest pass, at most MAX_RECORDED_ACTION_LEN bytes
+    pub last_risk_score: u8,         // Risk score of the latest pass
+    pub verified_volume: u64,        // Cumulative amount of the user's passed verifications
+    pub risk_cached_at: i64,         // When the cached risk score was read from the oracle (0 = none cached)
+    pub risk_cache_policy: Pubkey,   // Policy the cached risk score was assessed under
+    pub risk_cache_revision: u64,    // Revision of that policy at the time
+    pub cached_risk_score: u8,       // Risk score of the latest oracle read
+    pub cached_risk_model_version: u32, // Risk model version behind it
+}
+
+impl UserComplianceRecord {
+    pub const LEN: usize = 32 + 1 + 8 + 1 + 8 + 1 + 8 + 8 + 4 + 8 + 8 + 4 + MAX_RECORDED_ACTION_LEN + 1 + 8 + 8 + 32 + 8 + 1 + 4;
+    
+    /// Record a passed verification of `amount`. Longer actions are cut to
+    /// MAX_RECORDED_ACTION_LEN bytes, on a character boundary.
+    pub fn record_verification(&mut self, user: Pubkey, action: &str, risk_score: u8, amount: u64, now: i64) {
+        self.user = user;
+        self.verified_volume = self.verified_volume.saturating_add(amount);
+        self.last_verified_at = now;
+        self.verification_count = self.verification_count.saturating_add(1);
+        self.last_action = truncate_utf8(action, MAX_RECORDED_ACTION_LEN).to_string();
+        self.last_risk_score = risk_score;
+    }
+    
+    /// Cache a risk score just read from the oracle under `policy` at `revision`
+    pub fn cache_risk(&mut self, policy: Pubkey, revision: u64, risk_score: u8, model_version: u32, now: i64) {
+        self.risk_cached_at = now;
+        self.risk_cache_policy = policy;
+        self.risk_cache_revision = revision;
+        self.cached_risk_score = risk_score;
+        self.cached_risk_model_version = model_version;
+    }
+    
+    /// The cached risk score and model version, if they were assessed under `policy` at
+    /// `revision` less than `ttl` seconds ago and no behavioral report has come in since
+    pub fn cached_risk(&self, policy: &Pubkey, revision: u64, ttl: i64, now: i64) -> Option<(u8, u32)> {
+        (self.risk_cached_at > 0
+            && self.risk_cache_policy == *policy
+            && self.risk_cache_revision == revision
+            && self.behavioral_updated_at <= self.risk_cached_at
+            && now.saturating_sub(self.risk_cached_at) < ttl)
+            .then_some((self.cached_risk_score, self.cached_risk_model_version))
+    }
+}
+
+#[account]
+pub struct UserViolationRecord {
+    pub user: Pubkey,                // The wallet the violations were recorded against
+    pub count: u64,                  // Violations recorded against the wallet
+    pub last_reason: String,         // Reason of the latest violation, at most MAX_VIOLATION_REASON_LEN bytes
+    pub last_violation_at: i64,      // When the latest violation was recorded
+    pub last_category: u8,           // Category of the latest violation, a VIOLATION_CATEGORY_* value
+}
+
+impl UserViolationRecord {
+    pub const LEN: usize = 32 + 8 + 4 + MAX_VIOLATION_REASON_LEN + 8 + 1;
+    
+    /// Record one violation. Longer reasons are cut to MAX_VIOLATION_REASON_LEN bytes, on a
+    /// character boundary.
+    pub fn record(&mut self, user: Pubkey, reason: &str, category: u8, now: i64) {
+        self.user = user;
+        self.count = self.count.saturating_add(1);
+        self.last_reason = truncate_utf8(reason, MAX_VIOLATION_REASON_LEN).to_string();
+        self.last_violation_at = now;
+        self.last_category = category;
+    }
+}
+
+// Longest prefix of `text` that fits in `max` bytes without splitting a character
+fn truncate_utf8(text: &str, max: usize) -> &str {
+    let mut end = text.len().min(max);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[account]
+pub struct RiskCache {
+    pub user: Pubkey,                // The wallet whose score is cached
+    pub risk_score: u8,              // Last score read from the oracle
+    pub oracle: Pubkey,              // Oracle account the score was read from
+    pub oracle_updated_at: i64,      // When the oracle last updated the score
+    pub model_version: u32,          // Oracle risk model version that produced the score
+    pub refreshed_at: i64,           // When the cache was last refreshed (0 = never)
+}
+
+impl RiskCache {
+    pub const LEN: usize = 32 + 1 + 32 + 8 + 4 + 8;
+}
+
+#[account]
+pub struct RiskFeed {
+    pub wallet: Pubkey,              // The wallet the feed scores
+    pub aggregator: Pubkey,          // Switchboard aggregator reporting its risk score
+    pub set_at: i64,                 // When the feed was registered
+}
+
+impl RiskFeed {
+    pub const LEN: usize = 32 + 32 + 8;
+}
+
+#[account]
+pub struct ComplianceCertificate {
+    pub user: Pubkey,                // The wallet holding the certificate token
+    pub mint: Pubkey,                // Soulbound mint the certificate token belongs to
+    pub token_account: Pubkey,       // The user's token account holding it
+    pub verified_at: i64,            // When the user last passed with a certificate requested
+    pub risk_score: u8,              // Risk score of that pass
+    pub minted_at: i64,              // When the certificate token was minted
+}
+
+impl ComplianceCertificate {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + 8;
+}
+
+#[account]
+pub struct ComplianceReceipt {
+    pub user: Pubkey,                // The verified wallet
+    pub policy: Pubkey,              // Policy of the latest pass
+    pub verified_at: i64,            // When the wallet last passed verify_compliance
+    pub risk_score: u8,              // Risk score of that pass
+    pub valid_until: i64,            // When the pass stops counting as fresh
+}
+
+impl ComplianceReceipt {
+    pub const LEN: usize = 32 + 32 + 8 + 1 + 8;
+    
+    /// Whether the latest pass was under `policy` and is still fresh at `now`. Programs that
+    /// gate on compliance can check this on the account, read-only, in place of a CPI.
+    pub fn is_fresh(&self, policy: &Pubkey, now: i64) -> bool {
+        self.policy == *policy && now < self.valid_until
+    }
+}
+
+#[account]
+pub struct VerificationReceipt {
+    pub user: Pubkey,                // The verified wallet
+    pub policy: Pubkey,              // Policy the verification ran under
+    pub action_hash: [u8; 32],       // sha256 of the verified action
+    pub amount_bucket: u8,           // Amount order of magnitude this receipt covers
+    pub policy_revision: u64,        // Policy revision the cached outcome was computed under
+    pub risk_score: u8,              // Risk score of the cached verification
+    pub verified_at: i64,            // When the cached verification ran (0 = never)
+    pub valid_until: i64,            // When the cached outcome lapses
+    pub pending_since: i64,          // When an oracle check was deferred (0 = none pending)
+    pub pending_amount: u64,         // Amount of the deferred verification
+    pub risk_model_version: u32,     // Oracle risk model version behind the cached score
+}
+
+impl VerificationReceipt {
+    pub const LEN: usize = 32 + 32 + 32 + 1 + 8 + 1 + 8 + 8 + 8 + 8 + 4;
+}
+
+#[account]
+pub struct Consortium {
+    pub founder: Pubkey,             // Creator the consortium PDA is derived from
+    pub members: [Pubkey; MAX_CONSORTIUM_MEMBERS], // Member attesters (first member_count used)
+    pub member_count: u8,
+    pub threshold: u8,               // Votes a proposal needs to pass
+}
+
+impl Consortium {
+    pub const LEN: usize = 32 + 32 * MAX_CONSORTIUM_MEMBERS + 1 + 1;
+}
+
+#[account]
+pub struct ConsortiumProposal {
+    pub consortium: Pubkey,          // Consortium voting on the proposal
+    pub policy: Pubkey,              // Policy the proposal changes
+    pub revision: u64,               // Policy revision the proposal was made against
+    pub max_risk_score: u8,          // Proposed settings, as in set_policy
+    pub require_kyc: bool,
+    pub allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN],
+    pub votes: u16,                  // Bitmask of member indexes that voted
+    pub vote_count: u8,
+    pub executed: bool,              // Applied to the policy
+}
+
+impl ConsortiumProposal {
+    pub const LEN: usize = 32 + 32 + 8 + 1 + 1 + JURISDICTION_BITMAP_LEN + 2 + 1 + 1;
+}
+
+#[account]
+pub struct NullifierUsage {
+    pub nullifier: [u8; 32],         // Privacy nullifier the usage is tracked under
+    pub cumulative_volume: u64,      // Lifetime volume verified under the nullifier
+    pub first_used_at: i64,          // First anonymous verification (0 = unused)
+    pub last_used_at: i64,           // Most recent anonymous verification
+}
+
+impl NullifierUsage {
+    pub const LEN: usize = 32 + 8 + 8 + 8;
+}
+
+#[account]
+pub struct GlobalRollup {
+    pub state: Pubkey,                        // Compliance state the rollup aggregates
+    pub verified_users: u64,                  // Attestations currently verified
+    pub frozen_users: u64,                    // Wallets currently frozen
+    pub violations: u64,                      // Violations recorded since the rollup was created
+    pub level_counts: [u64; KYC_LEVEL_COUNT], // Attestations currently at each KYC level
+    pub created_at: i64,                      // When counting started
+}
+
+impl GlobalRollup {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 * KYC_LEVEL_COUNT + 8;
+    
+    /// Move one attestation between KYC levels, where None is an attestation that did not
+    /// (or no longer does) exist. Attestations issued before the rollup was created were
+    /// never counted, so removing one saturates at zero.
+    pub fn record_level_change(&mut self, from: Option<u8>, to: Option<u8>) -> Result<()> {
+        if let Some(level) = from {
+            let count = &mut self.level_counts[level as usize];
+            *count = count.saturating_sub(1);
+            if level != KYC_LEVEL_NONE {
+                self.verified_users = self.verified_users.saturating_sub(1);
+            }
+        }
+        if let Some(level) = to {
+            let count = &mut self.level_counts[level as usize];
+            *count = count.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+            if level != KYC_LEVEL_NONE {
+                self.verified_users = self.verified_users.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct SanctionEntry {
+    pub wallet: Pubkey,      // Sanctioned wallet
+    pub reason: String,      // Why it is listed, up to MAX_SANCTION_REASON_LEN bytes
+    pub added_by: Pubkey,    // State authority that listed it
+    pub added_at: i64,       // When it was listed
+}
+
+impl SanctionEntry {
+    pub const LEN: usize = 32 + 4 + MAX_SANCTION_REASON_LEN + 32 + 8;
+}
+
+// Attestation lifecycle operations recorded in an AttestationJournal
+pub const JOURNAL_OP_CREATE: u8 = 1;
+pub const JOURNAL_OP_UPDATE: u8 = 2; // An existing attestation was reissued
+pub const JOURNAL_OP_REVOKE: u8 = 3;
+pub const JOURNAL_OP_CLOSE: u8 = 4;  // Closed by its attester or purged after retention
+
+#[account]
+pub struct AttestationJournal {
+    pub state: Pubkey,      // Compliance state whose attestations are journaled
+    pub head: [u8; 32],     // Hash of the latest entry, chained from zero
+    pub sequence: u64,      // Entries appended so far
+    pub created_at: i64,    // When journaling started
+}
+
+impl AttestationJournal {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+    
+    /// Append one entry and return its sequence number. The new head is sha256 over:
+    ///   head (32) || sequence (u64 LE) || operation (u8) || wallet (32) || record_hash (32)
+    ///   || timestamp (i64 LE)
+    /// where sequence is the entry's own number, counting from 1, and record_hash is the
+    /// attestation's link hash after the operation (before it, for a close).
+    pub fn append(&mut self, operation: u8, wallet: &Pubkey, record_hash: &[u8; 32], timestamp: i64) -> Result<u64> {
+        self.sequence = self.sequence.checked_add(1).ok_or(CompliFiError::CounterOverflow)?;
+        self.head = solana_sha256_hasher::hashv(&[
+            &self.head,
+            &self.sequence.to_le_bytes(),
+            &[operation],
+            wallet.as_ref(),
+            record_hash,
+            &timestamp.to_le_bytes(),
+        ])
+        .to_bytes();
+        Ok(self.sequence)
+    }
+}
+
+/// Head of an attestation journal, as returned by get_journal_head
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct JournalHead {
+    pub head: [u8; 32],
+    pub sequence: u64,
+}
+
+#[account]
+pub struct DailyRoot {
+    pub state: Pubkey,            // Compliance state the root belongs to
+    pub day: i64,                 // Day index (unix days)
+    pub root: [u8; 32],           // Rolling hash: root = sha256(root || digest), from zero
+    pub verification_count: u32,  // Verifications folded into the root
+    pub closed_at: i64,           // When the day was closed
+}
+
+impl DailyRoot {
+    pub const LEN: usize = 32 + 8 + 32 + 4 + 8;
+}
+
+#[account]
+pub struct KycAttestation {
+    pub wallet: Pubkey,      // The wallet this attestation is for
+    pub flags: u16,          // Packed boolean flags, see the ATTESTATION_FLAG_* bits
+    pub authority: Pubkey,   // Authority that created this attestation
+    pub timestamp: i64,      // When the attestation was created/updated
+    pub jurisdiction: u8,    // Jurisdiction code
+    pub expires_at: i64,     // When the attestation lapses (0 = never)
+    pub reminders_fired: u8, // Bitmask of expiry reminder thresholds already emitted
+    pub renew_count: u32,    // Renewals since the attestation was issued
+    pub validity_period: i64, // Original lifetime in seconds, reused on renewal
+    pub source_chain: u16,   // Chain the KYC was performed on (0 = this chain)
+    pub screening_vendor_id: u8, // Vendor that screened the wallet (0 = unattributed)
+    pub field_commitments: [[u8; 32]; MAX_DISCLOSURE_FIELDS], // Per-field KYC data commitments (zero = none)
+    pub revocation_authority: Pubkey, // Backup signer allowed to revoke alongside the authority
+    pub jurisdiction_changed_at: i64, // When the jurisdiction last moved without re-verification (0 = none pending)
+    pub prev_attestation_hash: [u8; 32], // Hash of the record this one replaced (zero = first issuance)
+    pub imported_from: Pubkey,       // Deployment the attestation was imported from (default = issued here)
+    pub identity_verified_at: i64,   // When identity was last verified (0 = never)
+    pub sanctions_checked_at: i64,   // When the wallet was last sanctions-screened (0 = never)
+    pub accreditation_verified_at: i64, // When investor accreditation was last verified (0 = never)
+    pub jurisdiction_scheme: u8,     // Coding scheme the jurisdiction is expressed in
+    pub documents_reviewed: u8,      // Supporting documents the attester reviewed at issuance
+    pub cosigners: [Pubkey; MAX_ATTESTATION_COSIGNERS], // Attesters that co-signed after issuance (first cosigner_count used)
+    pub cosigner_count: u8,
+    pub allowed_counterparties: [Pubkey; MAX_ALLOWED_COUNTERPARTIES], // Counterparties the approval is scoped to (all default = any)
+    pub level: u8,                   // KYC tier the attester verified to, e.g. 1 = email, 2 = full ID, 3 = accredited
+    pub bump: u8,                    // Canonical bump of the attestation PDA
+}
+
+// KycAttestation flag bits
+pub const ATTESTATION_FLAG_VERIFIED: u16 = 1 << 0;          // Wallet has passed KYC
+pub const ATTESTATION_FLAG_AUTO_RENEW: u16 = 1 << 1;        // Wallet consented to automatic renewal
+pub const ATTESTATION_FLAG_OWNERSHIP_PROVEN: u16 = 1 << 2;  // Wallet signed the ownership challenge at issuance
+pub const ATTESTATION_FLAG_FROZEN: u16 = 1 << 3;            // Operator froze the wallet
+pub const ATTESTATION_FLAG_HELD: u16 = 1 << 4;              // Wallet's user record carries a regulatory hold
+pub const ATTESTATION_FLAG_RISK_FROZEN: u16 = 1 << 5;       // The freeze was placed by refresh_risk_freeze
+pub const ATTESTATION_FLAG_BEHAVIORAL_RISK: u16 = 1 << 6;   // Wallet's user record carries reported behavioral risk
+
+// Attestation facts with their own freshness timestamp, as a bitmask for refresh_attestation_checks
+pub const ATTESTATION_CHECK_IDENTITY: u8 = 1 << 0;
+pub const ATTESTATION_CHECK_SANCTIONS: u8 = 1 << 1;
+pub const ATTESTATION_CHECK_ACCREDITATION: u8 = 1 << 2;
+
+impl KycAttestation {
+    pub const LEN: usize = 32 + 2 + 32 + 8 + 1 + 8 + 1 + 4 + 8 + 2 + 1 + 32 * MAX_DISCLOSURE_FIELDS + 32 + 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 32 * MAX_ATTESTATION_COSIGNERS + 1 + 32 * MAX_ALLOWED_COUNTERPARTIES + 1 + 1;
+    
+    pub fn is_verified(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_VERIFIED)
+    }
+    
+    pub fn set_verified(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_VERIFIED, value);
+    }
+    
+    pub fn auto_renew(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_AUTO_RENEW)
+    }
+    
+    pub fn set_auto_renew(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_AUTO_RENEW, value);
+    }
+    
+    pub fn ownership_proven(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_OWNERSHIP_PROVEN)
+    }
+    
+    pub fn set_ownership_proven(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_OWNERSHIP_PROVEN, value);
+    }
+    
+    pub fn frozen(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_FROZEN)
+    }
+    
+    pub fn set_frozen(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_FROZEN, value);
+    }
+    
+    pub fn risk_frozen(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_RISK_FROZEN)
+    }
+    
+    pub fn set_risk_frozen(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_RISK_FROZEN, value);
+    }
+    
+    pub fn held(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_HELD)
+    }
+    
+    pub fn set_held(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_HELD, value);
+    }
+    
+    pub fn behavioral_risk_reported(&self) -> bool {
+        self.flag(ATTESTATION_FLAG_BEHAVIORAL_RISK)
+    }
+    
+    pub fn set_behavioral_risk_reported(&mut self, value: bool) {
+        self.set_flag(ATTESTATION_FLAG_BEHAVIORAL_RISK, value);
+    }
+    
+    /// Whether the attestation covers dealings with `counterparty`. An attestation with no
+    /// counterparties listed covers any.
+    pub fn counterparty_allowed(&self, counterparty: &Pubkey) -> bool {
+        let mut listed = self.allowed_counterparties.iter().filter(|key| **key != Pubkey::default()).peekable();
+        listed.peek().is_none() || listed.any(|key| key == counterparty)
+    }
+    
+    /// Start of the attestation's current validity cycle: issuance, or the start of the
+    /// period added by the latest renewal
+    pub fn lifetime_start(&self) -> i64 {
+        if self.renew_count > 0 && self.validity_period > 0 {
+            self.expires_at - self.validity_period
+        } else {
+            self.timestamp
+        }
+    }
+    
+    fn flag(&self, bit: u16) -> bool {
+        self.flags & bit != 0
+    }
+    
+    fn set_flag(&mut self, bit: u16, value: bool) {
+        if value {
+            self.flags |= bit;
+        } else {
+            self.flags &= !bit;
+        }
+    }
+}
+
+// KycRequest statuses
+pub const KYC_REQUEST_PENDING: u8 = 0;
+pub const KYC_REQUEST_FULFILLED: u8 = 1;
+
+#[account]
+pub struct KycRequest {
+    pub user: Pubkey,                // Wallet asking to be verified
+    pub requested_at: i64,           // When the latest request was made
+    pub status: u8,                  // KYC_REQUEST_PENDING or KYC_REQUEST_FULFILLED
+    pub fulfilled_by: Pubkey,        // Attester whose attestation resolved the request
+    pub fulfilled_at: i64,           // When it was resolved (0 = pending)
+}
+
+impl KycRequest {
+    pub const LEN: usize = 32 + 8 + 1 + 32 + 8;
+}
+
+/// KycAttestation layout from before its booleans were packed into `flags`,
+/// read only by `migrate_attestation`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyKycAttestation {
+    pub wallet: Pubkey,
+    pub is_verified: bool,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+    pub jurisdiction: u8,
+    pub expires_at: i64,
+    pub reminders_fired: u8,
+    pub auto_renew: bool,
+    pub renew_count: u32,
+    pub validity_period: i64,
+    pub source_chain: u16,
+    pub screening_vendor_id: u8,
+    pub field_commitments: [[u8; 32]; MAX_DISCLOSURE_FIELDS],
+    pub ownership_proven: bool,
+}
+
+impl LegacyKycAttestation {
+    pub const LEN: usize = 32 + 1 + 32 + 8 + 1 + 8 + 1 + 1 + 4 + 8 + 2 + 1 + 32 * MAX_DISCLOSURE_FIELDS + 1;
+}
+
+impl From<LegacyKycAttestation> for KycAttestation {
+    fn from(legacy: LegacyKycAttestation) -> Self {
+        let mut attestation = KycAttestation {
+            wallet: legacy.wallet,
+            flags: 0,
+            authority: legacy.authority,
+            timestamp: legacy.timestamp,
+            jurisdiction: legacy.jurisdiction,
+            expires_at: legacy.expires_at,
+            reminders_fired: legacy.reminders_fired,
+            renew_count: legacy.renew_count,
+            validity_period: legacy.validity_period,
+            source_chain: legacy.source_chain,
+            screening_vendor_id: legacy.screening_vendor_id,
+            field_commitments: legacy.field_commitments,
+            revocation_authority: legacy.authority,
+            jurisdiction_changed_at: 0,
+            prev_attestation_hash: [0; 32],
+            imported_from: Pubkey::default(),
+            identity_verified_at: legacy.timestamp,
+            sanctions_checked_at: legacy.timestamp,
+            accreditation_verified_at: 0,
+            jurisdiction_scheme: DEFAULT_JURISDICTION_SCHEME,
+            documents_reviewed: 0,
+            cosigners: [Pubkey::default(); MAX_ATTESTATION_COSIGNERS],
+            cosigner_count: 0,
+            allowed_counterparties: [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES],
+            level: 0,
+            bump: 0, // Set by migrate_attestation, which knows the PDA
+        };
+        attestation.set_verified(legacy.is_verified);
+        attestation.set_auto_renew(legacy.auto_renew);
+        attestation.set_ownership_proven(legacy.ownership_proven);
+        attestation
+    }
+}
+
+/// A user's behavioral risk and latest denial, returned by `get_user_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UserStatus {
+    pub user: Pubkey,
+    pub behavioral_risk: u8,
+    pub last_denial_code: u8,            // Reason code as returned by simulate_compliance
+    pub last_denial_ts: i64,             // 0 = never denied
+}
+
+/// Outcome of a passed `verify_compliance`, written as return data for CPI callers after
+/// any decision byte and result digest
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VerificationResult {
+    pub verified: bool,
+    pub risk_score: u8,                  // Score the user was checked against
+    pub jurisdiction: u8,                // Jurisdiction of the attestation that passed, 0 without one
+}
+
+/// Outcome of `check_compliance`, written as return data for CPI callers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ComplianceCheckResult {
+    pub passed: bool,
+    pub risk_score: u8,                  // u8::MAX when no score could be read
+    pub reason_code: u8,                 // First failure, as returned by simulate_compliance (0 when passed)
+}
+
+/// Snapshot of the program's headline counters, returned by `get_metrics`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ComplianceMetrics {
+    pub verification_count: u64,
+    pub violation_count: u64,
+    pub emergency_count: u64,
+    pub verifications_this_slot: u32,    // Throughput in the current slot
+    pub active_policy_count: u32,
+    pub paused: bool,
+}
+
+/// Creditworthiness signal derived from compliance history, returned by
+/// `get_underwriting_signal`. `score` is the sum of the four components, 0 to 1000.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UnderwritingSignal {
+    pub user: Pubkey,
+    pub score: u16,
+    pub kyc_component: u16,          // Higher KYC levels score more
+    pub age_component: u16,          // Longer-standing attestations score more
+    pub violation_component: u16,    // Each recorded violation scores less
+    pub risk_component: u16,         // Lower risk scores score more
+    pub kyc_level: u8,
+    pub attestation_age: i64,        // Seconds since identity was last verified
+    pub violation_count: u32,
+    pub risk_score: u8,              // Higher of oracle and behavioral risk
+}
+
+/// Cross-chain KYC message signed by the bridge relayer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BridgedKycMessage {
+    pub source_chain: u16,
+    pub sequence: u64,
+    pub wallet: Pubkey,
+    pub is_verified: bool,
+    pub jurisdiction: u8,
+    pub expires_at: i64,
+}
+
+/// One wallet to attest in `create_kyc_attestations_batch`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct KycEntry {
+    pub wallet: Pubkey,
+    pub is_verified: bool,
+    pub jurisdiction: u8,
+}
+
+/// KYC result signed off-chain by an allowlisted attestor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SignedKycMessage {
+    pub wallet: Pubkey,
+    pub jurisdiction: u8,
+    pub expires_at: i64,
+}
+
+#[account]
+pub struct PaymentMandate {
+    pub user: Pubkey,       // Verified wallet the mandate covers
+    pub policy: Pubkey,     // Policy the wallet was verified against
+    pub authority: Pubkey,  // Integrator allowed to consume the mandate
+    pub cap: u64,           // Total allowance for the period
+    pub remaining: u64,     // Allowance left to consume
+    pub created_at: i64,    // When the wallet was verified
+    pub expires_at: i64,    // End of the mandate period
+}
+
+impl PaymentMandate {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 8;
+}
+
+#[account]
+pub struct JurisdictionMeta {
+    pub jurisdiction: u8,   // Jurisdiction code, as stored on attestations
+    pub risk_tier: u8,      // 0 (lowest risk) to RISK_TIER_COUNT - 1 (highest)
+    pub retention_seconds: i64, // How long its attestations are kept before they may be purged (0 = forever)
+    pub name: String,       // Human-readable name, for off-chain tools and audits
+    pub iso_code: [u8; 2],  // ISO 3166-1 alpha-2 code (zeroed until set)
+}
+
+impl JurisdictionMeta {
+    pub const LEN: usize = 1 + 1 + 8 + (4 + MAX_JURISDICTION_NAME_LEN) + 2;
+}
+
+#[account]
+pub struct FeeLedger {
+    pub policy: Pubkey,          // Policy whose verification fees are recorded
+    pub fees_collected: u64,     // Lamports collected since the ledger was created
+    pub fee_payer_count: u64,    // Fee-paying verifications recorded
+    pub last_payer: Pubkey,      // Who paid the most recent fee
+    pub last_fee: u64,           // Amount of the most recent fee
+    pub last_paid_at: i64,       // When the most recent fee was paid
+    pub created_at: i64,         // When recording started
+}
+
+impl FeeLedger {
+    pub const LEN: usize = 32 + 8 + 8 + 32 + 8 + 8 + 8;
+    
+    /// Record one fee payment
+    pub fn record(&mut self, payer: Pubkey, fee: u64, now: i64) {
+        self.fees_collected = self.fees_collected.saturating_add(fee);
+        self.fee_payer_count = self.fee_payer_count.saturating_add(1);
+        self.last_payer = payer;
+        self.last_fee = fee;
+        self.last_paid_at = now;
+    }
+}
+
+#[account]
+pub struct JurisdictionLimit {
+    pub jurisdiction: u8,   // Jurisdiction code, as stored on attestations
+    pub max_amount: u64,    // Per-transaction amount cap for its users (0 = no cap)
+}
+
+impl JurisdictionLimit {
+    pub const LEN: usize = 1 + 8;
+}
+
+#[account]
+pub struct Attestor {
+    pub attestor: Pubkey,   // Key allowed to issue attestations
+    pub added_by: Pubkey,   // State authority that allowlisted it
+    pub added_at: i64,      // When it was allowlisted
+    pub allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN], // Jurisdictions it may attest (all zero = any)
+}
+
+impl Attestor {
+    pub const LEN: usize = 32 + 32 + 8 + JURISDICTION_BITMAP_LEN;
+}
+
+#[account]
+pub struct Attester {
+    pub attester: Pubkey,   // Key that issues or co-signs attestations
+    pub trust_weight: u8,   // Weight its signature carries toward a policy's min_trust_weight
+}
+
+impl Attester {
+    pub const LEN: usize = 32 + 1;
+}
+
+#[account]
+pub struct ScreeningVendor {
+    pub id: u8,             // Id recorded on attestations this vendor issues
+    pub name: String,       // Human-readable vendor name
+    pub key: Pubkey,        // Key the vendor signs attestations with
+}
+
+impl ScreeningVendor {
+    pub const LEN: usize = 1 + (4 + 32) + 32;
+}
+
+#[account]
+pub struct DelegationRecord {
+    pub principal: Pubkey,  // KYC'd wallet whose compliance is delegated
+    pub delegate: Pubkey,   // Wallet authorized to act for the principal
+    pub created_at: i64,    // When the delegation was authorized
+}
+
+impl DelegationRecord {
+    pub const LEN: usize = 32 + 32 + 8;
+}
+
+#[account]
+pub struct EntityHierarchy {
+    pub parent: Pubkey,     // Entity wallet whose KYB attestation the sub-account inherits
+    pub subaccount: Pubkey, // Sub-account wallet verifying under the parent
+    pub linked_at: i64,     // When the sub-account was linked
+}
+
+impl EntityHierarchy {
+    pub const LEN: usize = 32 + 32 + 8;
+}
+
+#[account]
+pub struct BridgeMessageReceipt {
+    pub consumed: bool,     // Message has been turned into an attestation
+    pub source_chain: u16,  // Chain the message originated from
+    pub sequence: u64,      // Per-chain message sequence number
+    pub wallet: Pubkey,     // Wallet the message attested
+}
+
+impl BridgeMessageReceipt {
+    pub const LEN: usize = 1 + 2 + 8 + 32;
+}
+
+/// Risk entry published by the Range Security Oracle for a single wallet.
+/// Stored after an 8-byte account discriminator in accounts owned by the oracle program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RangeRiskEntry {
+    pub wallet: Pubkey,
+    pub risk_score: u8,
+    pub last_updated: i64,
+    pub model_version: u32,
+}
+
+#[event]
+pub struct VerificationEvent {
+    pub user: Pubkey,
+    pub principal: Pubkey, // Wallet whose compliance was evaluated (differs from user for delegates)
+    pub action: String,
+    pub verified: bool,
+    pub risk_score: u8,
+    pub overridden: bool, // A stricter per-call risk threshold was applied
+    pub amount: u64,        // Raw amount in base units (0 = not provided)
+    pub scaled_amount: u64, // Amount in whole units of the policy denomination
+    pub fee: u64,           // Lamports charged for the verification
+    pub risk_model_version: u32, // Oracle risk model that produced the score (0 = unversioned)
+    pub policy_hash: [u8; 32],   // Snapshot hash of the policy configuration the check ran under
+    pub seq: u64,                // Position in the state's event sequence
+    pub integrator: Pubkey,      // Front-end or protocol that requested the verification (default = none)
+}
+
+#[event]
+pub struct SimulatedVerificationEvent {
+    pub user: Pubkey,
+    pub principal: Pubkey,
+    pub action: String,
+    pub risk_score: u8,
+    pub overridden: bool,
+    pub amount: u64,
+    pub fee: u64,                // Lamports a real verification would have charged
+    pub risk_model_version: u32,
+    pub policy_hash: [u8; 32],
+}
+
+#[event]
+pub struct PolicyUpdatedEvent {
+    pub policy: Pubkey,
+    pub authority: Pubkey,
+    pub revision: u64,
+    pub max_risk_score: u8,
+    pub require_kyc: bool,
+    pub allowed_jurisdictions: [u8; JURISDICTION_BITMAP_LEN], // Bitmap after the update
+    pub min_kyc_level: u8,
+    pub effective_from: i64,
+    pub effective_until: i64,
+    pub min_verify_interval: i64,
+    pub max_oracle_staleness: i64,
+    pub min_attestation_age: i64,
+    pub max_amount: u64,       // Per-transaction cap, 0 = unlimited
+    pub policy_hash: [u8; 32], // Snapshot hash of the configuration after the update
+}
+
+#[event]
+pub struct PolicyClosedEvent {
+    pub policy: Pubkey,
+    pub authority: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct AttesterTrustUpdatedEvent {
+    pub attester: Pubkey,
+    pub trust_weight: u8,
+}
+
+#[event]
+pub struct AttestationCosignedEvent {
+    pub wallet: Pubkey,
+    pub cosigner: Pubkey,
+    pub cosigner_count: u8,
+}
+
+#[event]
+pub struct RiskFreezeChangedEvent {
+    pub wallet: Pubkey,
+    pub frozen: bool,
+    pub risk_score: u8,
+}
+
+#[event]
+pub struct AttestationJournaledEvent {
+    pub sequence: u64,
+    pub operation: u8,
+    pub wallet: Pubkey,
+    pub record_hash: [u8; 32],
+    pub timestamp: i64,
+    pub head: [u8; 32],
+}
+
+#[event]
+pub struct AttestorAddedEvent {
+    pub attestor: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct AttestorRemovedEvent {
+    pub attestor: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct SanctionAddedEvent {
+    pub wallet: Pubkey,
+    pub reason: String,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct SanctionRemovedEvent {
+    pub wallet: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PauseStateChangedEvent {
+    pub paused: bool,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferredEvent {
+    pub old: Pubkey,
+    pub new: Pubkey,
+}
+
+#[event]
+pub struct ViolationEvent {
+    pub user: Pubkey,
+    pub reason: String,
+    pub category: u8,                    // VIOLATION_CATEGORY_* value
+    pub seq: u64,                        // Position in the state's event sequence
+}
+
+#[event]
+pub struct KycAttestationEvent {
+    pub wallet: Pubkey,
+    pub is_verified: bool,
+    pub jurisdiction: u8,
+    pub seq: u64, // Position in the state's event sequence
+}
+
+#[event]
+pub struct AttestationForceRevokedEvent {
+    pub wallet: Pubkey,
+    pub revoked_by: Pubkey, // State authority that overrode the attester
+    pub attester: Pubkey,   // Attester that issued the revoked attestation
+}
+
+#[event]
+pub struct KycAttestationClosedEvent {
+    pub wallet: Pubkey,
+    pub authority: Pubkey, // Attester the rent was returned to
+}
+
+#[event]
+pub struct DataPurgedEvent {
+    pub wallet: Pubkey,
+    pub jurisdiction: u8,
+    pub attested_at: i64, // When the purged attestation was last updated
+    pub purged_at: i64,
+}
+
+
+#[event]
+pub struct KycExpiryReminderEvent {
+    pub wallet: Pubkey,
+    pub threshold: u8,   // Lifetime percentage this reminder is for
+    pub pct_elapsed: u8, // Lifetime percentage elapsed when it fired
+}
+
+#[event]
+pub struct BehavioralRiskUpdatedEvent {
+    pub user: Pubkey,
+    pub behavioral_risk: u8,
+}
+
+#[event]
+pub struct ReportFinalizedEvent {
+    pub period_id: u64,
+    pub verification_count: u64,
+    pub violation_count: u64,
+    pub report_hash: [u8; 32],
+}
+
+#[event]
+pub struct Token2022TransferVerifiedEvent {
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub transfer_hook_program: Pubkey, // Mint's transfer hook (default = none)
+}
+
+#[event]
+pub struct RiskRefreshedEvent {
+    pub user: Pubkey,
+    pub risk_score: u8,
+    pub oracle: Pubkey,
+    pub refreshed_at: i64,
+}
+
+#[event]
+pub struct MarginalRiskEvent {
+    pub user: Pubkey,
+    pub risk_score: u8,
+    pub max_risk_score: u8,
+}
+
+#[event]
+pub struct GracePeriodWarningEvent {
+    pub user: Pubkey,
+    pub expired_at: i64,
+    pub grace_ends_at: i64, // From then on the attestation fails with AttestationExpired
+}
+
+#[event]
+pub struct RiskWarningEvent {
+    pub user: Pubkey,
+    pub risk_score: u8,
+}
+
+#[event]
+pub struct OracleFailureEvent {
+    pub user: Pubkey,
+    pub reason_code: u8, // ORACLE_FAILURE_*
+}
+
+#[event]
+pub struct RiskConflictResolvedEvent {
+    pub user: Pubkey,
+    pub risk_score: u8,
+    pub attester_verified: bool,
+    pub winner: u8,     // RISK_SOURCE_* whose verdict was applied
+    pub passed: bool,
+}
+
+#[event]
+pub struct DerivativeVerificationEvent {
+    pub user: Pubkey,
+    pub underlying_policy: Pubkey,
+    pub derivative_policy: Pubkey,
+    pub risk_score: u8,
+}
+
+#[event]
+pub struct MandateConsumedEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct FieldRevealedEvent {
+    pub wallet: Pubkey,
+    pub field_id: u8,
+    pub commitment: [u8; 32],
+    pub auditor: Pubkey,
+    pub revealed_at: i64,
+}
+
+#[event]
+pub struct AttestationRenewedEvent {
+    pub wallet: Pubkey,
+    pub expires_at: i64,
+    pub renew_count: u32,
+    pub fee: u64,
+}
+
+#[event]
+pub struct MultisigVerificationEvent {
+    pub multisig: Pubkey,
+    pub verified: bool,
+    pub compliant_members: u8,
+    pub required_members: u8,
+    pub failed_members: Vec<Pubkey>,
+}
+
+#[event]
+pub struct TreasuryOperationEvent {
+    pub operator: Pubkey,
+    pub operation: String,
+    pub amount: u64,
+    pub risk_score: u8,
+}
+
+#[event]
+pub struct EmergencyVerificationEvent {
+    pub user: Pubkey,
+    pub action: String,
+    pub reason: String,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct DelegationEvent {
+    pub principal: Pubkey,
+    pub delegate: Pubkey,
+    pub authorized: bool,
+}
+
+#[event]
+pub struct EntityLinkEvent {
+    pub parent: Pubkey,
+    pub subaccount: Pubkey,
+    pub linked: bool,
+}
+
+#[event]
+pub struct StatsResetEvent {
+    pub reset_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KycRequestedEvent {
+    pub user: Pubkey,
+    pub requested_at: i64,
+}
+
+#[event]
+pub struct KycRequestFulfilledEvent {
+    pub user: Pubkey,
+    pub attester: Pubkey,
+}
+
+#[event]
+pub struct CertificateMintedEvent {
+    pub user: Pubkey,
+    pub token_account: Pubkey,
+    pub risk_score: u8,
+    pub verified_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KYC_LEVEL_VERIFIED, VIOLATION_CATEGORY_KYC, VIOLATION_CATEGORY_RISK};
+
+    fn attestation() -> KycAttestation {
+        KycAttestation {
+            wallet: Pubkey::default(),
+            flags: 0,
+            authority: Pubkey::default(),
+            timestamp: 0,
+            jurisdiction: 0,
+            expires_at: 0,
+            reminders_fired: 0,
+            renew_count: 0,
+            validity_period: 0,
+            source_chain: 0,
+            screening_vendor_id: 0,
+            field_commitments: [[0; 32]; MAX_DISCLOSURE_FIELDS],
+            revocation_authority: Pubkey::default(),
+            jurisdiction_changed_at: 0,
+            prev_attestation_hash: [0; 32],
+            imported_from: Pubkey::default(),
+            identity_verified_at: 0,
+            sanctions_checked_at: 0,
+            accreditation_verified_at: 0,
+            jurisdiction_scheme: 0,
+            documents_reviewed: 0,
+            cosigners: [Pubkey::default(); MAX_ATTESTATION_COSIGNERS],
+            cosigner_count: 0,
+            allowed_counterparties: [Pubkey::default(); MAX_ALLOWED_COUNTERPARTIES],
+            level: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn verified_flag_round_trips() {
+        let mut a = attestation();
+        a.set_verified(true);
+        assert!(a.is_verified());
+        assert_eq!(a.flags, ATTESTATION_FLAG_VERIFIED);
+        a.set_verified(false);
+        assert!(!a.is_verified());
+    }
+
+    #[test]
+    fn auto_renew_flag_round_trips() {
+        let mut a = attestation();
+        a.set_auto_renew(true);
+        assert!(a.auto_renew());
+        assert_eq!(a.flags, ATTESTATION_FLAG_AUTO_RENEW);
+        a.set_auto_renew(false);
+        assert!(!a.auto_renew());
+    }
+
+    #[test]
+    fn ownership_proven_flag_round_trips() {
+        let mut a = attestation();
+        a.set_ownership_proven(true);
+        assert!(a.ownership_proven());
+        assert_eq!(a.flags, ATTESTATION_FLAG_OWNERSHIP_PROVEN);
+        a.set_ownership_proven(false);
+        assert!(!a.ownership_proven());
+    }
+
+    #[test]
+    fn flags_are_independent() {
+        let mut a = attestation();
+        a.set_verified(true);
+        a.set_ownership_proven(true);
+        a.set_auto_renew(true);
+        a.set_verified(false);
+        assert!(!a.is_verified());
+        assert!(a.auto_renew());
+        assert!(a.ownership_proven());
+    }
+
+    #[test]
+    fn lifetime_start_moves_to_the_renewed_period() {
+        let mut a = attestation();
+        a.timestamp = 100;
+        a.validity_period = 50;
+        a.expires_at = 150;
+        assert_eq!(a.lifetime_start(), 100);
+        
+        a.expires_at = 200;
+        a.renew_count = 1;
+        assert_eq!(a.lifetime_start(), 150);
+    }
+    
+    #[test]
+    fn packed_layout_matches_len() {
+        assert_eq!(attestation().try_to_vec().unwrap().len(), KycAttestation::LEN);
+    }
+
+    #[test]
+    fn legacy_booleans_map_to_flags() {
+        let legacy = LegacyKycAttestation {
+            wallet: Pubkey::new_unique(),
+            is_verified: true,
+            authority: Pubkey::default(),
+            timestamp: 7,
+            jurisdiction: 3,
+            expires_at: 0,
+            reminders_fired: 0,
+            auto_renew: false,
+            renew_count: 1,
+            validity_period: 0,
+            source_chain: 0,
+            screening_vendor_id: 0,
+            field_commitments: [[0; 32]; MAX_DISCLOSURE_FIELDS],
+            ownership_proven: true,
+        };
+        assert_eq!(legacy.try_to_vec().unwrap().len(), LegacyKycAttestation::LEN);
+        
+        let wallet = legacy.wallet;
+        let migrated = KycAttestation::from(legacy);
+        assert_eq!(migrated.wallet, wallet);
+        assert!(migrated.is_verified());
+        assert!(!migrated.auto_renew());
+        assert!(migrated.ownership_proven());
+        assert_eq!((migrated.timestamp, migrated.jurisdiction, migrated.renew_count), (7, 3, 1));
+    }
+
+    fn blank_user_record() -> UserComplianceRecord {
+        UserComplianceRecord {
+            user: Pubkey::default(),
+            behavioral_risk: 0,
+            behavioral_updated_at: 0,
+            last_denial_code: 0,
+            last_denial_ts: 0,
+            regulatory_hold: false,
+            hold_expires_at: 0,
+            nonce: 0,
+            violation_count: 0,
+            last_verified_at: 0,
+            verification_count: 0,
+            last_action: String::new(),
+            last_risk_score: 0,
+            verified_volume: 0,
+            risk_cached_at: 0,
+            risk_cache_policy: Pubkey::default(),
+            risk_cache_revision: 0,
+            cached_risk_score: 0,
+            cached_risk_model_version: 0,
+        }
+    }
+
+    #[test]
+    fn recorded_action_is_bounded() {
+        let mut record = blank_user_record();
+        let long = "é".repeat(MAX_RECORDED_ACTION_LEN);
+        record.record_verification(Pubkey::default(), &long, 2, 100, 9);
+        assert!(record.last_action.len() <= MAX_RECORDED_ACTION_LEN);
+        assert!(long.starts_with(&record.last_action));
+        assert_eq!(record.try_to_vec().unwrap().len(), UserComplianceRecord::LEN);
+        
+        record.record_verification(Pubkey::default(), "swap", 3, 50, 10);
+        assert_eq!((record.verification_count, record.last_verified_at), (2, 10));
+        assert_eq!(record.verified_volume, 150);
+        assert_eq!((record.last_action.as_str(), record.last_risk_score), ("swap", 3));
+    }
+
+    #[test]
+    fn cached_risk_is_bound_to_policy_ttl_and_behavior() {
+        let policy = Pubkey::new_unique();
+        let mut record = blank_user_record();
+        assert_eq!(record.cached_risk(&policy, 1, 60, 100), None);
+        
+        record.cache_risk(policy, 1, 4, 3, 100);
+        assert_eq!(record.cached_risk(&policy, 1, 60, 159), Some((4, 3)));
+        assert_eq!(record.cached_risk(&policy, 1, 60, 160), None);
+        assert_eq!(record.cached_risk(&policy, 2, 60, 120), None);
+        assert_eq!(record.cached_risk(&Pubkey::new_unique(), 1, 60, 120), None);
+        
+        // A behavioral report after the read changes the combined score
+        record.behavioral_updated_at = 110;
+        assert_eq!(record.cached_risk(&policy, 1, 60, 120), None);
+    }
+
+    #[test]
+    fn compliance_receipt_is_fresh_under_its_policy_until_it_lapses() {
+        let policy = Pubkey::new_unique();
+        let receipt = ComplianceReceipt {
+            user: Pubkey::new_unique(),
+            policy,
+            verified_at: 100,
+            risk_score: 2,
+            valid_until: 160,
+        };
+        assert_eq!(receipt.try_to_vec().unwrap().len(), ComplianceReceipt::LEN);
+        assert!(receipt.is_fresh(&policy, 100));
+        assert!(receipt.is_fresh(&policy, 159));
+        assert!(!receipt.is_fresh(&policy, 160));
+        assert!(!receipt.is_fresh(&Pubkey::new_unique(), 120));
+        
+        // A pass under a policy without a TTL never counts as fresh
+        let unset = ComplianceReceipt { valid_until: 100, ..receipt };
+        assert!(!unset.is_fresh(&policy, 100));
+    }
+
+    #[test]
+    fn violation_reason_is_bounded() {
+        let mut record = UserViolationRecord {
+            user: Pubkey::default(),
+            count: 0,
+            last_reason: String::new(),
+            last_violation_at: 0,
+            last_category: 0,
+        };
+        record.record(Pubkey::default(), &"x".repeat(MAX_VIOLATION_REASON_LEN + 1), VIOLATION_CATEGORY_RISK, 5);
+        record.record(Pubkey::default(), "late", VIOLATION_CATEGORY_KYC, 6);
+        assert_eq!((record.count, record.last_reason.as_str(), record.last_violation_at), (2, "late", 6));
+        assert_eq!(record.last_category, VIOLATION_CATEGORY_KYC);
+        
+        record.record(Pubkey::default(), &"x".repeat(MAX_VIOLATION_REASON_LEN + 1), VIOLATION_CATEGORY_RISK, 7);
+        assert_eq!(record.try_to_vec().unwrap().len(), UserViolationRecord::LEN);
+    }
+
+    #[test]
+    fn counterparty_scope() {
+        let mut a = attestation();
+        let allowed = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        assert!(a.counterparty_allowed(&other));
+        
+        a.allowed_counterparties[2] = allowed;
+        assert!(a.counterparty_allowed(&allowed));
+        assert!(!a.counterparty_allowed(&other));
+        assert!(!a.counterparty_allowed(&Pubkey::default()));
+    }
+
+    #[test]
+    fn journal_head_chains_entries() {
+        let journal = || AttestationJournal { state: Pubkey::default(), head: [0; 32], sequence: 0, created_at: 0 };
+        let wallet = Pubkey::new_unique();
+        let (mut a, mut b) = (journal(), journal());
+        for journal in [&mut a, &mut b] {
+            journal.append(JOURNAL_OP_CREATE, &wallet, &[1; 32], 10).unwrap();
+            assert_eq!(journal.append(JOURNAL_OP_REVOKE, &wallet, &[2; 32], 11).unwrap(), 2);
+        }
+        assert_eq!(a.head, b.head);
+        
+        // Any difference in an earlier entry carries through to the head
+        let mut c = journal();
+        c.append(JOURNAL_OP_UPDATE, &wallet, &[1; 32], 10).unwrap();
+        c.append(JOURNAL_OP_REVOKE, &wallet, &[2; 32], 11).unwrap();
+        assert_ne!(a.head, c.head);
+        assert_eq!(a.try_to_vec().unwrap().len(), AttestationJournal::LEN);
+    }
+
+    #[test]
+    fn counters_fail_cleanly_at_their_limit() {
+        let mut journal = AttestationJournal { state: Pubkey::default(), head: [0; 32], sequence: u64::MAX, created_at: 0 };
+        let wallet = Pubkey::new_unique();
+        assert_eq!(
+            journal.append(JOURNAL_OP_CREATE, &wallet, &[1; 32], 10).unwrap_err(),
+            CompliFiError::CounterOverflow.into()
+        );
+        
+        let mut rollup = GlobalRollup {
+            state: Pubkey::default(),
+            verified_users: 0,
+            frozen_users: 0,
+            violations: 0,
+            level_counts: [0; KYC_LEVEL_COUNT],
+            created_at: 0,
+        };
+        rollup.level_counts[KYC_LEVEL_VERIFIED as usize] = u64::MAX;
+        assert_eq!(rollup.record_level_change(None, Some(KYC_LEVEL_VERIFIED)).unwrap_err(), CompliFiError::CounterOverflow.into());
+        rollup.level_counts[KYC_LEVEL_VERIFIED as usize] = 0;
+        rollup.verified_users = u64::MAX;
+        assert_eq!(rollup.record_level_change(None, Some(KYC_LEVEL_VERIFIED)).unwrap_err(), CompliFiError::CounterOverflow.into());
+        // Leaving a level only saturates, so it never overflows
+        rollup.record_level_change(Some(KYC_LEVEL_VERIFIED), None).unwrap();
+    }
+}
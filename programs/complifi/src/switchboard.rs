@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+use crate::{CompliFiError, MAX_RISK_SCORE};
+
+pub const SWITCHBOARD_PROGRAM_ID: Pubkey = pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+// sha256("account:AggregatorAccountData")[..8]
+const AGGREGATOR_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+// AggregatorAccountData is packed: after the discriminator come name (32), metadata (128),
+// reserved (32), queue (32), four u32 settings (16), start_after (8), variance_threshold (20),
+// force_report_period (8), expiration (8), consecutive_failure_count (8),
+// next_allowed_update_time (8), is_locked (1) and crank (32), then latest_confirmed_round:
+// num_success (4), num_error (4), is_closed (1), round_open_slot (8),
+// round_open_timestamp (8) and result, a SwitchboardDecimal of mantissa (i128) and scale (u32)
+const ROUND_OFFSET: usize = 341;
+const ROUND_NUM_SUCCESS_OFFSET: usize = ROUND_OFFSET;
+const ROUND_OPEN_TIMESTAMP_OFFSET: usize = ROUND_OFFSET + 17;
+const ROUND_RESULT_MANTISSA_OFFSET: usize = ROUND_OFFSET + 25;
+const ROUND_RESULT_SCALE_OFFSET: usize = ROUND_RESULT_MANTISSA_OFFSET + 16;
+const ROUND_RESULT_END: usize = ROUND_RESULT_SCALE_OFFSET + 4;
+
+// Largest SwitchboardDecimal scale
+const MAX_DECIMAL_SCALE: u32 = 28;
+
+/// Latest confirmed result of a Switchboard aggregator
+pub struct AggregatorResult {
+    pub mantissa: i128,
+    pub scale: u32,
+    pub updated_at: i64,
+}
+
+/// Read the latest confirmed round of a Switchboard v2 aggregator
+pub fn read_aggregator_result(aggregator: &AccountInfo) -> Result<AggregatorResult> {
+    require_keys_eq!(*aggregator.owner, SWITCHBOARD_PROGRAM_ID, CompliFiError::OracleDataFetchFailed);
+    
+    let data = aggregator.try_borrow_data()?;
+    require!(
+        data.len() >= ROUND_RESULT_END && data[..8] == AGGREGATOR_DISCRIMINATOR,
+        CompliFiError::OracleDataFetchFailed
+    );
+    
+    let num_success = u32::from_le_bytes(data[ROUND_NUM_SUCCESS_OFFSET..ROUND_NUM_SUCCESS_OFFSET + 4].try_into().unwrap());
+    require!(num_success > 0, CompliFiError::OracleDataFetchFailed);
+    
+    Ok(AggregatorResult {
+        mantissa: i128::from_le_bytes(data[ROUND_RESULT_MANTISSA_OFFSET..ROUND_RESULT_SCALE_OFFSET].try_into().unwrap()),
+        scale: u32::from_le_bytes(data[ROUND_RESULT_SCALE_OFFSET..ROUND_RESULT_END].try_into().unwrap()),
+        updated_at: i64::from_le_bytes(
+            data[ROUND_OPEN_TIMESTAMP_OFFSET..ROUND_OPEN_TIMESTAMP_OFFSET + 8].try_into().unwrap(),
+        ),
+    })
+}
+
+/// Map an aggregator result onto the 0-10 risk scale. The feed reports the score on that
+/// scale; fractions round up so the mapping never understates risk, and values outside it
+/// are rejected rather than clamped.
+pub fn aggregator_risk_score(result: &AggregatorResult) -> Result<u8> {
+    require!(
+        result.mantissa >= 0 && result.scale <= MAX_DECIMAL_SCALE,
+        CompliFiError::OracleDataFetchFailed
+    );
+    let score = (result.mantissa as u128).div_ceil(10u128.pow(result.scale));
+    require!(score <= MAX_RISK_SCORE as u128, CompliFiError::OracleDataFetchFailed);
+    Ok(score as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(mantissa: i128, scale: u32) -> AggregatorResult {
+        AggregatorResult { mantissa, scale, updated_at: 0 }
+    }
+
+    #[test]
+    fn maps_whole_and_fractional_scores() {
+        assert_eq!(aggregator_risk_score(&result(2, 0)).unwrap(), 2);
+        assert_eq!(aggregator_risk_score(&result(2_000, 3)).unwrap(), 2);
+        // 2.001 rounds up rather than down
+        assert_eq!(aggregator_risk_score(&result(2_001, 3)).unwrap(), 3);
+        assert_eq!(aggregator_risk_score(&result(10, 0)).unwrap(), 10);
+    }
+
+    #[test]
+    fn rejects_values_off_the_risk_scale() {
+        assert!(aggregator_risk_score(&result(-1, 0)).is_err());
+        assert!(aggregator_risk_score(&result(10_001, 3)).is_err());
+        assert!(aggregator_risk_score(&result(1, MAX_DECIMAL_SCALE + 1)).is_err());
+    }
+}
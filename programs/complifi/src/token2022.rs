@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+
+use crate::CompliFiError;
+
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+// A base Mint is 82 bytes. Mints with extensions are padded to the 165-byte token account
+// size, followed by a one-byte account type and TLV entries (type: u16, length: u16, value).
+const MINT_BASE_LEN: usize = 82;
+const ACCOUNT_BASE_LEN: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const TLV_HEADER_LEN: usize = 4;
+
+// Token account layout: mint (32), owner (32), amount (8), delegate (36), state (1), ...
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+const TOKEN_ACCOUNT_STATE_FROZEN: u8 = 2;
+
+// Extension types that affect whether a transfer can happen
+const EXTENSION_CONFIDENTIAL_TRANSFER_MINT: u16 = 4;
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+
+// A mint carrying only the NonTransferable extension, which has no value
+pub const NON_TRANSFERABLE_MINT_LEN: usize = ACCOUNT_BASE_LEN + 1 + TLV_HEADER_LEN;
+
+// Token-2022 instruction tags, and the associated token program's CreateIdempotent
+const IX_MINT_TO: u8 = 7;
+const IX_INITIALIZE_MINT2: u8 = 20;
+const IX_INITIALIZE_NON_TRANSFERABLE_MINT: u8 = 32;
+const IX_CREATE_ASSOCIATED_IDEMPOTENT: u8 = 1;
+
+// ConfidentialTransferMint value: authority (32), auto_approve (1), auditor ElGamal key (32)
+const CONFIDENTIAL_AUDITOR_OFFSET: usize = 33;
+
+// TransferHook value: authority (32), program id (32)
+const TRANSFER_HOOK_PROGRAM_OFFSET: usize = 32;
+
+/// Transfer-relevant extension config of a Token-2022 mint
+pub struct MintTransferConfig {
+    pub non_transferable: bool,
+    pub confidential: bool,
+    pub confidential_auditable: bool,
+    pub transfer_hook_program: Option<Pubkey>,
+}
+
+/// The fields of a Token-2022 token account a transfer check needs
+pub struct TokenAccountState {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub frozen: bool,
+}
+
+/// Parse the transfer-relevant extensions of a Token-2022 mint
+pub fn read_mint_transfer_config(mint: &AccountInfo) -> Result<MintTransferConfig> {
+    let data = mint.try_borrow_data()?;
+    require!(data.len() >= MINT_BASE_LEN, CompliFiError::InvalidTokenAccount);
+    
+    let mut config = MintTransferConfig {
+        non_transferable: false,
+        confidential: false,
+        confidential_auditable: false,
+        transfer_hook_program: None,
+    };
+    if data.len() == MINT_BASE_LEN {
+        return Ok(config);
+    }
+    require!(
+        data.len() > ACCOUNT_BASE_LEN && data[ACCOUNT_BASE_LEN] == ACCOUNT_TYPE_MINT,
+        CompliFiError::InvalidTokenAccount
+    );
+    
+    let mut at = ACCOUNT_BASE_LEN + 1;
+    while at + TLV_HEADER_LEN <= data.len() {
+        let extension = u16::from_le_bytes([data[at], data[at + 1]]);
+        let len = u16::from_le_bytes([data[at + 2], data[at + 3]]) as usize;
+        let value = data
+            .get(at + TLV_HEADER_LEN..at + TLV_HEADER_LEN + len)
+            .ok_or(CompliFiError::InvalidTokenAccount)?;
+        
+        match extension {
+            EXTENSION_NON_TRANSFERABLE => config.non_transferable = true,
+            EXTENSION_CONFIDENTIAL_TRANSFER_MINT => {
+                let auditor = value
+                    .get(CONFIDENTIAL_AUDITOR_OFFSET..CONFIDENTIAL_AUDITOR_OFFSET + 32)
+                    .ok_or(CompliFiError::InvalidTokenAccount)?;
+                config.confidential = true;
+                config.confidential_auditable = auditor.iter().any(|b| *b != 0);
+            }
+            EXTENSION_TRANSFER_HOOK => {
+                let program = value
+                    .get(TRANSFER_HOOK_PROGRAM_OFFSET..TRANSFER_HOOK_PROGRAM_OFFSET + 32)
+                    .ok_or(CompliFiError::InvalidTokenAccount)?;
+                let program = Pubkey::try_from(program).unwrap();
+                config.transfer_hook_program = (program != Pubkey::default()).then_some(program);
+            }
+            _ => {}
+        }
+        at += TLV_HEADER_LEN + len;
+    }
+    Ok(config)
+}
+
+/// Parse the mint, owner and frozen state of a Token-2022 token account
+pub fn read_token_account(account: &AccountInfo) -> Result<TokenAccountState> {
+    let data = account.try_borrow_data()?;
+    require!(data.len() >= ACCOUNT_BASE_LEN, CompliFiError::InvalidTokenAccount);
+    
+    Ok(TokenAccountState {
+        mint: Pubkey::try_from(&data[..32]).unwrap(),
+        owner: Pubkey::try_from(&data[TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32]).unwrap(),
+        amount: u64::from_le_bytes(
+            data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8].try_into().unwrap(),
+        ),
+        frozen: data[TOKEN_ACCOUNT_STATE_OFFSET] == TOKEN_ACCOUNT_STATE_FROZEN,
+    })
+}
+
+/// Owner of a token account, or the default key if it is too short to be one. Used to
+/// derive attestation seeds; `read_token_account` rejects malformed accounts afterwards.
+pub fn token_account_owner(account: &AccountInfo) -> Pubkey {
+    read_token_account(account).map_or(Pubkey::default(), |state| state.owner)
+}
+
+/// Initialize `mint` as a non-transferable Token-2022 mint with no freeze authority. The
+/// account must already be allocated to NON_TRANSFERABLE_MINT_LEN and owned by Token-2022.
+pub fn initialize_non_transferable_mint<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    mint_authority: &Pubkey,
+    decimals: u8,
+) -> Result<()> {
+    // The extension has to be in place before the mint itself is initialized
+    let extension = Instruction {
+        program_id: TOKEN_2022_PROGRAM_ID,
+        accounts: vec![AccountMeta::new(*mint.key, false)],
+        data: vec![IX_INITIALIZE_NON_TRANSFERABLE_MINT],
+    };
+    invoke(&extension, &[mint.clone(), token_program.clone()])?;
+    
+    // mint authority, then an absent freeze authority
+    let mut data = vec![IX_INITIALIZE_MINT2, decimals];
+    data.extend_from_slice(mint_authority.as_ref());
+    data.push(0);
+    let initialize = Instruction {
+        program_id: TOKEN_2022_PROGRAM_ID,
+        accounts: vec![AccountMeta::new(*mint.key, false)],
+        data,
+    };
+    invoke(&initialize, &[mint.clone(), token_program.clone()])?;
+    Ok(())
+}
+
+/// Create `wallet`'s associated token account for a Token-2022 `mint`, unless it exists
+pub fn create_associated_token_account<'info>(
+    associated_token_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let create = Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer.key, true),
+            AccountMeta::new(*token_account.key, false),
+            AccountMeta::new_readonly(*wallet.key, false),
+            AccountMeta::new_readonly(*mint.key, false),
+            AccountMeta::new_readonly(*system_program.key, false),
+            AccountMeta::new_readonly(*token_program.key, false),
+        ],
+        data: vec![IX_CREATE_ASSOCIATED_IDEMPOTENT],
+    };
+    invoke(
+        &create,
+        &[
+            payer.clone(),
+            token_account.clone(),
+            wallet.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Mint `amount` of a Token-2022 `mint` to `destination`, signing for the PDA mint authority
+pub fn mint_to<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    mint_authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = vec![IX_MINT_TO];
+    data.extend_from_slice(&amount.to_le_bytes());
+    let mint_to = Instruction {
+        program_id: TOKEN_2022_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*mint.key, false),
+            AccountMeta::new(*destination.key, false),
+            AccountMeta::new_readonly(*mint_authority.key, true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &mint_to,
+        &[mint.clone(), destination.clone(), mint_authority.clone(), token_program.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::get_return_data;
+use complifi::cpi::accounts::{CheckCompliance, CreateKycAttestation, Guard, VerifyCompliance};
+use complifi::program::Complifi;
+use complifi::{
+    ComplianceCheckResult, ComplianceReceipt, ComplianceState, CompliancePolicy, KycAttestation, UserComplianceRecord,
+    VerificationResult, COMPLIANCE_RECEIPT_SEED,
+};
+
+declare_id!("HfDx5TZzCdhW1FohAf62uD5hQtyEnFKaoSk264rWMfbF");
+
+// Seed of the PDA this program issues attestations as
+pub const ISSUER_SEED: &[u8] = b"issuer";
+
+/// Minimal integrator used by the tests: `gated_action` only proceeds when the CompliFi
+/// guard, called as its first CPI, returns Ok
+#[program]
+pub mod guard_stub {
+    use super::*;
+
+    /// Run a gated action on behalf of `user`
+    pub fn gated_action<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GatedAction<'info>>,
+        user: Pubkey,
+        action: String,
+    ) -> Result<()> {
+        let cpi_accounts = Guard {
+            state: ctx.accounts.complifi_state.to_account_info(),
+            policy: ctx.accounts.policy.to_account_info(),
+            attestation: ctx.accounts.attestation.to_account_info(),
+            user_record: ctx.accounts.user_record.as_ref().map(|record| record.to_account_info()),
+            sanction: ctx.accounts.sanction.to_account_info(),
+            attestor_entry: ctx.accounts.attestor_entry.as_ref().map(|entry| entry.to_account_info()),
+            range_oracle: ctx.accounts.range_oracle.as_ref().map(|oracle| oracle.to_account_info()),
+            risk_feed: ctx.accounts.risk_feed.as_ref().map(|feed| feed.to_account_info()),
+            switchboard_aggregator: ctx.accounts.switchboard_aggregator.as_ref().map(|aggregator| aggregator.to_account_info()),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.complifi_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        let attestation_authority = ctx.accounts.attestation.authority;
+        complifi::cpi::guard(cpi_ctx, user, attestation_authority, action.clone())?;
+        
+        // Compliance passed; a real integrator would run its own logic from here
+        msg!("Gated action {} executed for {}", action, user);
+        Ok(())
+    }
+
+    /// Ask CompliFi whether `user` would pass, and hand back the outcome it wrote as return data
+    pub fn query_compliance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GatedAction<'info>>,
+        user: Pubkey,
+        action: String,
+    ) -> Result<ComplianceCheckResult> {
+        let cpi_accounts = CheckCompliance {
+            state: ctx.accounts.complifi_state.to_account_info(),
+            policy: ctx.accounts.policy.to_account_info(),
+            attestation: ctx.accounts.attestation.to_account_info(),
+            user_record: ctx.accounts.user_record.as_ref().map(|record| record.to_account_info()),
+            range_oracle: ctx.accounts.range_oracle.as_ref().map(|oracle| oracle.to_account_info()),
+            risk_feed: ctx.accounts.risk_feed.as_ref().map(|feed| feed.to_account_info()),
+            switchboard_aggregator: ctx.accounts.switchboard_aggregator.as_ref().map(|aggregator| aggregator.to_account_info()),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.complifi_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        complifi::cpi::check_compliance(cpi_ctx, user, action)?;
+        
+        let (program_id, data) = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+        require_keys_eq!(program_id, complifi::ID);
+        Ok(ComplianceCheckResult::try_from_slice(&data)?)
+    }
+    
+    /// Verify `user` through CompliFi and hand back the VerificationResult it returned. The
+    /// policy is expected to neither defer nor sign results, so the result is all there is.
+    pub fn verified_action(ctx: Context<VerifiedAction>, user: Pubkey, action: String) -> Result<VerificationResult> {
+        let cpi_accounts = VerifyCompliance {
+            state: ctx.accounts.complifi_state.to_account_info(),
+            policy: ctx.accounts.policy.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+            user: ctx.accounts.user.to_account_info(),
+            attestation_authority: ctx.accounts.attestation_authority.to_account_info(),
+            integrator: None,
+            delegation: None,
+            entity: None,
+            attestation: Some(ctx.accounts.attestation.to_account_info()),
+            attestor_entry: None,
+            user_record: None,
+            report: None,
+            compiled_policy: None,
+            rollout_baseline: None,
+            jurisdiction_limit: None,
+            range_oracle: ctx.accounts.range_oracle.as_ref().map(|oracle| oracle.to_account_info()),
+            risk_feed: ctx.accounts.risk_feed.as_ref().map(|feed| feed.to_account_info()),
+            switchboard_aggregator: ctx.accounts.switchboard_aggregator.as_ref().map(|aggregator| aggregator.to_account_info()),
+            receipt: None,
+            compliance_receipt: None,
+            fee_recipient: None,
+            fee_ledger: None,
+            sanction: ctx.accounts.sanction.to_account_info(),
+            certificate: None,
+            certificate_mint: None,
+            certificate_token_account: None,
+            token_program: None,
+            associated_token_program: None,
+            system_program: None,
+            event_authority: ctx.accounts.complifi_event_authority.to_account_info(),
+            program: ctx.accounts.complifi_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.complifi_program.to_account_info(), cpi_accounts);
+        complifi::cpi::verify_compliance(cpi_ctx, user, action, None, None, None, Pubkey::default(), None, false, false, false)?;
+        
+        let (program_id, data) = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+        require_keys_eq!(program_id, complifi::ID);
+        Ok(VerificationResult::try_from_slice(&data)?)
+    }
+    
+    /// Stand-in for an integrator's gated instruction, for CompliFi's verify_and_execute to
+    /// invoke once the user has passed
+    pub fn gated_target(_ctx: Context<GatedTarget>) -> Result<()> {
+        msg!("Gated target executed");
+        Ok(())
+    }
+    
+    /// Attest `wallet` through CompliFi as this program's issuer PDA, the way a provider that
+    /// operates through a program rather than a keypair would. The PDA must be on CompliFi's
+    /// attestor allowlist and hold the lamports for the attestation's rent.
+    pub fn issue_attestation(ctx: Context<IssueAttestation>, wallet: Pubkey, jurisdiction: u8) -> Result<()> {
+        let cpi_accounts = CreateKycAttestation {
+            attestation: ctx.accounts.attestation.to_account_info(),
+            authority: ctx.accounts.issuer.to_account_info(),
+            attestor_entry: ctx.accounts.attestor_entry.to_account_info(),
+            state: ctx.accounts.complifi_state.to_account_info(),
+            wallet: ctx.accounts.wallet.to_account_info(),
+            time_oracle: None,
+            vendor: None,
+            instructions: None,
+            policy: None,
+            jurisdiction_meta: None,
+            rollup: ctx.accounts.rollup.as_ref().map(|rollup| rollup.to_account_info()),
+            journal: ctx.accounts.journal.as_ref().map(|journal| journal.to_account_info()),
+            kyc_request: None,
+            system_program: ctx.accounts.system_program.to_account_info(),
+            event_authority: ctx.accounts.complifi_event_authority.to_account_info(),
+            program: ctx.accounts.complifi_program.to_account_info(),
+        };
+        let signer_seeds: &[&[&[u8]]] = &[&[ISSUER_SEED, &[ctx.bumps.issuer]]];
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.complifi_program.to_account_info(), cpi_accounts, signer_seeds);
+        complifi::cpi::create_kyc_attestation(cpi_ctx, wallet, true, jurisdiction, 0, None, None, 0, 0)?;
+        
+        msg!("Attestation for {} issued by {}", wallet, ctx.accounts.issuer.key());
+        Ok(())
+    }
+    
+    /// Run a gated action for `user` on the strength of their CompliFi receipt alone: no
+    /// CPI, just a read of the account and its freshness under `policy`
+    pub fn receipt_gated_action(ctx: Context<ReceiptGatedAction>, user: Pubkey, policy: Pubkey) -> Result<()> {
+        if !ctx.accounts.receipt.is_fresh(&policy, Clock::get()?.unix_timestamp) {
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+        
+        msg!("Receipt-gated action executed for {}", user);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct GatedAction<'info> {
+    pub complifi_state: Account<'info, ComplianceState>,
+    
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    pub attestation: Account<'info, KycAttestation>,
+    
+    pub user_record: Option<Account<'info, UserComplianceRecord>>,
+    
+    /// CHECK: The user's sanction entry address, checked by CompliFi's guard
+    pub sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: The attestation issuer's allowlist entry, checked by CompliFi's guard
+    pub attestor_entry: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The user's Range Oracle risk entry, checked by CompliFi
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The user's Switchboard risk feed, checked by CompliFi
+    pub risk_feed: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The Switchboard aggregator registered in `risk_feed`, checked by CompliFi
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    pub complifi_program: Program<'info, Complifi>,
+}
+
+#[derive(Accounts)]
+pub struct VerifiedAction<'info> {
+    #[account(mut)]
+    pub complifi_state: Account<'info, ComplianceState>,
+    
+    #[account(mut)]
+    pub policy: Account<'info, CompliancePolicy>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: The wallet being verified, checked by CompliFi
+    pub user: UncheckedAccount<'info>,
+    
+    /// CHECK: The authority whose attestation of `user` is read, checked by CompliFi
+    pub attestation_authority: UncheckedAccount<'info>,
+    
+    pub attestation: Account<'info, KycAttestation>,
+    
+    /// CHECK: The user's sanction entry address, checked by CompliFi
+    pub sanction: UncheckedAccount<'info>,
+    
+    /// CHECK: The user's Range Oracle risk entry, checked by CompliFi
+    pub range_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The user's Switchboard risk feed, checked by CompliFi
+    pub risk_feed: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The Switchboard aggregator registered in `risk_feed`, checked by CompliFi
+    pub switchboard_aggregator: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: CompliFi's event authority PDA, checked by CompliFi
+    pub complifi_event_authority: UncheckedAccount<'info>,
+    
+    pub complifi_program: Program<'info, Complifi>,
+}
+
+#[derive(Accounts)]
+pub struct GatedTarget {}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct ReceiptGatedAction<'info> {
+    #[account(seeds = [COMPLIANCE_RECEIPT_SEED, user.as_ref()], bump, seeds::program = complifi::ID)]
+    pub receipt: Account<'info, ComplianceReceipt>,
+}
+
+#[derive(Accounts)]
+pub struct IssueAttestation<'info> {
+    /// CHECK: The wallet's attestation PDA, created and checked by CompliFi
+    #[account(mut)]
+    pub attestation: UncheckedAccount<'info>,
+    
+    /// The attestor authority CompliFi records; it signs through this program and pays the rent
+    #[account(mut, seeds = [ISSUER_SEED], bump)]
+    pub issuer: SystemAccount<'info>,
+    
+    /// CHECK: The issuer's CompliFi allowlist entry, checked by CompliFi
+    pub attestor_entry: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub complifi_state: Account<'info, ComplianceState>,
+    
+    /// CHECK: The wallet being attested
+    pub wallet: UncheckedAccount<'info>,
+    
+    /// CHECK: CompliFi's dashboard rollup, checked by CompliFi
+    #[account(mut)]
+    pub rollup: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: CompliFi's attestation journal, checked by CompliFi
+    #[account(mut)]
+    pub journal: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: CompliFi's event authority PDA, checked by CompliFi
+    pub complifi_event_authority: UncheckedAccount<'info>,
+    
+    pub complifi_program: Program<'info, Complifi>,
+    
+    pub system_program: Program<'info, System>,
+}
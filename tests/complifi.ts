@@ -1,133 +1,9180 @@
-/* eslint-env mocha */
-import * as anchor from '@coral-xyz/anchor';
-import { expect } from 'chai';
-import { Buffer } from 'buffer';
-
-// Use local provider (Anchor.toml -> Localnet)
-const provider = (anchor as any).AnchorProvider.env();
-(anchor as any).setProvider(provider);
-
-// Program handle (no generated types needed)
-const program = (anchor as any).workspace.Complifi as any;
-
-describe('complifi program', () => {
-	it('initializes compliance state', async () => {
-		const state = (anchor as any).web3.Keypair.generate();
-		const authority = (provider as any).wallet.publicKey;
-
-		await program.methods
-			.initialize()
-			.accounts({
-				state: state.publicKey,
-				authority,
-				systemProgram: (anchor as any).web3.SystemProgram.programId,
-			})
-			.signers([state])
-			.rpc();
-
-		const stateAccount = await program.account.complianceState.fetch(state.publicKey);
-		expect(stateAccount.authority.toString()).to.equal(authority.toString());
-		expect(stateAccount.verificationCount.toString()).to.equal('0');
-		expect(stateAccount.violationCount.toString()).to.equal('0');
-	});
-
-	it('verifies compliance and increments verification_count', async () => {
-		const state = (anchor as any).web3.Keypair.generate();
-		const authority = (provider as any).wallet.publicKey;
-
-		// init
-		await program.methods
-			.initialize()
-			.accounts({
-				state: state.publicKey,
-				authority,
-				systemProgram: (anchor as any).web3.SystemProgram.programId,
-			})
-			.signers([state])
-			.rpc();
-
-		// create policy
-		const policy = (anchor as any).web3.Keypair.generate();
-		await program.methods
-			.initializePolicy()
-			.accounts({
-				policy: policy.publicKey,
-				authority,
-				systemProgram: (anchor as any).web3.SystemProgram.programId,
-			})
-			.signers([policy])
-			.rpc();
-
-		// set policy to allow jurisdiction 0 and higher risk threshold
-		const allowed: number[] = new Array(10).fill(0);
-		allowed[0] = 1; // allow jurisdiction bit 0
-		await program.methods
-			.setPolicy(5, true, allowed as any)
-			.accounts({
-				policy: policy.publicKey,
-				authority,
-			})
-			.rpc();
-
-		// derive attestation PDA for authority wallet
-		const [attestationPda] = (anchor as any).web3.PublicKey.findProgramAddressSync(
-			[Buffer.from('kyc-attestation'), authority.toBuffer()],
-			program.programId,
-		);
-
-		// create KYC attestation for authority in jurisdiction 0
-		await program.methods
-			.createKycAttestation(authority, true, 0)
-			.accounts({
-				attestation: attestationPda,
-				authority,
-				state: state.publicKey,
-				wallet: authority,
-				systemProgram: (anchor as any).web3.SystemProgram.programId,
-			})
-			.rpc();
-
-		// verify compliance (uses UncheckedAccount for user)
-		await program.methods
-			.verifyCompliance(authority, 'swap')
-			.accounts({
-				state: state.publicKey,
-				policy: policy.publicKey,
-				authority,
-				user: authority,
-				attestation: attestationPda,
-			})
-			.rpc();
-
-		const stateAfter = await program.account.complianceState.fetch(state.publicKey);
-		expect(stateAfter.verificationCount.toString()).to.equal('1');
-	});
-
-	it('records a violation and increments violation_count', async () => {
-		const state = (anchor as any).web3.Keypair.generate();
-		const authority = (provider as any).wallet.publicKey;
-
-		// init
-		await program.methods
-			.initialize()
-			.accounts({
-				state: state.publicKey,
-				authority,
-				systemProgram: (anchor as any).web3.SystemProgram.programId,
-			})
-			.signers([state])
-			.rpc();
-
-		// record violation
-		await program.methods
-			.recordViolation(authority, 'Risk score too high')
-			.accounts({
-				state: state.publicKey,
-			})
-			.rpc();
-
-		const stateAfter = await program.account.complianceState.fetch(state.publicKey);
-		expect(stateAfter.violationCount.toString()).to.equal('1');
-	});
-});
-
+/* eslint-env mocha */
+import * as anchor from '@coral-xyz/anchor';
+import { expect } from 'chai';
+import { Buffer } from 'buffer';
+import { createHash } from 'crypto';
+import { readFileSync } from 'fs';
+
+// Use local provider (Anchor.toml -> Localnet)
+const provider = (anchor as any).AnchorProvider.env();
+(anchor as any).setProvider(provider);
+
+// Program handle (no generated types needed)
+const program = (anchor as any).workspace.Complifi as any;
+
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+// Current cluster time in unix seconds
+async function chainNow(): Promise<number> {
+	const slot = await provider.connection.getSlot('confirmed');
+	return (await provider.connection.getBlockTime(slot)) as number;
+}
+
+// PDA that signs the program's self-CPI (emit_cpi!) events
+const EVENT_AUTHORITY = (anchor as any).web3.PublicKey.findProgramAddressSync(
+	[Buffer.from('__event_authority')],
+	program.programId,
+)[0];
+
+// Decode the events the program emitted by self-CPI in a confirmed transaction
+async function cpiEventsOf(signature: string): Promise<any[]> {
+	const tx = await provider.connection.getTransaction(signature, {
+		commitment: 'confirmed',
+		maxSupportedTransactionVersion: 0,
+	});
+	const keys = tx.transaction.message.staticAccountKeys;
+	const bs58 = (anchor as any).utils.bytes.bs58;
+	return tx.meta.innerInstructions
+		.flatMap((inner: any) => inner.instructions)
+		.filter((ix: any) => keys[ix.programIdIndex].equals(program.programId))
+		.map((ix: any) => Buffer.from(bs58.decode(ix.data)))
+		// Event instructions lead with Anchor's 8-byte event tag, ahead of the event itself
+		.map((data: Buffer) => program.coder.events.decode(data.subarray(8).toString('base64')))
+		.filter((event: any) => event);
+}
+
+// Decode the Anchor events emitted by a confirmed transaction
+async function eventsOf(signature: string): Promise<any[]> {
+	const tx = await provider.connection.getTransaction(signature, {
+		commitment: 'confirmed',
+		maxSupportedTransactionVersion: 0,
+	});
+	return eventsInLogs(tx.meta.logMessages);
+}
+
+// Decode the Anchor events in a list of program logs, such as a failed transaction's
+function eventsInLogs(logs: string[]): any[] {
+	const parser = new (anchor as any).EventParser(
+		program.programId,
+		new (anchor as any).BorshCoder(program.idl),
+	);
+	return Array.from(parser.parseLogs(logs));
+}
+
+// The program's singleton compliance state, its dashboard rollup and its attestation
+// journal. Every test shares them, so counters are asserted as deltas and global settings
+// are restored afterwards.
+const STATE = (anchor as any).web3.PublicKey.findProgramAddressSync(
+	[Buffer.from('compliance-state')],
+	program.programId,
+)[0];
+const ROLLUP = (anchor as any).web3.PublicKey.findProgramAddressSync(
+	[Buffer.from('rollup'), STATE.toBuffer()],
+	program.programId,
+)[0];
+const JOURNAL = (anchor as any).web3.PublicKey.findProgramAddressSync(
+	[Buffer.from('attestation-journal'), STATE.toBuffer()],
+	program.programId,
+)[0];
+
+// The singleton compliance state, owned by the provider wallet. Created with its rollup and
+// journal on first use; both must exist before any attestation so the rollup's counts stay
+// exact and the journal holds every attestation's history. The provider wallet is also
+// allowlisted as the attestor the tests issue attestations with.
+async function initState(): Promise<any> {
+	const authority = (provider as any).wallet.publicKey;
+	const systemProgram = (anchor as any).web3.SystemProgram.programId;
+	if (!(await provider.connection.getAccountInfo(STATE))) {
+		await program.methods.initialize().accounts({ state: STATE, authority, systemProgram }).rpc();
+	}
+	if (!(await provider.connection.getAccountInfo(ROLLUP))) {
+		await program.methods.initRollup().accounts({ state: STATE, rollup: ROLLUP, authority, systemProgram }).rpc();
+	}
+	if (!(await provider.connection.getAccountInfo(JOURNAL))) {
+		await program.methods
+			.initAttestationJournal()
+			.accounts({ state: STATE, journal: JOURNAL, authority, systemProgram })
+			.rpc();
+	}
+	if (!(await provider.connection.getAccountInfo(attestorPdaFor(authority)))) {
+		await program.methods
+			.addAttestor(authority)
+			.accounts({ state: STATE, attestorEntry: attestorPdaFor(authority), authority, systemProgram })
+			.rpc();
+	}
+	return STATE;
+}
+
+// Wallet covered by the mock Range Oracle entries in tests/fixtures (see Anchor.toml)
+const ORACLE_FIXTURE_USER = new (anchor as any).web3.PublicKey('R9E2G2kUwPzPVMsdHShs53rMNY338o3KLsQK9pEcWoU');
+const RANGE_ORACLES = {
+	a: new (anchor as any).web3.PublicKey('7DEQVCtdh5vpdCU1fCDGunR4ratkSMMBADdGrFHnXrWE'), // score 2
+	b: new (anchor as any).web3.PublicKey('GsgX5qxZHMi9xDT1AGhJ6aiZD9p5vMboziiRFwGdEZ6p'), // score 3
+	c: new (anchor as any).web3.PublicKey('6mmiX4KbAGENaXG3wk5RhqtJBHDGkEtdexgPw5Uyjzrm'), // score 9
+};
+// Mock Switchboard aggregators, registered in Anchor.toml, with their mapped risk scores
+const SWITCHBOARD_AGGREGATORS = {
+	low: new (anchor as any).web3.PublicKey('HY1HpRTEo2GgngDnLjgV2avgq7gKHYgocMBSNoXKJzyV'), // 2.0 -> score 2
+	high: new (anchor as any).web3.PublicKey('j6Z9Eng6371zgENGH2DBLnjiLvgcXGCyaNGhJoAW8Qt'), // 8.5 -> score 9
+};
+// Policy oracle kinds, mirroring lib.rs
+const ORACLE_KIND_RANGE = 0;
+const ORACLE_KIND_SWITCHBOARD = 1;
+// Risk model version every fixture oracle entry reports
+const FIXTURE_RISK_MODEL_VERSION = 3;
+// Unix time every fixture oracle entry was last updated at
+const FIXTURE_ORACLE_UPDATED_AT = 1_700_000_000;
+
+function policyPdaFor(name: string, authority: any = (provider as any).wallet.publicKey): any {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('policy'), authority.toBuffer(), Buffer.from(name)],
+		program.programId,
+	)[0];
+}
+
+function riskFeedPdaFor(wallet: any): any {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('risk-feed'), wallet.toBuffer()],
+		program.programId,
+	)[0];
+}
+
+// The aggregator each wallet's risk feed was last pointed at, keyed by wallet
+const riskFeedAggregators = new Map<string, any>();
+
+// Point `wallet`'s risk feed at a mock Switchboard aggregator
+async function setRiskFeed(state: any, wallet: any, aggregator: any = SWITCHBOARD_AGGREGATORS.low): Promise<void> {
+	await program.methods
+		.setRiskFeed(wallet, aggregator)
+		.accounts({
+			riskFeed: riskFeedPdaFor(wallet),
+			state,
+			authority: (provider as any).wallet.publicKey,
+			systemProgram: (anchor as any).web3.SystemProgram.programId,
+		})
+		.rpc();
+	riskFeedAggregators.set(wallet.toString(), aggregator);
+}
+
+// Oracle accounts scoring `subject` under a Switchboard policy through its registered feed,
+// or none when no feed was registered for it
+function riskOracleAccounts(subject: any): any {
+	const aggregator = riskFeedAggregators.get(subject.toString());
+	if (!aggregator) {
+		return { rangeOracle: null, riskFeed: null, switchboardAggregator: null };
+	}
+	return { rangeOracle: null, riskFeed: riskFeedPdaFor(subject), switchboardAggregator: aggregator };
+}
+
+// A member's group of remaining accounts for the batch and multisig checks: its attestation,
+// then its risk feed and aggregator. An unregistered member's feed address is passed anyway.
+function memberAccounts(member: any): any[] {
+	const aggregator = riskFeedAggregators.get(member.toString()) ?? SWITCHBOARD_AGGREGATORS.low;
+	return [attestationPdaFor(member), riskFeedPdaFor(member), aggregator].map((pubkey) => ({
+		pubkey,
+		isSigner: false,
+		isWritable: false,
+	}));
+}
+
+// Policies are named per authority, so each test policy gets a fresh name
+let policyNameCounter = 0;
+function nextPolicyName(): string {
+	return `test-policy-${policyNameCounter++}`;
+}
+
+// Create a policy allowing jurisdiction 0 with the given risk threshold. It reads risk from
+// Switchboard, so any wallet attest() registered a feed for scores 2.
+async function initPolicy(maxRiskScore = 5, name = nextPolicyName()): Promise<any> {
+	const authority = (provider as any).wallet.publicKey;
+	const policy = policyPdaFor(name);
+	await program.methods
+		.initializePolicy(name, [], 0, null, null)
+		.accounts({
+			policy,
+			authority,
+			state: null,
+			systemProgram: (anchor as any).web3.SystemProgram.programId,
+		})
+		.rpc();
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	await program.methods
+		.setPolicy(maxRiskScore, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+		.accounts({ policy, authority })
+		.rpc();
+	await program.methods.setOracleKind(ORACLE_KIND_SWITCHBOARD).accounts({ policy, authority }).rpc();
+	return policy;
+}
+
+// Attest `wallet` as verified in jurisdiction 0, registering the low-risk feed for it,
+// and return its attestation PDA
+async function attest(state: any, wallet: any, expiresAt = 0): Promise<any> {
+	const attestation = attestationPdaFor(wallet);
+	await program.methods
+		.createKycAttestation(wallet, true, 0, new (anchor as any).BN(expiresAt), null, null, 0, 0)
+		.accounts({
+			attestation,
+			authority: (provider as any).wallet.publicKey,
+			attestorEntry: attestorPdaFor((provider as any).wallet.publicKey),
+			state,
+			wallet,
+			timeOracle: null,
+			vendor: null,
+			instructions: null,
+			policy: null,
+			jurisdictionMeta: null,
+			rollup: ROLLUP,
+			journal: JOURNAL,
+			kycRequest: null,
+			systemProgram: (anchor as any).web3.SystemProgram.programId,
+		})
+		.rpc();
+	await setRiskFeed(state, wallet);
+	return attestation;
+}
+
+// Counterparty argument for verifications that are not scoped to one
+const NO_COUNTERPARTY = (anchor as any).web3.PublicKey.default;
+
+// Effective-window bound leaving a policy active on that side
+const UNBOUNDED = new (anchor as any).BN(0);
+
+// record_violation categories
+const VIOLATION_CATEGORY_RISK = 0;
+const VIOLATION_CATEGORY_JURISDICTION = 1;
+const VIOLATION_CATEGORY_SANCTION = 2;
+const VIOLATION_CATEGORY_KYC = 3;
+
+// Reverification interval that leaves verifications unthrottled
+const NO_COOLDOWN = new (anchor as any).BN(0);
+
+// Oracle staleness limit that accepts entries of any age
+const ANY_AGE = new (anchor as any).BN(0);
+
+// Minimum attestation age that lets attestations count as soon as they are issued
+const NO_MIN_AGE = new (anchor as any).BN(0);
+
+// Per-transaction amount cap that leaves amounts unlimited
+const NO_MAX_AMOUNT = new (anchor as any).BN(0);
+
+// Reason code of a CompliFiError, as returned by simulate_compliance
+const reasonCode = (name: string) => program.idl.errors.find((e: any) => e.name === name).code - 6000;
+
+// KycAttestation flag bits, mirroring state.rs
+const ATTESTATION_FLAG_VERIFIED = 1 << 0;
+const ATTESTATION_FLAG_AUTO_RENEW = 1 << 1;
+const ATTESTATION_FLAG_OWNERSHIP_PROVEN = 1 << 2;
+const ATTESTATION_FLAG_FROZEN = 1 << 3;
+
+// Accounts for verifyCompliance, with optional accounts defaulting to absent and the risk
+// oracle to the user's registered Switchboard feed, if any
+function verifyAccounts(accounts: any): any {
+	return {
+		// Reads the attestation the provider wallet issued unless a test picks another authority
+		attestationAuthority: (provider as any).wallet.publicKey,
+		integrator: null,
+		delegation: null,
+		entity: null,
+		attestorEntry: null,
+		userRecord: null,
+		report: null,
+		compiledPolicy: null,
+		rolloutBaseline: null,
+		jurisdictionLimit: null,
+		...(accounts.user ? riskOracleAccounts(accounts.user) : { rangeOracle: null, riskFeed: null, switchboardAggregator: null }),
+		receipt: null,
+		complianceReceipt: null,
+		feeRecipient: null,
+		feeLedger: null,
+		sanction: accounts.user ? sanctionPdaFor(accounts.user) : null,
+		certificate: null,
+		certificateMint: null,
+		certificateTokenAccount: null,
+		tokenProgram: null,
+		associatedTokenProgram: null,
+		// Needed whenever a user record is passed, which is created on first use
+		systemProgram: (anchor as any).web3.SystemProgram.programId,
+		...accounts,
+	};
+}
+
+// Replays the program's result digest: user || u32 LE action length || action || result || u64 LE slot || policy_hash
+function verificationDigest(user: any, action: string, slot: number, policyData: Buffer): Buffer {
+	const actionLen = Buffer.alloc(4);
+	actionLen.writeUInt32LE(action.length);
+	const slotBytes = Buffer.alloc(8);
+	slotBytes.writeBigUInt64LE(BigInt(slot));
+	const policyHash = createHash('sha256').update(policyData.subarray(8)).digest();
+	return createHash('sha256')
+		.update(Buffer.concat([user.toBuffer(), actionLen, Buffer.from(action), Buffer.from([1]), slotBytes, policyHash]))
+		.digest();
+}
+
+function userRecordPdaFor(user: any): any {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('user-record'), user.toBuffer()],
+		program.programId,
+	)[0];
+}
+
+function userViolationsPdaFor(user: any): any {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('user-violations'), user.toBuffer()],
+		program.programId,
+	)[0];
+}
+
+function attestorPdaFor(attestor: any): any {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('attestor'), attestor.toBuffer()],
+		program.programId,
+	)[0];
+}
+
+function sanctionPdaFor(wallet: any): any {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('sanction'), wallet.toBuffer()],
+		program.programId,
+	)[0];
+}
+
+// Attestations live under their issuing authority, which defaults to the provider wallet
+function attestationPdaFor(wallet: any, authority: any = (provider as any).wallet.publicKey): any {
+	return attestationPdaAndBumpFor(wallet, authority)[0];
+}
+
+function attestationPdaAndBumpFor(wallet: any, authority: any = (provider as any).wallet.publicKey): [any, number] {
+	return (anchor as any).web3.PublicKey.findProgramAddressSync(
+		[Buffer.from('kyc-attestation'), authority.toBuffer(), wallet.toBuffer()],
+		program.programId,
+	);
+}
+
+describe('complifi program', () => {
+	it('initializes compliance state', async () => {
+		const authority = (provider as any).wallet.publicKey;
+
+		// The first test to run creates the singleton
+		await program.methods
+			.initialize()
+			.accounts({
+				state: STATE,
+				authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		const stateAccount = await program.account.complianceState.fetch(STATE);
+		expect(stateAccount.authority.toString()).to.equal(authority.toString());
+		expect(stateAccount.verificationCount.toString()).to.equal('0');
+		expect(stateAccount.violationCount.toString()).to.equal('0');
+	});
+
+	it('rejects re-initializing the singleton state', async () => {
+		try {
+			await program.methods
+				.initialize()
+				.accounts({
+					state: STATE,
+					authority: (provider as any).wallet.publicKey,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+			expect.fail('expected the state PDA to already exist');
+		} catch (err: any) {
+			expect(err.logs.join('\n')).to.include('already in use');
+		}
+	});
+
+	it('verifies compliance and increments verification_count', async () => {
+		const state = await initState();
+		const authority = (provider as any).wallet.publicKey;
+		const { verificationCount } = await program.account.complianceState.fetch(state);
+
+		// create policy
+		const name = nextPolicyName();
+		const policy = policyPdaFor(name);
+		await program.methods
+			.initializePolicy(name, [], 0, null, null)
+			.accounts({
+				policy,
+				authority,
+				state: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await program.methods.setOracleKind(ORACLE_KIND_SWITCHBOARD).accounts({ policy, authority }).rpc();
+
+		// set policy to allow jurisdiction 0 and higher risk threshold
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1; // allow jurisdiction bit 0
+		await program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({
+				policy,
+				authority,
+			})
+			.rpc();
+
+		// derive attestation PDA for authority wallet
+		const [attestationPda] = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('kyc-attestation'), authority.toBuffer()],
+			program.programId,
+		);
+
+		// create KYC attestation for authority in jurisdiction 0
+		await program.methods
+			.createKycAttestation(authority, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPda,
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: authority,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, authority);
+
+		// verify compliance (uses UncheckedAccount for user)
+		await program.methods
+			.verifyCompliance(authority, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user: authority,
+					attestation: attestationPda,
+				}),
+			)
+			.rpc();
+
+		const stateAfter = await program.account.complianceState.fetch(state);
+		expect(stateAfter.verificationCount.toNumber()).to.equal(verificationCount.toNumber() + 1);
+	});
+
+	it('records a violation and increments violation_count', async () => {
+		const state = await initState();
+		const authority = (provider as any).wallet.publicKey;
+		const { violationCount } = await program.account.complianceState.fetch(state);
+
+		// record violation
+		await program.methods
+			.recordViolation(authority, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				report: null,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(authority),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		const stateAfter = await program.account.complianceState.fetch(state);
+		expect(stateAfter.violationCount.toNumber()).to.equal(violationCount.toNumber() + 1);
+	});
+});
+
+describe('expiry reminders', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	after(async () => {
+		await program.methods
+			.setExpiryReminderThresholds([50, 80, 95, 0])
+			.accounts({ state: await initState(), authority })
+			.rpc();
+	});
+
+	// Attest a fresh wallet for `lifetime` seconds under thresholds of 50% and 80%, and return a crank for it
+	const attestWithLifetime = async (lifetime: number) => {
+		const state = await initState();
+		await program.methods
+			.setExpiryReminderThresholds([50, 80, 0, 0])
+			.accounts({ state, authority })
+			.rpc();
+
+		const owner = (anchor as any).web3.Keypair.generate();
+		const wallet = owner.publicKey;
+		const attestation = attestationPdaFor(wallet);
+		await program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN((await chainNow()) + lifetime), null, null, 0, 0)
+			.accounts({
+				attestation,
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, wallet);
+
+		const crank = async () => {
+			const sig = await program.methods
+				.emitExpiryReminders(wallet)
+				.accounts({ state, attestation })
+				.rpc({ commitment: 'confirmed' });
+			return (await eventsOf(sig)).filter((e) => e.name === 'kycExpiryReminderEvent');
+		};
+		return { owner, attestation, crank };
+	};
+
+	it('fires each lifetime threshold reminder exactly once', async () => {
+		const { attestation, crank } = await attestWithLifetime(10);
+
+		// Nothing is due at creation
+		expect(await crank()).to.have.length(0);
+
+		await sleep(6_000);
+		const first = await crank();
+		expect(first).to.have.length(1);
+		expect(first[0].data.threshold).to.equal(50);
+		expect(first[0].data.pctElapsed).to.be.gte(50);
+		expect(await crank()).to.have.length(0);
+
+		await sleep(3_000);
+		const second = await crank();
+		expect(second).to.have.length(1);
+		expect(second[0].data.threshold).to.equal(80);
+		expect(second[0].data.pctElapsed).to.be.gte(80);
+		expect(await crank()).to.have.length(0);
+
+		const account = await program.account.kycAttestation.fetch(attestation);
+		expect(account.remindersFired).to.equal(0b11);
+	});
+
+	it('fires every threshold a late crank jumped past', async () => {
+		const { attestation, crank } = await attestWithLifetime(10);
+		await sleep(9_000);
+		const reminders = await crank();
+		expect(reminders.map((e) => e.data.threshold)).to.deep.equal([50, 80]);
+		expect(await crank()).to.have.length(0);
+		expect((await program.account.kycAttestation.fetch(attestation)).remindersFired).to.equal(0b11);
+	});
+
+	it('measures reminders after a renewal from the renewed period', async () => {
+		const { owner, attestation, crank } = await attestWithLifetime(10);
+		const state = await initState();
+		const policy = await initPolicy();
+		await program.methods
+			.setAutoRenew(true)
+			.accounts({ attestation, wallet: owner.publicKey })
+			.signers([owner])
+			.rpc();
+		await sleep(9_000);
+		expect((await crank()).map((e) => e.data.threshold)).to.deep.equal([50, 80]);
+
+		await program.methods
+			.renewAttestation(owner.publicKey)
+			.accounts({
+				state,
+				attestation,
+				policy,
+				renewer: authority,
+				feeRecipient: authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		// The renewed period has barely begun, so nothing is due yet
+		expect(await crank()).to.have.length(0);
+
+		await sleep(6_000);
+		const reminders = await crank();
+		expect(reminders.map((e) => e.data.threshold)).to.deep.equal([50]);
+		expect(reminders[0].data.pctElapsed).to.be.lt(80);
+	});
+
+	it('rejects non-ascending thresholds', async () => {
+		const state = await initState();
+		try {
+			await program.methods
+				.setExpiryReminderThresholds([80, 50, 0, 0])
+				.accounts({ state, authority })
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('oracle quorum', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = ORACLE_FIXTURE_USER;
+	const attestation = attestationPdaFor(user);
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		const name = nextPolicyName();
+		policy = policyPdaFor(name);
+		await program.methods
+			.initializePolicy(name, [], 0, null, null)
+			.accounts({ policy, authority, state: null, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		await program.methods.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+		await program.methods.setOracleQuorum(2).accounts({ policy, authority }).rpc();
+		await program.methods
+			.createKycAttestation(user, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation,
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+	});
+
+	const verifyWith = (oracles: any[]) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.remainingAccounts(oracles.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+			.rpc({ commitment: 'confirmed' });
+
+	it('passes when two of three oracles agree the score is within tolerance', async () => {
+		const sig = await verifyWith([RANGE_ORACLES.a, RANGE_ORACLES.b, RANGE_ORACLES.c]);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+		expect(event.data.riskScore).to.equal(3);
+	});
+
+	it('fails with OracleDataFetchFailed when only one oracle responds', async () => {
+		const missing = [(anchor as any).web3.Keypair.generate().publicKey, (anchor as any).web3.Keypair.generate().publicKey];
+		try {
+			await verifyWith([RANGE_ORACLES.a, ...missing]);
+			expect.fail('expected OracleDataFetchFailed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OracleDataFetchFailed');
+		}
+	});
+
+	it('does not count a duplicated oracle towards the quorum', async () => {
+		try {
+			await verifyWith([RANGE_ORACLES.a, RANGE_ORACLES.a]);
+			expect.fail('expected OracleDataFetchFailed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OracleDataFetchFailed');
+		}
+	});
+
+	it('does not count a stale oracle on the current model towards the quorum', async () => {
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		const setStaleness = (maxOracleStaleness: any) =>
+			program.methods
+				.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, maxOracleStaleness, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+				.accounts({ policy, authority })
+				.rpc();
+		await program.methods.setMinRiskModelVersion(FIXTURE_RISK_MODEL_VERSION).accounts({ policy, authority }).rpc();
+
+		// Every fixture entry runs the current model but was last updated long before the limit
+		const fixtureAge = (await chainNow()) - FIXTURE_ORACLE_UPDATED_AT;
+		await setStaleness(new (anchor as any).BN(fixtureAge - 60));
+		try {
+			await verifyWith([RANGE_ORACLES.a, RANGE_ORACLES.b, RANGE_ORACLES.c]);
+			expect.fail('expected OracleDataFetchFailed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OracleDataFetchFailed');
+		} finally {
+			await setStaleness(ANY_AGE);
+		}
+	});
+});
+
+describe('behavioral risk', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		await program.methods.setRiskMonitor(authority).accounts({ state, authority }).rpc();
+	});
+
+	const reportRisk = (user: any, score: number) =>
+		program.methods
+			.updateBehavioralRisk(user, score)
+			.accounts({
+				state,
+				userRecord: userRecordPdaFor(user),
+				attestation: attestationPdaFor(user),
+				monitor: authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const verify = (user: any, attestation: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation, userRecord: userRecordPdaFor(user) }))
+			.rpc();
+
+	it('fails a user with high behavioral risk despite a clean oracle score', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		await reportRisk(user, 9);
+
+		try {
+			await verify(user, attestation);
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+
+	it('rejects a high behavioral risk user who omits their record', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		await reportRisk(user, 9);
+
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation, userRecord: null }))
+				.rpc();
+			expect.fail('expected UserRecordRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UserRecordRequired');
+		}
+	});
+
+	it('blends behavioral risk in weighted mode', async () => {
+		const weighted = await initPolicy(5);
+		await program.methods.setBehavioralRiskMode(1, 2500).accounts({ policy: weighted, authority }).rpc();
+
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		await reportRisk(user, 9);
+
+		// 0.75 * 2 (oracle) + 0.25 * 9 (behavioral) = 3.75, rounded up to 4
+		await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy: weighted, authority, user, attestation, userRecord: userRecordPdaFor(user) }))
+			.rpc();
+	});
+
+	it('rejects reports from an unregistered monitor', async () => {
+		const impostor = (anchor as any).web3.Keypair.generate();
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const sig = await provider.connection.requestAirdrop(impostor.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+
+		try {
+			await program.methods
+				.updateBehavioralRisk(user, 1)
+				.accounts({
+					state,
+					userRecord: userRecordPdaFor(user),
+					attestation: attestationPdaFor(user),
+					monitor: impostor.publicKey,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.signers([impostor])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('per-call risk override', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+	});
+
+	const verify = (overrideMaxRisk: number | null) =>
+		program.methods
+			.verifyCompliance(user, 'withdraw', overrideMaxRisk, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc({ commitment: 'confirmed' });
+
+	it('applies a stricter override for a single call and flags it in the event', async () => {
+		// Stubbed oracle score is 2: an override of 1 rejects, 3 passes and is marked overridden
+		try {
+			await verify(1);
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+
+		const sig = await verify(3);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+		expect(event.data.overridden).to.equal(true);
+
+		const policyAccount = await program.account.compliancePolicy.fetch(policy);
+		expect(policyAccount.maxRiskScore).to.equal(5);
+	});
+
+	it('rejects an override that would loosen the policy', async () => {
+		try {
+			await verify(8);
+			expect.fail('expected OverrideLoosensPolicy');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OverrideLoosensPolicy');
+		}
+	});
+
+	it('does not flag calls without an override', async () => {
+		const sig = await verify(null);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+		expect(event.data.overridden).to.equal(false);
+	});
+});
+
+describe('compliance reports', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	it('accumulates period activity and seals it with a hash', async () => {
+		const state = await initState();
+		const policy = await initPolicy(5);
+		const periodId = new (anchor as any).BN(1);
+		const [report] = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('report'), state.toBuffer(), periodId.toArrayLike(Buffer, 'le', 8)],
+			program.programId,
+		);
+
+		await program.methods
+			.beginReport(periodId)
+			.accounts({ state, report, authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+
+		for (let i = 0; i < 2; i++) {
+			const user = (anchor as any).web3.Keypair.generate().publicKey;
+			const attestation = await attest(state, user);
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation, report }))
+				.rpc();
+		}
+		await program.methods
+			.recordViolation(authority, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				report,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(authority),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		await program.methods.finalizeReport().accounts({ state, report, authority }).rpc();
+
+		const sealed = await program.account.complianceReport.fetch(report);
+		expect(sealed.verificationCount.toString()).to.equal('2');
+		expect(sealed.violationCount.toString()).to.equal('1');
+		expect(sealed.jurisdictionCounts[0]).to.equal(2);
+
+		const u64 = (value: any) => new (anchor as any).BN(value.toString()).toArrayLike(Buffer, 'le', 8);
+		const counts = Buffer.alloc(4 * 256);
+		sealed.jurisdictionCounts.forEach((count: number, i: number) => counts.writeUInt32LE(count, i * 4));
+		const expected = createHash('sha256')
+			.update(Buffer.concat([
+				u64(sealed.periodId),
+				u64(sealed.startedAt),
+				u64(sealed.finalizedAt),
+				u64(sealed.verificationCount),
+				u64(sealed.violationCount),
+				counts,
+			]))
+			.digest();
+		expect(Buffer.from(sealed.reportHash).equals(expected)).to.equal(true);
+
+		const stateAccount = await program.account.complianceState.fetch(state);
+		expect(stateAccount.activeReport.toString()).to.equal((anchor as any).web3.PublicKey.default.toString());
+	});
+
+	it('rejects activity that omits the open report', async () => {
+		const state = await initState();
+		const periodId = new (anchor as any).BN(7);
+		const [report] = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('report'), state.toBuffer(), periodId.toArrayLike(Buffer, 'le', 8)],
+			program.programId,
+		);
+		await program.methods
+			.beginReport(periodId)
+			.accounts({ state, report, authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+
+		try {
+			await program.methods
+				.recordViolation(authority, 'late', VIOLATION_CATEGORY_KYC)
+				.accounts({
+					state,
+					report: null,
+					rollup: ROLLUP,
+					userRecord: null,
+					policy: null,
+					userViolations: userViolationsPdaFor(authority),
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+			expect.fail('expected ReportNotActive');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ReportNotActive');
+		}
+		await program.methods.finalizeReport().accounts({ state, report, authority }).rpc();
+	});
+});
+
+describe('attestation auto-renewal', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	const renew = (wallet: any, attestation: any, feeRecipient: any = authority) =>
+		program.methods
+			.renewAttestation(wallet)
+			.accounts({
+				state,
+				attestation,
+				policy,
+				renewer: authority,
+				feeRecipient,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	it('extends an opted-in attestation by its original validity', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate();
+		const attestation = await attest(state, wallet.publicKey, (await chainNow()) + 3600);
+		const before = await program.account.kycAttestation.fetch(attestation);
+
+		await program.methods
+			.setAutoRenew(true)
+			.accounts({ attestation, wallet: wallet.publicKey })
+			.signers([wallet])
+			.rpc();
+		await renew(wallet.publicKey, attestation);
+
+		const after = await program.account.kycAttestation.fetch(attestation);
+		expect(after.renewCount).to.equal(1);
+		expect(after.expiresAt.sub(before.expiresAt).toString()).to.equal(before.validityPeriod.toString());
+	});
+
+	it('rejects renewal when auto-renew is off', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, wallet, (await chainNow()) + 3600);
+		try {
+			await renew(wallet, attestation);
+			expect.fail('expected AutoRenewDisabled');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AutoRenewDisabled');
+		}
+	});
+});
+
+describe('multisig verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const multisig = (anchor as any).web3.Keypair.generate().publicKey;
+	let state: any;
+	let policy: any;
+	let members: any[];
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		members = [0, 1, 2].map(() => (anchor as any).web3.Keypair.generate().publicKey);
+		// The last member never gets attested
+		await attest(state, members[0]);
+		await attest(state, members[1]);
+	});
+
+	const verifyMultisig = () =>
+		program.methods
+			.verifyComplianceMultisig(multisig, members)
+			.accounts({ state, policy, authority, report: null })
+			.remainingAccounts(members.flatMap(memberAccounts))
+			.rpc({ commitment: 'confirmed' });
+
+	it('fails a require-all check when one member is not compliant', async () => {
+		try {
+			await verifyMultisig();
+			expect.fail('expected MultisigNotCompliant');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('MultisigNotCompliant');
+			expect(err.logs.join('\n')).to.include(members[2].toString());
+		}
+	});
+
+	it('passes a 2-of-3 threshold and reports the failed member', async () => {
+		await program.methods.setMultisigThreshold(2).accounts({ policy, authority }).rpc();
+
+		const sig = await verifyMultisig();
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'multisigVerificationEvent');
+		expect(event.data.verified).to.equal(true);
+		expect(event.data.compliantMembers).to.equal(2);
+		expect(event.data.failedMembers.map((m: any) => m.toString())).to.deep.equal([members[2].toString()]);
+	});
+});
+
+describe('emergency verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	it('records a forced pass separately from normal verifications', async () => {
+		const state = await initState();
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const counts = await program.account.complianceState.fetch(state);
+
+		const sig = await program.methods
+			.emergencyVerify(user, 'withdraw', 'Oracle outage during incident #12')
+			.accounts({ state, authority })
+			.rpc({ commitment: 'confirmed' });
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'emergencyVerificationEvent');
+		expect(event.data.user.toString()).to.equal(user.toString());
+		expect(event.data.reason).to.equal('Oracle outage during incident #12');
+
+		const stateAccount = await program.account.complianceState.fetch(state);
+		expect(stateAccount.emergencyCount.toNumber()).to.equal(counts.emergencyCount.toNumber() + 1);
+		expect(stateAccount.verificationCount.toNumber()).to.equal(counts.verificationCount.toNumber());
+	});
+
+	it('requires a reason', async () => {
+		const state = await initState();
+		try {
+			await program.methods
+				.emergencyVerify(authority, 'withdraw', '  ')
+				.accounts({ state, authority })
+				.rpc();
+			expect.fail('expected EmergencyReasonRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('EmergencyReasonRequired');
+		}
+	});
+});
+
+describe('time oracle timestamps', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const timeOracle = (anchor as any).web3.Keypair.generate();
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+		await program.methods
+			.setTimeOracle(timeOracle.publicKey, new (anchor as any).BN(30))
+			.accounts({ state, authority })
+			.rpc();
+	});
+
+	const attestAt = (wallet: any, timestamp: number) =>
+		program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), new (anchor as any).BN(timestamp), null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: timeOracle.publicKey,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.signers([timeOracle])
+			.rpc();
+
+	it('uses a time oracle timestamp within tolerance', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const timestamp = (await chainNow()) - 10;
+		await attestAt(wallet, timestamp);
+
+		const account = await program.account.kycAttestation.fetch(attestationPdaFor(wallet));
+		expect(account.timestamp.toNumber()).to.equal(timestamp);
+	});
+
+	it('rejects a backdated timestamp outside tolerance', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		try {
+			await attestAt(wallet, (await chainNow()) - 3600);
+			expect.fail('expected TimestampOutOfTolerance');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('TimestampOutOfTolerance');
+		}
+	});
+});
+
+describe('bridged attestations', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const relayer = (anchor as any).web3.Keypair.generate();
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+		await program.methods.setBridgeRelayer(relayer.publicKey).accounts({ state, authority }).rpc();
+	});
+
+	// Borsh layout of BridgedKycMessage
+	const encodeMessage = (msg: any) => {
+		const buf = Buffer.alloc(2 + 8 + 32 + 1 + 1 + 8);
+		buf.writeUInt16LE(msg.sourceChain, 0);
+		buf.writeBigUInt64LE(BigInt(msg.sequence.toString()), 2);
+		msg.wallet.toBuffer().copy(buf, 10);
+		buf.writeUInt8(msg.isVerified ? 1 : 0, 42);
+		buf.writeUInt8(msg.jurisdiction, 43);
+		buf.writeBigInt64LE(BigInt(msg.expiresAt.toString()), 44);
+		return buf;
+	};
+
+	const submit = (msg: any) => {
+		const [messageReceipt] = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[
+				Buffer.from('bridge-msg'),
+				new (anchor as any).BN(msg.sourceChain).toArrayLike(Buffer, 'le', 2),
+				msg.sequence.toArrayLike(Buffer, 'le', 8),
+			],
+			program.programId,
+		);
+		const ed25519Ix = (anchor as any).web3.Ed25519Program.createInstructionWithPrivateKey({
+			privateKey: relayer.secretKey,
+			message: encodeMessage(msg),
+		});
+		return program.methods
+			.createBridgedAttestation(msg)
+			.accounts({
+				state,
+				attestation: attestationPdaFor(msg.wallet, relayer.publicKey),
+				messageReceipt,
+				payer: authority,
+				instructions: (anchor as any).web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.preInstructions([ed25519Ix])
+			.rpc();
+	};
+
+	it('creates an attestation from a relayer-signed message and rejects its replay', async () => {
+		const msg = {
+			sourceChain: 2,
+			sequence: new (anchor as any).BN(42),
+			wallet: (anchor as any).web3.Keypair.generate().publicKey,
+			isVerified: true,
+			jurisdiction: 0,
+			expiresAt: new (anchor as any).BN(0),
+		};
+		await submit(msg);
+
+		const account = await program.account.kycAttestation.fetch(attestationPdaFor(msg.wallet, relayer.publicKey));
+		expect(account.sourceChain).to.equal(2);
+		expect(account.authority.toString()).to.equal(relayer.publicKey.toString());
+
+		try {
+			await submit(msg);
+			expect.fail('expected BridgeMessageReplayed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('BridgeMessageReplayed');
+		}
+	});
+});
+
+describe('delegated verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	const principal = (anchor as any).web3.Keypair.generate();
+
+	const delegationPda = (delegate: any) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('delegation'), principal.publicKey.toBuffer(), delegate.toBuffer()],
+			program.programId,
+		)[0];
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		await attest(state, principal.publicKey);
+		const sig = await provider.connection.requestAirdrop(principal.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+	});
+
+	const verifyAsDelegate = (delegate: any) =>
+		program.methods
+			.verifyCompliance(delegate, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user: delegate,
+					delegation: delegationPda(delegate),
+					attestation: attestationPdaFor(principal.publicKey),
+					// The principal's risk is scored, not the delegate's
+					...riskOracleAccounts(principal.publicKey),
+				}),
+			)
+			.rpc({ commitment: 'confirmed' });
+
+	it('passes an authorized delegate of a compliant principal', async () => {
+		const delegate = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.authorizeDelegate(delegate)
+			.accounts({
+				delegation: delegationPda(delegate),
+				principal: principal.publicKey,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.signers([principal])
+			.rpc();
+
+		const sig = await verifyAsDelegate(delegate);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+		expect(event.data.user.toString()).to.equal(delegate.toString());
+		expect(event.data.principal.toString()).to.equal(principal.publicKey.toString());
+	});
+
+	it('fails a delegate that was never authorized or has been revoked', async () => {
+		const stranger = (anchor as any).web3.Keypair.generate().publicKey;
+		try {
+			await verifyAsDelegate(stranger);
+			expect.fail('expected unauthorized delegate to fail');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AccountNotInitialized');
+		}
+
+		const delegate = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.authorizeDelegate(delegate)
+			.accounts({
+				delegation: delegationPda(delegate),
+				principal: principal.publicKey,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.signers([principal])
+			.rpc();
+		await program.methods
+			.revokeDelegate(delegate)
+			.accounts({ delegation: delegationPda(delegate), principal: principal.publicKey })
+			.signers([principal])
+			.rpc();
+		try {
+			await verifyAsDelegate(delegate);
+			expect.fail('expected revoked delegate to fail');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AccountNotInitialized');
+		}
+	});
+});
+
+describe('policy denomination', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	it('interprets the same raw amount according to policy decimals', async () => {
+		const state = await initState();
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		const mint = (anchor as any).web3.Keypair.generate().publicKey;
+		const raw = new (anchor as any).BN(5_000_000_000);
+
+		const scaledUnder = async (decimals: number) => {
+			const policy = await initPolicy(5);
+			await program.methods.setDenomination(mint, decimals).accounts({ policy, authority }).rpc();
+			const sig = await program.methods
+				.verifyCompliance(user, 'transfer', null, raw, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+				.rpc({ commitment: 'confirmed' });
+			const [event] = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+			expect(event.data.amount.toString()).to.equal(raw.toString());
+			return event.data.scaledAmount.toString();
+		};
+
+		expect(await scaledUnder(6)).to.equal('5000');
+		expect(await scaledUnder(9)).to.equal('5');
+	});
+
+	it('rejects decimals that overflow a u64 scale', async () => {
+		const policy = await initPolicy(5);
+		try {
+			await program.methods
+				.setDenomination((anchor as any).web3.PublicKey.default, 20)
+				.accounts({ policy, authority })
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('screening vendors', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	const vendorPda = (id: number) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync([Buffer.from('vendor'), Buffer.from([id])], program.programId)[0];
+
+	const registerVendor = async (state: any, id: number, name: string, key: any) =>
+		program.methods
+			.registerVendor(id, name, key)
+			.accounts({
+				vendor: vendorPda(id),
+				state,
+				authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const attestVia = async (state: any, wallet: any, vendor: any) =>
+		program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const requiredId = 1;
+	const otherId = 2;
+
+	it('accepts attestations from the required vendor and rejects others', async () => {
+		const state = await initState();
+		await registerVendor(state, requiredId, 'Chainalysis', authority);
+		await registerVendor(state, otherId, 'Elliptic', authority);
+
+		const policy = await initPolicy(5);
+		await program.methods.setRequiredVendor(requiredId).accounts({ policy, authority }).rpc();
+
+		const cleared = (anchor as any).web3.Keypair.generate().publicKey;
+		await attestVia(state, cleared, vendorPda(requiredId));
+		await setRiskFeed(state, cleared);
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(cleared));
+		expect(attestation.screeningVendorId).to.equal(requiredId);
+		await program.methods
+			.verifyCompliance(cleared, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user: cleared, attestation: attestationPdaFor(cleared) }))
+			.rpc();
+
+		const other = (anchor as any).web3.Keypair.generate().publicKey;
+		await attestVia(state, other, vendorPda(otherId));
+		try {
+			await program.methods
+				.verifyCompliance(other, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user: other, attestation: attestationPdaFor(other) }))
+				.rpc();
+			expect.fail('expected VendorNotAccepted');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('VendorNotAccepted');
+		}
+	});
+});
+
+describe('progressive disclosure', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const NATIONALITY = 2;
+
+	const commitmentOf = (wallet: any, fieldId: number, value: Buffer, blinding: Buffer) =>
+		Array.from(createHash('sha256').update(Buffer.concat([wallet.toBuffer(), Buffer.from([fieldId]), value, blinding])).digest());
+
+	let state: any;
+	let wallet: any;
+	const value = Buffer.from('NZ');
+	const blinding = Buffer.alloc(32, 7);
+
+	before(async () => {
+		state = await initState();
+		wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, wallet);
+		await program.methods.setAuditor(authority).accounts({ state, authority }).rpc();
+
+		const commitments = [0, 1, 2, 3].map((id) =>
+			id === NATIONALITY ? commitmentOf(wallet, id, value, blinding) : new Array(32).fill(0),
+		);
+		await program.methods
+			.commitAttestationFields(wallet, commitments)
+			.accounts({ attestation: attestationPdaFor(wallet), authority })
+			.rpc();
+	});
+
+	const reveal = (fieldValue: Buffer) =>
+		program.methods
+			.revealAttestationField(wallet, NATIONALITY, fieldValue, Array.from(blinding))
+			.accounts({ state, attestation: attestationPdaFor(wallet), auditor: authority })
+			.rpc({ commitment: 'confirmed' });
+
+	it('logs a field reveal matching the commitment', async () => {
+		const sig = await reveal(value);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'fieldRevealedEvent');
+		expect(event.data.fieldId).to.equal(NATIONALITY);
+		expect(event.data.auditor.toBase58()).to.equal(authority.toBase58());
+		expect(event.data.commitment).to.deep.equal(commitmentOf(wallet, NATIONALITY, value, blinding));
+	});
+
+	it('rejects a reveal that does not match the commitment', async () => {
+		try {
+			await reveal(Buffer.from('US'));
+			expect.fail('expected CommitmentMismatch');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('CommitmentMismatch');
+		}
+	});
+});
+
+describe('verification result signing', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	it('returns a digest that off-chain verifiers can replay deterministically', async () => {
+		const state = await initState();
+		const policy = await initPolicy(5);
+		await program.methods.setResultSigning(true).accounts({ policy, authority }).rpc();
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		const policyData = (await provider.connection.getAccountInfo(policy)).data;
+
+		const digests = [];
+		for (let i = 0; i < 2; i++) {
+			const sig = await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+				.rpc({ commitment: 'confirmed' });
+			const tx = await provider.connection.getTransaction(sig, {
+				commitment: 'confirmed',
+				maxSupportedTransactionVersion: 0,
+			});
+			// The digest is followed by the VerificationResult
+			const returned = Buffer.from(tx.meta.returnData.data[0], 'base64').subarray(0, 32);
+			expect(returned).to.deep.equal(verificationDigest(user, 'swap', tx.slot, policyData));
+			digests.push({ slot: tx.slot, returned });
+		}
+
+		// Identical inputs in the same slot must produce the same digest
+		if (digests[0].slot === digests[1].slot) {
+			expect(digests[0].returned).to.deep.equal(digests[1].returned);
+		}
+	});
+});
+
+describe('payment mandates', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const BN = (anchor as any).BN;
+
+	const mandatePda = (policy: any, user: any) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('mandate'), policy.toBuffer(), user.toBuffer()],
+			program.programId,
+		)[0];
+
+	const createMandate = async (cap: number, periodSecs: number) => {
+		const state = await initState();
+		const policy = await initPolicy(5);
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		const mandate = mandatePda(policy, user);
+		await program.methods
+			.createMandate(user, new BN(cap), new BN(periodSecs))
+			.accounts({
+				state,
+				policy,
+				attestation,
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				...riskOracleAccounts(user),
+				mandate,
+				authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		return mandate;
+	};
+
+	const consume = async (mandate: any, amount: number) => {
+		const { user } = await program.account.paymentMandate.fetch(mandate);
+		await program.methods
+			.consumeMandate(new BN(amount))
+			.accounts({ state: STATE, mandate, attestation: attestationPdaFor(user), sanction: sanctionPdaFor(user), authority })
+			.rpc();
+	};
+
+	it('consumes the allowance across calls until exhausted', async () => {
+		const mandate = await createMandate(100, 3600);
+		await consume(mandate, 40);
+		await consume(mandate, 60);
+		expect((await program.account.paymentMandate.fetch(mandate)).remaining.toNumber()).to.equal(0);
+
+		try {
+			await consume(mandate, 1);
+			expect.fail('expected MandateExhausted');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('MandateExhausted');
+		}
+	});
+
+	it('rejects consumption after the period ends', async () => {
+		const mandate = await createMandate(100, 1);
+		await sleep(3_000);
+		try {
+			await consume(mandate, 10);
+			expect.fail('expected MandateExpired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('MandateExpired');
+		}
+	});
+
+	it('blocks creating and consuming mandates while paused', async () => {
+		const mandate = await createMandate(100, 3600);
+		await program.methods.setPaused(true).accounts({ state: STATE, authority }).rpc();
+		try {
+			try {
+				await consume(mandate, 10);
+				expect.fail('expected ProgramPaused');
+			} catch (err: any) {
+				expect(err.error.errorCode.code).to.equal('ProgramPaused');
+			}
+			try {
+				await createMandate(100, 3600);
+				expect.fail('expected ProgramPaused');
+			} catch (err: any) {
+				expect(err.error.errorCode.code).to.equal('ProgramPaused');
+			}
+		} finally {
+			await program.methods.setPaused(false).accounts({ state: STATE, authority }).rpc();
+		}
+		await consume(mandate, 10);
+	});
+
+	it('stops a frozen wallet from spending its mandate', async () => {
+		const mandate = await createMandate(100, 3600);
+		const { user } = await program.account.paymentMandate.fetch(mandate);
+		await program.methods
+			.freezeWallet(user)
+			.accounts({ state: STATE, attestation: attestationPdaFor(user), authority, rollup: ROLLUP })
+			.rpc();
+		try {
+			await consume(mandate, 10);
+			expect.fail('expected WalletFrozen');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('WalletFrozen');
+		}
+		expect((await program.account.paymentMandate.fetch(mandate)).remaining.toNumber()).to.equal(100);
+	});
+});
+
+describe('policy governance signers', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+
+	let policy: any;
+	let cosigners: any[];
+
+	before(async () => {
+		cosigners = [(anchor as any).web3.Keypair.generate(), (anchor as any).web3.Keypair.generate()];
+		const name = nextPolicyName();
+		policy = policyPdaFor(name);
+		await program.methods
+			.initializePolicy(name, [authority, cosigners[0].publicKey, cosigners[1].publicKey], 2, null, null)
+			.accounts({ policy, authority, state: null, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+	});
+
+	it('rejects a threshold larger than the signer set', async () => {
+		const name = nextPolicyName();
+		try {
+			await program.methods
+				.initializePolicy(name, [authority], 2, null, null)
+				.accounts({ policy: policyPdaFor(name), authority, state: null, systemProgram: (anchor as any).web3.SystemProgram.programId })
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+
+	it('rejects a single-signer set_policy on a 2-of-3 policy', async () => {
+		try {
+			await program.methods.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+			expect.fail('expected InsufficientPolicySigners');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InsufficientPolicySigners');
+		}
+	});
+
+	it('accepts set_policy co-signed by a second governance signer', async () => {
+		await program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.remainingAccounts([{ pubkey: cosigners[0].publicKey, isSigner: true, isWritable: false }])
+			.signers([cosigners[0]])
+			.rpc();
+		const stored = await program.account.compliancePolicy.fetch(policy);
+		expect(stored.maxRiskScore).to.equal(5);
+		expect(stored.policyThreshold).to.equal(2);
+	});
+});
+
+describe('wallet ownership proofs', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	const attestWithProof = async (state: any, wallet: any, withProof: boolean) => {
+		const builder = program.methods
+			.createKycAttestation(wallet.publicKey, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet.publicKey),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: wallet.publicKey,
+				timeOracle: null,
+				vendor: null,
+				instructions: withProof ? (anchor as any).web3.SYSVAR_INSTRUCTIONS_PUBKEY : null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			});
+		if (withProof) {
+			const challenge = Buffer.concat([Buffer.from('complifi-ownership:'), wallet.publicKey.toBuffer(), authority.toBuffer()]);
+			builder.preInstructions([
+				(anchor as any).web3.Ed25519Program.createInstructionWithPrivateKey({
+					privateKey: wallet.secretKey,
+					message: challenge,
+				}),
+			]);
+		}
+		await builder.rpc();
+	};
+
+	const verify = (state: any, policy: any, wallet: any) =>
+		program.methods
+			.verifyCompliance(wallet.publicKey, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user: wallet.publicKey,
+					attestation: attestationPdaFor(wallet.publicKey),
+				}),
+			)
+			.rpc();
+
+	it('accepts a proven wallet and rejects an unproven one when required', async () => {
+		const state = await initState();
+		const policy = await initPolicy(5);
+		await program.methods.setRequireOwnershipProof(true).accounts({ policy, authority }).rpc();
+
+		const proven = (anchor as any).web3.Keypair.generate();
+		await attestWithProof(state, proven, true);
+		await setRiskFeed(state, proven.publicKey);
+		const proof = await program.account.kycAttestation.fetch(attestationPdaFor(proven.publicKey));
+		expect(proof.flags & ATTESTATION_FLAG_OWNERSHIP_PROVEN).to.not.equal(0);
+		await verify(state, policy, proven);
+
+		const unproven = (anchor as any).web3.Keypair.generate();
+		await attestWithProof(state, unproven, false);
+		try {
+			await verify(state, policy, unproven);
+			expect.fail('expected OwnershipProofRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OwnershipProofRequired');
+		}
+	});
+});
+
+describe('global verification throughput', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	after(async () => {
+		await program.methods.setMaxVerificationsPerSlot(0).accounts({ state: await initState(), authority }).rpc();
+	});
+
+	it('rejects verifications past the per-slot cap and recovers in the next slot', async () => {
+		const state = await initState();
+		const policy = await initPolicy(5);
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		await program.methods.setMaxVerificationsPerSlot(1).accounts({ state, authority }).rpc();
+
+		const verifyIx = (action: string) =>
+			program.methods
+				.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+				.instruction();
+
+		// Both verifications land in the same slot because they share a transaction
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+				.preInstructions([await verifyIx('deposit')])
+				.rpc();
+			expect.fail('expected ThroughputExceeded');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ThroughputExceeded');
+		}
+
+		await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc();
+		const stateAfter = await program.account.complianceState.fetch(state);
+		expect(stateAfter.verificationsThisSlot).to.equal(1);
+	});
+});
+
+describe('daily verification roots', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	it('accumulates verification digests and commits the day root', async () => {
+		const state = await initState();
+		const policy = await initPolicy(5);
+		const policyData = (await provider.connection.getAccountInfo(policy)).data;
+
+		// Earlier tests' verifications are already folded into the open day
+		const opening = await program.account.complianceState.fetch(state);
+		let expectedRoot = Buffer.from(opening.dailyVerificationRoot);
+		for (const action of ['swap', 'deposit', 'withdraw']) {
+			const user = (anchor as any).web3.Keypair.generate().publicKey;
+			const attestation = await attest(state, user);
+			const sig = await program.methods
+				.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+				.rpc({ commitment: 'confirmed' });
+			const tx = await provider.connection.getTransaction(sig, {
+				commitment: 'confirmed',
+				maxSupportedTransactionVersion: 0,
+			});
+			const digest = verificationDigest(user, action, tx.slot, policyData);
+			expectedRoot = createHash('sha256').update(Buffer.concat([expectedRoot, digest])).digest();
+		}
+
+		const open = await program.account.complianceState.fetch(state);
+		expect(Buffer.from(open.dailyVerificationRoot)).to.deep.equal(expectedRoot);
+		const [dailyRoot] = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('daily-root'), state.toBuffer(), open.verificationDay.toArrayLike(Buffer, 'le', 8)],
+			program.programId,
+		);
+		await program.methods
+			.closeVerificationDay()
+			.accounts({ state, dailyRoot, authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+
+		const committed = await program.account.dailyRoot.fetch(dailyRoot);
+		expect(Buffer.from(committed.root)).to.deep.equal(expectedRoot);
+		expect(committed.verificationCount).to.equal(opening.dayVerificationCount + 3);
+		const after = await program.account.complianceState.fetch(state);
+		expect(after.dayVerificationCount).to.equal(0);
+		expect(Buffer.from(after.dailyVerificationRoot)).to.deep.equal(Buffer.alloc(32));
+	});
+});
+
+describe('tiered renewal fees', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const BN = (anchor as any).BN;
+	// Large enough that a fresh recipient ends up rent-exempt
+	const EARLY_FEE = 1_000_000;
+	const LATE_FEE = 5_000_000;
+
+	it('charges the early fee before expiry and the late fee after it', async () => {
+		const state = await initState();
+		const policy = await initPolicy();
+		const feeRecipient = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.setRenewalFees(new BN(EARLY_FEE), new BN(LATE_FEE), feeRecipient)
+			.accounts({ policy, authority })
+			.rpc();
+
+		const optedIn = async (lifetime: number) => {
+			const wallet = (anchor as any).web3.Keypair.generate();
+			const attestation = await attest(state, wallet.publicKey, (await chainNow()) + lifetime);
+			await program.methods
+				.setAutoRenew(true)
+				.accounts({ attestation, wallet: wallet.publicKey })
+				.signers([wallet])
+				.rpc();
+			return { wallet: wallet.publicKey, attestation };
+		};
+
+		const renewFee = async ({ wallet, attestation }: any) => {
+			const sig = await program.methods
+				.renewAttestation(wallet)
+				.accounts({
+					state,
+					attestation,
+					policy,
+					renewer: authority,
+					feeRecipient,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc({ commitment: 'confirmed' });
+			const [event] = (await eventsOf(sig)).filter((e) => e.name === 'attestationRenewedEvent');
+			return event.data.fee.toNumber();
+		};
+
+		expect(await renewFee(await optedIn(3600))).to.equal(EARLY_FEE);
+
+		const expiring = await optedIn(2);
+		await sleep(4_000);
+		expect(await renewFee(expiring)).to.equal(LATE_FEE);
+
+		expect(await provider.connection.getBalance(feeRecipient, 'confirmed')).to.equal(EARLY_FEE + LATE_FEE);
+	});
+});
+
+describe('derivative verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let user: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+	});
+
+	const verifyDerivative = (underlying: any, derivative: any) =>
+		program.methods
+			.verifyDerivative(user, underlying, derivative)
+			.accounts({
+				state,
+				underlying,
+				derivative,
+				authority,
+				attestation,
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				...riskOracleAccounts(user),
+			})
+			.rpc();
+
+	it('passes when both the underlying and the overlay hold', async () => {
+		await verifyDerivative(await initPolicy(5), await initPolicy(3));
+	});
+
+	it('rejects a user passing the underlying but failing the overlay', async () => {
+		const underlying = await initPolicy(5);
+		const overlay = await initPolicy(1);
+		try {
+			await verifyDerivative(underlying, overlay);
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+
+	it('fails fast on the underlying policy', async () => {
+		const underlying = await initPolicy(5);
+		await program.methods
+			.setPolicy(5, true, new Array(32).fill(0) as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, true)
+			.accounts({ policy: underlying, authority })
+			.rpc();
+		try {
+			await verifyDerivative(underlying, await initPolicy(1));
+			expect.fail('expected RestrictedJurisdiction');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RestrictedJurisdiction');
+		}
+	});
+});
+
+describe('attestation flag migration', () => {
+	// Legacy-layout attestation loaded from tests/fixtures/legacy-attestation.json
+	const LEGACY_WALLET = new (anchor as any).web3.PublicKey('DmhAEPvs5qWFR6msJ6aqTUsgQ4rGZLrMyxZG4xRF1avj');
+	const LEGACY_AUTHORITY = new (anchor as any).web3.PublicKey('HAR5pvmmozFESbM1gs9TyZFBnCKJUJrbsHA3ihoYesdz');
+
+	it('repacks a legacy attestation preserving its fields', async () => {
+		const attestation = attestationPdaFor(LEGACY_WALLET, LEGACY_AUTHORITY);
+		await program.methods.migrateAttestation(LEGACY_WALLET, LEGACY_AUTHORITY).accounts({ attestation }).rpc();
+
+		const migrated = await program.account.kycAttestation.fetch(attestation);
+		expect(migrated.wallet.toBase58()).to.equal(LEGACY_WALLET.toBase58());
+		expect(migrated.flags).to.equal(
+			ATTESTATION_FLAG_VERIFIED | ATTESTATION_FLAG_AUTO_RENEW | ATTESTATION_FLAG_OWNERSHIP_PROVEN,
+		);
+		expect(migrated.renewCount).to.equal(2);
+		expect(migrated.timestamp.toNumber()).to.equal(1_700_000_000);
+		expect(migrated.bump).to.equal(attestationPdaAndBumpFor(LEGACY_WALLET, LEGACY_AUTHORITY)[1]);
+	});
+
+	it('rejects migrating an attestation that is already packed', async () => {
+		const state = await initState();
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, wallet);
+		try {
+			await program.methods
+				.migrateAttestation(wallet, (provider as any).wallet.publicKey)
+				.accounts({ attestation })
+				.rpc();
+			expect.fail('expected AttestationAlreadyMigrated');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationAlreadyMigrated');
+		}
+	});
+});
+
+describe('failure aggregation', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	before(async () => {
+		state = await initState();
+		// The stub oracle scores every wallet 2, and only jurisdiction 0 is allowed
+		policy = await initPolicy(1);
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, true, 1, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+	});
+
+	const simulate = (collectAll: boolean) =>
+		program.methods
+			.simulateCompliance(user, collectAll)
+			.accounts({
+				state,
+				policy,
+				user,
+				delegation: null,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				...riskOracleAccounts(user),
+			})
+			.view();
+
+	it('reports both the jurisdiction and risk failures when collecting all', async () => {
+		const failures = await simulate(true);
+		expect(Array.from(failures)).to.deep.equal([reasonCode('RestrictedJurisdiction'), reasonCode('RiskScoreTooHigh')]);
+	});
+
+	it('reports only the first failure otherwise', async () => {
+		const failures = await simulate(false);
+		expect(Array.from(failures)).to.deep.equal([reasonCode('RestrictedJurisdiction')]);
+	});
+});
+
+describe('token-2022 transfers', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const PublicKey = (anchor as any).web3.PublicKey;
+
+	// Mock Token-2022 accounts loaded from tests/fixtures/token2022-*.json
+	const MINT = new PublicKey('GMx2Nk4ctMvdSTdMgCHJU4pSvNeWCVadcJ2K4q5s5euQ');
+	const NON_TRANSFERABLE_MINT = new PublicKey('9JJJnKSgk4LCPT7GRxV6PwBePfch621oiUvkxUhUkfKt');
+	const ALICE = new PublicKey('9vUcE4a3K41s1K4Xz2Zxk9tdtb3mGrrAfBoudogbLR8F');
+	const BOB = new PublicKey('3NoEGbkyNeCU8sfxcRUnNFqP15oSjxEYh384XSrepMCL');
+	const ACCOUNTS = {
+		[MINT.toBase58()]: {
+			alice: new PublicKey('eXKSe6johJukB5xmUXbC1rP2QnpxpvCdYoj9uPUP5vk'),
+			bob: new PublicKey('HrJQugCjJ2X6uZnbEZTAwEV9UHD5sbeLsMU4ohJTYesi'),
+		},
+		[NON_TRANSFERABLE_MINT.toBase58()]: {
+			alice: new PublicKey('BQ2S4v5VjXfmHx1owShsddjiTbrm7mohJrJfDencfRsH'),
+			bob: new PublicKey('BfdtHdK9TtVxfNFmM6arVzLY3KpuFkPqMeu3wxxLr3W2'),
+		},
+	};
+
+	let state: any;
+	let policy: any;
+
+	const attestWallet = (wallet: any, isVerified: boolean) =>
+		program.methods
+			.createKycAttestation(wallet, isVerified, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	// Each party's oracle accounts, under the `source` or `destination` prefix
+	const partyOracleAccounts = (party: string, owner: any) => {
+		const { rangeOracle, riskFeed, switchboardAggregator } = riskOracleAccounts(owner);
+		return {
+			[`${party}RangeOracle`]: rangeOracle,
+			[`${party}RiskFeed`]: riskFeed,
+			[`${party}SwitchboardAggregator`]: switchboardAggregator,
+		};
+	};
+
+	const transfer = (mint: any) => {
+		const { alice, bob } = ACCOUNTS[mint.toBase58()];
+		return program.methods
+			.verifyToken2022Transfer(mint, alice, bob, new (anchor as any).BN(100))
+			.accounts({
+				state,
+				policy,
+				authority,
+				tokenMint: mint,
+				sourceToken: alice,
+				destinationToken: bob,
+				sourceAttestation: attestationPdaFor(ALICE),
+				destinationAttestation: attestationPdaFor(BOB),
+				sourceSanction: sanctionPdaFor(ALICE),
+				destinationSanction: sanctionPdaFor(BOB),
+				...partyOracleAccounts('source', ALICE),
+				...partyOracleAccounts('destination', BOB),
+			})
+			.rpc();
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		await attestWallet(ALICE, true);
+		await attestWallet(BOB, false);
+		await setRiskFeed(state, ALICE);
+		await setRiskFeed(state, BOB);
+	});
+
+	it('rejects a transfer the mint allows when CompliFi blocks the destination', async () => {
+		try {
+			await transfer(MINT);
+			expect.fail('expected KycNotVerified');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycNotVerified');
+		}
+	});
+
+	it('rejects a transfer CompliFi allows when the mint is non-transferable', async () => {
+		await attestWallet(BOB, true);
+		await transfer(MINT);
+
+		try {
+			await transfer(NON_TRANSFERABLE_MINT);
+			expect.fail('expected TokenTransferRestricted');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('TokenTransferRestricted');
+		}
+	});
+});
+
+describe('risk conflict resolution', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const ORACLE_WINS = 0;
+	const ATTESTER_WINS = 1;
+	const REQUIRE_BOTH = 2;
+	const RISK_SOURCE_ORACLE = 0;
+	const RISK_SOURCE_ATTESTER = 1;
+
+	let state: any;
+	let user: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+	});
+
+	// The stub oracle scores 2, so a max risk of 1 makes it disagree with the attester's vouch
+	const conflictingPolicy = async (resolution: number) => {
+		const policy = await initPolicy(1);
+		await program.methods.setRiskConflictResolution(resolution).accounts({ policy, authority }).rpc();
+		return policy;
+	};
+
+	const verify = (policy: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc({ commitment: 'confirmed' });
+
+	const expectRejected = async (policy: any, code: string) => {
+		try {
+			await verify(policy);
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	it('blocks the vouched wallet when the oracle wins', async () => {
+		await expectRejected(await conflictingPolicy(ORACLE_WINS), 'RiskScoreTooHigh');
+	});
+
+	it('passes the vouched wallet when the attester wins', async () => {
+		const sig = await verify(await conflictingPolicy(ATTESTER_WINS));
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'riskConflictResolvedEvent');
+		expect(event.data.winner).to.equal(RISK_SOURCE_ATTESTER);
+		expect(event.data.passed).to.equal(true);
+	});
+
+	it('blocks the vouched wallet when both are required', async () => {
+		await expectRejected(await conflictingPolicy(REQUIRE_BOTH), 'RiskScoreTooHigh');
+	});
+
+	it('lets the oracle pass an unvouched wallet only under oracle-wins', async () => {
+		const unvouched = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(unvouched, false, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(unvouched),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: unvouched,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, unvouched);
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+
+		for (const [resolution, passes] of [
+			[ORACLE_WINS, true],
+			[ATTESTER_WINS, false],
+			[REQUIRE_BOTH, false],
+		] as [number, boolean][]) {
+			const policy = await initPolicy(5);
+			await program.methods.setPolicy(5, false, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+			await program.methods.setRiskConflictResolution(resolution).accounts({ policy, authority }).rpc();
+			const call = program.methods
+				.verifyCompliance(unvouched, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(
+					verifyAccounts({ state, policy, authority, user: unvouched, attestation: attestationPdaFor(unvouched) }),
+				)
+				.rpc({ commitment: 'confirmed' });
+			if (passes) {
+				const [event] = (await eventsOf(await call)).filter((e) => e.name === 'riskConflictResolvedEvent');
+				expect(event.data.winner).to.equal(RISK_SOURCE_ORACLE);
+			} else {
+				try {
+					await call;
+					expect.fail('expected KycNotVerified');
+				} catch (err: any) {
+					expect(err.error.errorCode.code).to.equal('KycNotVerified');
+				}
+			}
+		}
+	});
+});
+
+describe('compiled policies', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+
+	let state: any;
+	let policy: any;
+	let compiledPolicy: any;
+	let user: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(4);
+		[compiledPolicy] = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('compiled-policy'), policy.toBuffer()],
+			program.programId,
+		);
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+	});
+
+	const compile = () =>
+		program.methods
+			.compilePolicy()
+			.accounts({ policy, compiledPolicy, payer: authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation, compiledPolicy }))
+			.rpc();
+
+	it('compiles the resolved policy values', async () => {
+		await compile();
+		const live = await program.account.compliancePolicy.fetch(policy);
+		const compiled = await program.account.compiledPolicy.fetch(compiledPolicy);
+		expect(compiled.sourceRevision.toString()).to.equal(live.revision.toString());
+		expect(compiled.effective.maxRiskScore).to.equal(live.maxRiskScore);
+		expect(compiled.effective.allowedJurisdictions).to.deep.equal(live.allowedJurisdictions);
+		await verify();
+	});
+
+	it('invalidates the compiled policy when the policy changes', async () => {
+		await program.methods.setPolicy(1, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+		try {
+			await verify();
+			expect.fail('expected CompiledPolicyStale');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('CompiledPolicyStale');
+		}
+
+		// Recompiling picks up the stricter threshold
+		await compile();
+		try {
+			await verify();
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+});
+
+describe('risk tolerance band', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let user: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+	});
+
+	// The stub oracle scores every wallet 2; vary the threshold around it with a band of 1
+	const verifyUnder = async (maxRisk: number) => {
+		const policy = await initPolicy(maxRisk);
+		await program.methods.setRiskTolerance(1).accounts({ policy, authority }).rpc();
+		const sig = await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc({ commitment: 'confirmed' });
+		return (await eventsOf(sig)).filter((e) => e.name === 'marginalRiskEvent');
+	};
+
+	it('passes a clearly compliant score without flagging it', async () => {
+		expect(await verifyUnder(3)).to.have.length(0);
+	});
+
+	it('passes a score within the band as marginal', async () => {
+		const passed = (await program.account.complianceState.fetch(state)).marginalPassCount.toNumber();
+		const [event] = await verifyUnder(1);
+		expect(event.data.riskScore).to.equal(2);
+		expect(event.data.maxRiskScore).to.equal(1);
+		const stateAfter = await program.account.complianceState.fetch(state);
+		expect(stateAfter.marginalPassCount.toNumber()).to.equal(passed + 1);
+	});
+
+	it('fails a score beyond the band', async () => {
+		try {
+			await verifyUnder(0);
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+});
+
+describe('jurisdiction risk tier validity', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const DAY = 86_400;
+	const LOW_RISK = 7;
+	const HIGH_RISK = 8;
+
+	let state: any;
+	let policy: any;
+
+	const jurisdictionPda = (jurisdiction: number) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('jurisdiction'), Buffer.from([jurisdiction])],
+			program.programId,
+		)[0];
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods
+			.setTierValidity([0, 365 * DAY, 180 * DAY, 90 * DAY].map((v) => new (anchor as any).BN(v)))
+			.accounts({ policy, authority })
+			.rpc();
+		for (const [jurisdiction, tier] of [
+			[LOW_RISK, 1],
+			[HIGH_RISK, 3],
+		]) {
+			await program.methods
+				.setJurisdictionMeta(jurisdiction, tier)
+				.accounts({
+					jurisdictionMeta: jurisdictionPda(jurisdiction),
+					state,
+					authority,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+		}
+	});
+
+	// Attest with no explicit expiry so the tier default applies
+	const attestIn = async (jurisdiction: number, expiresAt = 0) => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = attestationPdaFor(wallet);
+		await program.methods
+			.createKycAttestation(wallet, true, jurisdiction, new (anchor as any).BN(expiresAt), null, null, 0, 0)
+			.accounts({
+				attestation,
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy,
+				jurisdictionMeta: jurisdictionPda(jurisdiction),
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, wallet);
+		return program.account.kycAttestation.fetch(attestation);
+	};
+
+	it('gives a high-risk jurisdiction a shorter default validity', async () => {
+		const low = await attestIn(LOW_RISK);
+		const high = await attestIn(HIGH_RISK);
+		expect(low.validityPeriod.toNumber()).to.equal(365 * DAY);
+		expect(high.validityPeriod.toNumber()).to.equal(90 * DAY);
+		expect(high.expiresAt.toNumber()).to.be.lessThan(low.expiresAt.toNumber());
+	});
+
+	it('keeps an explicit expiry over the tier default', async () => {
+		const expiresAt = Math.floor(Date.now() / 1000) + 10 * DAY;
+		const attestation = await attestIn(HIGH_RISK, expiresAt);
+		expect(attestation.expiresAt.toNumber()).to.equal(expiresAt);
+	});
+
+	it('rejects a tier validity longer than ten years', async () => {
+		try {
+			await program.methods
+				.setTierValidity([0, 365 * DAY, 3651 * DAY, 90 * DAY].map((v) => new (anchor as any).BN(v)))
+				.accounts({ policy, authority })
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+
+	it('rejects a risk tier outside the supported range', async () => {
+		try {
+			await program.methods
+				.setJurisdictionMeta(9, 4)
+				.accounts({
+					jurisdictionMeta: jurisdictionPda(9),
+					state,
+					authority,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('jurisdiction info', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	const jurisdictionPda = (jurisdiction: number) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('jurisdiction'), Buffer.from([jurisdiction])],
+			program.programId,
+		)[0];
+
+	const setInfo = (jurisdiction: number, name: string, isoCode: string) =>
+		program.methods
+			.setJurisdictionInfo(jurisdiction, name, Array.from(Buffer.from(isoCode)) as any)
+			.accounts({
+				jurisdictionMeta: jurisdictionPda(jurisdiction),
+				state,
+				authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('stores a name and ISO code for a jurisdiction code', async () => {
+		await setInfo(1, 'United States', 'US');
+		const meta = await program.account.jurisdictionMeta.fetch(jurisdictionPda(1));
+		expect(meta.jurisdiction).to.equal(1);
+		expect(meta.name).to.equal('United States');
+		expect(Buffer.from(meta.isoCode).toString()).to.equal('US');
+	});
+
+	it('rejects an ISO code that is not two uppercase letters', async () => {
+		try {
+			await setInfo(1, 'United States', 'us');
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('compliance guard CPI', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const stub = (anchor as any).workspace.GuardStub as any;
+
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	// The stub's only instruction calls `guard` as its first CPI
+	const gatedAction = (user: any, target: any = policy) =>
+		stub.methods
+			.gatedAction(user, 'swap')
+			.accounts({
+				complifiState: state,
+				policy: target,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				attestorEntry: null,
+				complifiProgram: program.programId,
+				...riskOracleAccounts(user),
+			})
+			.rpc();
+
+	it('lets the caller proceed for a compliant user', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await gatedAction(user);
+	});
+
+	it('aborts the caller for an unverified user', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, false, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+		try {
+			await gatedAction(user);
+			expect.fail('expected KycNotVerified');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycNotVerified');
+		}
+	});
+
+	it('aborts the caller outside the policy trading window', async () => {
+		const DAY = 86_400;
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const timeOfDay = (await chainNow()) % DAY;
+		const closed = await initPolicy();
+		await program.methods
+			.setTradingWindow((timeOfDay + 3600) % DAY, (timeOfDay + 7200) % DAY)
+			.accounts({ policy: closed, authority })
+			.rpc();
+		try {
+			await gatedAction(user, closed);
+			expect.fail('expected OutsideTradingWindow');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OutsideTradingWindow');
+		}
+		await gatedAction(user);
+	});
+
+	it('aborts the caller when the policy requires a trusted attestor it is not shown', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const strict = await initPolicy();
+		await program.methods.setRequireTrustedAttestor(true).accounts({ policy: strict, authority }).rpc();
+		try {
+			await gatedAction(user, strict);
+			expect.fail('expected UntrustedAttestor');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UntrustedAttestor');
+		}
+	});
+
+	it('leaves the verification count untouched', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const count = (await program.account.complianceState.fetch(state)).verificationCount.toNumber();
+		await gatedAction(user);
+		const stateAfter = await program.account.complianceState.fetch(state);
+		expect(stateAfter.verificationCount.toNumber()).to.equal(count);
+	});
+});
+
+describe('compliance metrics', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	it('reports every counter in one read', async () => {
+		const state = await initState();
+		const counts = await program.methods.getMetrics().accounts({ state }).view();
+		const name = nextPolicyName();
+		const policy = policyPdaFor(name);
+		await program.methods
+			.initializePolicy(name, [], 0, null, null)
+			.accounts({ policy, authority, state, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		await program.methods.setOracleKind(ORACLE_KIND_SWITCHBOARD).accounts({ policy, authority }).rpc();
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		await program.methods.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc();
+		await program.methods
+			.recordViolation(user, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				authority,
+				report: null,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(user),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await program.methods.emergencyVerify(user, 'withdraw', 'Oracle outage').accounts({ state, authority }).rpc();
+
+		const metrics = await program.methods.getMetrics().accounts({ state }).view();
+		const stateAccount = await program.account.complianceState.fetch(state);
+		expect(metrics.verificationCount.toNumber()).to.equal(stateAccount.verificationCount.toNumber());
+		expect(metrics.verificationCount.toNumber()).to.equal(counts.verificationCount.toNumber() + 1);
+		expect(metrics.violationCount.toNumber()).to.equal(stateAccount.violationCount.toNumber());
+		expect(metrics.violationCount.toNumber()).to.equal(counts.violationCount.toNumber() + 1);
+		expect(metrics.emergencyCount.toNumber()).to.equal(stateAccount.emergencyCount.toNumber());
+		expect(metrics.emergencyCount.toNumber()).to.equal(counts.emergencyCount.toNumber() + 1);
+		// The verification landed in an earlier slot than the view runs in
+		expect(metrics.verificationsThisSlot).to.equal(0);
+		expect(metrics.activePolicyCount).to.equal(stateAccount.policyCount);
+		expect(metrics.activePolicyCount).to.equal(counts.activePolicyCount + 1);
+		expect(metrics.paused).to.equal(stateAccount.paused);
+		expect(metrics.paused).to.equal(false);
+	});
+});
+
+describe('attestation revocation authority', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let backup: any;
+
+	before(async () => {
+		state = await initState();
+		backup = (anchor as any).web3.Keypair.generate();
+	});
+
+	// Attest `wallet` as verified with `backup` as its designated revocation authority
+	const attestWithBackup = async (wallet: any) => {
+		const attestation = attestationPdaFor(wallet);
+		await program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, backup.publicKey, 0, 0)
+			.accounts({
+				attestation,
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, wallet);
+		return attestation;
+	};
+
+	it('defaults the revocation authority to the attester', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await program.account.kycAttestation.fetch(await attest(state, wallet));
+		expect(attestation.revocationAuthority.toString()).to.equal(authority.toString());
+	});
+
+	it('lets the backup authority revoke', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attestWithBackup(wallet);
+		await program.methods
+			.revokeKycAttestation(wallet)
+			.accounts({ attestation, authority: backup.publicKey, state, rollup: ROLLUP, journal: JOURNAL })
+			.signers([backup])
+			.rpc();
+		const attestationAccount = await program.account.kycAttestation.fetch(attestation);
+		expect(attestationAccount.flags & ATTESTATION_FLAG_VERIFIED).to.equal(0);
+	});
+
+	it('rejects an unrelated signer', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attestWithBackup(wallet);
+		const stranger = (anchor as any).web3.Keypair.generate();
+		try {
+			await program.methods
+				.revokeKycAttestation(wallet)
+				.accounts({ attestation, authority: stranger.publicKey, state, rollup: ROLLUP, journal: JOURNAL })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('fails verification with KycNotVerified once revoked', async () => {
+		const policy = await initPolicy();
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, wallet);
+		const verify = () =>
+			program.methods
+				.verifyCompliance(wallet, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user: wallet, attestation }))
+				.rpc();
+		await verify();
+
+		await program.methods.revokeKycAttestation(wallet).accounts({ attestation, authority, state, rollup: ROLLUP, journal: JOURNAL }).rpc();
+		const revoked = await program.account.kycAttestation.fetch(attestation);
+		expect(revoked.flags & ATTESTATION_FLAG_VERIFIED).to.equal(0);
+		try {
+			await verify();
+			expect.fail('expected KycNotVerified');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycNotVerified');
+		}
+	});
+});
+
+describe('jurisdiction change recheck', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		// Allow jurisdictions 0 and 1, and give moved users no grace period
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 0b11;
+		await program.methods.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+		await program.methods.setJurisdictionRecheck(true, 0).accounts({ policy, authority }).rpc();
+	});
+
+	const attestIn = (wallet: any, jurisdiction: number) =>
+		program.methods
+			.createKycAttestation(wallet, true, jurisdiction, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const verify = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	it('rejects a user whose jurisdiction change was never re-verified', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attestIn(user, 0);
+		await attestIn(user, 1);
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(user));
+		expect(attestation.jurisdictionChangedAt.toNumber()).to.be.greaterThan(0);
+
+		await sleep(2000);
+		try {
+			await verify(user);
+			expect.fail('expected JurisdictionRecheckRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('JurisdictionRecheckRequired');
+		}
+	});
+
+	it('passes once the user is re-attested in the new jurisdiction', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attestIn(user, 0);
+		await attestIn(user, 1);
+		await attestIn(user, 1);
+		await setRiskFeed(state, user);
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(user));
+		expect(attestation.jurisdictionChangedAt.toNumber()).to.equal(0);
+
+		await sleep(2000);
+		await verify(user);
+	});
+});
+
+describe('batch risk refresh', () => {
+	// Wallet and oracle entry (score 4) from tests/fixtures/range-oracle-refresh.json
+	const REFRESH_USER = new (anchor as any).web3.PublicKey('3ubmio3zGAAavjUcoAWWRFHg5TEaEiVbwGhRYAAxmoAQ');
+	const REFRESH_ORACLE = new (anchor as any).web3.PublicKey('8hz14Cefb6bWq1d43ebHB2beCo3f54T2frFF6JLPnzH8');
+
+	const riskCachePda = (user: any) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('risk-cache'), user.toBuffer()],
+			program.programId,
+		)[0];
+
+	it('refreshes every user it can and skips a failed lookup', async () => {
+		const unknown = (anchor as any).web3.Keypair.generate().publicKey;
+		const users = [ORACLE_FIXTURE_USER, REFRESH_USER, unknown];
+		for (const user of users) {
+			await program.methods
+				.initRiskCache(user)
+				.accounts({
+					riskCache: riskCachePda(user),
+					payer: (provider as any).wallet.publicKey,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+		}
+
+		// The unknown wallet has no entry in the oracle account it is paired with
+		const oracles = [RANGE_ORACLES.a, REFRESH_ORACLE, RANGE_ORACLES.b];
+		const sig = await program.methods
+			.refreshRiskBatch(users)
+			.accounts({ keeper: (provider as any).wallet.publicKey })
+			.remainingAccounts(
+				users.flatMap((user, i) => [
+					{ pubkey: oracles[i], isSigner: false, isWritable: false },
+					{ pubkey: riskCachePda(user), isSigner: false, isWritable: true },
+				]),
+			)
+			.rpc({ commitment: 'confirmed' });
+
+		const events = (await eventsOf(sig)).filter((e) => e.name === 'riskRefreshedEvent');
+		expect(events.map((e) => e.data.user.toString())).to.deep.equal([
+			ORACLE_FIXTURE_USER.toString(),
+			REFRESH_USER.toString(),
+		]);
+
+		const fixtureCache = await program.account.riskCache.fetch(riskCachePda(ORACLE_FIXTURE_USER));
+		expect(fixtureCache.riskScore).to.equal(2);
+		expect(fixtureCache.modelVersion).to.equal(FIXTURE_RISK_MODEL_VERSION);
+		const refreshCache = await program.account.riskCache.fetch(riskCachePda(REFRESH_USER));
+		expect(refreshCache.riskScore).to.equal(4);
+		expect(refreshCache.oracle.toString()).to.equal(REFRESH_ORACLE.toString());
+		const skipped = await program.account.riskCache.fetch(riskCachePda(unknown));
+		expect(skipped.refreshedAt.toNumber()).to.equal(0);
+	});
+
+	it('rejects a batch larger than eight users', async () => {
+		const users = Array.from({ length: 9 }, () => (anchor as any).web3.Keypair.generate().publicKey);
+		try {
+			await program.methods
+				.refreshRiskBatch(users)
+				.accounts({ keeper: (provider as any).wallet.publicKey })
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('attestation chaining', () => {
+	let state: any;
+	let wallet: any;
+	let attestation: any;
+	let history: any[];
+
+	// Issue, then re-attest twice, capturing each record just before it is replaced
+	before(async () => {
+		state = await initState();
+		wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, wallet);
+		history = [];
+		for (let i = 0; i < 2; i++) {
+			history.push(await program.account.kycAttestation.fetch(attestation));
+			await attest(state, wallet);
+		}
+	});
+
+	const verifyChain = (records: any[]) =>
+		program.methods.verifyAttestationChain(wallet, records).accounts({ attestation }).rpc();
+
+	it('starts the chain at a zero hash', async () => {
+		expect(history[0].prevAttestationHash).to.deep.equal(new Array(32).fill(0));
+		const current = await program.account.kycAttestation.fetch(attestation);
+		expect(current.prevAttestationHash).to.not.deep.equal(new Array(32).fill(0));
+	});
+
+	it('verifies an intact two-link chain', async () => {
+		await verifyChain(history);
+	});
+
+	it('detects a tampered prior record', async () => {
+		const tampered = [{ ...history[0], jurisdiction: 5 }, history[1]];
+		try {
+			await verifyChain(tampered);
+			expect.fail('expected AttestationChainBroken');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationChainBroken');
+		}
+	});
+
+	it('detects a missing link', async () => {
+		try {
+			await verifyChain([history[1]]);
+			expect.fail('expected AttestationChainBroken');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationChainBroken');
+		}
+	});
+});
+
+describe('jurisdiction amount caps', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const CAPPED = 1;
+
+	let state: any;
+	let policy: any;
+	let limit: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 0b11;
+		await program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, new (anchor as any).BN(10_000), false)
+			.accounts({ policy, authority })
+			.rpc();
+
+		limit = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('jurisdiction-limit'), Buffer.from([CAPPED])],
+			program.programId,
+		)[0];
+		await program.methods
+			.setJurisdictionLimit(CAPPED, new (anchor as any).BN(1_000))
+			.accounts({
+				jurisdictionLimit: limit,
+				state,
+				authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+	});
+
+	const attestIn = async (jurisdiction: number) => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, true, jurisdiction, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+		return user;
+	};
+
+	const verify = (user: any, amount: number, jurisdictionLimit: any = null) =>
+		program.methods
+			.verifyCompliance(user, 'transfer', null, new (anchor as any).BN(amount), null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), jurisdictionLimit }))
+			.rpc();
+
+	const expectOverLimit = async (promise: Promise<any>) => {
+		try {
+			await promise;
+			expect.fail('expected AmountExceedsLimit');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AmountExceedsLimit');
+		}
+	};
+
+	it('limits a capped-jurisdiction user below the policy-wide cap', async () => {
+		const user = await attestIn(CAPPED);
+		await verify(user, 1_000, limit);
+		await expectOverLimit(verify(user, 5_000, limit));
+	});
+
+	it('applies only the policy-wide cap elsewhere', async () => {
+		const user = await attestIn(0);
+		await verify(user, 5_000);
+		await expectOverLimit(verify(user, 20_000));
+	});
+});
+
+describe('stored denial reasons', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let user: any;
+
+	before(async () => {
+		state = await initState();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	const recordCheck = (policy: any) =>
+		program.methods
+			.recordComplianceCheck(user, 'swap')
+			.accounts({
+				state,
+				policy,
+				attestation: attestationPdaFor(user),
+				userRecord: userRecordPdaFor(user),
+				...riskOracleAccounts(user),
+				payer: authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const userStatus = () =>
+		program.methods.getUserStatus(user).accounts({ userRecord: userRecordPdaFor(user) }).view();
+
+	it('stores the denial reason without failing the transaction', async () => {
+		// The stub oracle scores 2, above this policy's threshold
+		await recordCheck(await initPolicy(1));
+
+		const status = await userStatus();
+		expect(status.lastDenialCode).to.equal(reasonCode('RiskScoreTooHigh'));
+		expect(status.lastDenialTs.toNumber()).to.be.greaterThan(0);
+	});
+
+	it('keeps the latest denial after a passing check', async () => {
+		const denied = await userStatus();
+		await recordCheck(await initPolicy(5));
+
+		const status = await userStatus();
+		expect(status.lastDenialCode).to.equal(denied.lastDenialCode);
+		expect(status.lastDenialTs.toNumber()).to.equal(denied.lastDenialTs.toNumber());
+	});
+});
+
+describe('trading windows', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const DAY = 86_400;
+
+	let state: any;
+	let user: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+	});
+
+	// Verify under a fresh policy whose window is offset from the current time of day
+	const verifyInWindow = async (startOffset: number, endOffset: number) => {
+		const timeOfDay = (await chainNow()) % DAY;
+		const policy = await initPolicy();
+		await program.methods
+			.setTradingWindow((timeOfDay + startOffset + DAY) % DAY, (timeOfDay + endOffset + DAY) % DAY)
+			.accounts({ policy, authority })
+			.rpc();
+		return program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc();
+	};
+
+	it('passes inside the window', async () => {
+		await verifyInWindow(-600, 600);
+	});
+
+	it('rejects outside the window', async () => {
+		try {
+			await verifyInWindow(3600, 7200);
+			expect.fail('expected OutsideTradingWindow');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OutsideTradingWindow');
+		}
+	});
+
+	it('handles a window that wraps past midnight', async () => {
+		// Starts after it ends: open from an hour from now, through midnight, until ten minutes from now
+		await verifyInWindow(3600, 600);
+		try {
+			await verifyInWindow(1200, -600);
+			expect.fail('expected OutsideTradingWindow');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('OutsideTradingWindow');
+		}
+	});
+});
+
+describe('attestation import', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+	});
+
+	const importAttestation = (wallet: any, originalTimestamp: number, sourceProgram: any, signer?: any) => {
+		const builder = program.methods
+			.importAttestation(wallet, true, 0, new (anchor as any).BN(originalTimestamp), sourceProgram)
+			.accounts({
+				attestation: attestationPdaFor(wallet, signer ? signer.publicKey : authority),
+				state,
+				authority: signer ? signer.publicKey : authority,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			});
+		return signer ? builder.signers([signer]).rpc() : builder.rpc();
+	};
+
+	it('preserves the original timestamp and source program', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const sourceProgram = (anchor as any).web3.Keypair.generate().publicKey;
+		const originalTimestamp = (await chainNow()) - 400 * 86_400;
+		await importAttestation(wallet, originalTimestamp, sourceProgram);
+
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(wallet));
+		expect(attestation.timestamp.toNumber()).to.equal(originalTimestamp);
+		expect(attestation.importedFrom.toString()).to.equal(sourceProgram.toString());
+		expect(attestation.flags & ATTESTATION_FLAG_VERIFIED).to.equal(ATTESTATION_FLAG_VERIFIED);
+	});
+
+	it('marks natively issued attestations as not imported', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await program.account.kycAttestation.fetch(await attest(state, wallet));
+		expect(attestation.importedFrom.toString()).to.equal((anchor as any).web3.PublicKey.default.toString());
+	});
+
+	it('rejects an import by anyone but the state authority', async () => {
+		const stranger = (anchor as any).web3.Keypair.generate();
+		const sig = await provider.connection.requestAirdrop(stranger.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		try {
+			await importAttestation(wallet, await chainNow(), (anchor as any).web3.Keypair.generate().publicKey, stranger);
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('policy logic expressions', () => {
+	const authority = (provider as any).wallet.publicKey;
+	// Token bytes from logic.rs
+	const KYC_VERIFIED = 0x01;
+	const OWNERSHIP_PROVEN = 0x03;
+	const JURISDICTION_IS = 0x04;
+	const AND = 0x80;
+	const OR = 0x81;
+	const EU = 3;
+
+	let state: any;
+	let euUser: any;
+	let euAttestation: any;
+	let otherUser: any;
+	let otherAttestation: any;
+
+	// Create a policy that leaves KYC entirely to its logic expression
+	const logicPolicy = async (tokens: number[]) => {
+		const policy = await initPolicy();
+		const allowed: number[] = new Array(32).fill(0);
+		await program.methods.setPolicy(5, false, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+		await program.methods.setPolicyLogic(Buffer.from(tokens)).accounts({ policy, authority }).rpc();
+		return policy;
+	};
+
+	const verify = (policy: any, user: any, attestation: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc({ commitment: 'confirmed' });
+
+	before(async () => {
+		state = await initState();
+		euUser = (anchor as any).web3.Keypair.generate().publicKey;
+		euAttestation = attestationPdaFor(euUser);
+		await program.methods
+			.createKycAttestation(euUser, true, EU, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: euAttestation,
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: euUser,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, euUser);
+		otherUser = (anchor as any).web3.Keypair.generate().publicKey;
+		otherAttestation = await attest(state, otherUser);
+	});
+
+	it('evaluates KYC AND (jurisdiction OR ownership proof)', async () => {
+		const policy = await logicPolicy([KYC_VERIFIED, JURISDICTION_IS, EU, OWNERSHIP_PROVEN, OR, AND]);
+		await verify(policy, euUser, euAttestation);
+		try {
+			await verify(policy, otherUser, otherAttestation);
+			expect.fail('expected PolicyLogicNotSatisfied');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('PolicyLogicNotSatisfied');
+		}
+	});
+
+	it('skips the right side once the left decides an OR', async () => {
+		const policy = await logicPolicy([KYC_VERIFIED, JURISDICTION_IS, EU, OR]);
+		const sig = await verify(policy, otherUser, otherAttestation);
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		expect(tx.meta.logMessages).to.include('Program log: Policy logic evaluated 1 checks');
+	});
+
+	it('rejects a malformed token stream', async () => {
+		const policy = await initPolicy();
+		try {
+			await program.methods.setPolicyLogic(Buffer.from([KYC_VERIFIED, AND])).accounts({ policy, authority }).rpc();
+			expect.fail('expected InvalidPolicyLogic');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyLogic');
+		}
+	});
+});
+
+describe('per-check freshness', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const CHECK_SANCTIONS = 1 << 1;
+	const CHECK_ACCREDITATION = 1 << 2;
+
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+	});
+
+	const maxAgesPolicy = async (identity: number, sanctions: number, accreditation: number) => {
+		const policy = await initPolicy();
+		await program.methods
+			.setCheckMaxAges(new (anchor as any).BN(identity), new (anchor as any).BN(sanctions), new (anchor as any).BN(accreditation))
+			.accounts({ policy, authority })
+			.rpc();
+		return policy;
+	};
+
+	const refresh = (wallet: any, checks: number) =>
+		program.methods.refreshAttestationChecks(wallet, checks).accounts({ attestation: attestationPdaFor(wallet), authority }).rpc();
+
+	const verify = (policy: any, user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	it('rejects stale sanctions screening while identity is still fresh', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const policy = await maxAgesPolicy(3600, 1, 0);
+		await sleep(2500);
+
+		try {
+			await verify(policy, user);
+			expect.fail('expected SanctionsScreeningStale');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('SanctionsScreeningStale');
+		}
+
+		// Re-screening alone restores compliance; identity keeps its original timestamp
+		await refresh(user, CHECK_SANCTIONS);
+		await verify(policy, user);
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(user));
+		expect(attestation.sanctionsCheckedAt.toNumber()).to.be.greaterThan(attestation.identityVerifiedAt.toNumber());
+	});
+
+	it('requires an accreditation check when the policy sets its max age', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const policy = await maxAgesPolicy(0, 0, 3600);
+		try {
+			await verify(policy, user);
+			expect.fail('expected AccreditationStale');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AccreditationStale');
+		}
+
+		await refresh(user, CHECK_ACCREDITATION);
+		await verify(policy, user);
+	});
+});
+
+describe('verification receipts by amount bucket', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const action = 'swap';
+
+	let state: any;
+	let policy: any;
+	let user: any;
+	let attestation: any;
+
+	const receiptPdaFor = (bucket: number) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[
+				Buffer.from('verification-receipt'),
+				user.toBuffer(),
+				policy.toBuffer(),
+				createHash('sha256').update(action).digest(),
+				Buffer.from([bucket]),
+			],
+			program.programId,
+		)[0];
+
+	// Verify `amount` against the receipt for `bucket`, returning whether it was a cache hit
+	const verifyHits = async (amount: number, bucket: number) => {
+		const sig = await program.methods
+			.verifyCompliance(user, action, null, new (anchor as any).BN(amount), null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation, receipt: receiptPdaFor(bucket) }))
+			.rpc({ commitment: 'confirmed' });
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		return tx.meta.logMessages.some((line: string) => line.includes('Verification receipt hit'));
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setReceiptTtl(new (anchor as any).BN(600)).accounts({ policy, authority }).rpc();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+		// 1_000 and 1_100 share bucket 4 ([1_000, 10_000)); 20_000 is in bucket 5
+		for (const bucket of [4, 5]) {
+			await program.methods
+				.initVerificationReceipt(user, action, bucket)
+				.accounts({
+					policy,
+					receipt: receiptPdaFor(bucket),
+					payer: authority,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+		}
+	});
+
+	it('reuses a cached outcome within the same bucket', async () => {
+		expect(await verifyHits(1_000, 4)).to.equal(false);
+		expect(await verifyHits(1_100, 4)).to.equal(true);
+
+		const receipt = await program.account.verificationReceipt.fetch(receiptPdaFor(4));
+		expect(receipt.amountBucket).to.equal(4);
+		expect(receipt.validUntil.toNumber() - receipt.verifiedAt.toNumber()).to.equal(600);
+	});
+
+	it('misses for an amount in a different bucket', async () => {
+		expect(await verifyHits(20_000, 5)).to.equal(false);
+	});
+
+	it('rejects a receipt for the wrong bucket', async () => {
+		try {
+			await verifyHits(20_000, 4);
+			expect.fail('expected ConstraintSeeds');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ConstraintSeeds');
+		}
+	});
+});
+
+describe('dashboard rollup', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	const rollup = ROLLUP;
+	let state: any;
+	let alice: any;
+	let bob: any;
+	// Rollup counts before this describe's activity
+	let baseline: any;
+
+	const attestWithRollup = (wallet: any, rollupAccount: any = rollup) =>
+		program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: rollupAccount,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const setFrozen = (wallet: any, frozen: boolean) =>
+		(frozen ? program.methods.freezeWallet(wallet) : program.methods.unfreezeWallet(wallet))
+			.accounts({ state, attestation: attestationPdaFor(wallet), authority, rollup })
+			.rpc();
+
+	const getRollup = () => program.methods.getRollup().accounts({ state, rollup }).view();
+
+	// Change of each rolled-up count since `baseline`
+	const delta = async () => {
+		const counts = await getRollup();
+		return {
+			verifiedUsers: counts.verifiedUsers.toNumber() - baseline.verifiedUsers.toNumber(),
+			frozenUsers: counts.frozenUsers.toNumber() - baseline.frozenUsers.toNumber(),
+			violations: counts.violations.toNumber() - baseline.violations.toNumber(),
+			levelCounts: counts.levelCounts.map((count: any, i: number) => count.toNumber() - baseline.levelCounts[i].toNumber()),
+		};
+	};
+
+	before(async () => {
+		state = await initState();
+		baseline = await getRollup();
+		alice = (anchor as any).web3.Keypair.generate().publicKey;
+		bob = (anchor as any).web3.Keypair.generate().publicKey;
+	});
+
+	it('counts verified users per KYC level as attestations are created', async () => {
+		await attestWithRollup(alice);
+		await attestWithRollup(bob);
+		// Re-attesting an already verified wallet does not double-count it
+		await attestWithRollup(alice);
+
+		const counts = await delta();
+		expect(counts.verifiedUsers).to.equal(2);
+		expect(counts.levelCounts).to.deep.equal([0, 2, 0]);
+	});
+
+	it('tracks frozen wallets and blocks their verification', async () => {
+		await setFrozen(bob, true);
+		await setFrozen(bob, true);
+		expect((await delta()).frozenUsers).to.equal(1);
+
+		const policy = await initPolicy();
+		try {
+			await program.methods
+				.verifyCompliance(bob, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user: bob, attestation: attestationPdaFor(bob) }))
+				.rpc();
+			expect.fail('expected WalletFrozen');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('WalletFrozen');
+		}
+
+		await setFrozen(bob, false);
+		expect((await delta()).frozenUsers).to.equal(0);
+	});
+
+	it('moves revoked attestations out of the verified count', async () => {
+		await program.methods
+			.revokeKycAttestation(alice)
+			.accounts({ attestation: attestationPdaFor(alice), authority, state, rollup, journal: JOURNAL })
+			.rpc();
+
+		const counts = await delta();
+		expect(counts.verifiedUsers).to.equal(1);
+		expect(counts.levelCounts).to.deep.equal([1, 1, 0]);
+	});
+
+	it('counts recorded violations', async () => {
+		await program.methods
+			.recordViolation(bob, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				authority,
+				report: null,
+				rollup,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(bob),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		expect((await delta()).violations).to.equal(1);
+	});
+
+	it('rejects a counted instruction that skips the rollup', async () => {
+		try {
+			await attestWithRollup((anchor as any).web3.Keypair.generate().publicKey, null);
+			expect.fail('expected RollupRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RollupRequired');
+		}
+	});
+});
+
+describe('anonymous bounded usage', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setAnonymousLifetimeCap(new (anchor as any).BN(1_000)).accounts({ policy, authority }).rpc();
+	});
+
+	const verifyAnonymous = (nullifier: Buffer, amount: number, attestation: any = null) =>
+		program.methods
+			.verifyAnonymous(Array.from(nullifier), new (anchor as any).BN(amount))
+			.accounts({
+				state,
+				policy,
+				usage: (anchor as any).web3.PublicKey.findProgramAddressSync(
+					[Buffer.from('nullifier'), nullifier],
+					program.programId,
+				)[0],
+				user: authority,
+				attestation,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	it('passes without KYC up to the lifetime cap', async () => {
+		const nullifier = createHash('sha256').update('device-a').digest();
+		await verifyAnonymous(nullifier, 600);
+		await verifyAnonymous(nullifier, 400);
+	});
+
+	it('requires KYC once cumulative volume crosses the cap', async () => {
+		const nullifier = createHash('sha256').update('device-b').digest();
+		await verifyAnonymous(nullifier, 900);
+		try {
+			await verifyAnonymous(nullifier, 200);
+			expect.fail('expected AnonymousCapExceeded');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AnonymousCapExceeded');
+		}
+
+		// With a verified attestation for the signer the same usage passes
+		const attestation = await attest(state, authority);
+		await verifyAnonymous(nullifier, 200, attestation);
+	});
+});
+
+describe('attester consortium governance', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const memberA = (anchor as any).web3.Keypair.generate();
+	const outsider = (anchor as any).web3.Keypair.generate();
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+
+	let consortium: any;
+	let policy: any;
+
+	const proposalPdaFor = (revision: any) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('consortium-proposal'), policy.toBuffer(), revision.toArrayLike(Buffer, 'le', 8)],
+			program.programId,
+		)[0];
+
+	// Propose a new max risk score as the provider wallet, returning the proposal
+	const propose = async (maxRiskScore: number) => {
+		const { revision } = await program.account.compliancePolicy.fetch(policy);
+		const proposal = proposalPdaFor(revision);
+		await program.methods
+			.proposePolicyChange(maxRiskScore, true, allowed as any)
+			.accounts({
+				consortium,
+				policy,
+				proposal,
+				proposer: authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		return proposal;
+	};
+
+	const vote = (proposal: any, voter: any) =>
+		program.methods.consortiumVote().accounts({ consortium, policy, proposal, voter: voter.publicKey }).signers([voter]).rpc();
+
+	before(async () => {
+		consortium = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('consortium'), authority.toBuffer()],
+			program.programId,
+		)[0];
+		const memberB = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createConsortium([authority, memberA.publicKey, memberB], 2)
+			.accounts({ consortium, founder: authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		policy = await initPolicy();
+		await program.methods.assignPolicyConsortium().accounts({ policy, authority, consortium }).rpc();
+	});
+
+	it('applies a change once a quorum of members votes for it', async () => {
+		const proposal = await propose(2);
+		expect((await program.account.compliancePolicy.fetch(policy)).maxRiskScore).to.equal(5);
+
+		await vote(proposal, memberA);
+		expect((await program.account.compliancePolicy.fetch(policy)).maxRiskScore).to.equal(2);
+		expect((await program.account.consortiumProposal.fetch(proposal)).executed).to.equal(true);
+	});
+
+	it('rejects changes that lack a quorum', async () => {
+		try {
+			await program.methods.setPolicy(7, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+			expect.fail('expected ConsortiumVoteRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ConsortiumVoteRequired');
+		}
+
+		const proposal = await propose(7);
+		try {
+			await vote(proposal, outsider);
+			expect.fail('expected NotConsortiumMember');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('NotConsortiumMember');
+		}
+		expect((await program.account.compliancePolicy.fetch(policy)).maxRiskScore).to.equal(2);
+		expect((await program.account.consortiumProposal.fetch(proposal)).executed).to.equal(false);
+	});
+});
+
+describe('deferred oracle checks', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const action = 'swap';
+	const PASS = 0;
+	const PENDING = 2;
+
+	let state: any;
+	let policy: any;
+	let user: any;
+	let attestation: any;
+	let receipt: any;
+
+	const returnedDecision = async (sig: string) => {
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		return Buffer.from(tx.meta.returnData.data[0], 'base64')[0];
+	};
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation, receipt }))
+			.rpc({ commitment: 'confirmed' });
+
+	const finalize = () =>
+		program.methods
+			.finalizeVerification(user, action, 0)
+			.accounts({ state, policy, userRecord: null, ...riskOracleAccounts(user), receipt })
+			.rpc({ commitment: 'confirmed' });
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setReceiptTtl(new (anchor as any).BN(600)).accounts({ policy, authority }).rpc();
+		await program.methods.setDeferRiskCheck(true).accounts({ policy, authority }).rpc();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		attestation = await attest(state, user);
+		// Calls without an amount use bucket 0
+		receipt = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[
+				Buffer.from('verification-receipt'),
+				user.toBuffer(),
+				policy.toBuffer(),
+				createHash('sha256').update(action).digest(),
+				Buffer.from([0]),
+			],
+			program.programId,
+		)[0];
+		await program.methods
+			.initVerificationReceipt(user, action, 0)
+			.accounts({ policy, receipt, payer: authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+	});
+
+	it('returns Pending, then Pass once finalized', async () => {
+		expect(await returnedDecision(await verify())).to.equal(PENDING);
+		const pending = await program.account.verificationReceipt.fetch(receipt);
+		expect(pending.pendingSince.toNumber()).to.be.greaterThan(0);
+
+		expect(await returnedDecision(await finalize())).to.equal(PASS);
+		const settled = await program.account.verificationReceipt.fetch(receipt);
+		expect(settled.pendingSince.toNumber()).to.equal(0);
+		expect(settled.verifiedAt.toNumber()).to.be.greaterThan(0);
+
+		try {
+			await finalize();
+			expect.fail('expected NoPendingVerification');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('NoPendingVerification');
+		}
+	});
+
+	it('passes immediately on a cache hit', async () => {
+		expect(await returnedDecision(await verify())).to.equal(PASS);
+		expect((await program.account.verificationReceipt.fetch(receipt)).pendingSince.toNumber()).to.equal(0);
+	});
+});
+
+describe('regulatory holds', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let policy: any;
+
+	const holdAccounts = (user: any) => ({
+		state,
+		userRecord: userRecordPdaFor(user),
+		attestation: attestationPdaFor(user),
+		authority,
+		systemProgram: (anchor as any).web3.SystemProgram.programId,
+	});
+
+	const verify = (user: any, userRecord: any = userRecordPdaFor(user)) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), userRecord }))
+			.rpc();
+
+	const expectHeld = async (call: Promise<any>) => {
+		try {
+			await call;
+			expect.fail('expected RegulatoryHold');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RegulatoryHold');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('blocks a held user until the hold expires', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const expiresAt = (await chainNow()) + 3;
+		await program.methods.placeHold(user, new (anchor as any).BN(expiresAt)).accounts(holdAccounts(user)).rpc();
+
+		await expectHeld(verify(user));
+		// Leaving out the record does not skip the hold
+		await expectHeld(verify(user, null));
+
+		await sleep(5_000);
+		await verify(user);
+	});
+
+	it('releases a user when the hold is lifted early', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const expiresAt = (await chainNow()) + 3600;
+		await program.methods.placeHold(user, new (anchor as any).BN(expiresAt)).accounts(holdAccounts(user)).rpc();
+		await expectHeld(verify(user));
+
+		await program.methods.liftHold(user).accounts(holdAccounts(user)).rpc();
+		const record = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user));
+		expect(record.regulatoryHold).to.equal(false);
+		await verify(user, null);
+	});
+});
+
+describe('risk-weighted verification fee', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const baseFee = 10_000;
+
+	let state: any;
+	let policy: any;
+	let feeRecipient: any;
+
+	const verify = (user: any, userRecord: any = null) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					userRecord,
+					feeRecipient,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				}),
+			)
+			.rpc();
+
+	const feePaid = async (call: () => Promise<any>) => {
+		const start = await provider.connection.getBalance(feeRecipient);
+		await call();
+		return (await provider.connection.getBalance(feeRecipient)) - start;
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setRiskMonitor(authority).accounts({ state, authority }).rpc();
+		feeRecipient = (anchor as any).web3.Keypair.generate().publicKey;
+		// Fund the recipient so small fees do not fall under the rent-exempt minimum
+		await provider.connection.confirmTransaction(
+			await provider.connection.requestAirdrop(feeRecipient, 1_000_000_000),
+			'confirmed',
+		);
+		await program.methods
+			.setRenewalFees(new (anchor as any).BN(0), new (anchor as any).BN(0), feeRecipient)
+			.accounts({ policy, authority })
+			.rpc();
+		// Each risk point adds the full base fee again
+		await program.methods
+			.setVerificationFee(new (anchor as any).BN(baseFee), 10_000)
+			.accounts({ policy, authority })
+			.rpc();
+	});
+
+	it('charges a high-risk user proportionally more for the same action', async () => {
+		const low = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, low);
+		// The low aggregator scores every wallet 2
+		expect(await feePaid(() => verify(low))).to.equal(baseFee * 3);
+
+		const high = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, high);
+		await program.methods
+			.updateBehavioralRisk(high, 5)
+			.accounts({
+				state,
+				userRecord: userRecordPdaFor(high),
+				attestation: attestationPdaFor(high),
+				monitor: authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		expect(await feePaid(() => verify(high, userRecordPdaFor(high)))).to.equal(baseFee * 6);
+	});
+
+	it('requires the fee accounts while a fee is configured', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+				.rpc();
+			expect.fail('expected FeeAccountsRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('FeeAccountsRequired');
+		}
+	});
+
+	it('fails cleanly when the caller cannot cover the fee', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		// The caller signs and pays the fee; the provider wallet still pays for the transaction
+		const caller = (anchor as any).web3.Keypair.generate();
+		const count = (await program.account.complianceState.fetch(state)).verificationCount.toNumber();
+		const paid = await feePaid(async () => {
+			try {
+				await program.methods
+					.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+					.accounts(
+						verifyAccounts({
+							state,
+							policy,
+							authority: caller.publicKey,
+							user,
+							attestation: attestationPdaFor(user),
+							feeRecipient,
+						}),
+					)
+					.signers([caller])
+					.rpc();
+				expect.fail('expected the fee transfer to fail');
+			} catch (err: any) {
+				expect(err.logs.join('\n')).to.include('insufficient lamports');
+			}
+		});
+		expect(paid).to.equal(0);
+		expect((await program.account.complianceState.fetch(state)).verificationCount.toNumber()).to.equal(count);
+	});
+});
+
+describe('institutional sub-accounts', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let policy: any;
+	let parent: any;
+	// Two wallets of the same institution, both linked to the parent entity
+	let subaccounts: any[];
+
+	const entityFor = (subaccount: any) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('entity-hierarchy'), subaccount.toBuffer()],
+			program.programId,
+		)[0];
+
+	const verifySubaccount = (subaccount: any) =>
+		program.methods
+			.verifyCompliance(subaccount, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user: subaccount,
+					entity: entityFor(subaccount),
+					attestation: attestationPdaFor(parent),
+				}),
+			)
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		parent = (anchor as any).web3.Keypair.generate().publicKey;
+		subaccounts = [0, 1].map(() => (anchor as any).web3.Keypair.generate().publicKey);
+		await attest(state, parent);
+		for (const subaccount of subaccounts) {
+			// Each sub-account is risk-scored as its own wallet
+			await setRiskFeed(state, subaccount);
+			await program.methods
+				.linkSubaccount(subaccount)
+				.accounts({
+					state,
+					entity: entityFor(subaccount),
+					parentAttestation: attestationPdaFor(parent),
+					authority,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+		}
+	});
+
+	it('passes every sub-account through its parent entity\'s KYB', async () => {
+		for (const subaccount of subaccounts) {
+			const link = await program.account.entityHierarchy.fetch(entityFor(subaccount));
+			expect(link.parent.toBase58()).to.equal(parent.toBase58());
+			await verifySubaccount(subaccount);
+		}
+		// Neither sub-account has an attestation of its own
+		expect(await provider.connection.getAccountInfo(attestationPdaFor(subaccounts[0]))).to.equal(null);
+	});
+
+	it('fails the sub-accounts once the parent is revoked', async () => {
+		await program.methods
+			.revokeKycAttestation(parent)
+			.accounts({ attestation: attestationPdaFor(parent), authority, state, rollup: ROLLUP, journal: JOURNAL })
+			.rpc();
+		for (const subaccount of subaccounts) {
+			try {
+				await verifySubaccount(subaccount);
+				expect.fail('expected KycNotVerified');
+			} catch (err: any) {
+				expect(err.error.errorCode.code).to.equal('KycNotVerified');
+			}
+		}
+	});
+
+	it('closes the link on unlink', async () => {
+		const entity = entityFor(subaccounts[0]);
+		await program.methods.unlinkSubaccount(subaccounts[0]).accounts({ state, entity, authority }).rpc();
+		expect(await provider.connection.getAccountInfo(entity)).to.equal(null);
+	});
+});
+
+describe('verification deadlines', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (deadline: number) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, new (anchor as any).BN(deadline), false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('rejects a verification landing after its deadline', async () => {
+		try {
+			await verify((await chainNow()) - 60);
+			expect.fail('expected DeadlineExceeded');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('DeadlineExceeded');
+		}
+	});
+
+	it('passes a verification before its deadline', async () => {
+		await verify((await chainNow()) + 60);
+	});
+});
+
+describe('verification nonces', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (nonce: number) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, new (anchor as any).BN(nonce), NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), userRecord: userRecordPdaFor(user) }),
+			)
+			.rpc();
+
+	const expectReplayed = async (nonce: number) => {
+		try {
+			await verify(nonce);
+			expect.fail('expected StaleNonce');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('StaleNonce');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		// A zero behavioral report creates the user's record
+		await program.methods.setRiskMonitor(authority).accounts({ state, authority }).rpc();
+		await program.methods
+			.updateBehavioralRisk(user, 0)
+			.accounts({
+				state,
+				userRecord: userRecordPdaFor(user),
+				attestation: attestationPdaFor(user),
+				monitor: authority,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+	});
+
+	it('advances the nonce on a higher value', async () => {
+		await verify(5);
+		expect((await program.account.userComplianceRecord.fetch(userRecordPdaFor(user))).nonce.toNumber()).to.equal(5);
+		await verify(9);
+		expect((await program.account.userComplianceRecord.fetch(userRecordPdaFor(user))).nonce.toNumber()).to.equal(9);
+	});
+
+	it('rejects an equal or lower nonce', async () => {
+		await expectReplayed(9);
+		await expectReplayed(3);
+	});
+
+	it('requires the user record when a nonce is supplied', async () => {
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, new (anchor as any).BN(10), NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+				.rpc();
+			expect.fail('expected UserRecordRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UserRecordRequired');
+		}
+	});
+});
+
+describe('staged policy rollout', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+	let state: any;
+	let policy: any;
+	let rolloutBaseline: any;
+	let canary: any;
+	let holdout: any;
+
+	// Mirrors the program's cohort bucket: first 8 bytes of sha256(user), LE, mod 10000
+	const rolloutBucket = (user: any) =>
+		Number(createHash('sha256').update(user.toBuffer()).digest().readBigUInt64LE(0) % BigInt(10_000));
+
+	const walletInBuckets = (low: number, high: number) => {
+		for (;;) {
+			const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+			const bucket = rolloutBucket(wallet);
+			if (bucket >= low && bucket < high) {
+				return wallet;
+			}
+		}
+	};
+
+	const verify = (user: any, baseline: any = rolloutBaseline) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), rolloutBaseline: baseline }),
+			)
+			.rpc();
+
+	const expectCode = async (call: Promise<any>, code: string) => {
+		try {
+			await call;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		rolloutBaseline = (anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('rollout-baseline'), policy.toBuffer()],
+			program.programId,
+		)[0];
+		canary = walletInBuckets(0, 5_000);
+		holdout = walletInBuckets(5_000, 10_000);
+		await attest(state, canary);
+		await attest(state, holdout);
+
+		// Half the users get the stricter policy, which the low aggregator's score of 2 fails
+		await program.methods
+			.beginPolicyRollout(5_000)
+			.accounts({ policy, rolloutBaseline, authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		await program.methods.setPolicy(1, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+	});
+
+	it('holds each user to a stable cohort', async () => {
+		for (let i = 0; i < 2; i++) {
+			await expectCode(verify(canary), 'RiskScoreTooHigh');
+			await verify(holdout);
+		}
+	});
+
+	it('requires the baseline for users outside the cohort', async () => {
+		await expectCode(verify(holdout, null), 'RolloutBaselineRequired');
+	});
+
+	it('brings more users in as the rollout widens', async () => {
+		await program.methods.setRolloutBps(10_000).accounts({ policy, authority }).rpc();
+		await expectCode(verify(holdout), 'RiskScoreTooHigh');
+		await expectCode(verify(canary), 'RiskScoreTooHigh');
+	});
+});
+
+describe('range oracle risk lookup', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = ORACLE_FIXTURE_USER;
+	const attestation = attestationPdaFor(user);
+
+	let state: any;
+	let policy: any;
+
+	const verifyWith = (rangeOracle: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation, rangeOracle }))
+			.rpc({ commitment: 'confirmed' });
+
+	const expectOracleError = async (rangeOracle: any, code: string) => {
+		try {
+			await verifyWith(rangeOracle);
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy, authority }).rpc();
+		await attest(state, user);
+	});
+
+	it('passes with the score read from the oracle entry', async () => {
+		const sig = await verifyWith(RANGE_ORACLES.a);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+		expect(event.data.riskScore).to.equal(2);
+	});
+
+	it('rejects a user whose oracle entry is over the threshold', async () => {
+		await expectOracleError(RANGE_ORACLES.c, 'RiskScoreTooHigh');
+	});
+
+	it('rejects an account the oracle program does not own', async () => {
+		await expectOracleError(policy, 'OracleDataFetchFailed');
+	});
+});
+
+describe('attestation expiry', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+	let policy: any;
+
+	const verify = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('rejects an attestation whose expiry has passed', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user, (await chainNow()) - 60);
+		try {
+			await verify(user);
+			expect.fail('expected AttestationExpired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationExpired');
+		}
+	});
+
+	it('passes an attestation that expires in the future', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user, (await chainNow()) + 3600);
+		await verify(user);
+	});
+});
+
+describe('risk model versions', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = ORACLE_FIXTURE_USER;
+
+	let state: any;
+	let policy: any;
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), rangeOracle: RANGE_ORACLES.a }),
+			)
+			.rpc({ commitment: 'confirmed' });
+
+	const setMinVersion = (version: number) =>
+		program.methods.setMinRiskModelVersion(version).accounts({ policy, authority }).rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy, authority }).rpc();
+		await attest(state, user);
+	});
+
+	it('rejects a score from an outdated model', async () => {
+		await setMinVersion(FIXTURE_RISK_MODEL_VERSION + 1);
+		try {
+			await verify();
+			expect.fail('expected RiskModelOutdated');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskModelOutdated');
+		}
+	});
+
+	it('accepts a current model and echoes its version', async () => {
+		await setMinVersion(FIXTURE_RISK_MODEL_VERSION);
+		const [event] = (await eventsOf(await verify())).filter((e) => e.name === 'verificationEvent');
+		expect(event.data.riskModelVersion).to.equal(FIXTURE_RISK_MODEL_VERSION);
+	});
+});
+
+describe('jurisdiction data retention', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const SHORT_RETENTION = 41;
+	const LONG_RETENTION = 42;
+
+	let state: any;
+
+	const jurisdictionPda = (jurisdiction: number) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('jurisdiction'), Buffer.from([jurisdiction])],
+			program.programId,
+		)[0];
+
+	const attestIn = async (jurisdiction: number) => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(wallet, true, jurisdiction, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, wallet);
+		return wallet;
+	};
+
+	const purge = (wallet: any, jurisdiction: number) =>
+		program.methods
+			.purgeAttestation(wallet)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				jurisdictionMeta: jurisdictionPda(jurisdiction),
+				attester: authority,
+				state,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+			})
+			.rpc({ commitment: 'confirmed' });
+
+	before(async () => {
+		state = await initState();
+		for (const [jurisdiction, retention] of [
+			[SHORT_RETENTION, 1],
+			[LONG_RETENTION, 3600],
+		]) {
+			await program.methods
+				.setJurisdictionRetention(jurisdiction, new (anchor as any).BN(retention))
+				.accounts({
+					jurisdictionMeta: jurisdictionPda(jurisdiction),
+					state,
+					authority,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc();
+		}
+	});
+
+	it('purges an attestation past a short retention period', async () => {
+		const wallet = await attestIn(SHORT_RETENTION);
+		await sleep(3_000);
+		const sig = await purge(wallet, SHORT_RETENTION);
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'dataPurgedEvent');
+		expect(event.data.wallet.toString()).to.equal(wallet.toString());
+		expect(await provider.connection.getAccountInfo(attestationPdaFor(wallet))).to.equal(null);
+	});
+
+	it('keeps an attestation within a long retention period', async () => {
+		const wallet = await attestIn(LONG_RETENTION);
+		try {
+			await purge(wallet, LONG_RETENTION);
+			expect.fail('expected RetentionNotElapsed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RetentionNotElapsed');
+		}
+	});
+});
+
+describe('closing attestations', () => {
+	const authority = (provider as any).wallet.publicKey;
+
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('returns the rent to the attester and removes the account', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, wallet);
+		const rent = (await provider.connection.getAccountInfo(attestation)).lamports;
+		const startBalance = await provider.connection.getBalance(authority);
+
+		const sig = await program.methods
+			.closeKycAttestation(wallet)
+			.accounts({ attestation, authority, state, rollup: ROLLUP, journal: JOURNAL })
+			.rpc({ commitment: 'confirmed' });
+
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		const endBalance = await provider.connection.getBalance(authority, 'confirmed');
+		expect(endBalance - startBalance + tx.meta.fee).to.equal(rent);
+		expect(await provider.connection.getAccountInfo(attestation)).to.equal(null);
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'kycAttestationClosedEvent');
+		expect(event.data.wallet.toString()).to.equal(wallet.toString());
+	});
+
+	it('rejects a signer other than the attester', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, wallet);
+		const stranger = (anchor as any).web3.Keypair.generate();
+		await provider.connection.confirmTransaction(
+			await provider.connection.requestAirdrop(stranger.publicKey, 1_000_000_000),
+			'confirmed',
+		);
+		try {
+			await program.methods
+				.closeKycAttestation(wallet)
+				.accounts({ attestation, authority: stranger.publicKey, state, rollup: ROLLUP, journal: JOURNAL })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('underwriting signal', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = (anchor as any).web3.Keypair.generate().publicKey;
+	const userRecord = userRecordPdaFor(user);
+	let state: any;
+	let attestation: any;
+
+	before(async () => {
+		state = await initState();
+		attestation = await attest(state, user);
+		await program.methods.setRiskMonitor(authority).accounts({ state, authority }).rpc();
+	});
+
+	const signal = (record: any = userRecord) =>
+		program.methods
+			.getUnderwritingSignal(user)
+			.accounts({ attestation, userRecord: record, ...riskOracleAccounts(user) })
+			.view();
+
+	it('scores a fresh attestation with no history', async () => {
+		const result = await signal(null);
+		expect(result.kycLevel).to.equal(1);
+		expect(result.kycComponent).to.equal(125);
+		expect(result.ageComponent).to.equal(0);
+		expect(result.violationCount).to.equal(0);
+		expect(result.violationComponent).to.equal(250);
+		// The low aggregator scores 2
+		expect(result.riskScore).to.equal(2);
+		expect(result.riskComponent).to.equal(200);
+		expect(result.score).to.equal(125 + 0 + 250 + 200);
+	});
+
+	it('reflects elevated behavioral risk', async () => {
+		await program.methods
+			.updateBehavioralRisk(user, 4)
+			.accounts({ state, userRecord, attestation, monitor: authority, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		const result = await signal();
+		expect(result.riskScore).to.equal(4);
+		expect(result.riskComponent).to.equal(150);
+	});
+
+	it('reflects recorded violations', async () => {
+		await program.methods
+			.recordViolation(user, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				authority,
+				report: null,
+				rollup: ROLLUP,
+				userRecord,
+				policy: null,
+				userViolations: userViolationsPdaFor(user),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		const result = await signal();
+		expect(result.violationCount).to.equal(1);
+		expect(result.violationComponent).to.equal(200);
+		expect((await program.account.userComplianceRecord.fetch(userRecord)).violationCount).to.equal(1);
+	});
+
+	it('reflects the attestation ageing', async () => {
+		const first = await signal();
+		await sleep(2000);
+		const second = await signal();
+		expect(second.attestationAge.toNumber()).to.be.greaterThan(first.attestationAge.toNumber());
+	});
+
+	it('drops the KYC component once revoked', async () => {
+		await program.methods.revokeKycAttestation(user).accounts({ attestation, authority, state, rollup: ROLLUP, journal: JOURNAL }).rpc();
+		const result = await signal();
+		expect(result.kycLevel).to.equal(0);
+		expect(result.kycComponent).to.equal(0);
+		expect(result.score).to.equal(0 + result.ageComponent + 200 + 150);
+	});
+});
+
+describe('authority transfer', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const successor = (anchor as any).web3.Keypair.generate();
+	const stranger = (anchor as any).web3.Keypair.generate();
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+	});
+
+	// Later tests administer the shared state as the provider wallet
+	after(async () => {
+		await program.methods
+			.transferAuthority(authority)
+			.accounts({ state, authority: successor.publicKey })
+			.signers([successor])
+			.rpc();
+		await program.methods.acceptAuthority().accounts({ state, newAuthority: authority }).rpc();
+	});
+
+	it('rejects a proposal from a non-authority', async () => {
+		try {
+			await program.methods
+				.transferAuthority(stranger.publicKey)
+				.accounts({ state, authority: stranger.publicKey })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('only lets the proposed key accept', async () => {
+		await program.methods.transferAuthority(successor.publicKey).accounts({ state, authority }).rpc();
+		const pending = await program.account.complianceState.fetch(state);
+		expect(pending.authority.toBase58()).to.equal(authority.toBase58());
+		expect(pending.pendingAuthority.toBase58()).to.equal(successor.publicKey.toBase58());
+
+		try {
+			await program.methods
+				.acceptAuthority()
+				.accounts({ state, newAuthority: stranger.publicKey })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('hands over the state once accepted', async () => {
+		const sig = await program.methods
+			.acceptAuthority()
+			.accounts({ state, newAuthority: successor.publicKey })
+			.signers([successor])
+			.rpc({ commitment: 'confirmed' });
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'authorityTransferredEvent');
+		expect(event.data.old.toBase58()).to.equal(authority.toBase58());
+		expect(event.data.new.toBase58()).to.equal(successor.publicKey.toBase58());
+
+		const account = await program.account.complianceState.fetch(state);
+		expect(account.authority.toBase58()).to.equal(successor.publicKey.toBase58());
+		expect(account.pendingAuthority.toBase58()).to.equal((anchor as any).web3.PublicKey.default.toBase58());
+
+		// The previous authority no longer administers the state
+		try {
+			await program.methods.setRiskMonitor(authority).accounts({ state, authority }).rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('jurisdiction schemes', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = (anchor as any).web3.Keypair.generate().publicKey;
+	const attestation = attestationPdaFor(user);
+	let state: any;
+	let policy: any;
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await attest(state, user);
+	});
+
+	it('verifies when both use the default scheme', async () => {
+		await verify();
+	});
+
+	it('rejects an attestation coded in another scheme', async () => {
+		await program.methods.setAttestationJurisdictionScheme(user, 1).accounts({ attestation, authority }).rpc();
+		try {
+			await verify();
+			expect.fail('expected JurisdictionSchemeMismatch');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('JurisdictionSchemeMismatch');
+		}
+	});
+
+	it('verifies again once the policy moves to the same scheme', async () => {
+		await program.methods.setJurisdictionScheme(1).accounts({ policy, authority }).rpc();
+		expect((await program.account.compliancePolicy.fetch(policy)).jurisdictionScheme).to.equal(1);
+		await verify();
+	});
+
+	it('only lets the attestation authority set its scheme', async () => {
+		const stranger = (anchor as any).web3.Keypair.generate();
+		try {
+			await program.methods
+				.setAttestationJurisdictionScheme(user, 2)
+				.accounts({ attestation, authority: stranger.publicKey })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('supporting document minimums', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const fresh = () => (anchor as any).web3.Keypair.generate().publicKey;
+	const issue = (wallet: any, level: number, documents: number, policyAccount: any = policy) =>
+		program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, documents, level)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: policyAccount,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const expectError = async (promise: Promise<any>, code: string) => {
+		try {
+			await promise;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	// Level 0: none, level 1: 2, level 2: 3, level 3 (accredited): 5
+	const minimums = [0, 2, 3, 5];
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setMinDocumentsForLevel(minimums).accounts({ policy, authority }).rpc();
+		await program.methods.setIssuancePolicy(policy).accounts({ state, authority }).rpc();
+	});
+
+	after(async () => {
+		await program.methods
+			.setIssuancePolicy((anchor as any).web3.PublicKey.default)
+			.accounts({ state, authority })
+			.rpc();
+	});
+
+	it('issues a level 0 attestation with no documents', async () => {
+		await issue(fresh(), 0, 0);
+	});
+
+	[1, 2, 3].forEach((level) => {
+		it(`rejects a level ${level} attestation with too few reviewed documents`, async () => {
+			await expectError(issue(fresh(), level, minimums[level] - 1), 'InsufficientDocumentsReviewed');
+		});
+
+		it(`issues a level ${level} attestation once its minimum is met`, async () => {
+			const wallet = fresh();
+			await issue(wallet, level, minimums[level]);
+			const account = await program.account.kycAttestation.fetch(attestationPdaFor(wallet));
+			expect(account.level).to.equal(level);
+			expect(account.documentsReviewed).to.equal(minimums[level]);
+		});
+	});
+
+	it('rejects a level beyond the configured levels', async () => {
+		await expectError(issue(fresh(), 4, 10), 'InvalidKycLevel');
+	});
+
+	it('rejects issuance that leaves out the issuance policy', async () => {
+		await expectError(issue(fresh(), 3, 0, null), 'IssuancePolicyRequired');
+	});
+
+	it('rejects issuance under a policy other than the issuance policy', async () => {
+		await expectError(issue(fresh(), 3, 0, await initPolicy()), 'IssuancePolicyRequired');
+	});
+
+	it('rejects a batch that leaves out the issuance policy', async () => {
+		const wallet = fresh();
+		await expectError(
+			program.methods
+				.createKycAttestationsBatch([{ wallet, isVerified: true, jurisdiction: 0 }])
+				.accounts({
+					authority,
+					attestorEntry: attestorPdaFor(authority),
+					state,
+					rollup: ROLLUP,
+					journal: JOURNAL,
+					policy: null,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.remainingAccounts([{ pubkey: attestationPdaFor(wallet), isSigner: false, isWritable: true }])
+				.rpc(),
+			'IssuancePolicyRequired',
+		);
+	});
+});
+
+describe('global pause', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = (anchor as any).web3.Keypair.generate().publicKey;
+	let state: any;
+	let policy: any;
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await attest(state, user);
+	});
+
+	// Later tests verify against the shared state
+	after(async () => {
+		await program.methods.setPaused(false).accounts({ state, authority }).rpc();
+	});
+
+	it('blocks verification while paused', async () => {
+		const sig = await program.methods.setPaused(true).accounts({ state, authority }).rpc({ commitment: 'confirmed' });
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'pauseStateChangedEvent');
+		expect(event.data.paused).to.equal(true);
+		expect((await program.account.complianceState.fetch(state)).paused).to.equal(true);
+
+		try {
+			await verify();
+			expect.fail('expected ProgramPaused');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ProgramPaused');
+		}
+	});
+
+	it('restores verification once unpaused', async () => {
+		await program.methods.setPaused(false).accounts({ state, authority }).rpc();
+		await verify();
+	});
+
+	it('only lets the state authority pause', async () => {
+		const stranger = (anchor as any).web3.Keypair.generate();
+		try {
+			await program.methods
+				.setPaused(true)
+				.accounts({ state, authority: stranger.publicKey })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('JSON outcome logs', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	// JSON objects the program logged in a confirmed transaction
+	const jsonLogs = async (signature: string) => {
+		const tx = await provider.connection.getTransaction(signature, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		return tx.meta.logMessages
+			.filter((line: string) => line.startsWith('Program log: {'))
+			.map((line: string) => JSON.parse(line.slice('Program log: '.length)));
+	};
+
+	const verify = async (action: string) => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = await attest(state, user);
+		const sig = await program.methods
+			.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation }))
+			.rpc({ commitment: 'confirmed' });
+		return { user, logs: await jsonLogs(sig) };
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('logs nothing extra by default', async () => {
+		expect((await verify('swap')).logs).to.have.length(0);
+	});
+
+	it('logs one parseable JSON line per verification when enabled', async () => {
+		await program.methods.setEmitJsonLog(true).accounts({ policy, authority }).rpc();
+		// A quote in the action must not break the JSON
+		const { user, logs } = await verify('swap "exact in"');
+		expect(logs).to.deep.equal([{ user: user.toBase58(), action: 'swap "exact in"', result: true, risk: 2 }]);
+	});
+});
+
+describe('full jurisdiction code range', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const attestIn = async (jurisdiction: number) => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(wallet, true, jurisdiction, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, wallet);
+		return wallet;
+	};
+
+	const verify = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		// Allow jurisdiction 200: byte 25, bit 0
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[200 >> 3] = 1 << (200 % 8);
+		await program.methods.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+	});
+
+	it('verifies an explicitly allowed high jurisdiction code', async () => {
+		await verify(await attestIn(200));
+	});
+
+	it('still restricts neighbouring codes that are not allowed', async () => {
+		const user = await attestIn(201);
+		try {
+			await verify(user);
+			expect.fail('expected RestrictedJurisdiction');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RestrictedJurisdiction');
+		}
+	});
+});
+
+describe('treasury operations', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const BN = (anchor as any).BN;
+	const operator = (anchor as any).web3.Keypair.generate();
+	let state: any;
+	let treasuryPolicy: any;
+
+	const treasuryOp = (amount: number, policy: any = treasuryPolicy) =>
+		program.methods
+			.verifyTreasuryOp('rebalance', new BN(amount))
+			.accounts({
+				state,
+				policy,
+				operator: operator.publicKey,
+				attestation: attestationPdaFor(operator.publicKey),
+				userRecord: null,
+				...riskOracleAccounts(operator.publicKey),
+			})
+			.signers([operator])
+			.rpc({ commitment: 'confirmed' });
+
+	before(async () => {
+		state = await initState();
+		// Tighter than the user-facing policies: risk 3 and a 1,000 unit cap
+		treasuryPolicy = await initPolicy(3);
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		await program.methods
+			.setPolicy(3, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, new BN(1_000), false)
+			.accounts({ policy: treasuryPolicy, authority })
+			.rpc();
+		await program.methods.setTreasuryPolicy(treasuryPolicy).accounts({ state, authority }).rpc();
+		await attest(state, operator.publicKey);
+	});
+
+	it('approves and logs an operation within the treasury policy', async () => {
+		const sig = await treasuryOp(1_000);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'treasuryOperationEvent');
+		expect(event.data.operator.toBase58()).to.equal(operator.publicKey.toBase58());
+		expect(event.data.operation).to.equal('rebalance');
+		expect(event.data.amount.toNumber()).to.equal(1_000);
+	});
+
+	it('rejects an operation over the treasury amount cap', async () => {
+		try {
+			await treasuryOp(1_001);
+			expect.fail('expected AmountExceedsLimit');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AmountExceedsLimit');
+		}
+	});
+
+	it('only checks against the designated treasury policy', async () => {
+		const userPolicy = await initPolicy();
+		try {
+			await treasuryOp(1, userPolicy);
+			expect.fail('expected NotTreasuryPolicy');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('NotTreasuryPolicy');
+		}
+	});
+});
+
+describe('single jurisdiction toggles', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let policy: any;
+
+	const bitmap = async (): Promise<number[]> =>
+		Array.from((await (program.account as any).compliancePolicy.fetch(policy)).allowedJurisdictions);
+
+	before(async () => {
+		await initState();
+		policy = await initPolicy();
+		// Allow jurisdictions 0, 9 and 200
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 0b1;
+		allowed[1] = 0b10;
+		allowed[25] = 0b1;
+		await program.methods.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+	});
+
+	it('allows one jurisdiction without touching the others', async () => {
+		const prior = await bitmap();
+		const sig = await program.methods.setJurisdictionAllowed(13, true).accounts({ policy, authority }).rpc();
+
+		const expected = [...prior];
+		expected[1] |= 1 << 5;
+		expect(await bitmap()).to.deep.equal(expected);
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'policyUpdatedEvent');
+		expect(event.data.policy.toBase58()).to.equal(policy.toBase58());
+		expect(Array.from(event.data.allowedJurisdictions)).to.deep.equal(expected);
+	});
+
+	it('disallows one jurisdiction without touching the others', async () => {
+		const prior = await bitmap();
+		await program.methods.setJurisdictionAllowed(9, false).accounts({ policy, authority }).rpc();
+
+		const expected = [...prior];
+		expected[1] &= ~0b10;
+		expect(await bitmap()).to.deep.equal(expected);
+		expect(expected[0]).to.equal(0b1);
+		expect(expected[25]).to.equal(0b1);
+	});
+
+	it('rejects toggles from anyone but the policy authority', async () => {
+		const outsider = (anchor as any).web3.Keypair.generate();
+		try {
+			await program.methods
+				.setJurisdictionAllowed(1, true)
+				.accounts({ policy, authority: outsider.publicKey })
+				.signers([outsider])
+				.rpc();
+			expect.fail('expected the toggle to be rejected');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('attester trust weights', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const lowTrust = [web3.Keypair.generate(), web3.Keypair.generate()];
+	let state: any;
+	let policy: any;
+
+	const profileFor = (attester: any) =>
+		web3.PublicKey.findProgramAddressSync([Buffer.from('attester'), attester.toBuffer()], program.programId)[0];
+
+	const setTrust = (attester: any, weight: number) =>
+		program.methods
+			.setAttesterTrustWeight(attester, weight)
+			.accounts({
+				attesterProfile: profileFor(attester),
+				state,
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const cosign = (wallet: any, cosigner: any) =>
+		program.methods
+			.cosignAttestation(wallet)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				attesterProfile: profileFor(cosigner.publicKey),
+				cosigner: cosigner.publicKey,
+			})
+			.signers([cosigner])
+			.rpc();
+
+	const verify = (user: any, attesters: any[]) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.remainingAccounts(attesters.map((a) => ({ pubkey: profileFor(a), isSigner: false, isWritable: false })))
+			.rpc();
+
+	const expectInsufficientTrust = async (call: Promise<any>) => {
+		try {
+			await call;
+			expect.fail('expected InsufficientAttesterTrust');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InsufficientAttesterTrust');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setMinTrustWeight(10).accounts({ policy, authority }).rpc();
+		for (const attester of lowTrust) {
+			await setTrust(attester.publicKey, 4);
+		}
+	});
+
+	after(async () => {
+		await setTrust(authority, 0);
+	});
+
+	it('accepts an attestation from a single high-trust attester', async () => {
+		await setTrust(authority, 10);
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await verify(user, [authority]);
+	});
+
+	it('rejects an attestation from a single low-trust attester', async () => {
+		await setTrust(authority, 3);
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await expectInsufficientTrust(verify(user, [authority]));
+	});
+
+	it('adds up the weights of low-trust co-signers', async () => {
+		await setTrust(authority, 3);
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await cosign(user, lowTrust[0]);
+		await expectInsufficientTrust(verify(user, [authority, lowTrust[0].publicKey]));
+
+		await cosign(user, lowTrust[1]);
+		await verify(user, [authority, lowTrust[0].publicKey, lowTrust[1].publicKey]);
+	});
+
+	it('counts each signer once and ignores attesters that did not sign', async () => {
+		await setTrust(authority, 3);
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await cosign(user, lowTrust[0]);
+		const p = lowTrust[0].publicKey;
+		await expectInsufficientTrust(verify(user, [authority, p, p]));
+		await expectInsufficientTrust(verify(user, [authority, p, lowTrust[1].publicKey]));
+	});
+
+	it('rejects a second signature from the same attester', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await cosign(user, lowTrust[0]);
+		try {
+			await cosign(user, lowTrust[0]);
+			expect.fail('expected AlreadyCosigned');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AlreadyCosigned');
+		}
+	});
+});
+
+describe('policy update events', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let policy: any;
+
+	before(async () => {
+		await initState();
+		policy = await initPolicy();
+	});
+
+	it('emits the values set_policy was called with', async () => {
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 0b101;
+		allowed[31] = 0x80;
+		const sig = await program.methods.setPolicy(7, false, allowed as any, 3, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'policyUpdatedEvent');
+		expect(event.data.policy.toBase58()).to.equal(policy.toBase58());
+		expect(event.data.authority.toBase58()).to.equal(authority.toBase58());
+		expect(event.data.maxRiskScore).to.equal(7);
+		expect(event.data.requireKyc).to.equal(false);
+		expect(Array.from(event.data.allowedJurisdictions)).to.deep.equal(allowed);
+		expect(event.data.minKycLevel).to.equal(3);
+
+		const account = await (program.account as any).compliancePolicy.fetch(policy);
+		expect(event.data.revision.toString()).to.equal(account.revision.toString());
+	});
+});
+
+describe('verification fee ledger', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const baseFee = 10_000;
+	let state: any;
+	let policy: any;
+	let feeRecipient: any;
+	let feeLedger: any;
+
+	const verify = (user: any, ledger: any = feeLedger) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					feeRecipient,
+					feeLedger: ledger,
+					systemProgram: web3.SystemProgram.programId,
+				}),
+			)
+			.rpc();
+
+	const freshUser = async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		return user;
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		feeRecipient = web3.Keypair.generate().publicKey;
+		// Fund the recipient so small fees do not fall under the rent-exempt minimum
+		await provider.connection.confirmTransaction(
+			await provider.connection.requestAirdrop(feeRecipient, 1_000_000_000),
+			'confirmed',
+		);
+		await program.methods
+			.setRenewalFees(new (anchor as any).BN(0), new (anchor as any).BN(0), feeRecipient)
+			.accounts({ policy, authority })
+			.rpc();
+		await program.methods.setVerificationFee(new (anchor as any).BN(baseFee), 0).accounts({ policy, authority }).rpc();
+
+		[feeLedger] = web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('fee-ledger'), policy.toBuffer()],
+			program.programId,
+		);
+		await program.methods
+			.initFeeLedger()
+			.accounts({ policy, feeLedger, authority, systemProgram: web3.SystemProgram.programId })
+			.rpc();
+	});
+
+	it('accumulates fees across fee-paying verifications', async () => {
+		const start = await provider.connection.getBalance(feeRecipient);
+		for (let i = 0; i < 3; i++) {
+			await verify(await freshUser());
+		}
+
+		const ledger = await program.methods.getFeeLedger().accounts({ policy, feeLedger }).view();
+		expect(ledger.policy.toBase58()).to.equal(policy.toBase58());
+		expect(ledger.feesCollected.toNumber()).to.equal(baseFee * 3);
+		expect(ledger.feePayerCount.toNumber()).to.equal(3);
+		expect(ledger.lastPayer.toBase58()).to.equal(authority.toBase58());
+		expect(ledger.lastFee.toNumber()).to.equal(baseFee);
+		// The ledger agrees with what the recipient actually received
+		expect((await provider.connection.getBalance(feeRecipient)) - start).to.equal(ledger.feesCollected.toNumber());
+	});
+
+	it('requires the ledger on fee-paying verifications once it exists', async () => {
+		try {
+			await verify(await freshUser(), null);
+			expect.fail('expected FeeLedgerRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('FeeLedgerRequired');
+		}
+	});
+});
+
+describe('counterparty-scoped attestations', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const allowed = web3.Keypair.generate().publicKey;
+	const disallowed = web3.Keypair.generate().publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (counterparty: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, counterparty, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	const scope = (counterparties: any[]) =>
+		program.methods
+			.setAllowedCounterparties(user, counterparties)
+			.accounts({ attestation: attestationPdaFor(user), authority })
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('accepts any counterparty while the attestation is unscoped', async () => {
+		await verify(disallowed);
+	});
+
+	it('accepts a counterparty the attestation is scoped to', async () => {
+		await scope([allowed]);
+		await verify(allowed);
+	});
+
+	it('rejects a counterparty outside the scope', async () => {
+		await scope([allowed]);
+		for (const counterparty of [disallowed, NO_COUNTERPARTY]) {
+			try {
+				await verify(counterparty);
+				expect.fail('expected CounterpartyNotAllowed');
+			} catch (err: any) {
+				expect(err.error.errorCode.code).to.equal('CounterpartyNotAllowed');
+			}
+		}
+	});
+
+	it('lifts the scope when the list is cleared', async () => {
+		await scope([]);
+		await verify(disallowed);
+	});
+});
+
+describe('per-user verification history', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const verify = (user: any, action: string) =>
+		program.methods
+			.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					userRecord: userRecordPdaFor(user),
+				}),
+			)
+			.rpc();
+
+	const recordOf = (user: any) => (program.account as any).userComplianceRecord.fetch(userRecordPdaFor(user));
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('creates the record on the first pass and counts every pass after it', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+
+		await verify(user, 'swap');
+		let record = await recordOf(user);
+		expect(record.user.toBase58()).to.equal(user.toBase58());
+		expect(record.verificationCount.toNumber()).to.equal(1);
+		expect(record.lastAction).to.equal('swap');
+		// The low aggregator scores every wallet 2
+		expect(record.lastRiskScore).to.equal(2);
+		expect(record.lastVerifiedAt.toNumber()).to.be.greaterThan(0);
+
+		await verify(user, 'lend');
+		await verify(user, 'withdraw');
+		record = await recordOf(user);
+		expect(record.verificationCount.toNumber()).to.equal(3);
+		expect(record.lastAction).to.equal('withdraw');
+	});
+
+	it('keeps only a bounded prefix of long actions', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await verify(user, 'x'.repeat(40));
+		expect((await recordOf(user)).lastAction).to.equal('x'.repeat(32));
+	});
+
+	it('rejects an action longer than the program accepts', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await verify(user, 'x'.repeat(64));
+		try {
+			await verify(user, 'x'.repeat(65));
+			expect.fail('expected StringTooLong');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('StringTooLong');
+		}
+	});
+
+	it('leaves the record alone when a verification fails', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await verify(user, 'swap');
+		await program.methods.freezeWallet(user).accounts({ state, attestation: attestationPdaFor(user), authority, rollup: ROLLUP }).rpc();
+		try {
+			await verify(user, 'lend');
+			expect.fail('expected WalletFrozen');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('WalletFrozen');
+		}
+		const record = await recordOf(user);
+		expect(record.verificationCount.toNumber()).to.equal(1);
+		expect(record.lastAction).to.equal('swap');
+	});
+});
+
+describe('risk freeze hysteresis', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	// Freeze above 6, lift only below 3
+	const FREEZE = 6;
+	const UNFREEZE = 3;
+	let state: any;
+	let policy: any;
+
+	const setRisk = (wallet: any, score: number) =>
+		program.methods
+			.updateBehavioralRisk(wallet, score)
+			.accounts({
+				state,
+				userRecord: userRecordPdaFor(wallet),
+				attestation: attestationPdaFor(wallet),
+				monitor: authority,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const refresh = (wallet: any) =>
+		program.methods
+			.refreshRiskFreeze(wallet)
+			.accounts({
+				state,
+				policy,
+				attestation: attestationPdaFor(wallet),
+				userRecord: userRecordPdaFor(wallet),
+				...riskOracleAccounts(wallet),
+				rollup: ROLLUP,
+			})
+			.rpc();
+
+	const flagsOf = async (wallet: any) => (await (program.account as any).kycAttestation.fetch(attestationPdaFor(wallet))).flags;
+	const frozen = async (wallet: any) => ((await flagsOf(wallet)) & ATTESTATION_FLAG_FROZEN) !== 0;
+
+	const freshWallet = async () => {
+		const wallet = web3.Keypair.generate().publicKey;
+		await attest(state, wallet);
+		return wallet;
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setRiskMonitor(authority).accounts({ state, authority }).rpc();
+		await program.methods.setFreezeThresholds(FREEZE, UNFREEZE).accounts({ policy, authority }).rpc();
+	});
+
+	it('keeps a frozen wallet frozen while its score sits between the thresholds', async () => {
+		const wallet = await freshWallet();
+		await setRisk(wallet, 8);
+		await refresh(wallet);
+		expect(await frozen(wallet)).to.equal(true);
+
+		await setRisk(wallet, 5);
+		await refresh(wallet);
+		expect(await frozen(wallet)).to.equal(true);
+
+		// Dropping below the unfreeze threshold lifts it; the low aggregator scores 2
+		await setRisk(wallet, 0);
+		await refresh(wallet);
+		expect(await frozen(wallet)).to.equal(false);
+	});
+
+	it('does not freeze an unfrozen wallet whose score sits between the thresholds', async () => {
+		const wallet = await freshWallet();
+		await setRisk(wallet, 5);
+		await refresh(wallet);
+		expect(await frozen(wallet)).to.equal(false);
+
+		// Exactly at the freeze threshold is not above it
+		await setRisk(wallet, FREEZE);
+		await refresh(wallet);
+		expect(await frozen(wallet)).to.equal(false);
+	});
+
+	it('leaves an operator freeze in place however low the score', async () => {
+		const wallet = await freshWallet();
+		await setRisk(wallet, 0);
+		await program.methods
+			.freezeWallet(wallet)
+			.accounts({ state, attestation: attestationPdaFor(wallet), authority, rollup: ROLLUP })
+			.rpc();
+		await refresh(wallet);
+		expect(await frozen(wallet)).to.equal(true);
+	});
+
+	it('rejects an unfreeze threshold above the freeze threshold', async () => {
+		try {
+			await program.methods.setFreezeThresholds(3, 6).accounts({ policy, authority }).rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('per-user violation records', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	const recordViolation = (user: any, reason: string, category = VIOLATION_CATEGORY_RISK) =>
+		program.methods
+			.recordViolation(user, reason, category)
+			.accounts({
+				state,
+				authority,
+				report: null,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(user),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+	const violationsOf = (user: any) => (program.account as any).userViolationRecord.fetch(userViolationsPdaFor(user));
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('counts repeat violations against the same user', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const globalBefore = (await program.account.complianceState.fetch(state)).violationCount.toNumber();
+
+		await recordViolation(user, 'Risk score too high');
+		await recordViolation(user, 'Restricted jurisdiction', VIOLATION_CATEGORY_JURISDICTION);
+
+		const record = await violationsOf(user);
+		expect(record.user.toBase58()).to.equal(user.toBase58());
+		expect(record.count.toNumber()).to.equal(2);
+		expect(record.lastReason).to.equal('Restricted jurisdiction');
+		expect(record.lastCategory).to.equal(VIOLATION_CATEGORY_JURISDICTION);
+		expect(record.lastViolationAt.toNumber()).to.be.greaterThan(0);
+
+		// The global counter keeps counting as well
+		const globalAfter = (await program.account.complianceState.fetch(state)).violationCount.toNumber();
+		expect(globalAfter - globalBefore).to.equal(2);
+	});
+
+	it("keeps each user's count separate", async () => {
+		const first = (anchor as any).web3.Keypair.generate().publicKey;
+		const second = (anchor as any).web3.Keypair.generate().publicKey;
+		await recordViolation(first, 'Risk score too high');
+		await recordViolation(second, 'Risk score too high');
+		expect((await violationsOf(first)).count.toNumber()).to.equal(1);
+		expect((await violationsOf(second)).count.toNumber()).to.equal(1);
+	});
+
+	it('carries the category in the violation event', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const sig = await recordViolation(user, 'Listed on OFAC', VIOLATION_CATEGORY_SANCTION);
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'violationEvent');
+		expect(event.data.user.toBase58()).to.equal(user.toBase58());
+		expect(event.data.reason).to.equal('Listed on OFAC');
+		expect(event.data.category).to.equal(VIOLATION_CATEGORY_SANCTION);
+	});
+
+	it('rejects an unknown category', async () => {
+		try {
+			await recordViolation((anchor as any).web3.Keypair.generate().publicKey, 'Other', 4);
+			expect.fail('expected InvalidViolationCategory');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidViolationCategory');
+		}
+	});
+
+	it('rejects a reason longer than the program accepts', async () => {
+		try {
+			await recordViolation((anchor as any).web3.Keypair.generate().publicKey, 'x'.repeat(257), VIOLATION_CATEGORY_RISK);
+			expect.fail('expected StringTooLong');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('StringTooLong');
+		}
+	});
+});
+
+describe('read-only compliance check', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const stub = (anchor as any).workspace.GuardStub as any;
+	let state: any;
+	let policy: any;
+
+	// The stub CPIs into check_compliance and decodes the return data it leaves behind
+	const query = (user: any) =>
+		stub.methods
+			.queryCompliance(user, 'swap')
+			.accounts({
+				complifiState: state,
+				policy,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				attestorEntry: null,
+				complifiProgram: program.programId,
+				...riskOracleAccounts(user),
+			})
+			.view();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('reports a pass with the risk score for a compliant user', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const result = await query(user);
+		expect(result.passed).to.equal(true);
+		// The low aggregator scores every wallet 2
+		expect(result.riskScore).to.equal(2);
+		expect(result.reasonCode).to.equal(0);
+	});
+
+	it('reports the failure reason for an unverified user', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, false, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+		const result = await query(user);
+		expect(result.passed).to.equal(false);
+		expect(result.reasonCode).to.equal(reasonCode('KycNotVerified'));
+	});
+
+	it('leaves the verification count untouched', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const count = (await program.account.complianceState.fetch(state)).verificationCount.toNumber();
+		await stub.methods
+			.queryCompliance(user, 'swap')
+			.accounts({
+				complifiState: state,
+				policy,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				attestorEntry: null,
+				complifiProgram: program.programId,
+				...riskOracleAccounts(user),
+			})
+			.rpc();
+		expect((await program.account.complianceState.fetch(state)).verificationCount.toNumber()).to.equal(count);
+	});
+});
+
+describe('attestation journal', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const systemProgram = (anchor as any).web3.SystemProgram.programId;
+	const JOURNAL_OP = { create: 1, update: 2, revoke: 3, close: 4 };
+	let state: any;
+
+	const journalHead = () => program.methods.getJournalHead().accounts({ state, journal: JOURNAL }).view();
+
+	const createAttestation = (wallet: any, journal: any = JOURNAL) =>
+		program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal,
+				kycRequest: null,
+				systemProgram,
+			})
+			.rpc({ commitment: 'confirmed' });
+
+	const journaled = async (sig: string) => {
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'attestationJournaledEvent');
+		return event.data;
+	};
+
+	// Link hash of an attestation as the program computes it
+	const recordHash = async (wallet: any) => {
+		const info = await provider.connection.getAccountInfo(attestationPdaFor(wallet), 'confirmed');
+		return Array.from(createHash('sha256').update(info!.data.subarray(8)).digest());
+	};
+
+	// Off-chain replay of one journal entry
+	const chain = (head: number[], entry: any) =>
+		Array.from(
+			createHash('sha256')
+				.update(
+					Buffer.concat([
+						Buffer.from(head),
+						entry.sequence.toArrayLike(Buffer, 'le', 8),
+						Buffer.from([entry.operation]),
+						entry.wallet.toBuffer(),
+						Buffer.from(entry.recordHash),
+						entry.timestamp.toTwos(64).toArrayLike(Buffer, 'le', 8),
+					]),
+				)
+				.digest(),
+		);
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('chains every lifecycle operation into a head that replays off-chain', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const attestation = attestationPdaFor(wallet);
+		const start = await journalHead();
+
+		const entries = [await journaled(await createAttestation(wallet))];
+		expect(entries[0].recordHash).to.deep.equal(await recordHash(wallet));
+		entries.push(await journaled(await createAttestation(wallet)));
+		expect(entries[1].recordHash).to.deep.equal(await recordHash(wallet));
+
+		const revokeSig = await program.methods
+			.revokeKycAttestation(wallet)
+			.accounts({ attestation, authority, state, rollup: ROLLUP, journal: JOURNAL })
+			.rpc({ commitment: 'confirmed' });
+		entries.push(await journaled(revokeSig));
+		expect(entries[2].recordHash).to.deep.equal(await recordHash(wallet));
+
+		const closeSig = await program.methods
+			.closeKycAttestation(wallet)
+			.accounts({ attestation, authority, state, rollup: ROLLUP, journal: JOURNAL })
+			.rpc({ commitment: 'confirmed' });
+		entries.push(await journaled(closeSig));
+		// A close records the final state of the record it removes
+		expect(entries[3].recordHash).to.deep.equal(entries[2].recordHash);
+
+		expect(entries.map((e) => e.operation)).to.deep.equal([
+			JOURNAL_OP.create,
+			JOURNAL_OP.update,
+			JOURNAL_OP.revoke,
+			JOURNAL_OP.close,
+		]);
+		let head = start.head;
+		entries.forEach((entry, i) => {
+			expect(entry.sequence.toNumber()).to.equal(start.sequence.toNumber() + i + 1);
+			head = chain(head, entry);
+			expect(entry.head).to.deep.equal(head);
+		});
+
+		const end = await journalHead();
+		expect(end.sequence.toNumber()).to.equal(start.sequence.toNumber() + 4);
+		expect(end.head).to.deep.equal(head);
+	});
+
+	it('requires the journal once the state keeps one', async () => {
+		const wallet = (anchor as any).web3.Keypair.generate().publicKey;
+		const start = await journalHead();
+		try {
+			await createAttestation(wallet, null);
+			expect.fail('expected JournalRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('JournalRequired');
+		}
+		expect((await journalHead()).sequence.toNumber()).to.equal(start.sequence.toNumber());
+	});
+});
+
+describe('sanctions blocklist', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	// The sanction entry's address is always passed, listed or not
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					sanction: sanctionPdaFor(user),
+				}),
+			)
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('passes a compliant wallet that is not listed', async () => {
+		await verify();
+	});
+
+	it('blocks a sanctioned wallet despite its KYC', async () => {
+		await program.methods
+			.addSanction(user, 'OFAC SDN list')
+			.accounts({ state, sanction: sanctionPdaFor(user), authority, systemProgram: web3.SystemProgram.programId })
+			.rpc();
+		const entry = await program.account.sanctionEntry.fetch(sanctionPdaFor(user));
+		expect(entry.wallet.toString()).to.equal(user.toString());
+		expect(entry.reason).to.equal('OFAC SDN list');
+
+		try {
+			await verify();
+			expect.fail('expected WalletSanctioned');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('WalletSanctioned');
+		}
+	});
+
+	it('blocks a sanctioned wallet on the guard and mandate paths too', async () => {
+		const stub = (anchor as any).workspace.GuardStub as any;
+		const sanction = sanctionPdaFor(user);
+		const attempts = [
+			stub.methods.gatedAction(user, 'swap').accounts({
+				complifiState: state,
+				policy,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				sanction,
+				attestorEntry: null,
+				complifiProgram: program.programId,
+				...riskOracleAccounts(user),
+			}),
+			program.methods.verifyDerivative(user, policy, policy).accounts({
+				state,
+				underlying: policy,
+				derivative: policy,
+				authority,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				sanction,
+				...riskOracleAccounts(user),
+			}),
+			program.methods.createMandate(user, new (anchor as any).BN(100), new (anchor as any).BN(3600)).accounts({
+				state,
+				policy,
+				attestation: attestationPdaFor(user),
+				userRecord: null,
+				sanction,
+				...riskOracleAccounts(user),
+				mandate: web3.PublicKey.findProgramAddressSync(
+					[Buffer.from('mandate'), policy.toBuffer(), user.toBuffer()],
+					program.programId,
+				)[0],
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			}),
+		];
+		for (const attempt of attempts) {
+			try {
+				await attempt.rpc();
+				expect.fail('expected WalletSanctioned');
+			} catch (err: any) {
+				expect(err.error.errorCode.code).to.equal('WalletSanctioned');
+			}
+		}
+	});
+
+	it('rejects an unlisted address passed in place of the sanctioned wallet\'s', async () => {
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(
+					verifyAccounts({
+						state,
+						policy,
+						authority,
+						user,
+						attestation: attestationPdaFor(user),
+						sanction: sanctionPdaFor(web3.Keypair.generate().publicKey),
+					}),
+				)
+				.rpc();
+			expect.fail('expected ConstraintSeeds');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ConstraintSeeds');
+		}
+	});
+
+	it('only lets the state authority change the blocklist', async () => {
+		const stranger = web3.Keypair.generate();
+		const other = web3.Keypair.generate().publicKey;
+		const sig = await provider.connection.requestAirdrop(stranger.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+		try {
+			await program.methods
+				.addSanction(other, 'spoofed')
+				.accounts({
+					state,
+					sanction: sanctionPdaFor(other),
+					authority: stranger.publicKey,
+					systemProgram: web3.SystemProgram.programId,
+				})
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('passes the wallet again once the sanction is removed', async () => {
+		await program.methods.removeSanction(user).accounts({ state, sanction: sanctionPdaFor(user), authority }).rpc();
+		expect(await provider.connection.getAccountInfo(sanctionPdaFor(user))).to.equal(null);
+		await verify();
+	});
+});
+
+describe('step-up verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (amount: number, userRecord: any = userRecordPdaFor(user.publicKey)) =>
+		program.methods
+			.verifyCompliance(user.publicKey, 'swap', null, new (anchor as any).BN(amount), null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user: user.publicKey,
+					attestation: attestationPdaFor(user.publicKey),
+					userRecord,
+				}),
+			)
+			.rpc();
+
+	const expectStepUp = async (amount: number) => {
+		try {
+			await verify(amount);
+			expect.fail('expected StepUpRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('StepUpRequired');
+		}
+	};
+
+	// Re-attest the user with a wallet ownership proof, raising them to KYC level 2
+	const stepUp = () =>
+		program.methods
+			.createKycAttestation(user.publicKey, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user.publicKey),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user.publicKey,
+				timeOracle: null,
+				vendor: null,
+				instructions: web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.preInstructions([
+				web3.Ed25519Program.createInstructionWithPrivateKey({
+					privateKey: user.secretKey,
+					message: Buffer.concat([Buffer.from('complifi-ownership:'), user.publicKey.toBuffer(), authority.toBuffer()]),
+				}),
+			])
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		// Past 100 in cumulative volume, the attestation must carry an ownership proof
+		await program.methods.setStepUp(new (anchor as any).BN(100), 2).accounts({ policy, authority }).rpc();
+		user = web3.Keypair.generate();
+		await attest(state, user.publicKey);
+	});
+
+	it('lets partial KYC through up to the threshold', async () => {
+		await verify(60);
+		await verify(40);
+		const record = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user.publicKey));
+		expect(record.verifiedVolume.toNumber()).to.equal(100);
+	});
+
+	it('requires a step-up once the threshold would be crossed', async () => {
+		await expectStepUp(1);
+		const record = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user.publicKey));
+		expect(record.verifiedVolume.toNumber()).to.equal(100);
+	});
+
+	it('requires the user record while step-up is configured', async () => {
+		try {
+			await verify(1, null);
+			expect.fail('expected UserRecordRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UserRecordRequired');
+		}
+	});
+
+	it('passes further actions once the user has stepped up', async () => {
+		await stepUp();
+		await verify(500);
+		const record = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user.publicKey));
+		expect(record.verifiedVolume.toNumber()).to.equal(600);
+	});
+
+	it('rejects a step-up level beyond the highest KYC level', async () => {
+		try {
+			await program.methods.setStepUp(new (anchor as any).BN(100), 3).accounts({ policy, authority }).rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('KYC levels', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	const setMinKycLevel = (minKycLevel: number) =>
+		program.methods.setPolicy(5, true, allowed as any, minKycLevel, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, true, 0, new (anchor as any).BN(0), null, null, 0, 1)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+	});
+
+	it('records the level the attester verified to', async () => {
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(user));
+		expect(attestation.level).to.equal(1);
+	});
+
+	it('rejects a level-1 attestation under a level-2 policy', async () => {
+		await setMinKycLevel(2);
+		expect((await program.account.compliancePolicy.fetch(policy)).minKycLevel).to.equal(2);
+		try {
+			await verify();
+			expect.fail('expected InsufficientKycLevel');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InsufficientKycLevel');
+		}
+	});
+
+	it('passes a level-1 attestation under a level-1 policy', async () => {
+		await setMinKycLevel(1);
+		await verify();
+	});
+});
+
+describe('attestor allowlist', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let issuer: any;
+
+	const attestAs = (signer: any, wallet: any, isVerified = true) =>
+		program.methods
+			.createKycAttestation(wallet, isVerified, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet, signer.publicKey),
+				authority: signer.publicKey,
+				attestorEntry: attestorPdaFor(signer.publicKey),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.signers([signer])
+			.rpc();
+
+	const expectUnauthorizedAttestor = async (signer: any) => {
+		try {
+			await attestAs(signer, web3.Keypair.generate().publicKey);
+			expect.fail('expected UnauthorizedAttestor');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UnauthorizedAttestor');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		issuer = web3.Keypair.generate();
+		const sig = await provider.connection.requestAirdrop(issuer.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+	});
+
+	it('rejects a signer that is not allowlisted', async () => {
+		await expectUnauthorizedAttestor(issuer);
+	});
+
+	it('lets an allowlisted attestor issue attestations', async () => {
+		await program.methods
+			.addAttestor(issuer.publicKey)
+			.accounts({
+				state,
+				attestorEntry: attestorPdaFor(issuer.publicKey),
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		const wallet = web3.Keypair.generate().publicKey;
+		await attestAs(issuer, wallet);
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(wallet, issuer.publicKey));
+		expect(attestation.authority.toBase58()).to.equal(issuer.publicKey.toBase58());
+	});
+
+	it('only lets the state authority allowlist attestors', async () => {
+		const other = web3.Keypair.generate().publicKey;
+		try {
+			await program.methods
+				.addAttestor(other)
+				.accounts({
+					state,
+					attestorEntry: attestorPdaFor(other),
+					authority: issuer.publicKey,
+					systemProgram: web3.SystemProgram.programId,
+				})
+				.signers([issuer])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('lets the issuing attestor update its own attestation', async () => {
+		const wallet = web3.Keypair.generate().publicKey;
+		await attestAs(issuer, wallet);
+		await attestAs(issuer, wallet, false);
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(wallet, issuer.publicKey));
+		expect(attestation.flags & ATTESTATION_FLAG_VERIFIED).to.equal(0);
+	});
+
+	it('keeps separate attestations when two attestors attest the same wallet', async () => {
+		const wallet = web3.Keypair.generate().publicKey;
+		// The provider wallet is allowlisted too; the issuer rejects the wallet it verifies
+		await attest(state, wallet);
+		await attestAs(issuer, wallet, false);
+
+		const ours = await program.account.kycAttestation.fetch(attestationPdaFor(wallet));
+		const theirs = await program.account.kycAttestation.fetch(attestationPdaFor(wallet, issuer.publicKey));
+		expect(ours.authority.toBase58()).to.equal(authority.toBase58());
+		expect(ours.flags & ATTESTATION_FLAG_VERIFIED).to.equal(ATTESTATION_FLAG_VERIFIED);
+		expect(theirs.authority.toBase58()).to.equal(issuer.publicKey.toBase58());
+		expect(theirs.flags & ATTESTATION_FLAG_VERIFIED).to.equal(0);
+
+		// Verification reads whichever authority's attestation the caller names
+		const policy = await initPolicy();
+		const verify = (attestationAuthority: any) =>
+			program.methods
+				.verifyCompliance(wallet, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(
+					verifyAccounts({
+						state,
+						policy,
+						authority,
+						user: wallet,
+						attestationAuthority,
+						attestation: attestationPdaFor(wallet, attestationAuthority),
+					}),
+				)
+				.rpc();
+		await verify(authority);
+		try {
+			await verify(issuer.publicKey);
+			expect.fail('expected KycNotVerified');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycNotVerified');
+		}
+	});
+
+	it('rejects an attestor again once removed', async () => {
+		await program.methods
+			.removeAttestor(issuer.publicKey)
+			.accounts({ state, attestorEntry: attestorPdaFor(issuer.publicKey), authority })
+			.rpc();
+		await expectUnauthorizedAttestor(issuer);
+	});
+});
+
+describe('attestor jurisdiction scopes', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const US = 1;
+	const EU = 3;
+	let state: any;
+	let issuer: any;
+
+	const attestIn = (jurisdiction: number) => {
+		const wallet = web3.Keypair.generate().publicKey;
+		return program.methods
+			.createKycAttestation(wallet, true, jurisdiction, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet, issuer.publicKey),
+				authority: issuer.publicKey,
+				attestorEntry: attestorPdaFor(issuer.publicKey),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.signers([issuer])
+			.rpc();
+	};
+
+	const scopeTo = (jurisdictions: number[]) => {
+		const bitmap: number[] = new Array(32).fill(0);
+		for (const jurisdiction of jurisdictions) {
+			bitmap[jurisdiction >> 3] |= 1 << (jurisdiction & 7);
+		}
+		return program.methods
+			.setAttestorJurisdictions(issuer.publicKey, bitmap as any)
+			.accounts({ state, attestorEntry: attestorPdaFor(issuer.publicKey), authority })
+			.rpc();
+	};
+
+	before(async () => {
+		state = await initState();
+		issuer = web3.Keypair.generate();
+		const sig = await provider.connection.requestAirdrop(issuer.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+		await program.methods
+			.addAttestor(issuer.publicKey)
+			.accounts({
+				state,
+				attestorEntry: attestorPdaFor(issuer.publicKey),
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+	});
+
+	it('lets an unscoped attestor attest any jurisdiction', async () => {
+		await attestIn(US);
+		await attestIn(EU);
+	});
+
+	it('stops an EU-only attestor attesting a US wallet', async () => {
+		await scopeTo([EU]);
+		await attestIn(EU);
+		try {
+			await attestIn(US);
+			expect.fail('expected JurisdictionNotDelegated');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('JurisdictionNotDelegated');
+		}
+	});
+
+	it('lifts the scope with an empty bitmap', async () => {
+		await scopeTo([]);
+		await attestIn(US);
+	});
+
+	it('only lets the state authority scope attestors', async () => {
+		try {
+			await program.methods
+				.setAttestorJurisdictions(issuer.publicKey, new Array(32).fill(0) as any)
+				.accounts({ state, attestorEntry: attestorPdaFor(issuer.publicKey), authority: issuer.publicKey })
+				.signers([issuer])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('off-chain signed attestations', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const kycProvider = web3.Keypair.generate();
+	let state: any;
+
+	// Borsh layout of SignedKycMessage
+	const encodeMessage = (msg: any) => {
+		const buf = Buffer.alloc(32 + 1 + 8);
+		msg.wallet.toBuffer().copy(buf, 0);
+		buf.writeUInt8(msg.jurisdiction, 32);
+		buf.writeBigInt64LE(BigInt(msg.expiresAt.toString()), 33);
+		return buf;
+	};
+
+	const newMessage = async (expiresIn = 3600) => ({
+		wallet: web3.Keypair.generate().publicKey,
+		jurisdiction: 0,
+		expiresAt: new (anchor as any).BN((await chainNow()) + expiresIn),
+	});
+
+	// Submit `msg` as signed by `providerKey`, with the Ed25519 instruction made by `signer`
+	// over `signed` (by default the message itself)
+	const submit = (msg: any, signer = kycProvider, providerKey = kycProvider.publicKey, signed = msg) =>
+		program.methods
+			.verifySignedAttestation(providerKey, msg)
+			.accounts({
+				state,
+				attestation: attestationPdaFor(msg.wallet, providerKey),
+				attestorEntry: attestorPdaFor(providerKey),
+				payer: authority,
+				instructions: web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.preInstructions([
+				web3.Ed25519Program.createInstructionWithPrivateKey({
+					privateKey: signer.secretKey,
+					message: encodeMessage(signed),
+				}),
+			])
+			.rpc();
+
+	const expectError = async (promise: Promise<any>, code: string) => {
+		try {
+			await promise;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		await program.methods
+			.addAttestor(kycProvider.publicKey)
+			.accounts({
+				state,
+				attestorEntry: attestorPdaFor(kycProvider.publicKey),
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+	});
+
+	it('creates an attestation from a valid provider signature', async () => {
+		const msg = await newMessage();
+		await submit(msg);
+
+		const account = await program.account.kycAttestation.fetch(attestationPdaFor(msg.wallet, kycProvider.publicKey));
+		expect(account.flags & ATTESTATION_FLAG_VERIFIED).to.not.equal(0);
+		expect(account.authority.toBase58()).to.equal(kycProvider.publicKey.toBase58());
+		expect(account.jurisdiction).to.equal(0);
+		expect(account.expiresAt.toString()).to.equal(msg.expiresAt.toString());
+	});
+
+	it('rejects a signature by another key', async () => {
+		const msg = await newMessage();
+		await expectError(submit(msg, web3.Keypair.generate()), 'SignatureVerificationFailed');
+		expect(await provider.connection.getAccountInfo(attestationPdaFor(msg.wallet, kycProvider.publicKey))).to.equal(null);
+	});
+
+	it('rejects a signature over a different message', async () => {
+		const msg = await newMessage();
+		const signed = { ...msg, jurisdiction: 1 };
+		await expectError(submit(msg, kycProvider, kycProvider.publicKey, signed), 'SignatureVerificationFailed');
+	});
+
+	it('rejects a provider that is not allowlisted', async () => {
+		const outsider = web3.Keypair.generate();
+		await expectError(submit(await newMessage(), outsider, outsider.publicKey), 'UnauthorizedAttestor');
+	});
+
+	it('rejects replaying a message once the attestation has moved on', async () => {
+		const msg = await newMessage();
+		await submit(msg);
+		await program.methods
+			.revokeKycAttestation(msg.wallet)
+			.accounts({
+				attestation: attestationPdaFor(msg.wallet, kycProvider.publicKey),
+				authority: kycProvider.publicKey,
+				state,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+			})
+			.signers([kycProvider])
+			.rpc();
+
+		await expectError(submit(msg), 'SignedAttestationReplayed');
+
+		// A fresh message with a later expiry re-issues it
+		await submit({ ...msg, expiresAt: msg.expiresAt.addn(60) });
+		const account = await program.account.kycAttestation.fetch(attestationPdaFor(msg.wallet, kycProvider.publicKey));
+		expect(account.flags & ATTESTATION_FLAG_VERIFIED).to.not.equal(0);
+	});
+
+	it('rejects an already expired message', async () => {
+		await expectError(submit(await newMessage(-60)), 'AttestationExpired');
+	});
+});
+
+describe('per-action risk thresholds', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (action: string, overrideMaxRisk: number | null = null) =>
+		program.methods
+			.verifyCompliance(user, action, overrideMaxRisk, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	const expectError = async (promise: Promise<any>, code: string) => {
+		try {
+			await promise;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		// The low aggregator scores every wallet 2, above the policy-wide threshold of 1
+		policy = await initPolicy(1);
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await program.methods.setActionThreshold('swap', 4).accounts({ policy, authority }).rpc();
+		await program.methods.setActionThreshold('withdraw', 1).accounts({ policy, authority }).rpc();
+	});
+
+	it('passes the same score for one action and fails it for another', async () => {
+		await verify('swap');
+		await expectError(verify('withdraw'), 'RiskScoreTooHigh');
+	});
+
+	it('falls back to max_risk_score for actions without a threshold', async () => {
+		await expectError(verify('lend'), 'RiskScoreTooHigh');
+	});
+
+	it('applies the action threshold through the guard CPI', async () => {
+		const stub = (anchor as any).workspace.GuardStub as any;
+		const gatedAction = (action: string) =>
+			stub.methods
+				.gatedAction(user, action)
+				.accounts({
+					complifiState: state,
+					policy,
+					attestation: attestationPdaFor(user),
+					userRecord: null,
+					sanction: sanctionPdaFor(user),
+					attestorEntry: null,
+					complifiProgram: program.programId,
+					...riskOracleAccounts(user),
+				})
+				.rpc();
+		await gatedAction('swap');
+		await expectError(gatedAction('withdraw'), 'RiskScoreTooHigh');
+	});
+
+	it('only lets a per-call override tighten the action threshold', async () => {
+		await expectError(verify('swap', 5), 'OverrideLoosensPolicy');
+		await expectError(verify('swap', 1), 'RiskScoreTooHigh');
+		await verify('swap', 3);
+	});
+
+	it('replaces an existing threshold and removes it again', async () => {
+		await program.methods.setActionThreshold('withdraw', 3).accounts({ policy, authority }).rpc();
+		await verify('withdraw');
+		const account = await program.account.compliancePolicy.fetch(policy);
+		expect(account.actionThresholdCount).to.equal(2);
+
+		await program.methods.removeActionThreshold('withdraw').accounts({ policy, authority }).rpc();
+		await expectError(verify('withdraw'), 'RiskScoreTooHigh');
+		expect((await program.account.compliancePolicy.fetch(policy)).actionThresholdCount).to.equal(1);
+		await verify('swap');
+	});
+});
+
+describe('allowed actions', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (action: string) =>
+		program.methods
+			.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	const expectNotAllowed = async (action: string) => {
+		try {
+			await verify(action);
+			expect.fail('expected ActionNotAllowed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ActionNotAllowed');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('permits any action while the whitelist is empty', async () => {
+		await verify('lend');
+	});
+
+	it('passes a listed action and rejects one off the list', async () => {
+		await program.methods.allowAction('swap').accounts({ policy, authority }).rpc();
+		await verify('swap');
+		await expectNotAllowed('lend');
+	});
+
+	it('holds the guard CPI to the whitelist', async () => {
+		const stub = (anchor as any).workspace.GuardStub as any;
+		const gatedAction = (action: string) =>
+			stub.methods
+				.gatedAction(user, action)
+				.accounts({
+					complifiState: state,
+					policy,
+					attestation: attestationPdaFor(user),
+					userRecord: null,
+					sanction: sanctionPdaFor(user),
+					attestorEntry: null,
+					complifiProgram: program.programId,
+					...riskOracleAccounts(user),
+				})
+				.rpc();
+		await gatedAction('swap');
+		try {
+			await gatedAction('lend');
+			expect.fail('expected ActionNotAllowed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ActionNotAllowed');
+		}
+	});
+
+	it('does not list an action twice', async () => {
+		await program.methods.allowAction('swap').accounts({ policy, authority }).rpc();
+		expect((await program.account.compliancePolicy.fetch(policy)).allowedActionCount).to.equal(1);
+	});
+
+	it('lifts the whitelist once its last action is disallowed', async () => {
+		await program.methods.allowAction('withdraw').accounts({ policy, authority }).rpc();
+		await program.methods.disallowAction('swap').accounts({ policy, authority }).rpc();
+		await expectNotAllowed('swap');
+		await verify('withdraw');
+
+		await program.methods.disallowAction('withdraw').accounts({ policy, authority }).rpc();
+		expect((await program.account.compliancePolicy.fetch(policy)).allowedActionCount).to.equal(0);
+		await verify('lend');
+	});
+});
+
+describe('policy effective window', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const setWindow = (from: any, until: any) =>
+		program.methods.setPolicy(5, true, allowed as any, 0, from, until, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false).accounts({ policy, authority }).rpc();
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	const expectNotActive = async () => {
+		try {
+			await verify();
+			expect.fail('expected PolicyNotActive');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('PolicyNotActive');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('rejects verifications before a staged policy takes effect', async () => {
+		const now = await chainNow();
+		await setWindow(new (anchor as any).BN(now + 3600), UNBOUNDED);
+		await expectNotActive();
+	});
+
+	it('rejects verifications once a policy has expired', async () => {
+		const now = await chainNow();
+		await setWindow(new (anchor as any).BN(now - 7200), new (anchor as any).BN(now - 3600));
+		await expectNotActive();
+	});
+
+	it('passes verifications inside the window', async () => {
+		const now = await chainNow();
+		await setWindow(new (anchor as any).BN(now - 3600), new (anchor as any).BN(now + 3600));
+		await verify();
+		const account = await program.account.compliancePolicy.fetch(policy);
+		expect(account.effectiveUntil.toNumber()).to.equal(now + 3600);
+	});
+
+	it('holds the guard, derivative and mandate paths to the window', async () => {
+		const web3 = (anchor as any).web3;
+		const BN = (anchor as any).BN;
+		const stub = (anchor as any).workspace.GuardStub as any;
+		const now = await chainNow();
+		await setWindow(new BN(now - 7200), new BN(now - 3600));
+		const attestation = attestationPdaFor(user);
+		const attempts = [
+			stub.methods.gatedAction(user, 'swap').accounts({
+				complifiState: state,
+				policy,
+				attestation,
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				attestorEntry: null,
+				complifiProgram: program.programId,
+				...riskOracleAccounts(user),
+			}),
+			program.methods
+				.verifyDerivative(user, policy, policy)
+				.accounts({
+					state,
+					underlying: policy,
+					derivative: policy,
+					authority,
+					attestation,
+					userRecord: null,
+					sanction: sanctionPdaFor(user),
+					...riskOracleAccounts(user),
+				}),
+			program.methods.createMandate(user, new BN(100), new BN(3600)).accounts({
+				state,
+				policy,
+				attestation,
+				userRecord: null,
+				sanction: sanctionPdaFor(user),
+				...riskOracleAccounts(user),
+				mandate: web3.PublicKey.findProgramAddressSync(
+					[Buffer.from('mandate'), policy.toBuffer(), user.toBuffer()],
+					program.programId,
+				)[0],
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			}),
+		];
+		for (const attempt of attempts) {
+			try {
+				await attempt.rpc();
+				expect.fail('expected PolicyNotActive');
+			} catch (err: any) {
+				expect(err.error.errorCode.code).to.equal('PolicyNotActive');
+			}
+		}
+	});
+
+	it('rejects a window that ends before it starts', async () => {
+		const now = await chainNow();
+		try {
+			await setWindow(new (anchor as any).BN(now + 60), new (anchor as any).BN(now));
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+
+	after(async () => {
+		await setWindow(UNBOUNDED, UNBOUNDED);
+	});
+});
+
+describe('named policies', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let user: any;
+	let retail: any;
+	let institutional: any;
+
+	const verify = (policy: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		retail = await initPolicy(5, 'retail');
+		institutional = await initPolicy(5, 'institutional');
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		// Institutional allows no jurisdiction, so only its own settings can decide the outcome
+		await program.methods
+			.setPolicy(5, true, new Array(32).fill(0) as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, true)
+			.accounts({ policy: institutional, authority })
+			.rpc();
+	});
+
+	it('derives each policy from its authority and name', async () => {
+		expect(retail.toBase58()).to.not.equal(institutional.toBase58());
+		expect((await program.account.compliancePolicy.fetch(retail)).name).to.equal('retail');
+		expect((await program.account.compliancePolicy.fetch(institutional)).name).to.equal('institutional');
+	});
+
+	it('verifies against each named policy independently', async () => {
+		await verify(retail);
+		try {
+			await verify(institutional);
+			expect.fail('expected RestrictedJurisdiction');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RestrictedJurisdiction');
+		}
+	});
+
+	it('rejects a second policy under a name already in use', async () => {
+		try {
+			await initPolicy(5, 'retail');
+			expect.fail('expected the name to be taken');
+		} catch (err: any) {
+			expect(err.logs.join('\n')).to.include('already in use');
+		}
+	});
+});
+
+describe('reverification cooldown', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const setCooldown = (seconds: any) =>
+		program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, seconds, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+
+	const verify = (userRecord: any = userRecordPdaFor(user)) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), userRecord }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await setCooldown(new (anchor as any).BN(2));
+	});
+
+	it('rejects a second verification inside the interval', async () => {
+		await verify();
+		try {
+			await verify();
+			expect.fail('expected VerificationTooSoon');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('VerificationTooSoon');
+		}
+	});
+
+	it('passes again once the interval has elapsed', async () => {
+		await sleep(3_000);
+		const before = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user));
+		await verify();
+		const record = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user));
+		expect(record.verificationCount.toNumber()).to.equal(before.verificationCount.toNumber() + 1);
+	});
+
+	it('requires the user record while a cooldown is configured', async () => {
+		try {
+			await verify(null);
+			expect.fail('expected UserRecordRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UserRecordRequired');
+		}
+	});
+
+	it('rejects a negative interval', async () => {
+		try {
+			await setCooldown(new (anchor as any).BN(-1));
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+
+	after(async () => {
+		await setCooldown(NO_COOLDOWN);
+	});
+});
+
+describe('batch attestation', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+
+	const createBatch = (entries: any[], attestations: any[] = entries.map((e) => attestationPdaFor(e.wallet))) =>
+		program.methods
+			.createKycAttestationsBatch(entries)
+			.accounts({
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				policy: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.remainingAccounts(attestations.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+			.rpc();
+
+	const entry = (jurisdiction: number) => ({ wallet: web3.Keypair.generate().publicKey, isVerified: true, jurisdiction });
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('creates three attestations in one call', async () => {
+		const entries = [entry(0), entry(1), entry(2)];
+		const sig = await createBatch(entries);
+
+		for (const { wallet, jurisdiction } of entries) {
+			const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(wallet));
+			expect(attestation.wallet.toBase58()).to.equal(wallet.toBase58());
+			expect(attestation.authority.toBase58()).to.equal(authority.toBase58());
+			expect(attestation.jurisdiction).to.equal(jurisdiction);
+			expect(attestation.flags & ATTESTATION_FLAG_VERIFIED).to.equal(ATTESTATION_FLAG_VERIFIED);
+		}
+		const events = (await eventsOf(sig)).filter((e) => e.name === 'kycAttestationEvent');
+		expect(events.map((e) => e.data.wallet.toBase58())).to.deep.equal(entries.map((e) => e.wallet.toBase58()));
+	});
+
+	it('rejects a remaining account count that does not match the entries', async () => {
+		const entries = [entry(0), entry(0)];
+		try {
+			await createBatch(entries, [attestationPdaFor(entries[0].wallet)]);
+			expect.fail('expected AccountCountMismatch');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AccountCountMismatch');
+		}
+	});
+
+	it('rejects an account that is not the wallet attestation PDA', async () => {
+		const entries = [entry(0), entry(0)];
+		try {
+			await createBatch(entries, [attestationPdaFor(entries[1].wallet), attestationPdaFor(entries[0].wallet)]);
+			expect.fail('expected AttestationAddressMismatch');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationAddressMismatch');
+		}
+	});
+
+	it('rejects wallets that already have an attestation', async () => {
+		const existing = entry(0);
+		await attest(state, existing.wallet);
+		try {
+			await createBatch([entry(0), existing]);
+			expect.fail('expected AttestationAlreadyExists');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationAlreadyExists');
+		}
+	});
+
+	it('caps the batch size', async () => {
+		const entries = Array.from({ length: 9 }, () => entry(0));
+		try {
+			await createBatch(entries);
+			expect.fail('expected BatchTooLarge');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('BatchTooLarge');
+		}
+	});
+});
+
+describe('oracle staleness', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const user = ORACLE_FIXTURE_USER;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+
+	const setStaleness = (seconds: any) =>
+		program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, seconds, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+
+	const verifyWith = (rangeOracle: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), rangeOracle }))
+			.rpc();
+
+	// Age of the fixture oracle entries, in seconds
+	const fixtureAge = async () => (await chainNow()) - FIXTURE_ORACLE_UPDATED_AT;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy, authority }).rpc();
+		await attest(state, user);
+	});
+
+	it('rejects an oracle entry older than the limit', async () => {
+		await setStaleness(new (anchor as any).BN((await fixtureAge()) - 60));
+		try {
+			await verifyWith(RANGE_ORACLES.a);
+			expect.fail('expected StaleOracleData');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('StaleOracleData');
+		}
+	});
+
+	it('accepts an oracle entry within the limit', async () => {
+		await setStaleness(new (anchor as any).BN((await fixtureAge()) + 3600));
+		await verifyWith(RANGE_ORACLES.a);
+	});
+
+	it('accepts entries of any age without a limit', async () => {
+		await setStaleness(ANY_AGE);
+		await verifyWith(RANGE_ORACLES.a);
+	});
+});
+
+describe('switchboard risk oracle', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let rangePolicy: any;
+	let switchboardPolicy: any;
+	let user: any;
+
+	const verify = (subject: any, policy: any, oracles: any) =>
+		program.methods
+			.verifyCompliance(subject, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user: subject, attestation: attestationPdaFor(subject), ...oracles }))
+			.rpc();
+
+	const expectError = async (promise: Promise<any>, code: string) => {
+		try {
+			await promise;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		rangePolicy = await initPolicy();
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy: rangePolicy, authority }).rpc();
+		switchboardPolicy = await initPolicy();
+		await attest(state, ORACLE_FIXTURE_USER);
+		user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('compares a low score the same way for both oracle kinds', async () => {
+		await verify(ORACLE_FIXTURE_USER, rangePolicy, { rangeOracle: RANGE_ORACLES.a });
+		await setRiskFeed(state, user, SWITCHBOARD_AGGREGATORS.low);
+		await verify(user, switchboardPolicy, { riskFeed: riskFeedPdaFor(user), switchboardAggregator: SWITCHBOARD_AGGREGATORS.low });
+	});
+
+	it('compares a high score the same way for both oracle kinds', async () => {
+		await expectError(verify(ORACLE_FIXTURE_USER, rangePolicy, { rangeOracle: RANGE_ORACLES.c }), 'RiskScoreTooHigh');
+		await setRiskFeed(state, user, SWITCHBOARD_AGGREGATORS.high);
+		await expectError(
+			verify(user, switchboardPolicy, { riskFeed: riskFeedPdaFor(user), switchboardAggregator: SWITCHBOARD_AGGREGATORS.high }),
+			'RiskScoreTooHigh',
+		);
+	});
+
+	it('only reads the aggregator registered for the wallet', async () => {
+		// The feed now points at the high aggregator, so the low one cannot stand in for it
+		await expectError(
+			verify(user, switchboardPolicy, { riskFeed: riskFeedPdaFor(user), switchboardAggregator: SWITCHBOARD_AGGREGATORS.low }),
+			'OracleDataFetchFailed',
+		);
+		await expectError(
+			verify(user, switchboardPolicy, { riskFeed: null, switchboardAggregator: SWITCHBOARD_AGGREGATORS.low }),
+			'OracleDataFetchFailed',
+		);
+	});
+
+	it('rejects an unknown oracle kind', async () => {
+		await expectError(program.methods.setOracleKind(2).accounts({ policy: switchboardPolicy, authority }).rpc(), 'InvalidPolicyParameters');
+	});
+});
+
+describe('minimum attestation age', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+
+	const verify = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, new (anchor as any).BN(2), NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+	});
+
+	it('rejects a brand-new attestation', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		try {
+			await verify(user);
+			expect.fail('expected AttestationTooNew');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationTooNew');
+		}
+	});
+
+	it('passes once the attestation is past the minimum age', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await sleep(3_000);
+		await verify(user);
+	});
+
+	it('rejects a negative minimum age', async () => {
+		try {
+			await program.methods
+				.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, new (anchor as any).BN(-1), NO_MAX_AMOUNT, false)
+				.accounts({ policy, authority })
+				.rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('verification result return data', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const stub = (anchor as any).workspace.GuardStub as any;
+	let state: any;
+	let policy: any;
+
+	const returnData = async (sig: string) => {
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		return Buffer.from(tx.meta.returnData.data[0], 'base64');
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('returns the outcome of a direct verification', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const sig = await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc({ commitment: 'confirmed' });
+		// verified, the low aggregator's risk score of 2, jurisdiction 0
+		expect(Array.from(await returnData(sig))).to.deep.equal([1, 2, 0]);
+	});
+
+	it('hands the outcome to a program that CPIs in', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const accounts = {
+			complifiState: state,
+			policy,
+			authority,
+			user,
+			attestationAuthority: authority,
+			attestation: attestationPdaFor(user),
+			sanction: sanctionPdaFor(user),
+			...riskOracleAccounts(user),
+			complifiEventAuthority: EVENT_AUTHORITY,
+			complifiProgram: program.programId,
+		};
+		const sig = await stub.methods.verifiedAction(user, 'swap').accounts(accounts).rpc({ commitment: 'confirmed' });
+		const result = stub.coder.types.decode('VerificationResult', await returnData(sig));
+		expect(result.verified).to.equal(true);
+		expect(result.riskScore).to.equal(2);
+		expect(result.jurisdiction).to.equal(0);
+	});
+});
+
+describe('self-CPI events', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const setCpiEvents = (enabled: boolean) =>
+		program.methods.setCpiEvents(enabled).accounts({ state, authority }).rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('emits only log events by default', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const sig = await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc({ commitment: 'confirmed' });
+		expect(await cpiEventsOf(sig)).to.deep.equal([]);
+		expect((await eventsOf(sig)).map((e) => e.name)).to.include('verificationEvent');
+	});
+
+	describe('when enabled', () => {
+		before(async () => {
+			await setCpiEvents(true);
+		});
+
+		it('emits the attestation event as an inner instruction', async () => {
+			const user = (anchor as any).web3.Keypair.generate().publicKey;
+			const sig = await program.methods
+				.createKycAttestation(user, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+				.accounts({
+					attestation: attestationPdaFor(user),
+					authority,
+					attestorEntry: attestorPdaFor(authority),
+					state,
+					wallet: user,
+					timeOracle: null,
+					vendor: null,
+					instructions: null,
+					policy: null,
+					jurisdictionMeta: null,
+					rollup: ROLLUP,
+					journal: JOURNAL,
+					kycRequest: null,
+					systemProgram: (anchor as any).web3.SystemProgram.programId,
+				})
+				.rpc({ commitment: 'confirmed' });
+			await setRiskFeed(state, user);
+			const [event] = await cpiEventsOf(sig);
+			expect(event.name).to.equal('kycAttestationEvent');
+			expect(event.data.wallet.toBase58()).to.equal(user.toBase58());
+		});
+
+		it('emits the verification event as an inner instruction, alongside the log event', async () => {
+			const user = (anchor as any).web3.Keypair.generate().publicKey;
+			await attest(state, user);
+			const sig = await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+				.rpc({ commitment: 'confirmed' });
+			const [event] = await cpiEventsOf(sig);
+			expect(event.name).to.equal('verificationEvent');
+			expect(event.data.user.toBase58()).to.equal(user.toBase58());
+			expect((await eventsOf(sig)).map((e) => e.name)).to.include('verificationEvent');
+		});
+
+		after(async () => {
+			await setCpiEvents(false);
+		});
+	});
+});
+
+describe('account migration', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	// Authority of the old-size fixtures in tests/fixtures (see Anchor.toml)
+	const fixtureAuthority = web3.Keypair.fromSecretKey(
+		Uint8Array.from(JSON.parse(readFileSync('tests/fixtures/migration-authority.json', 'utf8'))),
+	);
+	const OLD_POLICY = new web3.PublicKey('BCz91gkpGF3UdEkXEpYTDMTkHTH46DSnhF99WWBRzyim');
+	const OLD_ATTESTATION_WALLET = new web3.PublicKey('BJGHPQv3S7cF5xRHnpdPsjeqonUL7MFauyXdcFCiaQ9F');
+	// Sizes the fixtures were allocated at, before min_verify_interval/max_oracle_staleness and level
+	const OLD_POLICY_SIZE = 8 + 610;
+	const OLD_ATTESTATION_SIZE = 8 + 614;
+
+	const sizeOf = async (account: any) => (await provider.connection.getAccountInfo(account)).data.length;
+
+	before(async () => {
+		await initState();
+		const sig = await provider.connection.requestAirdrop(fixtureAuthority.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+	});
+
+	it('grows an old-size policy and zero-fills its new fields', async () => {
+		expect(await sizeOf(OLD_POLICY)).to.equal(OLD_POLICY_SIZE);
+		await program.methods
+			.migratePolicy()
+			.accounts({ policy: OLD_POLICY, authority: fixtureAuthority.publicKey })
+			.signers([fixtureAuthority])
+			.rpc();
+
+		expect(await sizeOf(OLD_POLICY)).to.be.greaterThan(OLD_POLICY_SIZE);
+		const policy = await program.account.compliancePolicy.fetch(OLD_POLICY);
+		expect(policy.authority.toBase58()).to.equal(fixtureAuthority.publicKey.toBase58());
+		expect(policy.minVerifyInterval.toNumber()).to.equal(0);
+		expect(policy.maxOracleStaleness.toNumber()).to.equal(0);
+		// The account stays rent-exempt at its new size
+		const info = await provider.connection.getAccountInfo(OLD_POLICY);
+		expect(info.lamports).to.be.at.least(await provider.connection.getMinimumBalanceForRentExemption(info.data.length));
+	});
+
+	it('grows an old-size attestation for its issuer', async () => {
+		const attestation = attestationPdaFor(OLD_ATTESTATION_WALLET, fixtureAuthority.publicKey);
+		expect(await sizeOf(attestation)).to.equal(OLD_ATTESTATION_SIZE);
+		await program.methods
+			.migrateAttestationSize(OLD_ATTESTATION_WALLET)
+			.accounts({ attestation, authority: fixtureAuthority.publicKey })
+			.signers([fixtureAuthority])
+			.rpc();
+
+		expect(await sizeOf(attestation)).to.be.greaterThan(OLD_ATTESTATION_SIZE);
+		const migrated = await program.account.kycAttestation.fetch(attestation);
+		expect(migrated.wallet.toBase58()).to.equal(OLD_ATTESTATION_WALLET.toBase58());
+		expect(migrated.flags & ATTESTATION_FLAG_VERIFIED).to.equal(ATTESTATION_FLAG_VERIFIED);
+		expect(migrated.level).to.equal(0);
+		expect(migrated.bump).to.equal(attestationPdaAndBumpFor(OLD_ATTESTATION_WALLET, fixtureAuthority.publicKey)[1]);
+	});
+
+	it('leaves an account already at full size unchanged', async () => {
+		const size = await sizeOf(STATE);
+		await program.methods.migrateState().accounts({ state: STATE, authority }).rpc();
+		expect(await sizeOf(STATE)).to.equal(size);
+	});
+
+	it('rejects a signer that is not the account authority', async () => {
+		const policy = await initPolicy();
+		try {
+			await program.methods
+				.migratePolicy()
+				.accounts({ policy, authority: fixtureAuthority.publicKey })
+				.signers([fixtureAuthority])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('stats reset', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('zeroes the verification and violation counters', async () => {
+		const policy = await initPolicy();
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+		await program.methods
+			.recordViolation(user, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				authority,
+				report: null,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(user),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		const before = await program.account.complianceState.fetch(state);
+		expect(before.verificationCount.toNumber()).to.be.greaterThan(0);
+		expect(before.violationCount.toNumber()).to.be.greaterThan(0);
+
+		const sig = await program.methods.resetStats().accounts({ state, authority }).rpc();
+		const after = await program.account.complianceState.fetch(state);
+		expect(after.verificationCount.toNumber()).to.equal(0);
+		expect(after.violationCount.toNumber()).to.equal(0);
+		expect(after.authority.toBase58()).to.equal(before.authority.toBase58());
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'statsResetEvent');
+		expect(event.data.resetBy.toBase58()).to.equal(authority.toBase58());
+		expect(event.data.timestamp.toNumber()).to.be.greaterThan(0);
+	});
+
+	it('only lets the state authority reset the counters', async () => {
+		const other = (anchor as any).web3.Keypair.generate();
+		try {
+			await program.methods.resetStats().accounts({ state, authority: other.publicKey }).signers([other]).rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('force revocation', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let issuer: any;
+	let wallet: any;
+
+	const forceRevoke = (signer: any = null) =>
+		program.methods
+			.forceRevokeAttestation(wallet)
+			.accounts({
+				attestation: attestationPdaFor(wallet, issuer.publicKey),
+				authority: signer ? signer.publicKey : authority,
+				state,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+			})
+			.signers(signer ? [signer] : [])
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		issuer = web3.Keypair.generate();
+		const sig = await provider.connection.requestAirdrop(issuer.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+		await program.methods
+			.addAttestor(issuer.publicKey)
+			.accounts({ state, attestorEntry: attestorPdaFor(issuer.publicKey), authority, systemProgram: web3.SystemProgram.programId })
+			.rpc();
+		wallet = web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet, issuer.publicKey),
+				authority: issuer.publicKey,
+				attestorEntry: attestorPdaFor(issuer.publicKey),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.signers([issuer])
+			.rpc();
+		await setRiskFeed(state, wallet);
+	});
+
+	it('does not let the state authority use the attester-scoped revoke', async () => {
+		try {
+			await program.methods
+				.revokeKycAttestation(wallet)
+				.accounts({ attestation: attestationPdaFor(wallet, issuer.publicKey), authority, state, rollup: ROLLUP, journal: JOURNAL })
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('rejects a signer that is not the state authority', async () => {
+		const other = web3.Keypair.generate();
+		try {
+			await forceRevoke(other);
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+
+	it('lets the state authority revoke another attester\'s attestation', async () => {
+		const sig = await forceRevoke();
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(wallet, issuer.publicKey));
+		expect(attestation.flags & ATTESTATION_FLAG_VERIFIED).to.equal(0);
+		// The issuing attester is left on record
+		expect(attestation.authority.toBase58()).to.equal(issuer.publicKey.toBase58());
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'attestationForceRevokedEvent');
+		expect(event.data.wallet.toBase58()).to.equal(wallet.toBase58());
+		expect(event.data.revokedBy.toBase58()).to.equal(authority.toBase58());
+		expect(event.data.attester.toBase58()).to.equal(issuer.publicKey.toBase58());
+	});
+});
+
+describe('compliance certificates', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const TOKEN_2022_PROGRAM_ID = new web3.PublicKey('TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb');
+	const ASSOCIATED_TOKEN_PROGRAM_ID = new web3.PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL');
+	const certificateMint = web3.PublicKey.findProgramAddressSync([Buffer.from('certificate-mint')], program.programId)[0];
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const certificatePdaFor = (wallet: any) =>
+		web3.PublicKey.findProgramAddressSync([Buffer.from('certificate'), wallet.toBuffer()], program.programId)[0];
+
+	const ataFor = (wallet: any) =>
+		web3.PublicKey.findProgramAddressSync(
+			[wallet.toBuffer(), TOKEN_2022_PROGRAM_ID.toBuffer(), certificateMint.toBuffer()],
+			ASSOCIATED_TOKEN_PROGRAM_ID,
+		)[0];
+
+	const certificateAccounts = (wallet: any) => ({
+		certificate: certificatePdaFor(wallet),
+		certificateMint,
+		certificateTokenAccount: ataFor(wallet),
+		tokenProgram: TOKEN_2022_PROGRAM_ID,
+		associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+	});
+
+	const verify = (accounts: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, true, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), ...accounts }))
+			.rpc();
+
+	const certificateBalance = async () =>
+		(await provider.connection.getTokenAccountBalance(ataFor(user))).value.amount;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods
+			.initCertificateMint()
+			.accounts({
+				certificateMint,
+				state,
+				authority,
+				tokenProgram: TOKEN_2022_PROGRAM_ID,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+		user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('mints a certificate to the user\'s associated token account', async () => {
+		const sig = await verify(certificateAccounts(user));
+		expect(await certificateBalance()).to.equal('1');
+
+		const certificate = await program.account.complianceCertificate.fetch(certificatePdaFor(user));
+		expect(certificate.user.toBase58()).to.equal(user.toBase58());
+		expect(certificate.mint.toBase58()).to.equal(certificateMint.toBase58());
+		expect(certificate.tokenAccount.toBase58()).to.equal(ataFor(user).toBase58());
+		expect(certificate.mintedAt.toNumber()).to.be.greaterThan(0);
+		expect(certificate.verifiedAt.toNumber()).to.equal(certificate.mintedAt.toNumber());
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'certificateMintedEvent');
+		expect(event.data.riskScore).to.equal(certificate.riskScore);
+	});
+
+	it('only refreshes the metadata on re-verification', async () => {
+		const before = await program.account.complianceCertificate.fetch(certificatePdaFor(user));
+		const sig = await verify(certificateAccounts(user));
+		expect(await certificateBalance()).to.equal('1');
+
+		const after = await program.account.complianceCertificate.fetch(certificatePdaFor(user));
+		expect(after.mintedAt.toNumber()).to.equal(before.mintedAt.toNumber());
+		expect(after.verifiedAt.toNumber()).to.be.at.least(before.verifiedAt.toNumber());
+		expect((await eventsOf(sig)).some((e) => e.name === 'certificateMintedEvent')).to.equal(false);
+	});
+
+	it('cannot be moved out of the user\'s account', async () => {
+		const info = await provider.connection.getParsedAccountInfo(certificateMint);
+		const extensions = info.value.data.parsed.info.extensions.map((e: any) => e.extension);
+		expect(extensions).to.include('nonTransferable');
+	});
+
+	it('requires the certificate accounts when a certificate is requested', async () => {
+		try {
+			await verify({});
+			expect.fail('expected CertificateAccountsRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('CertificateAccountsRequired');
+		}
+	});
+});
+
+describe('wallet freezes', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (action: string) =>
+		program.methods
+			.verifyCompliance(user, action, null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	const setFrozen = (frozen: boolean) =>
+		(frozen ? program.methods.freezeWallet(user) : program.methods.unfreezeWallet(user))
+			.accounts({ state, attestation: attestationPdaFor(user), authority, rollup: ROLLUP })
+			.rpc();
+
+	const expectError = async (promise: Promise<any>, code: string) => {
+		try {
+			await promise;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.allowAction('swap').accounts({ policy, authority }).rpc();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('blocks a previously passing wallet once frozen', async () => {
+		await verify('swap');
+		await setFrozen(true);
+		await expectError(verify('swap'), 'WalletFrozen');
+	});
+
+	it('rejects a frozen wallet before the policy checks', async () => {
+		// The action is off the whitelist too, but the freeze is reported first
+		await expectError(verify('withdraw'), 'WalletFrozen');
+	});
+
+	it('restores the wallet once unfrozen', async () => {
+		await setFrozen(false);
+		await verify('swap');
+		await expectError(verify('withdraw'), 'ActionNotAllowed');
+	});
+});
+
+describe('trusted attestors', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let policy: any;
+	let lenientPolicy: any;
+	let issuer: any;
+	let wallet: any;
+
+	const verify = (policy: any, attestorEntry: any) =>
+		program.methods
+			.verifyCompliance(wallet, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user: wallet,
+					attestationAuthority: issuer.publicKey,
+					attestation: attestationPdaFor(wallet, issuer.publicKey),
+					attestorEntry,
+				}),
+			)
+			.rpc();
+
+	const expectUntrusted = async (promise: Promise<any>) => {
+		try {
+			await promise;
+			expect.fail('expected UntrustedAttestor');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('UntrustedAttestor');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setRequireTrustedAttestor(true).accounts({ policy, authority }).rpc();
+		lenientPolicy = await initPolicy();
+
+		issuer = web3.Keypair.generate();
+		const sig = await provider.connection.requestAirdrop(issuer.publicKey, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+		await program.methods
+			.addAttestor(issuer.publicKey)
+			.accounts({ state, attestorEntry: attestorPdaFor(issuer.publicKey), authority, systemProgram: web3.SystemProgram.programId })
+			.rpc();
+		wallet = web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet, issuer.publicKey),
+				authority: issuer.publicKey,
+				attestorEntry: attestorPdaFor(issuer.publicKey),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.signers([issuer])
+			.rpc();
+		await setRiskFeed(state, wallet);
+	});
+
+	it('passes while the issuer is allowlisted', async () => {
+		await verify(policy, attestorPdaFor(issuer.publicKey));
+	});
+
+	it('needs the issuer\'s allowlist entry passed', async () => {
+		await expectUntrusted(verify(policy, null));
+	});
+
+	it('retires the attestations of a removed attestor', async () => {
+		await program.methods
+			.removeAttestor(issuer.publicKey)
+			.accounts({ state, attestorEntry: attestorPdaFor(issuer.publicKey), authority })
+			.rpc();
+		await expectUntrusted(verify(policy, attestorPdaFor(issuer.publicKey)));
+		// A policy that does not require it still takes the attestation
+		await verify(lenientPolicy, null);
+	});
+});
+
+describe('risk warning band', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	// RANGE_ORACLES.a scores the fixture user 2 and .b scores it 3
+	const verifyWith = (rangeOracle: any) =>
+		program.methods
+			.verifyCompliance(ORACLE_FIXTURE_USER, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({ state, policy, authority, user: ORACLE_FIXTURE_USER, attestation: attestationPdaFor(ORACLE_FIXTURE_USER), rangeOracle }),
+			)
+			.rpc();
+
+	const warningsOf = async (sig: string) => (await eventsOf(sig)).filter((e) => e.name === 'riskWarningEvent');
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy, authority }).rpc();
+		await program.methods.setWarnRiskScore(3).accounts({ policy, authority }).rpc();
+		await attest(state, ORACLE_FIXTURE_USER);
+	});
+
+	it('passes a mid-band score with a warning', async () => {
+		const [warning, ...rest] = await warningsOf(await verifyWith(RANGE_ORACLES.b));
+		expect(rest).to.have.length(0);
+		expect(warning.data.user.toBase58()).to.equal(ORACLE_FIXTURE_USER.toBase58());
+		expect(warning.data.riskScore).to.equal(3);
+	});
+
+	it('emits nothing for a score below the band', async () => {
+		expect(await warningsOf(await verifyWith(RANGE_ORACLES.a))).to.have.length(0);
+	});
+
+	it('rejects a band starting above the threshold', async () => {
+		try {
+			await program.methods.setWarnRiskScore(6).accounts({ policy, authority }).rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('batch verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let policy: any;
+
+	const verifyBatch = (users: any[]) =>
+		program.methods
+			.verifyComplianceBatch(users, 'settle')
+			.accounts({ state, policy, authority, report: null })
+			.remainingAccounts(users.flatMap(memberAccounts))
+			.rpc();
+
+	const verificationCount = async () => (await program.account.complianceState.fetch(state)).verificationCount.toNumber();
+
+	const attestedUsers = async (count: number) => {
+		const users = Array.from({ length: count }, () => web3.Keypair.generate().publicKey);
+		for (const user of users) {
+			await attest(state, user);
+		}
+		return users;
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('verifies every user in the batch', async () => {
+		const users = await attestedUsers(3);
+		const before = await verificationCount();
+		const sig = await verifyBatch(users);
+
+		expect(await verificationCount()).to.equal(before + 3);
+		const events = (await eventsOf(sig)).filter((e) => e.name === 'verificationEvent');
+		expect(events.map((e) => e.data.user.toBase58())).to.deep.equal(users.map((user) => user.toBase58()));
+		expect(events.every((e) => e.data.verified && e.data.action === 'settle')).to.equal(true);
+	});
+
+	it('aborts the whole batch when one user fails', async () => {
+		const users = await attestedUsers(3);
+		await program.methods.freezeWallet(users[1]).accounts({ state, attestation: attestationPdaFor(users[1]), authority, rollup: ROLLUP }).rpc();
+		const before = await verificationCount();
+		try {
+			await verifyBatch(users);
+			expect.fail('expected WalletFrozen');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('WalletFrozen');
+		}
+		expect(await verificationCount()).to.equal(before);
+	});
+
+	it('caps the batch size', async () => {
+		const users = Array.from({ length: 9 }, () => web3.Keypair.generate().publicKey);
+		try {
+			await verifyBatch(users);
+			expect.fail('expected BatchTooLarge');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('BatchTooLarge');
+		}
+	});
+});
+
+describe('policy snapshot hashes', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const eventOf = async (sig: string, name: string) => (await eventsOf(sig)).find((e) => e.name === name);
+
+	const verifiedHash = async () => {
+		const sig = await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+		return Buffer.from((await eventOf(sig, 'verificationEvent')).data.policyHash);
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('changes with the policy and matches the update that produced it', async () => {
+		const before = await verifiedHash();
+		expect(await verifiedHash()).to.deep.equal(before);
+
+		const sig = await program.methods
+			.setPolicy(4, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+		const updated = Buffer.from((await eventOf(sig, 'policyUpdatedEvent')).data.policyHash);
+		expect(updated).to.not.deep.equal(before);
+
+		expect(await verifiedHash()).to.deep.equal(updated);
+	});
+});
+
+describe('stored attestation bumps', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('stores the canonical bump and verifies against it', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const [address, bump] = attestationPdaAndBumpFor(user);
+		await attest(state, user);
+		expect((await program.account.kycAttestation.fetch(address)).bump).to.equal(bump);
+
+		await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: address }))
+			.rpc();
+	});
+
+	it('still rejects an attestation at another wallet\'s address', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const other = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await attest(state, other);
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(other) }))
+				.rpc();
+			expect.fail('expected ConstraintSeeds');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ConstraintSeeds');
+		}
+	});
+});
+
+describe('self-service KYC requests', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const systemProgram = (anchor as any).web3.SystemProgram.programId;
+	let state: any;
+
+	const kycRequestPdaFor = (user: any) =>
+		(anchor as any).web3.PublicKey.findProgramAddressSync(
+			[Buffer.from('kyc-request'), user.toBuffer()],
+			program.programId,
+		)[0];
+
+	const requestKyc = (user: any) =>
+		program.methods
+			.requestKyc()
+			.accounts({ kycRequest: kycRequestPdaFor(user), user: user.publicKey, systemProgram })
+			.signers([user])
+			.rpc();
+
+	const fundedUser = async () => {
+		const user = (anchor as any).web3.Keypair.generate();
+		await provider.connection.confirmTransaction(
+			await provider.connection.requestAirdrop(user.publicKey, 1_000_000_000),
+			'confirmed',
+		);
+		return user;
+	};
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('opens a pending request signed by the user', async () => {
+		const user = await fundedUser();
+		await requestKyc(user);
+
+		const request = await program.account.kycRequest.fetch(kycRequestPdaFor(user.publicKey));
+		expect(request.user.toString()).to.equal(user.publicKey.toString());
+		expect(request.status).to.equal(0);
+		expect(request.requestedAt.toNumber()).to.be.greaterThan(0);
+		expect(request.fulfilledAt.toNumber()).to.equal(0);
+
+		try {
+			await requestKyc(user);
+			expect.fail('expected KycRequestPending');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycRequestPending');
+		}
+	});
+
+	it('is marked fulfilled by the attestation that answers it', async () => {
+		const user = await fundedUser();
+		await requestKyc(user);
+
+		const wallet = user.publicKey;
+		const sig = await program.methods
+			.createKycAttestation(wallet, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: kycRequestPdaFor(wallet),
+				systemProgram,
+			})
+			.rpc({ commitment: 'confirmed' });
+		await setRiskFeed(state, wallet);
+
+		const request = await program.account.kycRequest.fetch(kycRequestPdaFor(wallet));
+		expect(request.status).to.equal(1);
+		expect(request.fulfilledBy.toString()).to.equal(authority.toString());
+		expect(request.fulfilledAt.toNumber()).to.be.greaterThan(0);
+		expect((await eventsOf(sig)).filter((e) => e.name === 'kycRequestFulfilledEvent')).to.have.length(1);
+
+		// A fulfilled request can be renewed
+		await requestKyc(user);
+		expect((await program.account.kycRequest.fetch(kycRequestPdaFor(wallet))).status).to.equal(0);
+	});
+});
+
+describe('rejections ahead of the oracle', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const systemProgram = (anchor as any).web3.SystemProgram.programId;
+	let state: any;
+	let policy: any;
+
+	// The policy needs two oracles, and none are passed: any call that reaches the oracle fails
+	const verifyWithoutOracles = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					sanction: sanctionPdaFor(user),
+				}),
+			)
+			.rpc();
+
+	const expectError = async (call: Promise<any>, code: string) => {
+		try {
+			await call;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setOracleQuorum(2).accounts({ policy, authority }).rpc();
+	});
+
+	it('needs the oracle accounts once the local checks pass', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await expectError(verifyWithoutOracles(user), 'OracleDataFetchFailed');
+	});
+
+	it('rejects a sanctioned wallet without the oracle accounts', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await program.methods
+			.addSanction(user, 'OFAC SDN list')
+			.accounts({ state, sanction: sanctionPdaFor(user), authority, systemProgram })
+			.rpc();
+		await expectError(verifyWithoutOracles(user), 'WalletSanctioned');
+	});
+
+	it('rejects missing fee accounts without the oracle accounts', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await program.methods.setVerificationFee(new (anchor as any).BN(1_000), 0).accounts({ policy, authority }).rpc();
+		try {
+			await expectError(verifyWithoutOracles(user), 'FeeAccountsRequired');
+		} finally {
+			await program.methods.setVerificationFee(new (anchor as any).BN(0), 0).accounts({ policy, authority }).rpc();
+		}
+	});
+});
+
+describe('oracle failure events', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const ORACLE_FAILURE = { missing: 0, wrongSubject: 3, stale: 4, noQuorum: 5 };
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+	let policy: any;
+
+	const verify = (subject: any, verifyPolicy: any, oracles: any = {}) =>
+		program.methods
+			.verifyCompliance(subject, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({ state, policy: verifyPolicy, authority, user: subject, attestation: attestationPdaFor(subject), ...oracles }),
+			)
+			.rpc();
+
+	// The failure event is read from the failed transaction's logs, alongside its error
+	const expectFailure = async (call: Promise<any>, code: string, subject: any, reasonCode: number) => {
+		try {
+			await call;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+			const events = eventsInLogs(err.logs).filter((e) => e.name === 'oracleFailureEvent');
+			expect(events).to.have.length(1);
+			expect(events[0].data.user.toString()).to.equal(subject.toString());
+			expect(events[0].data.reasonCode).to.equal(reasonCode);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy, authority }).rpc();
+		await attest(state, ORACLE_FIXTURE_USER);
+	});
+
+	it('reports an oracle entry for another wallet', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await expectFailure(verify(user, policy, { rangeOracle: RANGE_ORACLES.a }), 'OracleDataFetchFailed', user, ORACLE_FAILURE.wrongSubject);
+	});
+
+	it('reports a stale oracle entry', async () => {
+		const stalePolicy = await initPolicy();
+		await program.methods.setOracleKind(ORACLE_KIND_RANGE).accounts({ policy: stalePolicy, authority }).rpc();
+		// The fixture entries were last updated long before any test runs
+		await program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, new (anchor as any).BN(60), NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy: stalePolicy, authority })
+			.rpc();
+		await expectFailure(
+			verify(ORACLE_FIXTURE_USER, stalePolicy, { rangeOracle: RANGE_ORACLES.a }),
+			'StaleOracleData',
+			ORACLE_FIXTURE_USER,
+			ORACLE_FAILURE.stale,
+		);
+	});
+
+	it('reports a missing risk feed under a Switchboard policy', async () => {
+		const switchboardPolicy = await initPolicy();
+		await expectFailure(
+			verify(ORACLE_FIXTURE_USER, switchboardPolicy, { riskFeed: null, switchboardAggregator: SWITCHBOARD_AGGREGATORS.low }),
+			'OracleDataFetchFailed',
+			ORACLE_FIXTURE_USER,
+			ORACLE_FAILURE.missing,
+		);
+	});
+
+	it('fails a wallet no oracle account is passed for', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await expectFailure(verify(user, policy, { rangeOracle: null }), 'OracleDataFetchFailed', user, ORACLE_FAILURE.missing);
+
+		const switchboardPolicy = await initPolicy();
+		await expectFailure(
+			verify(user, switchboardPolicy, { riskFeed: null, switchboardAggregator: null }),
+			'OracleDataFetchFailed',
+			user,
+			ORACLE_FAILURE.missing,
+		);
+	});
+
+	it('reports a quorum the oracles fall short of', async () => {
+		const quorumPolicy = await initPolicy();
+		await program.methods.setOracleQuorum(2).accounts({ policy: quorumPolicy, authority }).rpc();
+		await expectFailure(
+			program.methods
+				.verifyCompliance(ORACLE_FIXTURE_USER, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(
+					verifyAccounts({
+						state,
+						policy: quorumPolicy,
+						authority,
+						user: ORACLE_FIXTURE_USER,
+						attestation: attestationPdaFor(ORACLE_FIXTURE_USER),
+					}),
+				)
+				.remainingAccounts([{ pubkey: RANGE_ORACLES.a, isSigner: false, isWritable: false }])
+				.rpc(),
+			'OracleDataFetchFailed',
+			ORACLE_FIXTURE_USER,
+			ORACLE_FAILURE.noQuorum,
+		);
+	});
+});
+
+describe('closing policies', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('returns the rent to the authority and removes the policy', async () => {
+		const name = nextPolicyName();
+		const policy = policyPdaFor(name);
+		await program.methods
+			.initializePolicy(name, [], 0, null, null)
+			.accounts({ policy, authority, state, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		const count = (await program.account.complianceState.fetch(state)).policyCount;
+		const rent = (await provider.connection.getAccountInfo(policy)).lamports;
+		const startBalance = await provider.connection.getBalance(authority);
+
+		const sig = await program.methods.closePolicy().accounts({ policy, authority, state }).rpc({ commitment: 'confirmed' });
+
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		const endBalance = await provider.connection.getBalance(authority, 'confirmed');
+		expect(endBalance - startBalance + tx.meta.fee).to.equal(rent);
+		expect(await provider.connection.getAccountInfo(policy)).to.equal(null);
+		expect((await program.account.complianceState.fetch(state)).policyCount).to.equal(count - 1);
+
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === 'policyClosedEvent');
+		expect(event.data.policy.toString()).to.equal(policy.toString());
+		expect(event.data.name).to.equal(name);
+	});
+
+	it('rejects a signer other than the policy authority', async () => {
+		const policy = await initPolicy();
+		const stranger = (anchor as any).web3.Keypair.generate();
+		await provider.connection.confirmTransaction(
+			await provider.connection.requestAirdrop(stranger.publicKey, 1_000_000_000),
+			'confirmed',
+		);
+		try {
+			await program.methods
+				.closePolicy()
+				.accounts({ policy, authority: stranger.publicKey, state: null })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('simulated verification', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (simulate: boolean) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, simulate, false)
+			.accounts(
+				verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), userRecord: userRecordPdaFor(user) }),
+			)
+			.rpc({ commitment: 'confirmed' });
+
+	const counts = async () => {
+		const complianceState = await program.account.complianceState.fetch(state, 'confirmed');
+		const record = await program.account.userComplianceRecord.fetchNullable(userRecordPdaFor(user), 'confirmed');
+		return {
+			verifications: complianceState.verificationCount.toNumber(),
+			userVerifications: record ? record.verificationCount.toNumber() : 0,
+		};
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('runs the checks without counting the verification', async () => {
+		const before = await counts();
+		const sig = await verify(true);
+		expect(await counts()).to.deep.equal(before);
+
+		const events = await eventsOf(sig);
+		expect(events.filter((e) => e.name === 'verificationEvent')).to.have.length(0);
+		const [event] = events.filter((e) => e.name === 'simulatedVerificationEvent');
+		expect(event.data.user.toString()).to.equal(user.toString());
+		expect(event.data.riskScore).to.equal(2);
+	});
+
+	it('counts the same call once it is real', async () => {
+		const before = await counts();
+		const sig = await verify(false);
+		const after = await counts();
+		expect(after.verifications).to.equal(before.verifications + 1);
+		expect(after.userVerifications).to.equal(before.userVerifications + 1);
+		expect((await eventsOf(sig)).filter((e) => e.name === 'simulatedVerificationEvent')).to.have.length(0);
+	});
+
+	it('still fails a simulated call the real one would fail', async () => {
+		const strict = await initPolicy(1);
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, true, false)
+				.accounts(verifyAccounts({ state, policy: strict, authority, user, attestation: attestationPdaFor(user) }))
+				.rpc();
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+});
+
+describe('jurisdiction risk caps', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = () =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy(5);
+		// Attested in jurisdiction 0; the low aggregator scores every wallet 2
+		user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+	});
+
+	it('leaves users outside a capped jurisdiction on the policy threshold', async () => {
+		await program.methods.setJurisdictionRiskCap(1, 1).accounts({ policy, authority }).rpc();
+		await verify();
+	});
+
+	it('holds users of a capped jurisdiction to the tighter cap', async () => {
+		await program.methods.setJurisdictionRiskCap(0, 1).accounts({ policy, authority }).rpc();
+		const stored = await program.account.compliancePolicy.fetch(policy);
+		expect(stored.jurisdictionRiskCapCount).to.equal(2);
+		try {
+			await verify();
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+
+	it('passes the user again once the cap is lifted', async () => {
+		await program.methods.removeJurisdictionRiskCap(0).accounts({ policy, authority }).rpc();
+		await verify();
+	});
+
+	it('rejects a cap off the risk scale', async () => {
+		try {
+			await program.methods.setJurisdictionRiskCap(0, 11).accounts({ policy, authority }).rpc();
+			expect.fail('expected InvalidPolicyParameters');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('InvalidPolicyParameters');
+		}
+	});
+});
+
+describe('event sequence numbers', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const seqOf = async (sig: string, name: string) => {
+		const [event] = (await eventsOf(sig)).filter((e) => e.name === name);
+		return event.data.seq.toNumber();
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('numbers attestation, verification and violation events in one sequence', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const start = (await program.account.complianceState.fetch(state)).eventSeq.toNumber();
+
+		const attested = await program.methods
+			.createKycAttestation(user, true, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc({ commitment: 'confirmed' });
+		await setRiskFeed(state, user);
+		const verified = await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc({ commitment: 'confirmed' });
+		const violated = await program.methods
+			.recordViolation(user, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				report: null,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: null,
+				userViolations: userViolationsPdaFor(user),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc({ commitment: 'confirmed' });
+
+		expect(await seqOf(attested, 'kycAttestationEvent')).to.equal(start + 1);
+		expect(await seqOf(verified, 'verificationEvent')).to.equal(start + 2);
+		expect(await seqOf(violated, 'violationEvent')).to.equal(start + 3);
+		expect((await program.account.complianceState.fetch(state)).eventSeq.toNumber()).to.equal(start + 3);
+	});
+
+	it('gives each event of a batch its own number', async () => {
+		const users = [0, 1].map(() => (anchor as any).web3.Keypair.generate().publicKey);
+		for (const user of users) {
+			await attest(state, user);
+		}
+		const start = (await program.account.complianceState.fetch(state)).eventSeq.toNumber();
+
+		const sig = await program.methods
+			.verifyComplianceBatch(users, 'swap')
+			.accounts({ state, policy, authority, report: null })
+			.remainingAccounts(users.flatMap(memberAccounts))
+			.rpc({ commitment: 'confirmed' });
+
+		const seqs = (await eventsOf(sig))
+			.filter((e) => e.name === 'verificationEvent')
+			.map((e) => e.data.seq.toNumber());
+		expect(seqs).to.deep.equal([start + 1, start + 2]);
+	});
+});
+
+describe('expiry grace period', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const verify = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc({ commitment: 'confirmed' });
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setExpiryGracePeriod(new (anchor as any).BN(3600)).accounts({ policy, authority }).rpc();
+	});
+
+	it('passes an attestation within the grace period, with a warning', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const expiredAt = (await chainNow()) - 60;
+		await attest(state, user, expiredAt);
+
+		const sig = await verify(user);
+		const [warning] = (await eventsOf(sig)).filter((e) => e.name === 'gracePeriodWarningEvent');
+		expect(warning.data.user.toString()).to.equal(user.toString());
+		expect(warning.data.expiredAt.toNumber()).to.equal(expiredAt);
+		expect(warning.data.graceEndsAt.toNumber()).to.equal(expiredAt + 3600);
+	});
+
+	it('fails an attestation past the grace period', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user, (await chainNow()) - 7200);
+		try {
+			await verify(user);
+			expect.fail('expected AttestationExpired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('AttestationExpired');
+		}
+	});
+
+	it('does not warn about an unexpired attestation', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const sig = await verify(user);
+		expect((await eventsOf(sig)).filter((e) => e.name === 'gracePeriodWarningEvent')).to.have.length(0);
+	});
+});
+
+describe('sanction merkle root', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	// Four listed wallets [i; 32] for i = 1..4, leaves keccak256(wallet), sibling pairs hashed
+	// in sorted order: root = H(H(leaf1, leaf2), H(leaf3, leaf4))
+	const listed = [1, 2, 3, 4].map((i) => new (anchor as any).web3.PublicKey(Buffer.alloc(32, i)));
+	const node = (hex: string) => Array.from(Buffer.from(hex, 'hex'));
+	const LEAF = [
+		'cebc8882fecbec7fb80d2cf4b312bec018884c2d66667c67a90508214bd8bafc',
+		'ee4a079f5b14a24465181d45af32a8053c2d446446d7019359e210b82e53b8ba',
+		'4a7a4de37def8e10861261f58e1003e6086df449b615bb411c39669548e19dba',
+		'b8bf1e33d35d228e8bf8fabdd5a4f9014038829dd90dbf59127ca095ad65c7cc',
+	].map(node);
+	const LEFT = node('596bf00ace9ca0f06c5f2e307f74f2c1da269fae2461421d15d37b6d0fd565e1');
+	const RIGHT = node('fea25366faacc54cd55736725f8c84688cb6f523c6356aa79d172f0aa8673ff6');
+	const ROOT = node('3df2c0c59f5153b5751383c7ac514416ec1b3085b96fe67b461a3c1893a22b78');
+
+	const check = (wallet: any, proof: number[][]) =>
+		program.methods.checkSanctionProof(wallet, proof).accounts({ state }).rpc();
+
+	const expectSanctioned = async (wallet: any, proof: number[][]) => {
+		try {
+			await check(wallet, proof);
+			expect.fail('expected WalletSanctioned');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('WalletSanctioned');
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+		await program.methods.setSanctionRoot(ROOT).accounts({ state, authority }).rpc();
+	});
+
+	after(async () => {
+		await program.methods.setSanctionRoot(Array(32).fill(0)).accounts({ state, authority }).rpc();
+	});
+
+	it('stores the root on the compliance state', async () => {
+		const account = await program.account.complianceState.fetch(state);
+		expect(account.sanctionMerkleRoot).to.deep.equal(ROOT);
+	});
+
+	it('fails wallets proven to be in the tree', async () => {
+		await expectSanctioned(listed[0], [LEAF[1], RIGHT]);
+		await expectSanctioned(listed[3], [LEAF[2], LEFT]);
+	});
+
+	it('passes wallets without a proof of inclusion', async () => {
+		const outsider = new (anchor as any).web3.PublicKey(Buffer.alloc(32, 5));
+		await check(outsider, [LEAF[1], RIGHT]);
+		// A listed wallet with the wrong siblings, or none, proves nothing
+		await check(listed[0], [LEAF[2], RIGHT]);
+		await check(listed[0], []);
+	});
+
+	it('only lets the state authority set the root', async () => {
+		const stranger = (anchor as any).web3.Keypair.generate();
+		try {
+			await program.methods
+				.setSanctionRoot(Array(32).fill(7))
+				.accounts({ state, authority: stranger.publicKey })
+				.signers([stranger])
+				.rpc();
+			expect.fail('expected Unauthorized');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('Unauthorized');
+		}
+	});
+});
+
+describe('per-policy counters', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policyA: any;
+	let policyB: any;
+
+	const verify = async (policy: any) => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+	};
+
+	before(async () => {
+		state = await initState();
+		policyA = await initPolicy();
+		policyB = await initPolicy();
+	});
+
+	it('tracks verifications per policy while the global count sums both', async () => {
+		const start = (await program.account.complianceState.fetch(state)).verificationCount.toNumber();
+		await verify(policyA);
+		await verify(policyA);
+		await verify(policyB);
+
+		expect((await program.account.compliancePolicy.fetch(policyA)).verificationCount.toNumber()).to.equal(2);
+		expect((await program.account.compliancePolicy.fetch(policyB)).verificationCount.toNumber()).to.equal(1);
+		expect((await program.account.complianceState.fetch(state)).verificationCount.toNumber()).to.equal(start + 3);
+	});
+
+	it('counts a violation against the policy it is recorded under', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		const start = (await program.account.complianceState.fetch(state)).violationCount.toNumber();
+		await program.methods
+			.recordViolation(user, 'Risk score too high', VIOLATION_CATEGORY_RISK)
+			.accounts({
+				state,
+				authority,
+				report: null,
+				rollup: ROLLUP,
+				userRecord: null,
+				policy: policyB,
+				userViolations: userViolationsPdaFor(user),
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		expect((await program.account.compliancePolicy.fetch(policyA)).violationCount.toNumber()).to.equal(0);
+		expect((await program.account.compliancePolicy.fetch(policyB)).violationCount.toNumber()).to.equal(1);
+		expect((await program.account.complianceState.fetch(state)).violationCount.toNumber()).to.equal(start + 1);
+	});
+
+	it('keeps the policy hash stable across verifications', async () => {
+		const hashOf = async () => {
+			const user = (anchor as any).web3.Keypair.generate().publicKey;
+			await attest(state, user);
+			const sig = await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy: policyA, authority, user, attestation: attestationPdaFor(user) }))
+				.rpc({ commitment: 'confirmed' });
+			return Buffer.from((await eventsOf(sig)).find((e) => e.name === 'verificationEvent').data.policyHash);
+		};
+		expect(await hashOf()).to.deep.equal(await hashOf());
+	});
+});
+
+describe('verification integrator', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+	let policy: any;
+
+	const verify = async (integrator: any) => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const call = program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					integrator: integrator ? integrator.publicKey : null,
+				}),
+			);
+		const sig = await (integrator ? call.signers([integrator]) : call).rpc({ commitment: 'confirmed' });
+		return (await eventsOf(sig)).find((e) => e.name === 'verificationEvent');
+	};
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('records the signing integrator in the verification event', async () => {
+		const integrator = (anchor as any).web3.Keypair.generate();
+		const event = await verify(integrator);
+		expect(event.data.integrator.toString()).to.equal(integrator.publicKey.toString());
+	});
+
+	it('records the default key when no integrator is passed', async () => {
+		const event = await verify(null);
+		expect(event.data.integrator.toString()).to.equal((anchor as any).web3.PublicKey.default.toString());
+	});
+});
+
+describe('bootstrapping a policy at initialization', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	const create = async (allowedJurisdictions: number[] | null, requireKyc: boolean | null) => {
+		const name = nextPolicyName();
+		const policy = policyPdaFor(name);
+		await program.methods
+			.initializePolicy(name, [], 0, allowedJurisdictions as any, requireKyc)
+			.accounts({ policy, authority, state: null, systemProgram: (anchor as any).web3.SystemProgram.programId })
+			.rpc();
+		await program.methods.setOracleKind(ORACLE_KIND_SWITCHBOARD).accounts({ policy, authority }).rpc();
+		return policy;
+	};
+
+	const verify = async (policy: any) => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		return program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+	};
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('verifies an allowed jurisdiction straight after initialization', async () => {
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		const policy = await create(allowed, true);
+
+		const account = await program.account.compliancePolicy.fetch(policy);
+		expect(account.allowedJurisdictions).to.deep.equal(allowed);
+		expect(account.requireKyc).to.equal(true);
+		await verify(policy);
+	});
+
+	it('keeps deny-all and required KYC when the options are omitted', async () => {
+		const policy = await create(null, null);
+
+		const account = await program.account.compliancePolicy.fetch(policy);
+		expect(account.allowedJurisdictions).to.deep.equal(new Array(32).fill(0));
+		expect(account.requireKyc).to.equal(true);
+		try {
+			await verify(policy);
+			expect.fail('expected RestrictedJurisdiction');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RestrictedJurisdiction');
+		}
+	});
+
+	it('takes require_kyc from initialization', async () => {
+		const policy = await create(null, false);
+		expect((await program.account.compliancePolicy.fetch(policy)).requireKyc).to.equal(false);
+	});
+});
+
+describe('dual attestation', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const secondAttester = web3.Keypair.generate();
+	let state: any;
+	let policy: any;
+
+	const profileFor = (attester: any) =>
+		web3.PublicKey.findProgramAddressSync([Buffer.from('attester'), attester.toBuffer()], program.programId)[0];
+
+	const verify = (user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setRequireDualAttestation(true).accounts({ policy, authority }).rpc();
+		await program.methods
+			.setAttesterTrustWeight(secondAttester.publicKey, 1)
+			.accounts({
+				attesterProfile: profileFor(secondAttester.publicKey),
+				state,
+				authority,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+	});
+
+	it('rejects an attestation vouched for by its issuer alone', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		try {
+			await verify(user);
+			expect.fail('expected DualAttestationRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('DualAttestationRequired');
+		}
+	});
+
+	it('passes once a second attester co-signs', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await program.methods
+			.cosignAttestation(user)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				attesterProfile: profileFor(secondAttester.publicKey),
+				cosigner: secondAttester.publicKey,
+			})
+			.signers([secondAttester])
+			.rpc();
+
+		await verify(user);
+	});
+});
+
+describe('risk scale normalization', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const allowed: number[] = new Array(32).fill(0);
+	allowed[0] = 1;
+	let state: any;
+
+	const setMaxRisk = (policy: any, maxRiskScore: number) =>
+		program.methods
+			.setPolicy(maxRiskScore, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+
+	const verify = async (policy: any) => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		return program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+			.rpc();
+	};
+
+	const expectError = async (call: Promise<any>, code: string) => {
+		try {
+			await call;
+			expect.fail(`expected ${code}`);
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal(code);
+		}
+	};
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('compares thresholds against scores normalized onto a 0-100 scale', async () => {
+		const policy = await initPolicy();
+		await program.methods.setRiskScale(100).accounts({ policy, authority }).rpc();
+
+		// The low aggregator's 2 of 10 is 20 of 100
+		await setMaxRisk(policy, 19);
+		await expectError(verify(policy), 'RiskScoreTooHigh');
+		await setMaxRisk(policy, 20);
+		await verify(policy);
+	});
+
+	it('compares thresholds against scores normalized onto a 0-5 scale', async () => {
+		const policy = await initPolicy(0);
+		await program.methods.setRiskScale(5).accounts({ policy, authority }).rpc();
+
+		// 2 of 10 is 1 of 5
+		await expectError(verify(policy), 'RiskScoreTooHigh');
+		await setMaxRisk(policy, 1);
+		await verify(policy);
+	});
+
+	it('validates thresholds against the policy scale', async () => {
+		const policy = await initPolicy();
+		await program.methods.setRiskScale(20).accounts({ policy, authority }).rpc();
+		await setMaxRisk(policy, 20);
+		await expectError(setMaxRisk(policy, 21), 'InvalidPolicyParameters');
+		// Shrinking the scale under a threshold already set is refused
+		await expectError(program.methods.setRiskScale(10).accounts({ policy, authority }).rpc(), 'InvalidPolicyParameters');
+	});
+});
+
+describe('verify and execute', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const stub = (anchor as any).workspace.GuardStub as any;
+	// guard-stub's gated_target takes no accounts or arguments, so its data is the discriminator
+	const GATED_TARGET_IX = createHash('sha256').update('global:gated_target').digest().subarray(0, 8);
+	let state: any;
+	let policy: any;
+
+	const verifyAndExecute = (user: any) =>
+		program.methods
+			.verifyAndExecute(user, 'swap', null, null, GATED_TARGET_IX)
+			.accounts({
+				verify: verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }),
+				targetProgram: stub.programId,
+			})
+			.rpc({ commitment: 'confirmed' });
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+	});
+
+	it('runs the gated instruction for a compliant user', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		const sig = await verifyAndExecute(user);
+
+		const tx = await provider.connection.getTransaction(sig, {
+			commitment: 'confirmed',
+			maxSupportedTransactionVersion: 0,
+		});
+		expect(tx.meta.logMessages.join('\n')).to.include('Gated target executed');
+		expect((await eventsOf(sig)).filter((e) => e.name === 'verificationEvent')).to.have.length(1);
+	});
+
+	it('never reaches the gated instruction when compliance fails', async () => {
+		const user = (anchor as any).web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, false, 0, new (anchor as any).BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: (anchor as any).web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+		try {
+			await verifyAndExecute(user);
+			expect.fail('expected KycNotVerified');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycNotVerified');
+			expect(err.logs.join('\n')).to.not.include('Gated target executed');
+		}
+	});
+});
+
+describe('missing attestation', () => {
+	const authority = (provider as any).wallet.publicKey;
+	let state: any;
+
+	const verify = (policy: any, user: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: null }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+	});
+
+	it('rejects a never-attested user under a policy that requires KYC', async () => {
+		const policy = await initPolicy();
+		try {
+			await verify(policy, (anchor as any).web3.Keypair.generate().publicKey);
+			expect.fail('expected KycNotVerified');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('KycNotVerified');
+		}
+	});
+
+	it('passes on the oracle alone when the policy does not require KYC', async () => {
+		const policy = await initPolicy();
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		await program.methods
+			.setPolicy(5, false, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+
+		await verify(policy, (anchor as any).web3.Keypair.generate().publicKey);
+	});
+});
+
+describe('user record risk cache', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	let state: any;
+	let policy: any;
+	let user: any;
+
+	const verify = (oracles: any, forceRefresh: boolean) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, forceRefresh)
+			.accounts(
+				verifyAccounts({
+					state,
+					policy,
+					authority,
+					user,
+					attestation: attestationPdaFor(user),
+					userRecord: userRecordPdaFor(user),
+					...oracles,
+				}),
+			)
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setCacheTtl(new BN(3600)).accounts({ policy, authority }).rpc();
+		user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+
+		// The first pass reads the low aggregator and caches its score on the user record
+		await setRiskFeed(state, user, SWITCHBOARD_AGGREGATORS.low);
+		await verify({ riskFeed: riskFeedPdaFor(user), switchboardAggregator: SWITCHBOARD_AGGREGATORS.low }, false);
+		// From here on a fresh read would see a score over the threshold
+		await setRiskFeed(state, user, SWITCHBOARD_AGGREGATORS.high);
+	});
+
+	it('reuses the cached score within the TTL without an oracle account', async () => {
+		await verify({ riskFeed: null, switchboardAggregator: null }, false);
+		await verify({ riskFeed: riskFeedPdaFor(user), switchboardAggregator: SWITCHBOARD_AGGREGATORS.high }, false);
+
+		const record = await program.account.userComplianceRecord.fetch(userRecordPdaFor(user));
+		expect(record.cachedRiskScore).to.equal(2);
+	});
+
+	it('reads the oracle afresh under force_refresh', async () => {
+		try {
+			await verify({ riskFeed: riskFeedPdaFor(user), switchboardAggregator: SWITCHBOARD_AGGREGATORS.high }, true);
+			expect.fail('expected RiskScoreTooHigh');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RiskScoreTooHigh');
+		}
+	});
+});
+
+describe('jurisdiction rejection logs', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const JURISDICTION = 42;
+
+	it('names the offending jurisdiction code in a JSON log line', async () => {
+		const state = await initState();
+		const policy = await initPolicy();
+		const allowed: number[] = new Array(32).fill(0);
+		allowed[0] = 1;
+		await program.methods
+			.setPolicy(5, true, allowed as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, false)
+			.accounts({ policy, authority })
+			.rpc();
+
+		const user = web3.Keypair.generate().publicKey;
+		await program.methods
+			.createKycAttestation(user, true, JURISDICTION, new BN(0), null, null, 0, 0)
+			.accounts({
+				attestation: attestationPdaFor(user),
+				authority,
+				attestorEntry: attestorPdaFor(authority),
+				state,
+				wallet: user,
+				timeOracle: null,
+				vendor: null,
+				instructions: null,
+				policy: null,
+				jurisdictionMeta: null,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				kycRequest: null,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+		await setRiskFeed(state, user);
+
+		try {
+			await program.methods
+				.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+				.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user) }))
+				.rpc();
+			expect.fail('expected RestrictedJurisdiction');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('RestrictedJurisdiction');
+			const line = err.logs.find((log: string) => log.includes('"error":"RestrictedJurisdiction"'));
+			const logged = JSON.parse(line.slice(line.indexOf('{')));
+			expect(logged.user).to.equal(user.toBase58());
+			expect(logged.jurisdiction).to.equal(JURISDICTION);
+		}
+	});
+});
+
+describe('attestation issued by a program PDA', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const stub = (anchor as any).workspace.GuardStub as any;
+	const [issuer] = web3.PublicKey.findProgramAddressSync([Buffer.from('issuer')], stub.programId);
+	let state: any;
+
+	before(async () => {
+		state = await initState();
+		// The PDA pays the attestation's rent itself
+		const sig = await provider.connection.requestAirdrop(issuer, 1_000_000_000);
+		await provider.connection.confirmTransaction(sig);
+		await program.methods
+			.addAttestor(issuer)
+			.accounts({ state, attestorEntry: attestorPdaFor(issuer), authority, systemProgram: web3.SystemProgram.programId })
+			.rpc();
+	});
+
+	it('records the PDA that signed through the CPI as the authority', async () => {
+		const wallet = web3.Keypair.generate().publicKey;
+		await stub.methods
+			.issueAttestation(wallet, 0)
+			.accounts({
+				attestation: attestationPdaFor(wallet, issuer),
+				issuer,
+				attestorEntry: attestorPdaFor(issuer),
+				complifiState: state,
+				wallet,
+				rollup: ROLLUP,
+				journal: JOURNAL,
+				complifiEventAuthority: EVENT_AUTHORITY,
+				complifiProgram: program.programId,
+				systemProgram: web3.SystemProgram.programId,
+			})
+			.rpc();
+
+		const attestation = await program.account.kycAttestation.fetch(attestationPdaFor(wallet, issuer));
+		expect(attestation.authority.toBase58()).to.equal(issuer.toBase58());
+	});
+});
+
+describe('empty jurisdiction guard', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const none: number[] = new Array(32).fill(0);
+
+	const setEmpty = (policy: any, requireKyc: boolean, allowEmpty: boolean) =>
+		program.methods
+			.setPolicy(5, requireKyc, none as any, 0, UNBOUNDED, UNBOUNDED, NO_COOLDOWN, ANY_AGE, NO_MIN_AGE, NO_MAX_AMOUNT, allowEmpty)
+			.accounts({ policy, authority })
+			.rpc();
+
+	it('rejects requiring KYC with no jurisdiction allowed', async () => {
+		const policy = await initPolicy();
+		try {
+			await setEmpty(policy, true, false);
+			expect.fail('expected NoJurisdictionsAllowed');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('NoJurisdictionsAllowed');
+		}
+	});
+
+	it('accepts an empty bitmap when explicitly allowed, or when KYC is off', async () => {
+		const policy = await initPolicy();
+		await setEmpty(policy, true, true);
+		expect(Array.from((await program.account.compliancePolicy.fetch(policy)).allowedJurisdictions)).to.deep.equal(none);
+
+		await setEmpty(await initPolicy(), false, false);
+	});
+});
+
+describe('compliance receipt', () => {
+	const authority = (provider as any).wallet.publicKey;
+	const web3 = (anchor as any).web3;
+	const stub = (anchor as any).workspace.GuardStub as any;
+	const TTL = 3600;
+	let state: any;
+	let policy: any;
+
+	const complianceReceiptPdaFor = (user: any) =>
+		web3.PublicKey.findProgramAddressSync([Buffer.from('receipt'), user.toBuffer()], program.programId)[0];
+
+	const verify = (user: any, complianceReceipt: any) =>
+		program.methods
+			.verifyCompliance(user, 'swap', null, null, null, NO_COUNTERPARTY, null, false, false, false)
+			.accounts(verifyAccounts({ state, policy, authority, user, attestation: attestationPdaFor(user), complianceReceipt }))
+			.rpc();
+
+	before(async () => {
+		state = await initState();
+		policy = await initPolicy();
+		await program.methods.setComplianceReceiptTtl(new BN(TTL)).accounts({ policy, authority }).rpc();
+	});
+
+	it('requires the receipt under a policy with a receipt TTL', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		try {
+			await verify(user, null);
+			expect.fail('expected ComplianceReceiptRequired');
+		} catch (err: any) {
+			expect(err.error.errorCode.code).to.equal('ComplianceReceiptRequired');
+		}
+	});
+
+	it('writes the pass to the receipt, which a program checks without a CPI', async () => {
+		const user = web3.Keypair.generate().publicKey;
+		await attest(state, user);
+		await verify(user, complianceReceiptPdaFor(user));
+
+		const receipt = await program.account.complianceReceipt.fetch(complianceReceiptPdaFor(user));
+		expect(receipt.user.toBase58()).to.equal(user.toBase58());
+		expect(receipt.policy.toBase58()).to.equal(policy.toBase58());
+		expect(receipt.riskScore).to.equal(2);
+		expect(receipt.validUntil.sub(receipt.verifiedAt).toNumber()).to.equal(TTL);
+
+		await stub.methods.receiptGatedAction(user, policy).accounts({ receipt: complianceReceiptPdaFor(user) }).rpc();
+		// The receipt only vouches for the policy the user passed
+		try {
+			await stub.methods
+				.receiptGatedAction(user, web3.Keypair.generate().publicKey)
+				.accounts({ receipt: complianceReceiptPdaFor(user) })
+				.rpc();
+			expect.fail('expected a receipt for another policy to be rejected');
+		} catch (err: any) {
+			expect(err.logs.join('\n')).to.include('invalid account data');
+		}
+	});
+});